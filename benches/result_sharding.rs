@@ -0,0 +1,82 @@
+//! Compares collecting per-file results through one shared `Mutex<Vec<_>>` (the old
+//! approach) with `Pipeline::run_collect`'s per-thread shards. Workers only hash each
+//! file's path, standing in for tiny files, so pushing the result is most of the work
+//! and the shared lock is as contended as it gets.
+//!
+//! Run with `cargo bench --bench result_sharding`.
+
+use std::fs;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use hash_folderoo::{MemoryMode, Pipeline};
+
+const FILES: usize = 20_000;
+const ROUNDS: usize = 5;
+
+fn digest(path: &Path) -> (String, String) {
+    let path = path.to_string_lossy().into_owned();
+    let hash = blake3::hash(path.as_bytes()).to_hex().to_string();
+    (path, hash)
+}
+
+fn pipeline() -> Pipeline {
+    Pipeline::new(MemoryMode::Booster)
+}
+
+fn shared_mutex(root: &Path) -> (Duration, Vec<(String, String)>) {
+    let results = Arc::new(Mutex::new(Vec::new()));
+    let sink = results.clone();
+    let start = Instant::now();
+    pipeline()
+        .run(root, &[], None, false, false, move |path, _pool| {
+            sink.lock().unwrap().push(digest(&path));
+            Ok(())
+        })
+        .unwrap();
+    let elapsed = start.elapsed();
+    let mut results = std::mem::take(&mut *results.lock().unwrap());
+    results.sort();
+    (elapsed, results)
+}
+
+fn sharded(root: &Path) -> (Duration, Vec<(String, String)>) {
+    let start = Instant::now();
+    let (_, mut results) = pipeline()
+        .run_collect(root, &[], None, false, false, |path, _pool| {
+            Ok(Some(digest(&path)))
+        })
+        .unwrap();
+    let elapsed = start.elapsed();
+    results.sort();
+    (elapsed, results)
+}
+
+fn main() {
+    let dir = tempfile::tempdir().unwrap();
+    for i in 0..FILES {
+        let sub = dir.path().join(format!("d{:03}", i % 200));
+        fs::create_dir_all(&sub).unwrap();
+        fs::write(sub.join(format!("f{:05}", i)), b"").unwrap();
+    }
+
+    let (mut mutex_best, mut shard_best) = (Duration::MAX, Duration::MAX);
+    for _ in 0..ROUNDS {
+        let (mutex_time, mutex_results) = shared_mutex(dir.path());
+        let (shard_time, shard_results) = sharded(dir.path());
+        assert_eq!(mutex_results.len(), FILES);
+        assert_eq!(mutex_results, shard_results);
+        mutex_best = mutex_best.min(mutex_time);
+        shard_best = shard_best.min(shard_time);
+    }
+    println!("{} tiny files, best of {} rounds", FILES, ROUNDS);
+    println!(
+        "  shared Mutex<Vec>:  {:>8.2} ms",
+        mutex_best.as_secs_f64() * 1000.0
+    );
+    println!(
+        "  per-thread shards:  {:>8.2} ms",
+        shard_best.as_secs_f64() * 1000.0
+    );
+}
@@ -45,6 +45,10 @@ impl HasherImpl for Blake3Hasher {
         self.hasher.update(data);
     }
 
+    fn update_parallel(&mut self, data: &[u8]) {
+        self.hasher.update_rayon(data);
+    }
+
     fn finalize_hex(&self, out_len: usize) -> String {
         // Use XOF output reader to produce arbitrary length
         let mut reader: OutputReader = self.hasher.finalize_xof();
@@ -2,13 +2,18 @@ use crate::hash::{AlgorithmInfo, HasherImpl};
 use tiny_keccak::{Hasher as TKHasher, KangarooTwelve};
 
 pub struct K12Hasher {
-    hasher: KangarooTwelve<&'static [u8]>,
+    hasher: KangarooTwelve<Vec<u8>>,
 }
 
 impl K12Hasher {
     pub fn new() -> Self {
+        Self::with_customization(b"")
+    }
+
+    /// KangarooTwelve with customization string `custom` for domain separation.
+    pub fn with_customization(custom: &[u8]) -> Self {
         Self {
-            hasher: KangarooTwelve::new(b""),
+            hasher: KangarooTwelve::new(custom.to_vec()),
         }
     }
 }
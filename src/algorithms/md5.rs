@@ -0,0 +1,66 @@
+use crate::hash::{AlgorithmInfo, HasherImpl};
+use md5::{Digest, Md5};
+
+/// MD5, kept only to interoperate with legacy `md5sum` manifests. It is broken for
+/// collision resistance, so it is flagged non-cryptographic.
+pub struct Md5Hasher {
+    state: Md5,
+}
+
+impl Md5Hasher {
+    pub fn new() -> Self {
+        Self { state: Md5::new() }
+    }
+}
+
+impl Default for Md5Hasher {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl HasherImpl for Md5Hasher {
+    fn name(&self) -> &str {
+        "md5"
+    }
+
+    fn info(&self) -> AlgorithmInfo {
+        AlgorithmInfo {
+            name: "md5".to_string(),
+            is_cryptographic: false,
+            supports_xof: false,
+            output_len_default: 16,
+        }
+    }
+
+    fn new_boxed() -> Box<dyn HasherImpl>
+    where
+        Self: Sized,
+    {
+        Box::new(Self::new())
+    }
+
+    fn update(&mut self, data: &[u8]) {
+        self.state.update(data);
+    }
+
+    fn finalize_hex(&self, out_len: usize) -> String {
+        let digest = self.state.clone().finalize();
+        if out_len <= digest.len() {
+            return hex::encode(&digest[..out_len]);
+        }
+
+        // Longer outputs chain MD5(digest || counter) blocks, as blake2b does
+        let mut out = Vec::with_capacity(out_len);
+        let mut counter: u32 = 0;
+        while out.len() < out_len {
+            let mut block = Md5::new();
+            block.update(digest);
+            block.update(counter.to_le_bytes());
+            out.extend_from_slice(&block.finalize());
+            counter = counter.wrapping_add(1);
+        }
+        out.truncate(out_len);
+        hex::encode(out)
+    }
+}
@@ -2,6 +2,7 @@ pub mod blake2b;
 pub mod blake2bp;
 pub mod blake3;
 pub mod k12;
+pub mod md5;
 pub mod parallelhash;
 pub mod registry;
 pub mod shake256;
@@ -16,8 +17,9 @@ pub use blake2b::Blake2bHasher;
 pub use blake2bp::Blake2bpHasher;
 pub use blake3::Blake3Hasher;
 pub use k12::K12Hasher;
+pub use md5::Md5Hasher;
 pub use parallelhash::ParallelHash256Hasher;
-pub use registry::Algorithm;
+pub use registry::{Algorithm, AlgorithmParams};
 pub use shake256::Shake256Hasher;
 pub use turboshake::TurboShake256Hasher;
 pub use wyhash::WyHashExpander;
@@ -1,7 +1,12 @@
+use anyhow::Result;
+
 use crate::hash::{AlgorithmInfo, HasherImpl};
 use tiny_keccak::{Hasher as TKHasher, IntoXof, ParallelHash, Xof};
 
-const DEFAULT_BLOCK_SIZE: usize = 8192;
+/// Block size used when none is configured. ParallelHash output depends on it.
+pub const DEFAULT_BLOCK_SIZE: usize = 8192;
+/// Largest accepted block size (1 GiB).
+pub const MAX_BLOCK_SIZE: usize = 1 << 30;
 
 pub struct ParallelHash256Hasher {
     state: ParallelHash,
@@ -9,12 +14,34 @@ pub struct ParallelHash256Hasher {
 
 impl ParallelHash256Hasher {
     pub fn new() -> Self {
+        Self::with_block_size(DEFAULT_BLOCK_SIZE)
+    }
+
+    /// ParallelHash256 over blocks of `block_size` bytes (see `validate_block_size`).
+    pub fn with_block_size(block_size: usize) -> Self {
+        Self::with_params(b"", block_size)
+    }
+
+    /// ParallelHash256 with customization string `custom` and the given block size.
+    pub fn with_params(custom: &[u8], block_size: usize) -> Self {
         Self {
-            state: ParallelHash::v256(b"", DEFAULT_BLOCK_SIZE),
+            state: ParallelHash::v256(custom, block_size),
         }
     }
 }
 
+/// Block sizes must be a power of two no larger than `MAX_BLOCK_SIZE`.
+pub fn validate_block_size(block_size: usize) -> Result<()> {
+    if block_size == 0 || !block_size.is_power_of_two() || block_size > MAX_BLOCK_SIZE {
+        anyhow::bail!(
+            "invalid ParallelHash block size {}: use a power of two between 1 and {}",
+            block_size,
+            MAX_BLOCK_SIZE
+        );
+    }
+    Ok(())
+}
+
 impl Default for ParallelHash256Hasher {
     fn default() -> Self {
         Self::new()
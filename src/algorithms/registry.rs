@@ -1,8 +1,19 @@
+use crate::algorithms::parallelhash::DEFAULT_BLOCK_SIZE;
 use crate::algorithms::{
-    Blake2bHasher, Blake2bpHasher, Blake3Hasher, K12Hasher, ParallelHash256Hasher, Shake256Hasher,
-    TurboShake256Hasher, WyHashExpander, Xxh3Expander,
+    Blake2bHasher, Blake2bpHasher, Blake3Hasher, K12Hasher, Md5Hasher, ParallelHash256Hasher,
+    Shake256Hasher, TurboShake256Hasher, WyHashExpander, Xxh3Expander,
 };
 use crate::hash::HasherImpl;
+use std::sync::Once;
+
+/// Tunables for algorithms that take parameters beyond the output length.
+#[derive(Clone, Debug, Default)]
+pub struct AlgorithmParams {
+    /// ParallelHash256 block size in bytes (defaults to 8192).
+    pub block_size: Option<usize>,
+    /// Customization string for K12 and ParallelHash256 (domain separation).
+    pub customization: Option<String>,
+}
 
 #[derive(Clone, Copy, Debug)]
 pub enum Algorithm {
@@ -15,6 +26,8 @@ pub enum Algorithm {
     ParallelHash256,
     Xxh3_1024,
     Wyhash1024,
+    /// Legacy interop only; selecting it logs a deprecation warning.
+    Md5,
 }
 
 impl Algorithm {
@@ -29,17 +42,36 @@ impl Algorithm {
             Algorithm::ParallelHash256,
             Algorithm::Xxh3_1024,
             Algorithm::Wyhash1024,
+            Algorithm::Md5,
         ]
     }
 
+    /// Names `--algorithm all` expands to: every algorithm except deprecated ones.
     pub fn list() -> Vec<&'static str> {
-        Self::all().iter().map(|alg| alg.name()).collect()
+        Self::all()
+            .iter()
+            .filter(|alg| !alg.is_deprecated())
+            .map(|alg| alg.name())
+            .collect()
     }
 
     pub fn from_name(name: &str) -> Option<Algorithm> {
         name.parse().ok()
     }
 
+    /// Create a hasher honoring `params`; parameters that don't apply are ignored.
+    pub fn create_with(&self, params: &AlgorithmParams) -> Box<dyn HasherImpl> {
+        let custom = params.customization.as_deref().unwrap_or("").as_bytes();
+        match self {
+            Algorithm::ParallelHash256 => Box::new(ParallelHash256Hasher::with_params(
+                custom,
+                params.block_size.unwrap_or(DEFAULT_BLOCK_SIZE),
+            )),
+            Algorithm::K12 => Box::new(K12Hasher::with_customization(custom)),
+            _ => self.create(),
+        }
+    }
+
     pub fn create(&self) -> Box<dyn HasherImpl> {
         match self {
             Algorithm::Blake2b => Blake2bHasher::new_boxed(),
@@ -51,6 +83,7 @@ impl Algorithm {
             Algorithm::ParallelHash256 => ParallelHash256Hasher::new_boxed(),
             Algorithm::Xxh3_1024 => Xxh3Expander::new_boxed(),
             Algorithm::Wyhash1024 => WyHashExpander::new_boxed(),
+            Algorithm::Md5 => Md5Hasher::new_boxed(),
         }
     }
 
@@ -65,6 +98,31 @@ impl Algorithm {
             Algorithm::ParallelHash256 => "parallelhash256",
             Algorithm::Xxh3_1024 => "xxh3-1024",
             Algorithm::Wyhash1024 => "wyhash-1024",
+            Algorithm::Md5 => "md5",
+        }
+    }
+
+    /// Whether this algorithm takes a customization string (`AlgorithmParams::customization`).
+    pub fn supports_customization(&self) -> bool {
+        matches!(self, Algorithm::K12 | Algorithm::ParallelHash256)
+    }
+
+    /// Whether this algorithm is kept only for legacy interoperability.
+    pub fn is_deprecated(&self) -> bool {
+        matches!(self, Algorithm::Md5)
+    }
+
+    /// Warn (once per run) when a deprecated algorithm is picked. Called where the user
+    /// selects an algorithm, so listing or looping over [`Algorithm::all`] stays quiet.
+    pub fn warn_if_deprecated(&self) {
+        static WARNED: Once = Once::new();
+        if self.is_deprecated() {
+            WARNED.call_once(|| {
+                log::warn!(
+                    "{} is deprecated and cryptographically broken; use it only to match legacy manifests",
+                    self.name()
+                )
+            });
         }
     }
 
@@ -79,7 +137,7 @@ impl Algorithm {
             | Algorithm::Xxh3_1024
             | Algorithm::Wyhash1024 => true,
             // The remaining algorithms are fixed-output
-            Algorithm::Blake2b | Algorithm::Blake2bp => false,
+            Algorithm::Blake2b | Algorithm::Blake2bp | Algorithm::Md5 => false,
         }
     }
 }
@@ -97,6 +155,7 @@ impl std::str::FromStr for Algorithm {
             "parallelhash" | "parallelhash256" => Ok(Algorithm::ParallelHash256),
             "xxh3" | "xxh3-1024" => Ok(Algorithm::Xxh3_1024),
             "wyhash" | "wyhash-1024" => Ok(Algorithm::Wyhash1024),
+            "md5" => Ok(Algorithm::Md5),
             _ => Err(()),
         }
     }
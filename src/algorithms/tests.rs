@@ -1,11 +1,12 @@
 use crate::algorithms::{
-    Algorithm, Blake2bHasher, Blake2bpHasher, Blake3Hasher, K12Hasher, ParallelHash256Hasher,
-    Shake256Hasher, TurboShake256Hasher, WyHashExpander, Xxh3Expander,
+    Algorithm, AlgorithmParams, Blake2bHasher, Blake2bpHasher, Blake3Hasher, K12Hasher, Md5Hasher,
+    ParallelHash256Hasher, Shake256Hasher, TurboShake256Hasher, WyHashExpander, Xxh3Expander,
 };
-use crate::hash::{expand_digest, HasherImpl};
+use crate::hash::{expand_digest, DigestEncoding, HasherImpl};
 use std::io::Read;
 
 #[cfg(test)]
+#[allow(clippy::module_inception)]
 mod tests {
     use super::*;
     use blake2b_simd::{blake2bp, Params};
@@ -210,6 +211,76 @@ mod tests {
         }
     }
 
+    #[test]
+    fn parallelhash_block_size_is_configurable() {
+        use crate::algorithms::parallelhash::validate_block_size;
+
+        let input = vec![0xA5u8; 5000];
+        let digest = |block_size: usize| {
+            let mut h = ParallelHash256Hasher::with_block_size(block_size);
+            h.update(&input);
+            h.finalize_hex(32)
+        };
+        assert_ne!(digest(1024), digest(8192));
+        assert_eq!(digest(8192), {
+            let mut h = ParallelHash256Hasher::new();
+            h.update(&input);
+            h.finalize_hex(32)
+        });
+
+        let mut ref_hasher = ParallelHash::v256(b"", 1024);
+        ref_hasher.update(&input);
+        let mut xof = ref_hasher.into_xof();
+        let mut out = vec![0u8; 32];
+        xof.squeeze(&mut out);
+        assert_eq!(digest(1024), hex::encode(out));
+
+        assert!(validate_block_size(1024).is_ok());
+        assert!(validate_block_size(0).is_err());
+        assert!(validate_block_size(1000).is_err());
+    }
+
+    #[test]
+    fn customization_matches_reference_and_separates_domains() {
+        let input = b"The quick brown fox";
+
+        let mut h = K12Hasher::with_customization(b"app-v1");
+        h.update(input);
+        let mut reference = KangarooTwelve::new(b"app-v1");
+        reference.update(input);
+        let mut out = vec![0u8; 32];
+        reference.finalize(&mut out);
+        assert_eq!(h.finalize_hex(32), hex::encode(out));
+
+        let mut h = ParallelHash256Hasher::with_params(b"app-v1", 8192);
+        h.update(input);
+        let mut reference = ParallelHash::v256(b"app-v1", 8192);
+        reference.update(input);
+        let mut xof = reference.into_xof();
+        let mut out = vec![0u8; 32];
+        xof.squeeze(&mut out);
+        assert_eq!(h.finalize_hex(32), hex::encode(out));
+
+        for alg in [Algorithm::K12, Algorithm::ParallelHash256] {
+            let digest = |custom: Option<&str>| {
+                let params = AlgorithmParams {
+                    customization: custom.map(str::to_string),
+                    ..Default::default()
+                };
+                let mut h = alg.create_with(&params);
+                h.update(input);
+                h.finalize_hex(32)
+            };
+            assert_ne!(digest(Some("a")), digest(Some("b")), "{:?}", alg);
+            assert_ne!(digest(Some("a")), digest(None), "{:?}", alg);
+            assert_eq!(digest(None), {
+                let mut h = alg.create();
+                h.update(input);
+                h.finalize_hex(32)
+            });
+        }
+    }
+
     fn expand_seed(seed: u64, out_len: usize) -> Vec<u8> {
         if out_len == 0 {
             return Vec::new();
@@ -265,6 +336,31 @@ mod tests {
         }
     }
 
+    #[test]
+    fn md5_matches_reference() {
+        use ::md5::{Digest, Md5};
+        let inputs: &[&[u8]] = &[b"", b"abc", b"The quick brown fox jumps over the lazy dog"];
+        for &inp in inputs {
+            let mut h = Md5Hasher::new();
+            h.update_reader(&mut &inp[..]).unwrap();
+            let expected = hex::encode(Md5::digest(inp));
+            assert_eq!(h.finalize_hex(16), expected);
+        }
+        // the well-known md5sum of the empty input
+        assert_eq!(
+            Algorithm::Md5.create().finalize_hex(16),
+            "d41d8cd98f00b204e9800998ecf8427e"
+        );
+    }
+
+    #[test]
+    fn all_selection_leaves_out_deprecated_algorithms() {
+        let listed = Algorithm::list();
+        assert!(!listed.contains(&"md5"));
+        assert!(listed.contains(&"blake3"));
+        assert_eq!(listed.len(), Algorithm::all().len() - 1);
+    }
+
     #[test]
     fn expand_digest_shake256_xof_matches_adapter() {
         let inp = b"abc";
@@ -504,9 +600,17 @@ mod tests {
     #[test]
     fn cryptographic_flags_are_set() {
         // Verify cryptographic algorithms are marked correctly
-        let crypto_algs = ["blake2b", "blake2bp", "blake3", "shake256", "k12", "turboshake256", "parallelhash256"];
-        let non_crypto = ["xxh3-1024", "wyhash-1024"];
-        
+        let crypto_algs = [
+            "blake2b",
+            "blake2bp",
+            "blake3",
+            "shake256",
+            "k12",
+            "turboshake256",
+            "parallelhash256",
+        ];
+        let non_crypto = ["xxh3-1024", "wyhash-1024", "md5"];
+
         for alg in Algorithm::all() {
             let h = alg.create();
             let info = h.info();
@@ -773,6 +877,45 @@ mod tests {
         }
     }
 
+    #[test]
+    fn update_reader_buffer_size_does_not_change_digest() {
+        let data: Vec<u8> = (0..200_000u32).map(|i| (i % 253) as u8).collect();
+        for alg in Algorithm::all() {
+            if alg.name() == "wyhash-1024" {
+                continue; // Skip stream-dependent
+            }
+            let mut small = alg.create();
+            small.update_reader_with_buffer(&mut &data[..], 7).unwrap();
+            let mut large = alg.create();
+            large
+                .update_reader_with_buffer(&mut &data[..], 1024 * 1024)
+                .unwrap();
+            let mut default = alg.create();
+            default.update_reader(&mut &data[..]).unwrap();
+
+            let expected = default.finalize_hex(32);
+            assert_eq!(small.finalize_hex(32), expected, "{} small buffer", alg.name());
+            assert_eq!(large.finalize_hex(32), expected, "{} large buffer", alg.name());
+        }
+    }
+
+    #[test]
+    fn update_parallel_matches_update() {
+        let data: Vec<u8> = (0..3_000_000u32).map(|i| (i % 251) as u8).collect();
+        for alg in Algorithm::all() {
+            let mut sequential = alg.create();
+            sequential.update(&data);
+            let mut parallel = alg.create();
+            parallel.update_parallel(&data);
+            assert_eq!(
+                parallel.finalize_hex(32),
+                sequential.finalize_hex(32),
+                "{}",
+                alg.name()
+            );
+        }
+    }
+
     #[test]
     fn algorithm_all_bytes_coverage() {
         // Test with data containing all possible byte values
@@ -785,4 +928,33 @@ mod tests {
             assert_eq!(hash.len(), 64);
         }
     }
+
+    #[test]
+    fn digest_encodings_round_trip() {
+        let mut h = Blake3Hasher::new();
+        h.update(b"hello");
+        let hex_digest = h.finalize_hex(32);
+
+        for enc in [
+            DigestEncoding::Hex,
+            DigestEncoding::Base64,
+            DigestEncoding::Base64Std,
+            DigestEncoding::Base32,
+        ] {
+            assert_eq!(DigestEncoding::from_name(enc.name()), Some(enc));
+            let encoded = enc.reencode_hex(&hex_digest).unwrap();
+            assert_eq!(
+                enc.decode_to_hex(&encoded).unwrap(),
+                hex_digest,
+                "{}",
+                enc.name()
+            );
+        }
+
+        // URL-safe alphabet without padding by default
+        assert_eq!(DigestEncoding::Base64.encode(&[0xfb, 0xff]), "-_8");
+        assert_eq!(DigestEncoding::Base64Std.encode(&[0xfb, 0xff]), "+/8=");
+        assert_eq!(DigestEncoding::Base32.encode(b"foobar"), "MZXW6YTBOI======");
+        assert!(DigestEncoding::Base32.decode("not base32!").is_err());
+    }
 }
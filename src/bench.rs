@@ -7,6 +7,8 @@ use anyhow::Result;
 use serde::{Deserialize, Serialize};
 
 use crate::algorithms::Algorithm;
+use crate::hash::DEFAULT_READ_BUFFER_SIZE;
+use crate::memory::{recommend_config, MemoryMode};
 
 /// Benchmark result schema for persistence and comparison
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -115,15 +117,20 @@ pub fn run_benchmark_structured(algorithm: &str, size_mb: usize) -> Result<Bench
             anyhow::bail!("Unknown algorithm '{}'", algorithm);
         }
     };
+    alg_enum.warn_if_deprecated();
 
     let mut hasher = alg_enum.create();
     let info = hasher.info();
     let out_len = info.output_len_default;
 
     let mut reader = Cursor::new(&buf);
+    // Read in the same chunk size the hashing pipeline would use by default
+    let read_buf_size = recommend_config(MemoryMode::Balanced, None, None)
+        .map(|plan| plan.buffer_size)
+        .unwrap_or(DEFAULT_READ_BUFFER_SIZE);
 
     let start = Instant::now();
-    hasher.update_reader(&mut reader)?;
+    hasher.update_reader_with_buffer(&mut reader, read_buf_size)?;
     let _hash = hasher.finalize_hex(out_len);
     let elapsed = start.elapsed();
     let secs = elapsed.as_secs_f64().max(1e-9);
@@ -170,14 +177,14 @@ pub fn run_benchmark_and_save(algorithm: &str, size_mb: usize, out_path: &Path)
     Ok(())
 }
 
-/// Run benchmarks for all algorithms and save to a report file
+/// Run benchmarks for all algorithms (deprecated ones excluded) and save to a report file
 pub fn run_all_benchmarks_and_save(size_mb: usize, out_path: &Path) -> Result<()> {
     let mut report = BenchmarkReport::new();
-    
-    for alg in Algorithm::all() {
-        match run_benchmark_structured(alg.name(), size_mb) {
+
+    for name in Algorithm::list() {
+        match run_benchmark_structured(name, size_mb) {
             Ok(result) => report.add_result(result),
-            Err(e) => eprintln!("Benchmark failed for {}: {}", alg.name(), e),
+            Err(e) => eprintln!("Benchmark failed for {}: {}", name, e),
         }
     }
     
@@ -21,7 +21,7 @@ pub struct Cli {
 #[derive(Subcommand, Debug)]
 pub enum Commands {
     /// Create a hashmap of files in a directory
-    Hashmap(HashmapArgs),
+    Hashmap(Box<HashmapArgs>),
     /// Compare two hashmaps or directories
     Compare(CompareArgs),
     /// Create or execute a copy plan based on diffs
@@ -34,6 +34,11 @@ pub enum Commands {
     Benchmark(BenchmarkArgs),
     /// Generate reports from inputs
     Report(ReportArgs),
+    /// Check a stored map file for structural problems
+    VerifyMap(VerifyMapArgs),
+    /// Hash a download streamed from a URL without saving it
+    #[cfg(feature = "url")]
+    Hash(HashArgs),
 }
 
 #[derive(Args, Debug)]
@@ -46,15 +51,46 @@ pub struct HashmapArgs {
     #[arg(long, short('o'))]
     pub output: Option<PathBuf>,
 
-    /// Output format (json/csv)
+    /// Output format (auto/json/csv/ndjson/sums); `auto`, the default, follows the
+    /// --output extension and writes json to stdout
     #[arg(long, short('f'))]
     pub format: Option<String>,
 
-    /// Hash algorithm to use (e.g. blake3, sha3)
+    /// Field delimiter for CSV maps: one ASCII character or `tab` (default `,`)
+    #[arg(long = "csv-delimiter", value_name = "CHAR")]
+    pub csv_delimiter: Option<String>,
+
+    /// Leave out the header row of CSV maps (columns stay path,hash,size,mtime)
+    #[arg(long = "csv-no-header")]
+    pub csv_no_header: bool,
+
+    /// End CSV records with CRLF instead of LF
+    #[arg(long = "csv-crlf")]
+    pub csv_crlf: bool,
+
+    /// Add an `algorithm` column to CSV maps (always present on multi-algorithm CSV maps)
+    #[arg(long = "csv-include-algorithm", conflicts_with = "csv_no_header")]
+    pub csv_include_algorithm: bool,
+
+    /// Directory for the temporary file the output is staged in before the atomic rename
+    #[arg(long = "temp-dir", value_name = "DIR")]
+    pub temp_dir: Option<PathBuf>,
+
+    /// Hash algorithm to use (e.g. blake3, sha3); `all` hashes with every algorithm
     #[arg(long, short('a'))]
     pub algorithm: Option<String>,
 
-    /// Maximum directory traversal depth
+    /// Hash each file with several algorithms in one read (first one fills `hash`)
+    #[arg(
+        long,
+        value_delimiter = ',',
+        conflicts_with_all = ["algorithm", "structure_only", "allow_special"]
+    )]
+    pub algorithms: Vec<String>,
+
+    /// Levels below the root to descend: 1 hashes only the files directly inside --path,
+    /// 2 also those one directory down, and so on. 0 covers the root itself, so it is
+    /// only valid when --path is a single file
     #[arg(long)]
     pub depth: Option<usize>,
 
@@ -66,6 +102,14 @@ pub struct HashmapArgs {
     #[arg(long = "xof-length")]
     pub xof_length: Option<usize>,
 
+    /// ParallelHash256 block size in bytes (power of two, default 8192); changes the digest
+    #[arg(long = "block-size")]
+    pub block_size: Option<usize>,
+
+    /// Customization string for k12/parallelhash256 (domain separation; changes the digest)
+    #[arg(long)]
+    pub customization: Option<String>,
+
     /// Allow requesting XOF-like output lengths for algorithms that don't natively support XOF.
     /// This enables deterministic expansion behavior (opt-in) and is intentionally required
     /// to avoid accidental non-standard output when users request large lengths for fixed-output algorithms.
@@ -76,14 +120,54 @@ pub struct HashmapArgs {
     #[arg(long, value_delimiter = ',')]
     pub exclude: Vec<String>,
 
+    /// Match --exclude patterns ignoring case (`*.log` also excludes `ERROR.LOG`)
+    #[arg(long = "exclude-case-insensitive")]
+    pub exclude_case_insensitive: bool,
+
     /// Follow symbolic links when walking directories
     #[arg(long = "follow-symlinks")]
     pub follow_symlinks: bool,
 
+    /// Record symlinks with a hash of their target path (as read by readlink) instead of
+    /// skipping them
+    #[arg(long = "hash-symlink-target-path", conflicts_with = "follow_symlinks")]
+    pub hash_symlink_target_path: bool,
+
     /// Show a progress bar while hashing
     #[arg(long = "progress")]
     pub progress: bool,
 
+    /// Draw the progress bar on stderr (the default; keeps piped stdout output clean)
+    #[arg(long = "progress-to-stderr", conflicts_with = "progress_to_stdout")]
+    pub progress_to_stderr: bool,
+
+    /// Draw the progress bar on stdout instead of stderr
+    #[arg(long = "progress-to-stdout")]
+    pub progress_to_stdout: bool,
+
+    /// Redraw the progress bar every <ms> milliseconds (with --progress-plain: print a
+    /// line every <ms>, default 5000)
+    #[arg(long = "progress-refresh", value_name = "MS")]
+    pub progress_refresh: Option<u64>,
+
+    /// Print periodic plain progress lines instead of redrawing a bar (for CI logs; the
+    /// default when the progress stream is not a terminal or CI is set)
+    #[arg(long = "progress-plain")]
+    pub progress_plain: bool,
+
+    /// Redraw the progress bar even when its stream is not a terminal or CI is set
+    #[arg(long = "force-progress", conflicts_with = "progress_plain")]
+    pub force_progress: bool,
+
+    /// Weight (0-1] of the newest sample in the smoothed throughput behind the progress
+    /// ETA; lower values steady the ETA on uneven trees (default 0.3)
+    #[arg(long = "progress-eta-smoothing", value_name = "ALPHA")]
+    pub progress_eta_smoothing: Option<f64>,
+
+    /// Custom indicatif template for the progress bar, e.g. "{pos}/{len} {wide_bar}"
+    #[arg(long = "progress-template", value_name = "TPL")]
+    pub progress_template: Option<String>,
+
     /// Perform a dry-run (hash files but skip writing output)
     #[arg(long = "dry-run")]
     pub dry_run: bool,
@@ -92,10 +176,35 @@ pub struct HashmapArgs {
     #[arg(long)]
     pub silent: bool,
 
+    /// After the run, summarize files skipped due to errors, grouped by cause
+    #[arg(long = "ignore-errors-summary")]
+    pub ignore_errors_summary: bool,
+
     /// Number of worker threads to use
     #[arg(long)]
     pub threads: Option<usize>,
 
+    /// Don't descend into directories on other filesystems (Unix only, like `find -xdev`)
+    #[arg(long = "one-file-system")]
+    pub one_file_system: bool,
+
+    /// Threads that open and read files (splits reading from hashing; defaults to --threads)
+    #[arg(long = "io-threads")]
+    pub io_threads: Option<usize>,
+
+    /// Threads that hash buffers filled by the I/O threads (defaults to --threads)
+    #[arg(long = "hash-threads")]
+    pub hash_threads: Option<usize>,
+
+    /// Hash files of at least <BYTES> on a separate group of workers with larger read
+    /// buffers, so huge files don't stall the small ones
+    #[arg(
+        long = "large-file-threshold",
+        value_name = "BYTES",
+        conflicts_with_all = ["io_threads", "hash_threads"]
+    )]
+    pub large_file_threshold: Option<u64>,
+
     /// Memory mode (e.g. auto, low, high)
     #[arg(long = "mem-mode")]
     pub mem_mode: Option<String>,
@@ -103,6 +212,176 @@ pub struct HashmapArgs {
     /// Maximum memory budget in bytes for hashing buffers
     #[arg(long = "max-ram")]
     pub max_ram: Option<u64>,
+
+    /// Print the memory plan chosen for --mem-mode/--threads/--max-ram (text, or JSON with
+    /// `--format json`) and exit without hashing
+    #[arg(long = "explain-plan")]
+    pub explain_plan: bool,
+
+    /// Stop dispatching new files once more than this many bytes have been hashed (in-flight files finish)
+    #[arg(long = "max-total-bytes")]
+    pub max_total_bytes: Option<u64>,
+
+    /// Fail if the tree holds more than this many files (guards against a misconfigured root)
+    #[arg(long = "max-files", value_name = "N")]
+    pub max_files: Option<u64>,
+
+    /// Fail, without writing a map, when no files were hashed (guards against a wrong root
+    /// or excludes that match everything)
+    #[arg(long = "fail-on-empty")]
+    pub fail_on_empty: bool,
+
+    /// Order of the map's entries: path (default) or hash, which puts duplicates next to
+    /// each other
+    #[arg(long, visible_alias = "sort-entries-by", value_name = "KEY")]
+    pub sort: Option<String>,
+
+    /// Hex digit case for recorded hashes (lower, upper)
+    #[arg(long = "hex-case")]
+    pub hex_case: Option<String>,
+
+    /// Text encoding for recorded digests (hex, base64 [URL-safe, no padding], base64-std, base32);
+    /// recorded in the map header so loaders can decode it
+    #[arg(long = "digest-encoding")]
+    pub digest_encoding: Option<String>,
+
+    /// Baseline map to update incrementally (used with --changed-from)
+    #[arg(long = "baseline", requires = "changed_from")]
+    pub baseline: Option<PathBuf>,
+
+    /// File listing changed paths (one per line, relative to --path); only these are rehashed
+    /// and all other entries are carried over from --baseline
+    #[arg(long = "changed-from", requires = "baseline")]
+    pub changed_from: Option<PathBuf>,
+
+    /// Keep the --changed-from list as written instead of canonicalizing and
+    /// de-duplicating its paths
+    #[arg(long = "no-dedupe-inputs", requires = "changed_from")]
+    pub no_dedupe_inputs: bool,
+
+    /// Walk in sorted order and skip every file up to and including this path (relative
+    /// to --path, or absolute inside it), to continue an interrupted run
+    #[arg(
+        long = "resume-from",
+        value_name = "PATH",
+        conflicts_with = "changed_from"
+    )]
+    pub resume_from: Option<PathBuf>,
+
+    /// Only hash files with these extensions (comma-separated, case-insensitive, e.g.
+    /// `jpg,png`); checked on the file name before any stat or glob matching
+    #[arg(long, value_name = "EXTS", value_delimiter = ',')]
+    pub ext: Vec<String>,
+
+    /// Rescan every N seconds, rewriting the output and logging what changed since the
+    /// previous scan (for mounts where change notifications are unreliable)
+    #[arg(long, value_name = "SECONDS", conflicts_with = "changed_from")]
+    pub interval: Option<u64>,
+
+    /// Stop after this many scans when --interval is set (default: run until interrupted)
+    #[arg(long = "max-cycles", value_name = "N", requires = "interval")]
+    pub max_cycles: Option<u64>,
+
+    /// Accept input maps that list the same path more than once (warn instead of failing)
+    #[arg(long = "allow-duplicate-paths")]
+    pub allow_duplicate_paths: bool,
+
+    /// Record platform-neutral paths (forward slashes, no drive/UNC prefix) and omit the absolute root
+    #[arg(long = "portable-paths")]
+    pub portable_paths: bool,
+
+    /// Line layout of sums files (`--format sums`, --per-dir-output): gnu (default,
+    /// `<hash>  <path>`) or bsd (`BLAKE3 (<path>) = <hash>`, as `*sum --tag` writes)
+    #[arg(long = "checksum-style", value_name = "STYLE")]
+    pub checksum_style: Option<String>,
+
+    /// Also write a coreutils-style sums file with this name into every directory, listing
+    /// that directory's own files (the map itself is then only written with --output)
+    #[arg(long = "per-dir-output")]
+    pub per_dir_output: Option<String>,
+
+    /// Print only the Merkle root of the map (a one-line fingerprint of the tree) instead
+    /// of the map itself
+    #[arg(long = "print-root", conflicts_with_all = ["output", "per_dir_output"])]
+    pub print_root: bool,
+
+    /// Write JSON Lines events (scan_started, file_hashed, error, scan_completed) to this file
+    #[arg(long = "events-file", conflicts_with = "events_fd")]
+    pub events_file: Option<PathBuf>,
+
+    /// Write the JSON Lines event stream to this already-open file descriptor (unix)
+    #[arg(long = "events-fd")]
+    pub events_fd: Option<i32>,
+
+    /// Record canonical absolute paths instead of root-relative ones (not portable across machines)
+    #[arg(
+        long = "absolute-paths",
+        conflicts_with_all = ["strip_prefix", "portable_paths"]
+    )]
+    pub absolute_paths: bool,
+
+    /// On Windows, access files through `\\?\` extended-length paths to get past the
+    /// 260-character limit (no effect elsewhere)
+    #[arg(long = "long-paths")]
+    pub long_paths: bool,
+
+    /// Record paths and sizes only, without reading file contents (size stands in for the hash)
+    #[arg(
+        long = "structure-only",
+        conflicts_with_all = ["io_threads", "hash_threads", "allow_special"]
+    )]
+    pub structure_only: bool,
+
+    /// Allow `--path` to be a non-regular file (block/char device, FIFO) and hash it by reading until EOF
+    #[arg(long = "allow-special")]
+    pub allow_special: bool,
+
+    /// Record the inode of hard-linked files so reports count them as one physical file (Unix)
+    #[arg(long = "coalesce-hardlinks")]
+    pub coalesce_hardlinks: bool,
+
+    /// Abort on the first file that can't be read or hashed (exit non-zero, no map written)
+    #[arg(long = "fail-fast")]
+    pub fail_fast: bool,
+
+    /// Abort (exit non-zero, no map written) once more than <N> files can't be read or hashed
+    #[arg(long = "max-errors", value_name = "N")]
+    pub max_errors: Option<u64>,
+
+    /// Abort (exit non-zero, no map written) once more than <PCT> percent of the files
+    /// can't be read or hashed
+    #[arg(long = "max-error-rate", value_name = "PCT")]
+    pub max_error_rate: Option<f64>,
+
+    /// Number of slowest files to log after hashing (0 disables the list)
+    #[arg(long = "show-slowest", value_name = "N", default_value_t = 5)]
+    pub show_slowest: usize,
+
+    /// Write every file's hashing time, slowest first, to this JSON file
+    #[arg(long = "show-slowest-json", value_name = "PATH")]
+    pub show_slowest_json: Option<PathBuf>,
+
+    /// Write a JSON summary of the run (files, bytes, elapsed time, errors, algorithm and
+    /// memory plan the run used) to this file, whatever the map's format or destination,
+    /// once the map has been written
+    #[arg(long = "run-summary", value_name = "PATH")]
+    pub run_summary: Option<PathBuf>,
+
+    /// Also write `<output>.<alg>`, a sums line with the digest of the map file itself
+    /// (default algorithm: the map's). The old sidecar is removed before the map is
+    /// replaced, so a sidecar that exists always matches the map next to it
+    #[arg(
+        long = "with-checksum",
+        value_name = "ALG",
+        num_args = 0..=1,
+        default_missing_value = "",
+        requires = "output"
+    )]
+    pub with_checksum: Option<String>,
+
+    /// Permissions for the written map file as an octal mode, e.g. 600 (Unix; default keeps the umask)
+    #[arg(long = "output-mode", value_name = "OCTAL")]
+    pub output_mode: Option<String>,
 }
 
 #[derive(Args, Debug)]
@@ -126,6 +405,62 @@ pub struct CompareArgs {
     /// Hash algorithm to use when hashing directories
     #[arg(long, short('a'))]
     pub algorithm: Option<String>,
+
+    /// Also report groups of source paths sharing a hash (target defaults to the source)
+    #[arg(long = "self-dupes")]
+    pub self_dupes: bool,
+
+    /// Merge-join two path-sorted map files (NDJSON or CSV) in bounded memory, writing
+    /// rows as they are classified (json output becomes one object per line)
+    #[arg(long = "streaming", conflicts_with = "self_dupes")]
+    pub streaming: bool,
+
+    /// Accept input maps that list the same path more than once (warn instead of failing)
+    #[arg(long = "allow-duplicate-paths")]
+    pub allow_duplicate_paths: bool,
+
+    /// When a map file records a different algorithm than --algorithm: `error` (default)
+    /// or `rehash` the directory side with the map's algorithm
+    #[arg(long = "algorithm-mismatch", value_name = "POLICY")]
+    pub algorithm_mismatch: Option<String>,
+
+    /// Compare map files even when their header fingerprints (algorithm settings and
+    /// tool version) differ
+    #[arg(long = "force-compare")]
+    pub force_compare: bool,
+
+    /// Only report these categories (identical, changed, moved, missing, new); default is all
+    #[arg(long, value_delimiter = ',')]
+    pub only: Vec<String>,
+    /// Convert backslashes in loaded map paths to `/` so maps written on Windows line up
+    /// with maps from other platforms
+    #[arg(long = "normalize-separators", conflicts_with = "streaming")]
+    pub normalize_separators: bool,
+    /// Compare only the first N bytes of each digest, for maps of a prefix-stable XOF
+    /// (e.g. blake3) written at different lengths
+    #[arg(
+        long = "compare-prefix-bytes",
+        value_name = "N",
+        conflicts_with = "streaming"
+    )]
+    pub compare_prefix_bytes: Option<usize>,
+    /// Write each non-empty category to its own file (`missing.csv`, `new.csv`, ...) in DIR
+    #[arg(
+        long = "split-output",
+        value_name = "DIR",
+        conflicts_with_all = ["output", "streaming"]
+    )]
+    pub split_output: Option<PathBuf>,
+    /// Emit a git-style diff for each changed pair, with content hunks when both files
+    /// are on disk
+    #[arg(long = "git-diff", conflicts_with = "streaming")]
+    pub git_diff: bool,
+    /// Number of context lines to include around each hunk in `--git-diff`
+    #[arg(long = "git-diff-context", default_value_t = 3)]
+    pub git_diff_context: usize,
+    /// Write git-diff output to a file instead of stdout
+    #[arg(long = "git-diff-output", requires = "git_diff")]
+    pub git_diff_output: Option<PathBuf>,
 }
 
 #[derive(Args, Debug)]
@@ -141,6 +476,24 @@ pub struct CopydiffArgs {
     /// Show what would be done without executing
     #[arg(long = "dry-run")]
     pub dry_run: bool,
+    /// Print only a one-line count of the plan's operations instead of listing them:
+    /// `text` (default, `plan: N copy, N delete, N skip, N bytes`) or `json`
+    #[arg(
+        long,
+        value_name = "FORMAT",
+        num_args = 0..=1,
+        default_missing_value = "text",
+        conflicts_with = "execute"
+    )]
+    pub summary: Option<String>,
+    /// Check an already executed --plan without copying: hash each destination, compare it
+    /// with its source and report ok/mismatch/missing (non-zero exit on any problem)
+    #[arg(
+        long = "verify-only",
+        requires = "plan",
+        conflicts_with_all = ["execute", "summary", "resume"]
+    )]
+    pub verify_only: bool,
     /// When showing a dry-run or run summary, emit a git-style diff for each planned operation
     #[arg(long = "git-diff")]
     pub git_diff: bool,
@@ -168,13 +521,34 @@ pub struct CopydiffArgs {
     #[arg(long, short('a'))]
     pub algorithm: Option<String>,
 
-    /// Conflict handling strategy (overwrite, skip, rename)
-    #[arg(long = "conflict", default_value = "overwrite")]
+    /// Conflict handling strategy (overwrite, skip, rename, prompt)
+    #[arg(
+        long = "conflict",
+        visible_alias = "on-conflict",
+        default_value = "overwrite"
+    )]
     pub conflict: String,
 
+    /// Strategy used by `--conflict prompt` when not attached to a terminal (overwrite, skip, rename)
+    #[arg(long = "conflict-fallback", default_value = "skip")]
+    pub conflict_fallback: String,
+
     /// Preserve file modification times when copying
     #[arg(long = "preserve-times")]
     pub preserve_times: bool,
+
+    /// Re-apply the source's permission bits to each copy (the copy already carries them;
+    /// this also restores setuid/setgid after --preserve-owner)
+    #[arg(long = "preserve-perms")]
+    pub preserve_perms: bool,
+
+    /// Give each copy the source's owner and group (Unix, best effort; needs privileges)
+    #[arg(long = "preserve-owner")]
+    pub preserve_owner: bool,
+
+    /// Accept input maps that list the same path more than once (warn instead of failing)
+    #[arg(long = "allow-duplicate-paths")]
+    pub allow_duplicate_paths: bool,
 }
 
 #[derive(Args, Debug)]
@@ -194,6 +568,9 @@ pub struct RemovemptyArgs {
     /// Directory exclusion patterns
     #[arg(long, value_delimiter = ',')]
     pub exclude: Vec<String>,
+    /// Leave directories on other filesystems untouched (Unix only, like `find -xdev`)
+    #[arg(long = "one-file-system")]
+    pub one_file_system: bool,
     /// Emit git-style diff entries for removals when performing a dry-run or run
     #[arg(long = "git-diff")]
     pub git_diff: bool,
@@ -206,6 +583,9 @@ pub struct RemovemptyArgs {
     /// Write git-diff output to a file instead of stdout
     #[arg(long = "git-diff-output")]
     pub git_diff_output: Option<PathBuf>,
+    /// Abort on the first directory that can't be read or removed (default: warn and continue)
+    #[arg(long)]
+    pub strict: bool,
 }
 
 #[derive(Args, Debug)]
@@ -243,9 +623,17 @@ pub struct RenamerArgs {
     #[arg(long = "git-diff-output")]
     pub git_diff_output: Option<PathBuf>,
 
+    /// When a target already exists, hash it and the source with this algorithm and
+    /// delete the source if both match (otherwise the rename is skipped as usual)
+    #[arg(long = "dedupe-on-collision", value_name = "ALG")]
+    pub dedupe_on_collision: Option<String>,
+
     /// Don't actually rename, just show what would be renamed
     #[arg(long = "dry-run")]
     pub dry_run: bool,
+    /// Don't print the final summary line
+    #[arg(long)]
+    pub silent: bool,
 }
 
 #[derive(Args, Debug)]
@@ -265,15 +653,111 @@ pub struct ReportArgs {
     #[arg(long)]
     pub input: Option<PathBuf>,
 
-    /// Output format (json/csv)
+    /// Output format (text/json/html)
     #[arg(long)]
     pub format: Option<String>,
 
-    /// Sections to include (comma-separated: stats,duplicates,largest)
+    /// Sections to include (comma-separated: stats,duplicates,largest,dir-summary)
     #[arg(long, value_delimiter = ',')]
     pub include: Vec<String>,
 
     /// Number of entries for top lists
     #[arg(long = "top-n")]
     pub top_n: Option<usize>,
+
+    /// Keep one file per duplicate group and delete the rest (dry run unless --yes)
+    #[arg(long = "dedupe-execute")]
+    pub dedupe_execute: bool,
+
+    /// Which copy survives --dedupe-execute (first/newest/oldest/shortest-path)
+    #[arg(long, requires = "dedupe_execute")]
+    pub keep: Option<String>,
+
+    /// Replace duplicates with hard links to the kept copy instead of deleting them
+    #[arg(long, requires = "dedupe_execute")]
+    pub hardlink: bool,
+
+    /// Actually apply --dedupe-execute (without it only the plan is printed)
+    #[arg(long, requires = "dedupe_execute")]
+    pub yes: bool,
+
+    /// Directory the map's relative paths live under (defaults to the map's `root`)
+    #[arg(long, requires = "dedupe_execute")]
+    pub root: Option<PathBuf>,
+
+    /// Older map to diff `--input` against; prints a churn summary instead of a map report
+    #[arg(long)]
+    pub since: Option<PathBuf>,
+
+    /// Group zero-byte files in the duplicates section (excluded by default)
+    #[arg(long = "include-empty")]
+    pub include_empty: bool,
+
+    /// Accept input maps that list the same path more than once (warn instead of failing)
+    #[arg(long = "allow-duplicate-paths")]
+    pub allow_duplicate_paths: bool,
+
+    /// When --input is a directory, only hash files sharing these keys (size[,mtime])
+    #[arg(long = "dedup-by", value_delimiter = ',')]
+    pub dedup_by: Vec<String>,
+
+    /// Show sizes in text output as KiB/MiB/GiB instead of exact byte counts. JSON
+    /// always keeps raw integers
+    #[arg(long)]
+    pub human: bool,
+
+    /// Count double extensions such as `.tar.gz` and `.tar.zst` as one type in top extensions
+    #[arg(long = "compound-ext")]
+    pub compound_ext: bool,
+
+    /// Only list duplicate groups with at least this many copies
+    #[arg(long = "min-dupe-copies", value_name = "N")]
+    pub min_dupe_copies: Option<u64>,
+
+    /// Only list duplicate groups whose files are at least this many bytes each
+    #[arg(long = "min-dupe-size", value_name = "BYTES")]
+    pub min_dupe_size: Option<u64>,
+
+    /// Print the map's paths as an indented directory tree with sizes and hashes
+    #[arg(long, conflicts_with_all = ["since", "dedupe_execute"])]
+    pub tree: bool,
+
+    /// Print only duplicate groups, every one of them. On a directory, only files whose
+    /// size another file shares are hashed, and unique files are never held in memory
+    #[arg(
+        long = "only-duplicates",
+        conflicts_with_all = ["since", "dedupe_execute", "tree"]
+    )]
+    pub only_duplicates: bool,
+}
+
+#[cfg(feature = "url")]
+#[derive(Args, Debug)]
+pub struct HashArgs {
+    /// HTTP(S) URL whose response body is hashed (redirects are followed)
+    #[arg(long)]
+    pub url: String,
+
+    /// Hash algorithm (default blake3)
+    #[arg(long, short('a'))]
+    pub algorithm: Option<String>,
+}
+
+#[derive(Args, Debug)]
+pub struct VerifyMapArgs {
+    /// Map file to check (JSON or CSV)
+    #[arg(long, short('i'))]
+    pub input: Option<PathBuf>,
+
+    /// Also rehash the files under this directory and compare them with the map
+    #[arg(long, short('p'))]
+    pub path: Option<PathBuf>,
+
+    /// Algorithm for --path when the map header doesn't record one (default: blake3)
+    #[arg(long, requires = "path")]
+    pub algorithm: Option<String>,
+
+    /// Report files whose size differs from the map as modified without hashing them
+    #[arg(long = "rehash-on-size-change-only", requires = "path")]
+    pub rehash_on_size_change_only: bool,
 }
@@ -1,15 +1,14 @@
+use std::collections::{HashMap, HashSet};
 use std::io::Write;
 use std::path::{Path, PathBuf};
-use std::sync::{Arc, Mutex};
 
 use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
 
-use crate::algorithms::Algorithm;
-use crate::hash::hash_path_with_pool;
+use crate::algorithms::{Algorithm, AlgorithmParams};
 use crate::io;
-use crate::memory::MemoryMode;
-use crate::pipeline::Pipeline;
+use crate::map::{hash_directory, HashOptions};
+use crate::report::DuplicateGroup;
 
 /// Comparison report describing differences between two maps.
 #[derive(Debug, Serialize, Deserialize)]
@@ -21,6 +20,9 @@ pub struct ComparisonReport {
     pub moved: Vec<(io::MapEntry, io::MapEntry)>,
     pub missing: Vec<io::MapEntry>, // in source but not in target
     pub new: Vec<io::MapEntry>,     // in target but not in source
+    /// Groups of source paths sharing a hash (only filled by `compare --self-dupes`)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub self_duplicates: Vec<DuplicateGroup>,
 }
 
 impl ComparisonReport {
@@ -31,6 +33,7 @@ impl ComparisonReport {
             moved: Vec::new(),
             missing: Vec::new(),
             new: Vec::new(),
+            self_duplicates: Vec::new(),
         }
     }
 }
@@ -41,10 +44,292 @@ impl Default for ComparisonReport {
     }
 }
 
+/// Report sections that `compare --only` can select.
+pub const CATEGORIES: &[&str] = &["identical", "changed", "moved", "missing", "new"];
+
+/// Resolve `--only` names (case-insensitive) to entries of [`CATEGORIES`].
+/// An empty list selects every category.
+pub fn parse_categories(only: &[String]) -> Result<Vec<&'static str>> {
+    if only.is_empty() {
+        return Ok(CATEGORIES.to_vec());
+    }
+    let mut selected = Vec::new();
+    for name in only {
+        let name = name.trim().to_lowercase();
+        let category = CATEGORIES.iter().find(|c| **c == name).ok_or_else(|| {
+            anyhow::anyhow!(
+                "unknown --only category {:?} (expected one of {})",
+                name,
+                CATEGORIES.join(", ")
+            )
+        })?;
+        if !selected.contains(category) {
+            selected.push(*category);
+        }
+    }
+    Ok(selected)
+}
+
+/// What `compare` does when a map file records a different algorithm than the one
+/// requested for hashing the directory side.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AlgorithmMismatch {
+    /// Refuse to compare (the default).
+    Error,
+    /// Hash the directory side with the map's algorithm instead.
+    Rehash,
+}
+
+impl AlgorithmMismatch {
+    pub fn from_name(s: &str) -> Option<Self> {
+        match s.to_lowercase().as_str() {
+            "error" => Some(AlgorithmMismatch::Error),
+            "rehash" => Some(AlgorithmMismatch::Rehash),
+            _ => None,
+        }
+    }
+}
+
+/// How directory inputs are hashed so their digests line up with a map being compared:
+/// the algorithm together with the parameters and digest length the map was made with.
+#[derive(Debug, Clone)]
+pub struct DirectoryHashing {
+    pub algorithm: Algorithm,
+    pub params: AlgorithmParams,
+    /// Digest length in bytes (defaults to the algorithm's own).
+    pub output_len: Option<usize>,
+}
+
+impl DirectoryHashing {
+    /// Hash with `algorithm`'s default parameters and digest length.
+    pub fn new(algorithm: Algorithm) -> Self {
+        Self {
+            algorithm,
+            params: AlgorithmParams::default(),
+            output_len: None,
+        }
+    }
+}
+
+/// Settings to hash directory inputs with, checked against the algorithm recorded in
+/// the header of any map file among `inputs`. Without `requested` the map's algorithm
+/// is used; two maps recording different algorithms can never be compared. Whenever
+/// the map's algorithm is used, so are its recorded parameters and digest length.
+pub fn resolve_algorithm(
+    inputs: &[&Path],
+    requested: Option<Algorithm>,
+    on_mismatch: AlgorithmMismatch,
+) -> Result<DirectoryHashing> {
+    let mut recorded: Option<(DirectoryHashing, &Path)> = None;
+    let mut has_dir = false;
+    for input in inputs {
+        if input.is_dir() {
+            has_dir = true;
+            continue;
+        }
+        if !input.is_file() {
+            continue;
+        }
+        let settings = match crate::verify::load_map_algorithm(input)? {
+            Some((name, params)) => {
+                Algorithm::from_name(&name).map(|algorithm| (algorithm, params))
+            }
+            None => None,
+        };
+        match (settings, &recorded) {
+            (Some((alg, _)), Some((prev, prev_path))) if alg.name() != prev.algorithm.name() => {
+                anyhow::bail!(
+                    "{} was hashed with {} but {} with {}; the maps cannot be compared",
+                    prev_path.display(),
+                    prev.algorithm.name(),
+                    input.display(),
+                    alg.name()
+                )
+            }
+            (Some((algorithm, params)), None) => {
+                let output_len = crate::verify::load_map_output_len(input)?;
+                recorded = Some((
+                    DirectoryHashing {
+                        algorithm,
+                        params,
+                        output_len,
+                    },
+                    input,
+                ))
+            }
+            _ => {}
+        }
+    }
+
+    match (recorded, requested) {
+        (None, requested) => Ok(DirectoryHashing::new(
+            requested.unwrap_or(Algorithm::Blake3),
+        )),
+        (Some((map, _)), None) => Ok(map),
+        (Some((map, _)), Some(req)) if req.name() == map.algorithm.name() => Ok(map),
+        (Some(_), Some(req)) if !has_dir => Ok(DirectoryHashing::new(req)),
+        (Some((map, map_path)), Some(req)) => match on_mismatch {
+            AlgorithmMismatch::Error => anyhow::bail!(
+                "{} was hashed with {} but --algorithm {} was given for the directory side \
+                 (use --algorithm-mismatch rehash to hash it with {})",
+                map_path.display(),
+                map.algorithm.name(),
+                req.name(),
+                map.algorithm.name()
+            ),
+            AlgorithmMismatch::Rehash => {
+                log::warn!(
+                    "{} was hashed with {}; hashing the directory side with {} instead of {}",
+                    map_path.display(),
+                    map.algorithm.name(),
+                    map.algorithm.name(),
+                    req.name()
+                );
+                Ok(map)
+            }
+        },
+    }
+}
+
+/// Refuse to compare two map files whose header fingerprints differ (see
+/// [`io::map_fingerprint`]); with `force` only warn. Inputs without a fingerprint
+/// (directories, CSV maps, maps from older versions) are not checked.
+pub fn check_fingerprints(source: &Path, target: &Path, force: bool) -> Result<()> {
+    match (map_fingerprint(source)?, map_fingerprint(target)?) {
+        (Some(src), Some(tgt)) if src != tgt => {
+            fingerprint_mismatch(source, &src, target, &tgt, force)
+        }
+        _ => Ok(()),
+    }
+}
+
+/// [`check_fingerprints`] for `--compare-prefix-bytes`, where the maps' digest lengths
+/// (`source_len` and `target_len` bytes) may differ. Differing fingerprints are
+/// accepted only when each is reproduced by its own header's algorithm settings at its
+/// own length and those settings are the same on both sides.
+pub fn check_prefix_fingerprints(
+    source: &Path,
+    source_len: usize,
+    target: &Path,
+    target_len: usize,
+    force: bool,
+) -> Result<()> {
+    let (src, tgt) = match (map_fingerprint(source)?, map_fingerprint(target)?) {
+        (Some(src), Some(tgt)) if src != tgt => (src, tgt),
+        _ => return Ok(()),
+    };
+    // the algorithm settings a fingerprint was derived from, if its header names them
+    let settings = |path: &Path, fingerprint: &str, len: usize| -> Result<Option<_>> {
+        Ok(crate::verify::load_map_algorithm(path)?
+            .filter(|(name, params)| io::map_fingerprint(name, len, params) == fingerprint))
+    };
+    match (
+        settings(source, &src, source_len)?,
+        settings(target, &tgt, target_len)?,
+    ) {
+        (Some((src_name, src_params)), Some((tgt_name, tgt_params)))
+            if src_name.eq_ignore_ascii_case(&tgt_name)
+                && src_params.block_size == tgt_params.block_size
+                && src_params.customization == tgt_params.customization =>
+        {
+            Ok(())
+        }
+        _ => fingerprint_mismatch(source, &src, target, &tgt, force),
+    }
+}
+
+/// The fingerprint recorded in `path`'s header; `None` for directories and maps without one.
+fn map_fingerprint(path: &Path) -> Result<Option<String>> {
+    if path.is_file() {
+        io::load_map_fingerprint(path)
+    } else {
+        Ok(None)
+    }
+}
+
+fn fingerprint_mismatch(
+    source: &Path,
+    src: &str,
+    target: &Path,
+    tgt: &str,
+    force: bool,
+) -> Result<()> {
+    let message = format!(
+        "{} (fingerprint {}) and {} (fingerprint {}) were hashed with different algorithm \
+         settings or tool versions, so their digests can't be compared",
+        source.display(),
+        src,
+        target.display(),
+        tgt
+    );
+    if !force {
+        anyhow::bail!("{}; pass --force-compare to compare them anyway", message);
+    }
+    log::warn!("{}; comparing anyway (--force-compare)", message);
+    Ok(())
+}
+
+/// Whether `input` names an `http://` or `https://` URL rather than a local path.
+pub fn is_url(input: &str) -> bool {
+    let lower = input.get(..8).unwrap_or(input).to_ascii_lowercase();
+    lower.starts_with("http://") || lower.starts_with("https://")
+}
+
+/// A compare/copydiff input made local: an `http(s)://` URL is downloaded to a
+/// temporary map file (removed on drop) so its header can be checked like any other.
+pub struct LocalInput {
+    path: String,
+    #[cfg(feature = "url")]
+    _staged: Option<tempfile::NamedTempFile>,
+}
+
+impl LocalInput {
+    /// Path of the local directory or map file.
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+}
+
+/// Download `input` when it is a URL (see [`crate::remote::stage_map`]); other inputs
+/// are returned unchanged.
+pub fn localize_input(input: &str) -> Result<LocalInput> {
+    if !is_url(input) {
+        return Ok(LocalInput {
+            path: input.to_string(),
+            #[cfg(feature = "url")]
+            _staged: None,
+        });
+    }
+    #[cfg(feature = "url")]
+    {
+        let staged = crate::remote::stage_map(input)?;
+        log::info!("Fetched {} to {}", input, staged.path().display());
+        Ok(LocalInput {
+            path: staged.path().to_string_lossy().into_owned(),
+            _staged: Some(staged),
+        })
+    }
+    #[cfg(not(feature = "url"))]
+    anyhow::bail!(
+        "{} is a URL; remote maps need a build with the `url` feature",
+        input
+    );
+}
+
 /// Load a map from either a file (json/csv) or by hashing a directory.
-/// `input` may be a path to a file (json/csv) or a directory.
-/// When hashing a directory the provided `algorithm` is used with balanced memory mode.
-pub fn get_map_from_input(input: &str, algorithm: Algorithm) -> Result<Vec<io::MapEntry>> {
+/// `input` may be a path to a file (json/csv), a directory, or (with the `url` feature)
+/// an `http(s)://` URL of a map.
+/// When hashing a directory the settings in `hashing` are used with balanced memory mode.
+pub fn get_map_from_input(input: &str, hashing: &DirectoryHashing) -> Result<Vec<io::MapEntry>> {
+    if is_url(input) {
+        #[cfg(feature = "url")]
+        return crate::remote::fetch_map(input);
+        #[cfg(not(feature = "url"))]
+        anyhow::bail!(
+            "{} is a URL; remote maps need a build with the `url` feature",
+            input
+        );
+    }
     let p = Path::new(input);
 
     if p.exists() && p.is_file() {
@@ -58,11 +343,22 @@ pub fn get_map_from_input(input: &str, algorithm: Algorithm) -> Result<Vec<io::M
                 "csv" => {
                     return io::load_map_from_csv(p).with_context(|| format!("loading csv {:?}", p))
                 }
+                "ndjson" | "jsonl" => {
+                    return io::load_map_from_ndjson(p)
+                        .with_context(|| format!("loading ndjson {:?}", p))
+                }
+                "sums" => {
+                    return io::load_map_from_sums(p)
+                        .with_context(|| format!("loading sums {:?}", p))
+                }
                 _ => {}
             }
         }
 
-        // Fallback: try json then csv
+        // Fallback: sniff sums files (`SHA256SUMS`, `CHECKSUMS.txt`), then try json then csv
+        if io::looks_like_sums(p) {
+            return io::load_map_from_sums(p).with_context(|| format!("loading sums {:?}", p));
+        }
         if let Ok(m) = io::load_map_from_json(p) {
             return Ok(m);
         }
@@ -74,55 +370,39 @@ pub fn get_map_from_input(input: &str, algorithm: Algorithm) -> Result<Vec<io::M
     }
 
     if p.exists() && p.is_dir() {
-        // Hash the directory using pipeline similar to hashmap command.
-        let alg = algorithm;
-        let probe = alg.create();
-        let out_len = probe.info().output_len_default;
-
-        let pipeline = Pipeline::new(MemoryMode::Balanced);
-
-        let entries: Arc<Mutex<Vec<io::MapEntry>>> = Arc::new(Mutex::new(Vec::new()));
-        let entries_clone = entries.clone();
-
-        let alg_for_worker = alg;
-        let worker = move |path_buf: PathBuf,
-                           buffer_pool: Arc<crate::memory::BufferPool>|
-              -> anyhow::Result<()> {
-            if !path_buf.is_file() {
-                return Ok(());
-            }
-            let rel = path_buf.to_string_lossy().into_owned();
-            let metadata = path_buf.metadata().ok();
-            let size = metadata.as_ref().map(|m| m.len()).unwrap_or(0);
-            let mtime = metadata
-                .as_ref()
-                .and_then(|m| m.modified().ok())
-                .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
-                .map(|dur| dur.as_secs() as i64);
-            let mut hasher = alg_for_worker.create();
-            hash_path_with_pool(hasher.as_mut(), &path_buf, &buffer_pool)?;
-            let h = hasher.finalize_hex(out_len);
-            let me = io::MapEntry {
-                path: rel,
-                hash: h,
-                size,
-                mtime,
-            };
-            let mut guard = entries_clone.lock().unwrap();
-            guard.push(me);
-            Ok(())
-        };
+        // Canonical so the recorded paths are absolute and resolve from anywhere
+        let root = p
+            .canonicalize()
+            .with_context(|| format!("resolve {:?}", p))?;
+        return hash_directory(
+            &root,
+            &HashOptions {
+                algorithm: hashing.algorithm,
+                params: hashing.params.clone(),
+                output_len: hashing.output_len,
+                show_progress: true,
+                absolute_paths: true,
+                ..Default::default()
+            },
+        );
+    }
 
-        pipeline
-            .run(p, &[], None, false, true, worker)
-            .context("running pipeline to build map")?;
+    anyhow::bail!("input path does not exist: {}", input);
+}
 
-        let mut vec = entries.lock().unwrap().clone();
-        vec.sort_by(|a, b| a.path.cmp(&b.path));
-        return Ok(vec);
+/// Key under which entries are matched by content: the lowercased digest, kept apart
+/// for symlinks hashed by their target path so a link never pairs with a regular file
+/// whose contents happen to hash the same.
+fn content_key(e: &io::MapEntry) -> String {
+    match e.kind {
+        Some(io::EntryKind::Symlink) => format!("symlink:{}", e.hash.to_ascii_lowercase()),
+        None => e.hash.to_ascii_lowercase(),
     }
+}
 
-    anyhow::bail!("input path does not exist: {}", input);
+/// Whether two entries for the same path hold the same content (see [`content_key`]).
+fn same_content(a: &io::MapEntry, b: &io::MapEntry) -> bool {
+    a.kind == b.kind && a.hash.eq_ignore_ascii_case(&b.hash)
 }
 
 /// Compare two maps (source and target) and produce a ComparisonReport.
@@ -130,12 +410,16 @@ pub fn get_map_from_input(input: &str, algorithm: Algorithm) -> Result<Vec<io::M
 /// Rules:
 /// - Identical: same path present in both with same hash
 /// - Changed: same path present in both with different hash
-/// - Moved: same hash present in both but different paths (pair source->target)
+/// - Moved: same hash present in both but different paths (pair source->target).
+///   Sources are paired in path order, each with the lexicographically smallest target
+///   path of that hash not already claimed (by an earlier pair or by a source at the
+///   same path); when every candidate is claimed the smallest one is reused.
 /// - Missing: entry present in source but its hash not present in target and path not present
 /// - New: entry present in target but its hash not present in source and path not present
+///
+/// Symlinks recorded by target path (`kind: symlink`) only match other symlinks: a link
+/// replaced by a file at the same path is changed, and a link is never a move of a file.
 pub fn compare_maps(source: Vec<io::MapEntry>, target: Vec<io::MapEntry>) -> ComparisonReport {
-    use std::collections::HashMap;
-
     let mut report = ComparisonReport::new();
 
     let mut src_by_path: HashMap<String, io::MapEntry> = HashMap::new();
@@ -143,35 +427,51 @@ pub fn compare_maps(source: Vec<io::MapEntry>, target: Vec<io::MapEntry>) -> Com
     let mut src_by_hash: HashMap<String, Vec<io::MapEntry>> = HashMap::new();
     let mut tgt_by_hash: HashMap<String, Vec<io::MapEntry>> = HashMap::new();
 
+    // Hex digests are compared case-insensitively so upper- and lowercase maps line up
     for e in source.into_iter() {
         src_by_path.insert(e.path.clone(), e.clone());
-        src_by_hash.entry(e.hash.clone()).or_default().push(e);
+        src_by_hash.entry(content_key(&e)).or_default().push(e);
     }
     for e in target.into_iter() {
         tgt_by_path.insert(e.path.clone(), e.clone());
-        tgt_by_hash.entry(e.hash.clone()).or_default().push(e);
+        tgt_by_hash.entry(content_key(&e)).or_default().push(e);
+    }
+
+    // Candidates for a move are tried smallest path first, so the pairing doesn't
+    // depend on input order
+    for tgts in tgt_by_hash.values_mut() {
+        tgts.sort_by(|a, b| a.path.cmp(&b.path));
     }
+    let mut src_paths: Vec<&String> = src_by_path.keys().collect();
+    src_paths.sort();
 
     // Track which target paths have been accounted for (to avoid double counting as new)
     let mut accounted_target_paths: std::collections::HashSet<String> =
         std::collections::HashSet::new();
 
-    // Process source entries
-    for (path, src_entry) in &src_by_path {
-        if let Some(tgt_entry) = tgt_by_path.get(path) {
-            if src_entry.hash == tgt_entry.hash {
+    // Same-path entries first, so a move never claims a target its own source matches
+    for path in &src_paths {
+        let src_entry = &src_by_path[*path];
+        if let Some(tgt_entry) = tgt_by_path.get(*path) {
+            if same_content(src_entry, tgt_entry) {
                 report.identical.push(src_entry.clone());
-                accounted_target_paths.insert(tgt_entry.path.clone());
             } else {
                 report.changed.push((src_entry.clone(), tgt_entry.clone()));
-                accounted_target_paths.insert(tgt_entry.path.clone());
             }
+            accounted_target_paths.insert(tgt_entry.path.clone());
+        }
+    }
+
+    // Process the remaining source entries
+    for path in &src_paths {
+        if tgt_by_path.contains_key(*path) {
             continue;
         }
+        let src_entry = &src_by_path[*path];
 
         // No same path in target. If same hash exists somewhere in target -> moved
-        if let Some(tgts) = tgt_by_hash.get(&src_entry.hash) {
-            // choose the first matching target entry that hasn't been accounted for yet if possible
+        if let Some(tgts) = tgt_by_hash.get(&content_key(src_entry)) {
+            // choose the smallest-path target entry not yet accounted for, if there is one
             let mut chosen: Option<io::MapEntry> = None;
             for te in tgts {
                 if !accounted_target_paths.contains(&te.path) {
@@ -197,13 +497,16 @@ pub fn compare_maps(source: Vec<io::MapEntry>, target: Vec<io::MapEntry>) -> Com
     }
 
     // Process target entries to find new files that weren't matched above
-    for (path, tgt_entry) in &tgt_by_path {
+    let mut tgt_paths: Vec<&String> = tgt_by_path.keys().collect();
+    tgt_paths.sort();
+    for path in tgt_paths {
+        let tgt_entry = &tgt_by_path[path];
         if accounted_target_paths.contains(path) {
             continue;
         }
 
         // If target hash exists in source_by_hash then it was already handled as moved (but maybe not accounted)
-        if let Some(_srcs) = src_by_hash.get(&tgt_entry.hash) {
+        if let Some(_srcs) = src_by_hash.get(&content_key(tgt_entry)) {
             // If none of the source paths matched this target path, consider it moved and add pair(s)
             // We skip adding moved here to avoid duplicating; the moved pairs were added when iterating source.
             accounted_target_paths.insert(tgt_entry.path.clone());
@@ -218,6 +521,377 @@ pub fn compare_maps(source: Vec<io::MapEntry>, target: Vec<io::MapEntry>) -> Com
     report
 }
 
+/// One classified row produced by [`compare_sorted_maps`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CompareRow {
+    Identical(io::MapEntry),
+    /// (source, target)
+    Changed(io::MapEntry, io::MapEntry),
+    /// (source, target) -- same hash, different path
+    Moved(io::MapEntry, io::MapEntry),
+    Missing(io::MapEntry),
+    New(io::MapEntry),
+}
+
+impl CompareRow {
+    pub fn status(&self) -> &'static str {
+        match self {
+            CompareRow::Identical(_) => "identical",
+            CompareRow::Changed(..) => "changed",
+            CompareRow::Moved(..) => "moved",
+            CompareRow::Missing(_) => "missing",
+            CompareRow::New(_) => "new",
+        }
+    }
+
+    pub fn source(&self) -> Option<&io::MapEntry> {
+        match self {
+            CompareRow::Identical(s) | CompareRow::Missing(s) => Some(s),
+            CompareRow::Changed(s, _) | CompareRow::Moved(s, _) => Some(s),
+            CompareRow::New(_) => None,
+        }
+    }
+
+    pub fn target(&self) -> Option<&io::MapEntry> {
+        match self {
+            CompareRow::Identical(t) | CompareRow::New(t) => Some(t),
+            CompareRow::Changed(_, t) | CompareRow::Moved(_, t) => Some(t),
+            CompareRow::Missing(_) => None,
+        }
+    }
+}
+
+impl ComparisonReport {
+    /// Empty every section whose name is not in `categories`.
+    pub fn retain_categories(&mut self, categories: &[&str]) {
+        if !categories.contains(&"identical") {
+            self.identical.clear();
+        }
+        if !categories.contains(&"changed") {
+            self.changed.clear();
+        }
+        if !categories.contains(&"moved") {
+            self.moved.clear();
+        }
+        if !categories.contains(&"missing") {
+            self.missing.clear();
+        }
+        if !categories.contains(&"new") {
+            self.new.clear();
+        }
+    }
+
+    /// Add a streamed row to the matching section.
+    pub fn push(&mut self, row: CompareRow) {
+        match row {
+            CompareRow::Identical(e) => self.identical.push(e),
+            CompareRow::Changed(s, t) => self.changed.push((s, t)),
+            CompareRow::Moved(s, t) => self.moved.push((s, t)),
+            CompareRow::Missing(e) => self.missing.push(e),
+            CompareRow::New(e) => self.new.push(e),
+        }
+    }
+}
+
+/// Map entries read in order, enforcing strictly increasing paths.
+struct SortedEntries {
+    path: PathBuf,
+    entries: Box<dyn Iterator<Item = Result<io::MapEntry>>>,
+    last: Option<String>,
+}
+
+impl SortedEntries {
+    fn open(path: &Path) -> Result<Self> {
+        Ok(Self {
+            path: path.to_path_buf(),
+            entries: io::stream_map_entries(path)?,
+            last: None,
+        })
+    }
+
+    fn next_entry(&mut self) -> Result<Option<io::MapEntry>> {
+        let entry = match self.entries.next() {
+            Some(e) => e.with_context(|| format!("reading {:?}", self.path))?,
+            None => return Ok(None),
+        };
+        if let Some(last) = &self.last {
+            if entry.path <= *last {
+                anyhow::bail!(
+                    "{:?} is not sorted by path: {:?} follows {:?}",
+                    self.path,
+                    entry.path,
+                    last
+                );
+            }
+        }
+        self.last = Some(entry.path.clone());
+        Ok(Some(entry))
+    }
+}
+
+/// Re-read `path` and return the first entry (in file order) for each wanted hash.
+fn first_entries_by_hash(
+    path: &Path,
+    wanted: &HashSet<String>,
+) -> Result<HashMap<String, io::MapEntry>> {
+    let mut found = HashMap::new();
+    if wanted.is_empty() {
+        return Ok(found);
+    }
+    for entry in io::stream_map_entries(path)? {
+        let entry = entry.with_context(|| format!("reading {:?}", path))?;
+        let key = content_key(&entry);
+        if wanted.contains(&key) && !found.contains_key(&key) {
+            found.insert(key, entry);
+            if found.len() == wanted.len() {
+                break;
+            }
+        }
+    }
+    Ok(found)
+}
+
+/// Compare two map files sorted by path (NDJSON or CSV) with a merge-join, handing
+/// each row to `emit` as soon as it is known instead of building a [`ComparisonReport`].
+///
+/// Classification follows [`compare_maps`]. Identical and changed rows stream out
+/// during the join; only entries without a same-path partner are kept in memory for
+/// move detection, and the inputs are re-read when a move or new file has to be
+/// checked against the rest of the other side. Inputs that are not strictly sorted
+/// by path (or repeat a path) are rejected.
+pub fn compare_sorted_maps<F>(source: &Path, target: &Path, mut emit: F) -> Result<()>
+where
+    F: FnMut(CompareRow) -> Result<()>,
+{
+    use std::cmp::Ordering;
+
+    let mut src = SortedEntries::open(source)?;
+    let mut tgt = SortedEntries::open(target)?;
+    let mut src_only: Vec<io::MapEntry> = Vec::new();
+    let mut tgt_only: Vec<io::MapEntry> = Vec::new();
+
+    let mut s = src.next_entry()?;
+    let mut t = tgt.next_entry()?;
+    loop {
+        match (s.take(), t.take()) {
+            (None, None) => break,
+            (Some(se), None) => {
+                src_only.push(se);
+                s = src.next_entry()?;
+            }
+            (None, Some(te)) => {
+                tgt_only.push(te);
+                t = tgt.next_entry()?;
+            }
+            (Some(se), Some(te)) => match se.path.cmp(&te.path) {
+                Ordering::Less => {
+                    src_only.push(se);
+                    s = src.next_entry()?;
+                    t = Some(te);
+                }
+                Ordering::Greater => {
+                    tgt_only.push(te);
+                    t = tgt.next_entry()?;
+                    s = Some(se);
+                }
+                Ordering::Equal => {
+                    if same_content(&se, &te) {
+                        emit(CompareRow::Identical(se))?;
+                    } else {
+                        emit(CompareRow::Changed(se, te))?;
+                    }
+                    s = src.next_entry()?;
+                    t = tgt.next_entry()?;
+                }
+            },
+        }
+    }
+
+    // Moves: pair each unmatched source entry with an unclaimed target-only entry of
+    // the same hash, otherwise with the first target entry carrying that hash.
+    let mut tgt_only_by_hash: HashMap<String, Vec<usize>> = HashMap::new();
+    for (idx, te) in tgt_only.iter().enumerate() {
+        tgt_only_by_hash
+            .entry(content_key(te))
+            .or_default()
+            .push(idx);
+    }
+    let mut claimed = vec![false; tgt_only.len()];
+    let mut src_only_hashes: HashSet<String> = HashSet::new();
+    let mut unpaired: Vec<io::MapEntry> = Vec::new();
+    for se in src_only {
+        let key = content_key(&se);
+        let free = tgt_only_by_hash
+            .get(&key)
+            .and_then(|idxs| idxs.iter().copied().find(|i| !claimed[*i]));
+        src_only_hashes.insert(key);
+        match free {
+            Some(i) => {
+                claimed[i] = true;
+                emit(CompareRow::Moved(se, tgt_only[i].clone()))?;
+            }
+            None => unpaired.push(se),
+        }
+    }
+    let wanted: HashSet<String> = unpaired.iter().map(content_key).collect();
+    let first_in_target = first_entries_by_hash(target, &wanted)?;
+    for se in unpaired {
+        match first_in_target.get(&content_key(&se)) {
+            Some(te) => emit(CompareRow::Moved(se, te.clone()))?,
+            None => emit(CompareRow::Missing(se))?,
+        }
+    }
+
+    // New: unclaimed target-only entries whose hash appears nowhere in the source.
+    // Like `compare_maps`, entries whose content exists under another source path are
+    // treated as the far side of a move and not reported again.
+    let unclaimed: Vec<io::MapEntry> = tgt_only
+        .into_iter()
+        .zip(claimed)
+        .filter(|(te, claimed)| !claimed && !src_only_hashes.contains(&content_key(te)))
+        .map(|(te, _)| te)
+        .collect();
+    let wanted: HashSet<String> = unclaimed.iter().map(content_key).collect();
+    let in_source = first_entries_by_hash(source, &wanted)?;
+    for te in unclaimed {
+        if !in_source.contains_key(&content_key(&te)) {
+            emit(CompareRow::New(te))?;
+        }
+    }
+    Ok(())
+}
+
+/// Algorithm named by the first entry of a streamable map (its `algorithm` column or
+/// field), if any.
+fn first_entry_algorithm(path: &Path) -> Result<Option<String>> {
+    let first = io::stream_map_entries(path)?
+        .next()
+        .transpose()
+        .with_context(|| format!("reading {:?}", path))?;
+    Ok(first.and_then(|e| e.algorithm).map(|name| {
+        Algorithm::from_name(&name)
+            .map(|alg| alg.name().to_string())
+            .unwrap_or(name)
+    }))
+}
+
+/// Refuse to stream-compare maps whose first entries name different algorithms: every
+/// digest would differ and every row would be reported as changed. With `force` only
+/// warn. Streamable maps carry no header, so this stands in for the header algorithm
+/// and fingerprint checks of an in-memory compare.
+pub fn check_streaming_algorithms(source: &Path, target: &Path, force: bool) -> Result<()> {
+    let (src, tgt) = match (
+        first_entry_algorithm(source)?,
+        first_entry_algorithm(target)?,
+    ) {
+        (Some(src), Some(tgt)) if src != tgt => (src, tgt),
+        _ => return Ok(()),
+    };
+    let message = format!(
+        "{} was hashed with {} but {} with {}, so their digests can't be compared",
+        source.display(),
+        src,
+        target.display(),
+        tgt
+    );
+    if !force {
+        anyhow::bail!("{}; pass --force-compare to compare them anyway", message);
+    }
+    log::warn!("{}; comparing anyway (--force-compare)", message);
+    Ok(())
+}
+
+/// Stream a [`compare_sorted_maps`] comparison to `output` (or stdout) as it runs.
+/// `format` is "csv" (same columns as [`write_report`]) or "json"/"ndjson", which
+/// writes one `{"status", "source", "target"}` object per line. Only rows whose
+/// status is in `categories` are written.
+pub fn write_streaming_report(
+    source: &Path,
+    target: &Path,
+    output: Option<&Path>,
+    format: &str,
+    categories: &[&str],
+) -> Result<()> {
+    let out: Box<dyn Write> = match output {
+        Some(p) => {
+            Box::new(std::fs::File::create(p).with_context(|| format!("create report {:?}", p))?)
+        }
+        None => Box::new(std::io::stdout()),
+    };
+    let out = std::io::BufWriter::new(out);
+
+    match format.to_lowercase().as_str() {
+        "csv" => {
+            let mut wtr = csv::Writer::from_writer(out);
+            compare_sorted_maps(source, target, |row| {
+                if !categories.contains(&row.status()) {
+                    return Ok(());
+                }
+                wtr.serialize(CsvRow::new(row.status(), row.source(), row.target()))?;
+                Ok(())
+            })?;
+            wtr.flush()?;
+        }
+        "json" | "ndjson" => {
+            #[derive(Serialize)]
+            struct Line<'a> {
+                status: &'a str,
+                #[serde(skip_serializing_if = "Option::is_none")]
+                source: Option<&'a io::MapEntry>,
+                #[serde(skip_serializing_if = "Option::is_none")]
+                target: Option<&'a io::MapEntry>,
+            }
+            let mut out = out;
+            compare_sorted_maps(source, target, |row| {
+                if !categories.contains(&row.status()) {
+                    return Ok(());
+                }
+                let line = Line {
+                    status: row.status(),
+                    source: row.source(),
+                    target: row.target(),
+                };
+                serde_json::to_writer(&mut out, &line)?;
+                out.write_all(b"\n")?;
+                Ok(())
+            })?;
+            out.flush()?;
+        }
+        other => anyhow::bail!("unsupported format: {}", other),
+    }
+    Ok(())
+}
+
+/// Flat CSV row describing one observed change.
+#[derive(Serialize)]
+struct CsvRow<'a> {
+    status: &'a str,
+    source_path: Option<&'a str>,
+    source_hash: Option<&'a str>,
+    source_size: Option<u64>,
+    target_path: Option<&'a str>,
+    target_hash: Option<&'a str>,
+    target_size: Option<u64>,
+}
+
+impl<'a> CsvRow<'a> {
+    fn new(
+        status: &'a str,
+        source: Option<&'a io::MapEntry>,
+        target: Option<&'a io::MapEntry>,
+    ) -> Self {
+        Self {
+            status,
+            source_path: source.map(|e| e.path.as_str()),
+            source_hash: source.map(|e| e.hash.as_str()),
+            source_size: source.map(|e| e.size),
+            target_path: target.map(|e| e.path.as_str()),
+            target_hash: target.map(|e| e.hash.as_str()),
+            target_size: target.map(|e| e.size),
+        }
+    }
+}
+
 /// Save or print a comparison report.
 /// If `output` is Some(path) the report is written to that file, otherwise printed to stdout.
 /// `format` is "json" or "csv".
@@ -236,74 +910,7 @@ pub fn write_report(report: &ComparisonReport, output: Option<&Path>, format: &s
         }
         "csv" => {
             // Emit a flat CSV with rows describing each observed change.
-            #[derive(Serialize)]
-            struct Row<'a> {
-                status: &'a str,
-                source_path: Option<&'a str>,
-                source_hash: Option<&'a str>,
-                source_size: Option<u64>,
-                target_path: Option<&'a str>,
-                target_hash: Option<&'a str>,
-                target_size: Option<u64>,
-            }
-
-            let mut rows: Vec<Row> = Vec::new();
-            for r in &report.identical {
-                rows.push(Row {
-                    status: "identical",
-                    source_path: Some(&r.path),
-                    source_hash: Some(&r.hash),
-                    source_size: Some(r.size),
-                    target_path: Some(&r.path),
-                    target_hash: Some(&r.hash),
-                    target_size: Some(r.size),
-                });
-            }
-            for (s, t) in &report.changed {
-                rows.push(Row {
-                    status: "changed",
-                    source_path: Some(&s.path),
-                    source_hash: Some(&s.hash),
-                    source_size: Some(s.size),
-                    target_path: Some(&t.path),
-                    target_hash: Some(&t.hash),
-                    target_size: Some(t.size),
-                });
-            }
-            for (s, t) in &report.moved {
-                rows.push(Row {
-                    status: "moved",
-                    source_path: Some(&s.path),
-                    source_hash: Some(&s.hash),
-                    source_size: Some(s.size),
-                    target_path: Some(&t.path),
-                    target_hash: Some(&t.hash),
-                    target_size: Some(t.size),
-                });
-            }
-            for s in &report.missing {
-                rows.push(Row {
-                    status: "missing",
-                    source_path: Some(&s.path),
-                    source_hash: Some(&s.hash),
-                    source_size: Some(s.size),
-                    target_path: None,
-                    target_hash: None,
-                    target_size: None,
-                });
-            }
-            for t in &report.new {
-                rows.push(Row {
-                    status: "new",
-                    source_path: None,
-                    source_hash: None,
-                    source_size: None,
-                    target_path: Some(&t.path),
-                    target_hash: Some(&t.hash),
-                    target_size: Some(t.size),
-                });
-            }
-
+            let rows = csv_rows(report);
             if let Some(p) = output {
                 io::write_csv(p, &rows).with_context(|| format!("write csv {:?}", p))?;
             } else {
@@ -319,6 +926,113 @@ pub fn write_report(report: &ComparisonReport, output: Option<&Path>, format: &s
     }
 }
 
+/// Flatten `report` into one [`CsvRow`] per entry, self-duplicates included.
+fn csv_rows(report: &ComparisonReport) -> Vec<CsvRow<'_>> {
+    let mut rows: Vec<CsvRow> = Vec::new();
+    for r in &report.identical {
+        rows.push(CsvRow::new("identical", Some(r), Some(r)));
+    }
+    for (s, t) in &report.changed {
+        rows.push(CsvRow::new("changed", Some(s), Some(t)));
+    }
+    for (s, t) in &report.moved {
+        rows.push(CsvRow::new("moved", Some(s), Some(t)));
+    }
+    for s in &report.missing {
+        rows.push(CsvRow::new("missing", Some(s), None));
+    }
+    for t in &report.new {
+        rows.push(CsvRow::new("new", None, Some(t)));
+    }
+
+    for g in &report.self_duplicates {
+        for path in &g.paths {
+            rows.push(CsvRow {
+                status: "duplicate",
+                source_path: Some(path),
+                source_hash: Some(&g.hash),
+                source_size: Some(g.size),
+                target_path: None,
+                target_hash: None,
+                target_size: None,
+            });
+        }
+    }
+    rows
+}
+
+/// File stems [`write_split_report`] writes: the report's sections.
+const SPLIT_SECTIONS: &[&str] = &[
+    "identical",
+    "changed",
+    "moved",
+    "missing",
+    "new",
+    "self_duplicates",
+];
+
+/// Write each non-empty report category to its own file in `dir` (created if needed),
+/// named after the report section: `missing.csv`, `self_duplicates.csv`, ... for CSV, or
+/// `missing.json`, `self_duplicates.json`, ... holding that section of the JSON report.
+/// Category files left in `dir` by an earlier run are removed first, so every file
+/// there belongs to this report. Returns the files written.
+pub fn write_split_report(
+    report: &ComparisonReport,
+    dir: &Path,
+    format: &str,
+) -> Result<Vec<PathBuf>> {
+    std::fs::create_dir_all(dir).with_context(|| format!("create directory {:?}", dir))?;
+    for name in SPLIT_SECTIONS {
+        for ext in ["json", "csv"] {
+            let stale = dir.join(format!("{}.{}", name, ext));
+            match std::fs::remove_file(&stale) {
+                Err(e) if e.kind() != std::io::ErrorKind::NotFound => {
+                    return Err(e).with_context(|| format!("remove stale {:?}", stale));
+                }
+                _ => {}
+            }
+        }
+    }
+    let mut written = Vec::new();
+    match format.to_lowercase().as_str() {
+        "json" => {
+            let value = serde_json::to_value(report).context("serialize report to json")?;
+            let sections = value.as_object().into_iter().flatten();
+            for (name, section) in sections {
+                if section.as_array().is_some_and(|a| !a.is_empty()) {
+                    let path = dir.join(format!("{}.json", name));
+                    io::write_json(&path, section)
+                        .with_context(|| format!("write json {:?}", path))?;
+                    written.push(path);
+                }
+            }
+        }
+        "csv" => {
+            let rows = csv_rows(report);
+            let mut statuses: Vec<&str> = Vec::new();
+            for row in &rows {
+                if !statuses.contains(&row.status) {
+                    statuses.push(row.status);
+                }
+            }
+            for status in statuses {
+                let part: Vec<&CsvRow> = rows.iter().filter(|r| r.status == status).collect();
+                // duplicate rows come from the `self_duplicates` section, as in JSON
+                let name = if status == "duplicate" {
+                    "self_duplicates"
+                } else {
+                    status
+                };
+                let path = dir.join(format!("{}.csv", name));
+                io::write_csv(&path, &part).with_context(|| format!("write csv {:?}", path))?;
+                written.push(path);
+            }
+        }
+        other => anyhow::bail!("unsupported format: {}", other),
+    }
+    Ok(written)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -330,19 +1044,19 @@ mod tests {
                 path: "a.txt".into(),
                 hash: "h1".into(),
                 size: 1,
-                mtime: None,
+                ..Default::default()
             },
             io::MapEntry {
                 path: "b.txt".into(),
                 hash: "h2".into(),
                 size: 2,
-                mtime: None,
+                ..Default::default()
             },
             io::MapEntry {
                 path: "c.txt".into(),
                 hash: "h3".into(),
                 size: 3,
-                mtime: None,
+                ..Default::default()
             },
         ];
         let b = vec![
@@ -350,25 +1064,25 @@ mod tests {
                 path: "a.txt".into(),
                 hash: "h1".into(),
                 size: 1,
-                mtime: None,
+                ..Default::default()
             }, // identical
             io::MapEntry {
                 path: "b.txt".into(),
                 hash: "h2b".into(),
                 size: 2,
-                mtime: None,
+                ..Default::default()
             }, // changed
             io::MapEntry {
                 path: "d.txt".into(),
                 hash: "h3".into(),
                 size: 3,
-                mtime: None,
+                ..Default::default()
             }, // moved (c -> d)
             io::MapEntry {
                 path: "e.txt".into(),
                 hash: "h4".into(),
                 size: 4,
-                mtime: None,
+                ..Default::default()
             }, // new
         ];
 
@@ -379,4 +1093,188 @@ mod tests {
         assert_eq!(r.missing.len(), 0);
         assert_eq!(r.new.len(), 1);
     }
+
+    #[test]
+    fn compare_ignores_hex_case() {
+        let entry = |path: &str, hash: &str| io::MapEntry {
+            path: path.into(),
+            hash: hash.into(),
+            size: 1,
+            ..Default::default()
+        };
+        let a = vec![entry("a.txt", "abcdef01"), entry("b.txt", "00ff00ff")];
+        let b = vec![entry("a.txt", "ABCDEF01"), entry("moved.txt", "00FF00FF")];
+
+        let r = compare_maps(a, b);
+        assert_eq!(r.identical.len(), 1);
+        assert_eq!(r.changed.len(), 0);
+        assert_eq!(r.moved.len(), 1);
+        assert_eq!(r.new.len(), 0);
+    }
+
+    #[test]
+    fn symlinks_only_match_symlinks() {
+        let entry = |path: &str, hash: &str, link: bool| io::MapEntry {
+            path: path.into(),
+            hash: hash.into(),
+            size: 1,
+            kind: link.then_some(io::EntryKind::Symlink),
+            ..Default::default()
+        };
+        let a = vec![
+            entry("link", "aa", true),
+            entry("file.txt", "bb", false),
+            entry("old-link", "cc", true),
+        ];
+        let b = vec![
+            entry("link", "aa", false),
+            entry("copy.txt", "bb", true),
+            entry("new-link", "cc", true),
+        ];
+
+        let r = compare_maps(a.clone(), b.clone());
+        assert!(r.identical.is_empty());
+        assert_eq!(r.changed.len(), 1);
+        assert_eq!(r.changed[0].0.path, "link");
+        assert_eq!(r.moved.len(), 1);
+        assert_eq!(r.moved[0].1.path, "new-link");
+        assert_eq!(r.missing.len(), 1);
+        assert_eq!(r.missing[0].path, "file.txt");
+        assert_eq!(r.new.len(), 1);
+        assert_eq!(r.new[0].path, "copy.txt");
+
+        let dir = tempfile::tempdir().unwrap();
+        let src = dir.path().join("src.ndjson");
+        let tgt = dir.path().join("tgt.ndjson");
+        let sorted = |path: &Path, mut entries: Vec<io::MapEntry>| {
+            entries.sort_by(|x, y| x.path.cmp(&y.path));
+            io::write_ndjson(&mut std::fs::File::create(path).unwrap(), &entries).unwrap();
+        };
+        sorted(&src, a);
+        sorted(&tgt, b);
+        let mut streamed = ComparisonReport::new();
+        compare_sorted_maps(&src, &tgt, |row| {
+            streamed.push(row);
+            Ok(())
+        })
+        .unwrap();
+        let (streamed, r) = (sorted_report(streamed), sorted_report(r));
+        assert_eq!(streamed.identical, r.identical);
+        assert_eq!(streamed.changed, r.changed);
+        assert_eq!(streamed.moved, r.moved);
+        assert_eq!(streamed.missing, r.missing);
+        assert_eq!(streamed.new, r.new);
+    }
+
+    #[test]
+    fn moved_pairs_with_smallest_unclaimed_target() {
+        let entry = |path: &str, hash: &str| io::MapEntry {
+            path: path.into(),
+            hash: hash.into(),
+            size: 1,
+            ..Default::default()
+        };
+        let source = vec![entry("src/two.txt", "aa"), entry("src/one.txt", "aa")];
+        let target = vec![
+            entry("z/copy.txt", "aa"),
+            entry("m/copy.txt", "aa"),
+            entry("b/copy.txt", "aa"),
+        ];
+
+        let mut reversed = target.clone();
+        reversed.reverse();
+        for target in [target, reversed] {
+            let r = compare_maps(source.clone(), target);
+            let pairs: Vec<(&str, &str)> = r
+                .moved
+                .iter()
+                .map(|(s, t)| (s.path.as_str(), t.path.as_str()))
+                .collect();
+            assert_eq!(
+                pairs,
+                [("src/one.txt", "b/copy.txt"), ("src/two.txt", "m/copy.txt")]
+            );
+            assert!(r.new.is_empty());
+        }
+    }
+
+    fn write_ndjson(path: &Path, entries: &[(&str, &str)]) {
+        let lines: Vec<String> = entries
+            .iter()
+            .map(|(p, h)| format!(r#"{{"path":"{}","hash":"{}","size":1}}"#, p, h))
+            .collect();
+        std::fs::write(path, lines.join("\n")).unwrap();
+    }
+
+    fn sorted_report(mut r: ComparisonReport) -> ComparisonReport {
+        r.identical.sort_by(|a, b| a.path.cmp(&b.path));
+        r.changed.sort_by(|a, b| a.0.path.cmp(&b.0.path));
+        r.moved.sort_by(|a, b| a.0.path.cmp(&b.0.path));
+        r.missing.sort_by(|a, b| a.path.cmp(&b.path));
+        r.new.sort_by(|a, b| a.path.cmp(&b.path));
+        r
+    }
+
+    #[test]
+    fn streaming_compare_matches_in_memory() {
+        let dir = tempfile::tempdir().unwrap();
+        let src = dir.path().join("src.ndjson");
+        let tgt = dir.path().join("tgt.ndjson");
+        write_ndjson(
+            &src,
+            &[
+                ("a.txt", "aa"),    // identical
+                ("b.txt", "bb"),    // changed
+                ("c.txt", "cc"),    // moved to d.txt
+                ("copy.txt", "AA"), // moved onto a.txt, which is matched by path
+                ("gone.txt", "ff"), // missing
+                ("keep.txt", "11"), // identical
+            ],
+        );
+        write_ndjson(
+            &tgt,
+            &[
+                ("a.txt", "AA"),
+                ("b.txt", "b2"),
+                ("d.txt", "cc"),
+                ("dup-of-keep.txt", "11"), // content already in source: not new
+                ("keep.txt", "11"),
+                ("z.txt", "99"), // new
+            ],
+        );
+
+        let mut streamed = ComparisonReport::new();
+        compare_sorted_maps(&src, &tgt, |row| {
+            streamed.push(row);
+            Ok(())
+        })
+        .unwrap();
+        let in_memory = compare_maps(
+            io::load_map_from_ndjson(&src).unwrap(),
+            io::load_map_from_ndjson(&tgt).unwrap(),
+        );
+
+        let streamed = sorted_report(streamed);
+        let in_memory = sorted_report(in_memory);
+        assert_eq!(streamed.identical, in_memory.identical);
+        assert_eq!(streamed.changed, in_memory.changed);
+        assert_eq!(streamed.moved, in_memory.moved);
+        assert_eq!(streamed.missing, in_memory.missing);
+        assert_eq!(streamed.new, in_memory.new);
+        assert_eq!(streamed.identical.len(), 2);
+        assert_eq!(streamed.moved.len(), 2);
+        assert_eq!(streamed.new.len(), 1);
+    }
+
+    #[test]
+    fn streaming_compare_rejects_unsorted_input() {
+        let dir = tempfile::tempdir().unwrap();
+        let src = dir.path().join("src.ndjson");
+        let tgt = dir.path().join("tgt.ndjson");
+        write_ndjson(&src, &[("b.txt", "bb"), ("a.txt", "aa")]);
+        write_ndjson(&tgt, &[("a.txt", "aa")]);
+
+        let err = compare_sorted_maps(&src, &tgt, |_| Ok(())).unwrap_err();
+        assert!(err.to_string().contains("not sorted by path"), "{}", err);
+    }
 }
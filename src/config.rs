@@ -1,4 +1,5 @@
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::env;
 use std::fs::File;
 use std::io::Read;
@@ -6,6 +7,8 @@ use std::path::{Path, PathBuf};
 
 use anyhow::Context;
 
+use crate::algorithms::Algorithm;
+
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct GeneralConfig {
     pub path: Option<String>,
@@ -24,6 +27,24 @@ pub struct GeneralConfig {
 pub struct AlgorithmConfig {
     pub name: Option<String>,
     pub xof_length: Option<usize>,
+    /// Output length per algorithm name (`[algorithm.xof_lengths]`), ahead of `xof_length`.
+    pub xof_lengths: Option<BTreeMap<String, usize>>,
+    pub block_size: Option<usize>,
+    pub customization: Option<String>,
+}
+
+impl AlgorithmConfig {
+    /// Configured output length for `algorithm`: its `xof_lengths` entry, else `xof_length`.
+    pub fn xof_length_for(&self, algorithm: Algorithm) -> Option<usize> {
+        self.xof_lengths
+            .iter()
+            .flatten()
+            .find(|(name, _)| {
+                Algorithm::from_name(name).map(|a| a.name()) == Some(algorithm.name())
+            })
+            .map(|(_, &len)| len)
+            .or(self.xof_length)
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
@@ -34,14 +55,42 @@ pub struct MemoryConfig {
 
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct RuntimeConfig {
+    /// Base config this one overrides, relative to this file's directory.
+    /// Resolved (and cleared) by [`RuntimeConfig::load_from_file`].
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub extends: Option<String>,
     pub general: Option<GeneralConfig>,
     pub algorithm: Option<AlgorithmConfig>,
     pub memory: Option<MemoryConfig>,
 }
 
 impl RuntimeConfig {
+    /// Load a config file, first loading and merging the chain of `extends` bases under it.
     pub fn load_from_file<P: AsRef<Path>>(path: P) -> anyhow::Result<Self> {
-        let p = path.as_ref();
+        Self::load_extending(path.as_ref(), &mut Vec::new())
+    }
+
+    fn load_extending(p: &Path, chain: &mut Vec<PathBuf>) -> anyhow::Result<Self> {
+        let canonical = p
+            .canonicalize()
+            .with_context(|| format!("resolve config path {:?}", p))?;
+        if chain.contains(&canonical) {
+            anyhow::bail!("config extends cycle: {:?} is already being loaded", p);
+        }
+        chain.push(canonical);
+
+        let mut cfg = Self::parse_file(p)?;
+        if let Some(base) = cfg.extends.take() {
+            let base_path = p.parent().unwrap_or(Path::new(".")).join(base);
+            let mut merged = Self::load_extending(&base_path, chain)
+                .with_context(|| format!("loading base config {:?}", base_path))?;
+            merged.merge(cfg);
+            cfg = merged;
+        }
+        Ok(cfg)
+    }
+
+    fn parse_file(p: &Path) -> anyhow::Result<Self> {
         let mut s = String::new();
         let mut f = File::open(p)?;
         f.read_to_string(&mut s)?;
@@ -103,6 +152,18 @@ impl RuntimeConfig {
                 if a.xof_length.is_some() {
                     target.xof_length = a.xof_length;
                 }
+                if let Some(lengths) = a.xof_lengths {
+                    target
+                        .xof_lengths
+                        .get_or_insert_with(Default::default)
+                        .extend(lengths);
+                }
+                if a.block_size.is_some() {
+                    target.block_size = a.block_size;
+                }
+                if a.customization.is_some() {
+                    target.customization = a.customization;
+                }
             } else {
                 self.algorithm = Some(a);
             }
@@ -126,8 +187,11 @@ impl RuntimeConfig {
         if let Some(g) = &self.general {
             if let Some(format) = g.format.as_deref() {
                 let fmt = format.to_lowercase();
-                if fmt != "json" && fmt != "csv" {
-                    anyhow::bail!("invalid general.format '{}': use json or csv", format);
+                if !matches!(fmt.as_str(), "auto" | "json" | "csv" | "ndjson" | "sums") {
+                    anyhow::bail!(
+                        "invalid general.format '{}': use auto, json, csv, ndjson or sums",
+                        format
+                    );
                 }
             }
             if let Some(threads) = g.threads {
@@ -153,6 +217,18 @@ impl RuntimeConfig {
                     anyhow::bail!("algorithm.xof_length must be greater than 0");
                 }
             }
+            for (name, &len) in a.xof_lengths.iter().flatten() {
+                if Algorithm::from_name(name).is_none() {
+                    anyhow::bail!("algorithm.xof_lengths: unknown algorithm '{}'", name);
+                }
+                if len == 0 {
+                    anyhow::bail!("algorithm.xof_lengths.{} must be greater than 0", name);
+                }
+            }
+            if let Some(block_size) = a.block_size {
+                crate::algorithms::parallelhash::validate_block_size(block_size)
+                    .context("algorithm.block_size")?;
+            }
         }
 
         if let Some(m) = &self.memory {
@@ -376,4 +452,62 @@ mod tests {
         };
         assert!(cfg.validate().is_ok());
     }
+
+    #[test]
+    fn xof_length_for_prefers_the_algorithm_entry() {
+        let mut cfg = RuntimeConfig {
+            algorithm: Some(AlgorithmConfig {
+                xof_length: Some(48),
+                xof_lengths: Some(BTreeMap::from([("xxh3".to_string(), 20)])),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        cfg.merge(RuntimeConfig {
+            algorithm: Some(AlgorithmConfig {
+                xof_lengths: Some(BTreeMap::from([("blake3".to_string(), 24)])),
+                ..Default::default()
+            }),
+            ..Default::default()
+        });
+        assert!(cfg.validate().is_ok());
+        let a = cfg.algorithm.as_ref().unwrap();
+        assert_eq!(a.xof_length_for(Algorithm::Xxh3_1024), Some(20));
+        assert_eq!(a.xof_length_for(Algorithm::Blake3), Some(24));
+        assert_eq!(a.xof_length_for(Algorithm::Shake256), Some(48));
+
+        cfg.algorithm.as_mut().unwrap().xof_lengths =
+            Some(BTreeMap::from([("sha1".to_string(), 20)]));
+        assert!(cfg.validate().is_err());
+    }
+
+    #[test]
+    fn extends_merges_base_under_derived_config() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(dir.path().join("shared")).unwrap();
+        std::fs::write(
+            dir.path().join("shared").join("base.toml"),
+            "[general]\nformat = \"csv\"\nthreads = 2\n\n[memory]\nmode = \"stream\"\n",
+        )
+        .unwrap();
+        let derived = dir.path().join("derived.toml");
+        std::fs::write(
+            &derived,
+            "extends = \"shared/base.toml\"\n\n[general]\nthreads = 8\n",
+        )
+        .unwrap();
+
+        let cfg = RuntimeConfig::load_from_file(&derived).unwrap();
+        let general = cfg.general.unwrap();
+        assert_eq!(general.format.as_deref(), Some("csv"));
+        assert_eq!(general.threads, Some(8));
+        assert_eq!(cfg.memory.unwrap().mode.as_deref(), Some("stream"));
+        assert!(cfg.extends.is_none());
+
+        // a.toml -> b.toml -> a.toml
+        std::fs::write(dir.path().join("a.toml"), "extends = \"b.toml\"\n").unwrap();
+        std::fs::write(dir.path().join("b.toml"), "extends = \"a.toml\"\n").unwrap();
+        let err = RuntimeConfig::load_from_file(dir.path().join("a.toml")).unwrap_err();
+        assert!(format!("{:#}", err).contains("cycle"), "{:#}", err);
+    }
 }
@@ -1,19 +1,24 @@
 use chrono::Utc;
 use std::fs;
-use std::io::{self as stdio, Write};
+use std::io::{self as stdio, BufRead, IsTerminal, Write};
 use std::path::{Path, PathBuf};
 
 use anyhow::{Context, Result};
 use filetime::FileTime;
 use serde::{Deserialize, Serialize};
 
+use crate::algorithms::Algorithm;
 use crate::compare::ComparisonReport;
+use crate::hash::DigestEncoding;
+use crate::io::EntryKind;
 
 #[derive(Debug, Clone, Copy)]
 pub enum ConflictStrategy {
     Overwrite,
     Skip,
     Rename,
+    /// Ask on stdin for each conflict (interactive terminals only)
+    Prompt,
 }
 
 impl ConflictStrategy {
@@ -29,6 +34,7 @@ impl std::str::FromStr for ConflictStrategy {
             "overwrite" => Ok(ConflictStrategy::Overwrite),
             "skip" => Ok(ConflictStrategy::Skip),
             "rename" => Ok(ConflictStrategy::Rename),
+            "prompt" => Ok(ConflictStrategy::Prompt),
             _ => Err(()),
         }
     }
@@ -38,6 +44,74 @@ impl std::str::FromStr for ConflictStrategy {
 pub struct CopyOptions {
     pub conflict: ConflictStrategy,
     pub preserve_times: bool,
+    /// Re-apply the source's permission bits once the copy's other metadata is set.
+    pub preserve_perms: bool,
+    /// Give the copy the source's owner and group (Unix; needs privileges to change owner).
+    pub preserve_owner: bool,
+    /// Strategy used instead of `Prompt` when stdin/stdout are not a terminal
+    pub prompt_fallback: ConflictStrategy,
+}
+
+/// Answer given at an interactive conflict prompt.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PromptChoice {
+    Overwrite,
+    Skip,
+    Rename,
+    Abort,
+}
+
+/// Asks how to resolve destination conflicts when using `ConflictStrategy::Prompt`.
+/// Answering with a trailing `all` (e.g. `skip all`) applies the choice to every
+/// remaining conflict without asking again. End of input is treated as abort.
+pub struct ConflictPrompter<R, W> {
+    input: R,
+    output: W,
+    sticky: Option<PromptChoice>,
+}
+
+impl<R: BufRead, W: Write> ConflictPrompter<R, W> {
+    pub fn new(input: R, output: W) -> Self {
+        Self {
+            input,
+            output,
+            sticky: None,
+        }
+    }
+
+    pub fn ask(&mut self, dst: &Path) -> Result<PromptChoice> {
+        if let Some(choice) = self.sticky {
+            return Ok(choice);
+        }
+        loop {
+            write!(
+                self.output,
+                "{} exists: [o]verwrite, [s]kip, [r]ename, [a]bort (add 'all' to apply to the rest)? ",
+                dst.display()
+            )?;
+            self.output.flush()?;
+            let mut line = String::new();
+            if self.input.read_line(&mut line)? == 0 {
+                return Ok(PromptChoice::Abort);
+            }
+            let answer = line.trim().to_lowercase();
+            let mut words = answer.split_whitespace();
+            let choice = match words.next() {
+                Some("o") | Some("overwrite") => PromptChoice::Overwrite,
+                Some("s") | Some("skip") => PromptChoice::Skip,
+                Some("r") | Some("rename") => PromptChoice::Rename,
+                Some("a") | Some("abort") => PromptChoice::Abort,
+                _ => {
+                    writeln!(self.output, "unrecognized answer: {:?}", line.trim())?;
+                    continue;
+                }
+            };
+            if words.next() == Some("all") {
+                self.sticky = Some(choice);
+            }
+            return Ok(choice);
+        }
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -52,6 +126,21 @@ pub struct CopyOp {
     /// using `done` will still be honored.
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub status: Option<CopyStatus>,
+    /// Path actually written once the op completes; differs from `dst` when the
+    /// rename conflict strategy picked a free name.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub final_dst: Option<String>,
+    /// Digest of the source recorded when the plan was generated, in the map's encoding.
+    /// `--verify-only` checks the destination against it rather than re-reading the source.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hash: Option<String>,
+    /// Algorithm `hash` was computed with.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub algorithm: Option<String>,
+    /// `symlink` when the source entry is a link hashed by its target path; the link is
+    /// recreated instead of copying the file it points to.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub kind: Option<EntryKind>,
 }
 
 #[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
@@ -61,6 +150,8 @@ pub enum CopyStatus {
     InProgress,
     Done,
     Failed,
+    /// Left alone by the conflict strategy (`skip`, or a skip answer at the prompt).
+    Skipped,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -87,6 +178,14 @@ impl CopyPlan {
             ops: Vec::new(),
         }
     }
+
+    /// Record `algorithm` as the one each op's `hash` was computed with.
+    pub fn with_algorithm(mut self, algorithm: Algorithm) -> Self {
+        for op in self.ops.iter_mut().filter(|op| op.hash.is_some()) {
+            op.algorithm = Some(algorithm.name().to_string());
+        }
+        self
+    }
 }
 
 impl Default for CopyPlan {
@@ -102,6 +201,8 @@ impl Default for CopyPlan {
 /// - For `missing` entries (present in source but not in target) create copy from source.path -> corresponding target path.
 ///   If `source_root` and `target_root` are provided and the source path starts with `source_root` the target
 ///   path will be generated by replacing the prefix with `target_root`. Otherwise the destination will be the same as source.
+///
+/// Relative entry paths (as recorded in hash maps) are resolved against the matching root when one is given.
 pub fn generate_copy_plan(
     report: &ComparisonReport,
     source_root: Option<&Path>,
@@ -118,28 +219,37 @@ pub fn generate_copy_plan(
     // Handle changed files (explicit source -> target mapping)
     for (s, t) in &report.changed {
         plan.ops.push(CopyOp {
-            src: s.path.clone(),
-            dst: t.path.clone(),
+            src: resolve_entry_path(source_root, &s.path),
+            dst: resolve_entry_path(target_root, &t.path),
             op: "copy".into(),
             done: false,
             status: None,
+            final_dst: None,
+            hash: Some(s.hash.clone()),
+            algorithm: None,
+            kind: s.kind,
         });
     }
 
     // Handle moved files (t.path exists in target)
     for (s, t) in &report.moved {
         plan.ops.push(CopyOp {
-            src: s.path.clone(),
-            dst: t.path.clone(),
+            src: resolve_entry_path(source_root, &s.path),
+            dst: resolve_entry_path(target_root, &t.path),
             op: "copy".into(),
             done: false,
             status: None,
+            final_dst: None,
+            hash: Some(s.hash.clone()),
+            algorithm: None,
+            kind: s.kind,
         });
     }
 
     // Handle missing files (present in source but not in target) by constructing dst path
     for s in &report.missing {
-        let src_p = Path::new(&s.path);
+        let src_str = resolve_entry_path(source_root, &s.path);
+        let src_p = Path::new(&src_str);
         let dst_str = if let (Some(sr), Some(tr)) = (source_root, target_root) {
             // Try to strip source_root from src path to create relative path
             if let Ok(rel) = src_p.strip_prefix(sr) {
@@ -147,25 +257,37 @@ pub fn generate_copy_plan(
                 candidate.to_string_lossy().into_owned()
             } else {
                 // fallback to using same path
-                s.path.clone()
+                src_str.clone()
             }
         } else {
             // No roots provided - fall back to using same path
-            s.path.clone()
+            src_str.clone()
         };
 
         plan.ops.push(CopyOp {
-            src: s.path.clone(),
+            src: src_str.clone(),
             dst: dst_str,
             op: "copy".into(),
             done: false,
             status: None,
+            final_dst: None,
+            hash: Some(s.hash.clone()),
+            algorithm: None,
+            kind: s.kind,
         });
     }
 
     plan
 }
 
+/// Join a relative map entry path onto `root`; absolute paths (or a missing root) are kept as-is.
+fn resolve_entry_path(root: Option<&Path>, path: &str) -> String {
+    match root {
+        Some(r) if Path::new(path).is_relative() => r.join(path).to_string_lossy().into_owned(),
+        _ => path.to_string(),
+    }
+}
+
 pub fn write_plan(path: &Path, plan: &CopyPlan) -> Result<()> {
     crate::io::write_json(path, plan)
 }
@@ -181,20 +303,12 @@ fn resolve_destination(dst: &Path, strategy: ConflictStrategy) -> Result<Option<
     }
     match strategy {
         ConflictStrategy::Overwrite => Ok(Some(dst.to_path_buf())),
-        ConflictStrategy::Skip => Ok(None),
+        // prompts are resolved by the caller; never overwrite without an answer
+        ConflictStrategy::Skip | ConflictStrategy::Prompt => Ok(None),
         ConflictStrategy::Rename => {
             let mut idx = 1;
-            let parent = dst.parent().unwrap_or(Path::new(""));
-            let stem = dst
-                .file_stem()
-                .map(|s| s.to_string_lossy().into_owned())
-                .unwrap_or_else(|| "copy".to_string());
-            let ext = dst.extension().map(|s| s.to_string_lossy().into_owned());
             loop {
-                let mut candidate = parent.join(format!("{}-copy{}", stem, idx));
-                if let Some(e) = &ext {
-                    candidate = candidate.with_extension(e);
-                }
+                let candidate = rename_candidate(dst, idx);
                 if !candidate.exists() {
                     return Ok(Some(candidate));
                 }
@@ -207,6 +321,72 @@ fn resolve_destination(dst: &Path, strategy: ConflictStrategy) -> Result<Option<
     }
 }
 
+/// Recreate the symlink `src` at `dst` with the same target, replacing whatever `dst`
+/// holds (only reached when the conflict strategy allows overwriting it).
+fn copy_symlink(src: &Path, dst: &Path) -> Result<()> {
+    let target = fs::read_link(src).with_context(|| format!("read link {:?}", src))?;
+    if dst.symlink_metadata().is_ok() {
+        fs::remove_file(dst).with_context(|| format!("remove {:?}", dst))?;
+    }
+    create_symlink(&target, dst).with_context(|| format!("link {:?} -> {:?}", dst, target))
+}
+
+#[cfg(unix)]
+fn create_symlink(target: &Path, link: &Path) -> std::io::Result<()> {
+    std::os::unix::fs::symlink(target, link)
+}
+
+#[cfg(windows)]
+fn create_symlink(target: &Path, link: &Path) -> std::io::Result<()> {
+    std::os::windows::fs::symlink_file(target, link)
+}
+
+#[cfg(not(any(unix, windows)))]
+fn create_symlink(_target: &Path, _link: &Path) -> std::io::Result<()> {
+    Err(std::io::Error::new(
+        std::io::ErrorKind::Unsupported,
+        "symlinks are not supported on this platform",
+    ))
+}
+
+/// Name tried for the `idx`-th rename of `dst`: `name (idx).ext`, or `name (idx)` when
+/// there is no extension.
+fn rename_candidate(dst: &Path, idx: usize) -> PathBuf {
+    let parent = dst.parent().unwrap_or(Path::new(""));
+    let stem = dst
+        .file_stem()
+        .map(|s| s.to_string_lossy().into_owned())
+        .unwrap_or_else(|| "copy".to_string());
+    match dst.extension() {
+        Some(ext) => parent.join(format!("{} ({}).{}", stem, idx, ext.to_string_lossy())),
+        None => parent.join(format!("{} ({})", stem, idx)),
+    }
+}
+
+fn prompt_strategy(choice: PromptChoice, dst: &Path) -> Result<ConflictStrategy> {
+    match choice {
+        PromptChoice::Overwrite => Ok(ConflictStrategy::Overwrite),
+        PromptChoice::Skip => Ok(ConflictStrategy::Skip),
+        PromptChoice::Rename => Ok(ConflictStrategy::Rename),
+        PromptChoice::Abort => anyhow::bail!("aborted at conflict for {}", dst.display()),
+    }
+}
+
+/// Give `dst` the owner and group of the file `src` was read from.
+#[cfg(unix)]
+fn copy_owner(src: &fs::Metadata, dst: &Path) -> std::io::Result<()> {
+    use std::os::unix::fs::MetadataExt;
+    std::os::unix::fs::chown(dst, Some(src.uid()), Some(src.gid()))
+}
+
+#[cfg(not(unix))]
+fn copy_owner(_src: &fs::Metadata, _dst: &Path) -> std::io::Result<()> {
+    Err(std::io::Error::new(
+        std::io::ErrorKind::Unsupported,
+        "--preserve-owner is only supported on Unix",
+    ))
+}
+
 pub fn execute_copy_plan(
     plan: &mut CopyPlan,
     opts: CopyOptions,
@@ -216,6 +396,18 @@ pub fn execute_copy_plan(
     context: usize,
     git_diff_output: Option<&Path>,
 ) -> Result<()> {
+    let mut prompter = match opts.conflict {
+        ConflictStrategy::Prompt
+            if stdio::stdin().is_terminal() && stdio::stdout().is_terminal() =>
+        {
+            Some(ConflictPrompter::new(
+                stdio::stdin().lock(),
+                stdio::stdout(),
+            ))
+        }
+        _ => None,
+    };
+    let mut owner_warned = false;
     for i in 0..plan.ops.len() {
         // take a short-lived mutable borrow for the current op
         if plan.ops[i].done || plan.ops[i].status == Some(CopyStatus::Done) {
@@ -230,9 +422,15 @@ pub fn execute_copy_plan(
         let dst_str = plan.ops[i].dst.clone();
         let src = Path::new(&src_str);
         let dst = Path::new(&dst_str);
+        let is_link = plan.ops[i].kind == Some(EntryKind::Symlink);
 
-        // Ensure source exists
-        if !src.exists() {
+        // Ensure source exists (a link only needs to exist itself, not its target)
+        let present = if is_link {
+            src.symlink_metadata().is_ok()
+        } else {
+            src.exists()
+        };
+        if !present {
             // mark failed and persist (if requested) so user can inspect and resume later
             plan.ops[i].status = Some(CopyStatus::Failed);
             if let Some(path) = persist_path {
@@ -246,9 +444,22 @@ pub fn execute_copy_plan(
                 .with_context(|| format!("create parent dirs {:?}", parent))?;
         }
 
-        let target_path = match resolve_destination(dst, opts.conflict)? {
+        let strategy = match opts.conflict {
+            ConflictStrategy::Prompt if dst.exists() => match prompter.as_mut() {
+                Some(p) => prompt_strategy(p.ask(dst)?, dst)?,
+                None => opts.prompt_fallback,
+            },
+            other => other,
+        };
+        let target_path = match resolve_destination(dst, strategy)? {
             Some(p) => p,
-            None => continue,
+            None => {
+                plan.ops[i].status = Some(CopyStatus::Skipped);
+                if let Some(path) = persist_path {
+                    write_plan(path, plan)?;
+                }
+                continue;
+            }
         };
 
         // mark in-progress and persist immediately (if requested)
@@ -258,9 +469,14 @@ pub fn execute_copy_plan(
         }
 
         // perform copy
-        if let Err(e) = fs::copy(src, &target_path)
-            .with_context(|| format!("copy {:?} -> {:?}", src, target_path))
-        {
+        let copied = if is_link {
+            copy_symlink(src, &target_path)
+        } else {
+            fs::copy(src, &target_path)
+                .map(|_| ())
+                .with_context(|| format!("copy {:?} -> {:?}", src, target_path))
+        };
+        if let Err(e) = copied {
             // store failed status and persist before returning
             plan.ops[i].status = Some(CopyStatus::Failed);
             if let Some(path) = persist_path {
@@ -269,7 +485,7 @@ pub fn execute_copy_plan(
             return Err(e);
         }
 
-        if git_diff {
+        if git_diff && !is_link {
             let diff = crate::diff::format_copy_diff(
                 src,
                 &target_path,
@@ -298,17 +514,39 @@ pub fn execute_copy_plan(
             }
         }
 
-        // preserve permissions if possible
-        if let Ok(metadata) = fs::metadata(src) {
-            let perms = metadata.permissions();
-            if let Err(e) = fs::set_permissions(&target_path, perms) {
-                // non-fatal; log to stderr
-                let _ = writeln!(
-                    stdio::stderr(),
-                    "warning: failed to set permissions on {}: {}",
-                    target_path.display(),
-                    e
-                );
+        // preserve metadata if possible; fs::copy already carries the permission bits.
+        // Links are left as created: their metadata would be read through the target.
+        let metadata = if is_link {
+            None
+        } else {
+            fs::metadata(src).ok()
+        };
+        if let Some(metadata) = metadata {
+            if opts.preserve_owner {
+                if let Err(e) = copy_owner(&metadata, &target_path) {
+                    if !owner_warned {
+                        owner_warned = true;
+                        let _ = writeln!(
+                            stdio::stderr(),
+                            "warning: failed to set owner on {}: {} (changing owners usually needs root; further failures are not reported)",
+                            target_path.display(),
+                            e
+                        );
+                    }
+                }
+            }
+
+            // a chown clears setuid/setgid, so the mode goes back on after it
+            if opts.preserve_perms || opts.preserve_owner {
+                if let Err(e) = fs::set_permissions(&target_path, metadata.permissions()) {
+                    // non-fatal; log to stderr
+                    let _ = writeln!(
+                        stdio::stderr(),
+                        "warning: failed to set permissions on {}: {}",
+                        target_path.display(),
+                        e
+                    );
+                }
             }
 
             if opts.preserve_times {
@@ -328,6 +566,7 @@ pub fn execute_copy_plan(
         }
         plan.ops[i].done = true;
         plan.ops[i].status = Some(CopyStatus::Done);
+        plan.ops[i].final_dst = Some(target_path.to_string_lossy().into_owned());
         if let Some(path) = persist_path {
             // mutable borrow ended here; safe to write the plan
             write_plan(path, plan)?;
@@ -336,6 +575,219 @@ pub fn execute_copy_plan(
     Ok(())
 }
 
+/// Operation counts for `copydiff --summary`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct PlanSummary {
+    /// Pending copy (or move) operations.
+    pub copy: usize,
+    /// Pending delete operations (only found in hand-written plans).
+    pub delete: usize,
+    /// Operations already marked done, which execution skips.
+    pub skip: usize,
+    /// Size of the sources still to be copied; unreadable sources count as 0.
+    pub bytes: u64,
+}
+
+impl std::fmt::Display for PlanSummary {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "plan: {} copy, {} delete, {} skip, {} bytes",
+            self.copy, self.delete, self.skip, self.bytes
+        )
+    }
+}
+
+/// Count a plan's operations for [`PlanSummary`].
+pub fn summarize_plan(plan: &CopyPlan) -> PlanSummary {
+    let mut summary = PlanSummary::default();
+    for op in &plan.ops {
+        if op.done || op.status == Some(CopyStatus::Done) {
+            summary.skip += 1;
+        } else if op.op.eq_ignore_ascii_case("delete") {
+            summary.delete += 1;
+        } else {
+            summary.copy += 1;
+            summary.bytes += fs::metadata(&op.src).map(|m| m.len()).unwrap_or(0);
+        }
+    }
+    summary
+}
+
+/// Outcome of checking one op in [`verify_copy_plan`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum VerifyStatus {
+    /// The destination hashes as expected.
+    Ok,
+    /// The destination differs from the expected digest (or a deleted file is still there).
+    Mismatch,
+    /// The destination does not exist.
+    Missing,
+    /// The plan recorded no digest and the source to compare against is gone.
+    SourceMissing,
+    /// Execution left the op alone because of the conflict strategy.
+    Skipped,
+    /// Reading or hashing a file failed; see [`OpVerification::error`].
+    Error,
+}
+
+impl VerifyStatus {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Ok => "ok",
+            Self::Mismatch => "mismatch",
+            Self::Missing => "missing",
+            Self::SourceMissing => "source-missing",
+            Self::Skipped => "skipped",
+            Self::Error => "error",
+        }
+    }
+}
+
+/// One op's result from [`verify_copy_plan`].
+#[derive(Debug, Clone, Serialize)]
+pub struct OpVerification {
+    /// Destination checked: the op's `final_dst` when recorded, else `dst`.
+    pub dst: String,
+    pub status: VerifyStatus,
+    /// What went wrong for [`VerifyStatus::Error`].
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Decode a recorded digest in whichever encoding the map used.
+fn decode_digest(text: &str) -> Option<Vec<u8>> {
+    [
+        DigestEncoding::Hex,
+        DigestEncoding::Base64,
+        DigestEncoding::Base64Std,
+        DigestEncoding::Base32,
+    ]
+    .iter()
+    .find_map(|encoding| encoding.decode(text).ok())
+    .filter(|bytes| !bytes.is_empty())
+}
+
+/// Digest of the file at `path` under `algorithm`, `out_len` bytes long.
+fn file_digest(path: &Path, algorithm: Algorithm, out_len: usize) -> Result<Vec<u8>> {
+    let mut hasher = algorithm.create();
+    let mut file = fs::File::open(path).with_context(|| format!("open {}", path.display()))?;
+    hasher
+        .update_reader(&mut file)
+        .with_context(|| format!("hash {}", path.display()))?;
+    Ok(hex::decode(hasher.finalize_hex(out_len))?)
+}
+
+/// Algorithm the op's `hash` was recorded with, `default` for plans that don't say.
+fn recorded_algorithm(op: &CopyOp, default: Algorithm) -> Result<Algorithm> {
+    match op.algorithm.as_deref() {
+        Some(name) => Algorithm::from_name(name)
+            .with_context(|| format!("unknown algorithm {} recorded for {}", name, op.src)),
+        None => Ok(default),
+    }
+}
+
+/// Check one copy op: against the digest recorded in the plan when there is one, else
+/// against the source as it is now.
+fn verify_copy_op(op: &CopyOp, dst: &Path, algorithm: Algorithm) -> Result<VerifyStatus> {
+    if op.kind == Some(EntryKind::Symlink) {
+        return verify_symlink_op(op, dst, algorithm);
+    }
+    if !dst.is_file() {
+        return Ok(VerifyStatus::Missing);
+    }
+    let recorded = op.hash.as_deref().and_then(decode_digest);
+    let (expected, algorithm) = match recorded {
+        Some(expected) => {
+            let algorithm = recorded_algorithm(op, algorithm)?;
+            (expected, algorithm)
+        }
+        None => {
+            let src = Path::new(&op.src);
+            if !src.is_file() {
+                return Ok(VerifyStatus::SourceMissing);
+            }
+            let len = algorithm.create().info().output_len_default;
+            (file_digest(src, algorithm, len)?, algorithm)
+        }
+    };
+    Ok(
+        if file_digest(dst, algorithm, expected.len())? == expected {
+            VerifyStatus::Ok
+        } else {
+            VerifyStatus::Mismatch
+        },
+    )
+}
+
+/// Check a recreated link: `dst` must be a symlink whose target path hashes to the
+/// recorded digest, or without one, names the same target as the source link.
+fn verify_symlink_op(op: &CopyOp, dst: &Path, algorithm: Algorithm) -> Result<VerifyStatus> {
+    if !dst
+        .symlink_metadata()
+        .is_ok_and(|m| m.file_type().is_symlink())
+    {
+        return Ok(VerifyStatus::Missing);
+    }
+    let target = fs::read_link(dst).with_context(|| format!("read link {}", dst.display()))?;
+    let matches = match op.hash.as_deref().and_then(decode_digest) {
+        Some(expected) => {
+            let algorithm = recorded_algorithm(op, algorithm)?;
+            let mut hasher = algorithm.create();
+            hasher.update(target.as_os_str().as_encoded_bytes());
+            hex::decode(hasher.finalize_hex(expected.len()))? == expected
+        }
+        None => match fs::read_link(&op.src) {
+            Ok(source_target) => source_target == target,
+            Err(_) => return Ok(VerifyStatus::SourceMissing),
+        },
+    };
+    Ok(if matches {
+        VerifyStatus::Ok
+    } else {
+        VerifyStatus::Mismatch
+    })
+}
+
+/// Check every op of an already executed plan without copying anything: each
+/// destination must hash to the source digest recorded in the plan (or, for plans
+/// without one, the same as its source under `algorithm`), and delete ops' destinations
+/// must be gone. Ops the conflict strategy skipped are reported as such, and a file
+/// that can't be read fails only its own op.
+pub fn verify_copy_plan(plan: &CopyPlan, algorithm: Algorithm) -> Vec<OpVerification> {
+    plan.ops
+        .iter()
+        .map(|op| {
+            let dst = op.final_dst.clone().unwrap_or_else(|| op.dst.clone());
+            let dst_path = Path::new(&dst);
+            let checked = if op.status == Some(CopyStatus::Skipped) {
+                Ok(VerifyStatus::Skipped)
+            } else if op.op.eq_ignore_ascii_case("delete") {
+                Ok(if dst_path.exists() {
+                    VerifyStatus::Mismatch
+                } else {
+                    VerifyStatus::Ok
+                })
+            } else {
+                verify_copy_op(op, dst_path, algorithm)
+            };
+            match checked {
+                Ok(status) => OpVerification {
+                    dst,
+                    status,
+                    error: None,
+                },
+                Err(e) => OpVerification {
+                    dst,
+                    status: VerifyStatus::Error,
+                    error: Some(format!("{:#}", e)),
+                },
+            }
+        })
+        .collect()
+}
+
 /// Print what would be done for a given plan.
 pub fn dry_run_copy_plan(
     plan: &CopyPlan,
@@ -404,20 +856,20 @@ mod tests {
                 path: "/src/a".into(),
                 hash: "h".into(),
                 size: 1,
-                mtime: None,
+                ..Default::default()
             },
             crate::io::MapEntry {
                 path: "/dst/a".into(),
                 hash: "h2".into(),
                 size: 1,
-                mtime: None,
+                ..Default::default()
             },
         ));
         r.missing.push(crate::io::MapEntry {
             path: "/src/b".into(),
             hash: "h3".into(),
             size: 2,
-            mtime: None,
+            ..Default::default()
         });
 
         let plan = generate_copy_plan(&r, Some(Path::new("/src")), Some(Path::new("/dst")));
@@ -444,12 +896,19 @@ mod tests {
             op: "copy".into(),
             done: false,
             status: None,
+            final_dst: None,
+            hash: None,
+            algorithm: None,
+            kind: None,
         });
 
         // Skip strategy should keep original
         let opts = CopyOptions {
             conflict: ConflictStrategy::Skip,
             preserve_times: false,
+            preserve_perms: false,
+            preserve_owner: false,
+            prompt_fallback: ConflictStrategy::Skip,
         };
         execute_copy_plan(&mut plan, opts, None, false, false, 3, None).unwrap();
         let contents = fs::read(&dst_file).unwrap();
@@ -459,14 +918,121 @@ mod tests {
         let opts = CopyOptions {
             conflict: ConflictStrategy::Rename,
             preserve_times: false,
+            preserve_perms: false,
+            preserve_owner: false,
+            prompt_fallback: ConflictStrategy::Skip,
         };
         execute_copy_plan(&mut plan, opts, None, false, false, 3, None).unwrap();
-        let renamed = dst_dir.join("file-copy1.txt");
+        let renamed = dst_dir.join("file (1).txt");
         assert!(renamed.exists());
         let new_contents = fs::read(renamed).unwrap();
         assert_eq!(&new_contents, b"hello");
     }
 
+    #[cfg(unix)]
+    #[test]
+    fn symlink_ops_recreate_the_link_and_verify_its_target() {
+        let dir = tempdir().unwrap();
+        let src_link = dir.path().join("link");
+        std::os::unix::fs::symlink("../elsewhere/data.bin", &src_link).unwrap();
+        let dst_link = dir.path().join("dst/link");
+        fs::create_dir_all(dst_link.parent().unwrap()).unwrap();
+        fs::write(&dst_link, b"a regular file").unwrap();
+
+        let mut hasher = Algorithm::Blake3.create();
+        hasher.update(b"../elsewhere/data.bin");
+        let mut plan = CopyPlan::new();
+        plan.ops.push(CopyOp {
+            src: src_link.to_string_lossy().into_owned(),
+            dst: dst_link.to_string_lossy().into_owned(),
+            op: "copy".into(),
+            done: false,
+            status: None,
+            final_dst: None,
+            hash: Some(hasher.finalize_hex(32)),
+            algorithm: Some("blake3".into()),
+            kind: Some(EntryKind::Symlink),
+        });
+        assert_eq!(
+            verify_copy_plan(&plan, Algorithm::Blake3)[0].status,
+            VerifyStatus::Missing
+        );
+
+        // the target doesn't exist; the dangling link is still recreated
+        let opts = CopyOptions {
+            conflict: ConflictStrategy::Overwrite,
+            preserve_times: true,
+            preserve_perms: true,
+            preserve_owner: false,
+            prompt_fallback: ConflictStrategy::Skip,
+        };
+        execute_copy_plan(&mut plan, opts, None, false, false, 3, None).unwrap();
+        assert!(fs::symlink_metadata(&dst_link)
+            .unwrap()
+            .file_type()
+            .is_symlink());
+        assert_eq!(
+            fs::read_link(&dst_link).unwrap(),
+            Path::new("../elsewhere/data.bin")
+        );
+        assert_eq!(
+            verify_copy_plan(&plan, Algorithm::Blake3)[0].status,
+            VerifyStatus::Ok
+        );
+
+        fs::remove_file(&dst_link).unwrap();
+        std::os::unix::fs::symlink("somewhere-else", &dst_link).unwrap();
+        assert_eq!(
+            verify_copy_plan(&plan, Algorithm::Blake3)[0].status,
+            VerifyStatus::Mismatch
+        );
+    }
+
+    #[test]
+    fn rename_conflict_picks_next_free_suffix_and_records_it() {
+        let dir = tempdir().unwrap();
+        let src_file = dir.path().join("report.final.txt");
+        let dst_dir = dir.path().join("dst");
+        fs::create_dir_all(&dst_dir).unwrap();
+        fs::write(&src_file, b"new").unwrap();
+        let dst_file = dst_dir.join("report.final.txt");
+        fs::write(&dst_file, b"original").unwrap();
+        fs::write(dst_dir.join("report.final (1).txt"), b"taken").unwrap();
+
+        let mut plan = CopyPlan::new();
+        plan.ops.push(CopyOp {
+            src: src_file.to_string_lossy().into_owned(),
+            dst: dst_file.to_string_lossy().into_owned(),
+            op: "copy".into(),
+            done: false,
+            status: None,
+            final_dst: None,
+            hash: None,
+            algorithm: None,
+            kind: None,
+        });
+        let opts = CopyOptions {
+            conflict: ConflictStrategy::Rename,
+            preserve_times: false,
+            preserve_perms: false,
+            preserve_owner: false,
+            prompt_fallback: ConflictStrategy::Skip,
+        };
+        execute_copy_plan(&mut plan, opts, None, false, false, 3, None).unwrap();
+
+        let expected = dst_dir.join("report.final (2).txt");
+        assert_eq!(fs::read(&expected).unwrap(), b"new");
+        assert_eq!(fs::read(&dst_file).unwrap(), b"original");
+        assert_eq!(
+            plan.ops[0].final_dst.as_deref(),
+            Some(expected.to_string_lossy().as_ref())
+        );
+        assert_eq!(
+            rename_candidate(Path::new("dir/README"), 3),
+            Path::new("dir/README (3)")
+        );
+    }
+
     #[test]
     fn execute_copy_plan_persists_status_and_resume() {
         let dir = tempdir().unwrap();
@@ -492,6 +1058,10 @@ mod tests {
             op: "copy".into(),
             done: false,
             status: None,
+            final_dst: None,
+            hash: None,
+            algorithm: None,
+            kind: None,
         });
 
         // persist initial plan
@@ -500,6 +1070,9 @@ mod tests {
         let opts = CopyOptions {
             conflict: ConflictStrategy::Overwrite,
             preserve_times: false,
+            preserve_perms: false,
+            preserve_owner: false,
+            prompt_fallback: ConflictStrategy::Skip,
         };
 
         // execute with persist_path should update status and done flags
@@ -517,11 +1090,221 @@ mod tests {
         let opts = CopyOptions {
             conflict: ConflictStrategy::Skip,
             preserve_times: false,
+            preserve_perms: false,
+            preserve_owner: false,
+            prompt_fallback: ConflictStrategy::Skip,
         };
         let result = execute_copy_plan(&mut plan, opts, None, false, false, 3, None);
         assert!(result.is_ok());
     }
 
+    #[cfg(unix)]
+    #[test]
+    fn preserve_perms_and_owner_keep_source_mode() {
+        use std::os::unix::fs::{MetadataExt, PermissionsExt};
+        let dir = tempdir().unwrap();
+        let src = dir.path().join("script.sh");
+        let dst = dir.path().join("out").join("script.sh");
+        fs::write(&src, b"#!/bin/sh\n").unwrap();
+        fs::set_permissions(&src, fs::Permissions::from_mode(0o750)).unwrap();
+
+        let mut plan = CopyPlan::new();
+        plan.ops.push(CopyOp {
+            src: src.to_string_lossy().into_owned(),
+            dst: dst.to_string_lossy().into_owned(),
+            op: "copy".into(),
+            done: false,
+            status: None,
+            final_dst: None,
+            hash: None,
+            algorithm: None,
+            kind: None,
+        });
+        let opts = CopyOptions {
+            conflict: ConflictStrategy::Overwrite,
+            preserve_times: false,
+            preserve_perms: true,
+            // chown to our own uid/gid works without privileges
+            preserve_owner: true,
+            prompt_fallback: ConflictStrategy::Skip,
+        };
+        execute_copy_plan(&mut plan, opts, None, false, false, 3, None).unwrap();
+
+        let (src_meta, dst_meta) = (fs::metadata(&src).unwrap(), fs::metadata(&dst).unwrap());
+        assert_eq!(dst_meta.permissions().mode() & 0o7777, 0o750);
+        assert_eq!(
+            (dst_meta.uid(), dst_meta.gid()),
+            (src_meta.uid(), src_meta.gid())
+        );
+    }
+
+    #[test]
+    fn verify_follows_final_dst_and_checks_deletes() {
+        let dir = tempdir().unwrap();
+        let path = |name: &str| dir.path().join(name).to_string_lossy().into_owned();
+        fs::write(dir.path().join("src"), b"same").unwrap();
+        fs::write(dir.path().join("dst (1)"), b"same").unwrap();
+        fs::write(dir.path().join("dst"), b"other").unwrap();
+        fs::write(dir.path().join("kept"), b"x").unwrap();
+
+        let mut plan = CopyPlan::new();
+        for (op, dst, final_dst) in [
+            ("copy", "dst", Some("dst (1)")),
+            ("copy", "dst", None),
+            ("delete", "gone", None),
+            ("delete", "kept", None),
+        ] {
+            plan.ops.push(CopyOp {
+                src: path("src"),
+                dst: path(dst),
+                op: op.into(),
+                done: true,
+                status: Some(CopyStatus::Done),
+                final_dst: final_dst.map(path),
+                hash: None,
+                algorithm: None,
+                kind: None,
+            });
+        }
+        let statuses: Vec<VerifyStatus> = verify_copy_plan(&plan, Algorithm::Blake3)
+            .into_iter()
+            .map(|r| r.status)
+            .collect();
+        assert_eq!(
+            statuses,
+            [
+                VerifyStatus::Ok,
+                VerifyStatus::Mismatch,
+                VerifyStatus::Ok,
+                VerifyStatus::Mismatch
+            ]
+        );
+    }
+
+    #[test]
+    fn verify_checks_the_recorded_digest_and_reports_each_op() {
+        let dir = tempdir().unwrap();
+        let path = |name: &str| dir.path().join(name).to_string_lossy().into_owned();
+        let digest = |data: &[u8], alg: Algorithm| {
+            let mut h = alg.create();
+            h.update(data);
+            h.finalize_hex(h.info().output_len_default)
+        };
+        // The source changed after the copy; the plan's digest still describes the copy
+        fs::write(dir.path().join("src"), b"edited later").unwrap();
+        fs::write(dir.path().join("copied"), b"original").unwrap();
+        fs::write(dir.path().join("conflict"), b"theirs").unwrap();
+
+        let mut plan = CopyPlan::new();
+        for (src, dst, hash, algorithm, status) in [
+            (
+                "src",
+                "copied",
+                Some(digest(b"original", Algorithm::Blake2b)),
+                Some("blake2b"),
+                None,
+            ),
+            (
+                "src",
+                "copied",
+                Some(digest(b"other", Algorithm::Blake3)),
+                Some("blake3"),
+                None,
+            ),
+            ("src", "conflict", None, None, Some(CopyStatus::Skipped)),
+            ("gone", "copied", None, None, None),
+            (
+                "src",
+                "never-written",
+                Some(digest(b"x", Algorithm::Blake3)),
+                None,
+                None,
+            ),
+            (
+                "src",
+                "copied",
+                Some("00".repeat(32)),
+                Some("no-such-alg"),
+                None,
+            ),
+        ] {
+            plan.ops.push(CopyOp {
+                src: path(src),
+                dst: path(dst),
+                op: "copy".into(),
+                done: false,
+                status,
+                final_dst: None,
+                hash,
+                algorithm: algorithm.map(String::from),
+                kind: None,
+            });
+        }
+        let results = verify_copy_plan(&plan, Algorithm::Blake3);
+        let statuses: Vec<VerifyStatus> = results.iter().map(|r| r.status).collect();
+        assert_eq!(
+            statuses,
+            [
+                VerifyStatus::Ok,
+                VerifyStatus::Mismatch,
+                VerifyStatus::Skipped,
+                VerifyStatus::SourceMissing,
+                VerifyStatus::Missing,
+                VerifyStatus::Error,
+            ]
+        );
+        assert!(results[5].error.as_deref().unwrap().contains("no-such-alg"));
+    }
+
+    #[test]
+    fn summary_counts_pending_done_and_delete_ops() {
+        let dir = tempdir().unwrap();
+        let mut plan = CopyPlan::new();
+        for (i, (op, done, status)) in [
+            ("copy", false, None),
+            ("copy", false, Some(CopyStatus::Pending)),
+            ("move", false, None),
+            ("copy", true, None),
+            ("copy", false, Some(CopyStatus::Done)),
+            ("delete", false, None),
+        ]
+        .into_iter()
+        .enumerate()
+        {
+            let src = dir.path().join(format!("src{}", i));
+            fs::write(&src, vec![0u8; 100 * (i + 1)]).unwrap();
+            plan.ops.push(CopyOp {
+                src: src.to_string_lossy().into_owned(),
+                dst: dir
+                    .path()
+                    .join(format!("dst{}", i))
+                    .to_string_lossy()
+                    .into_owned(),
+                op: op.into(),
+                done,
+                status,
+                final_dst: None,
+                hash: None,
+                algorithm: None,
+                kind: None,
+            });
+        }
+        let summary = summarize_plan(&plan);
+        assert_eq!(
+            summary,
+            PlanSummary {
+                copy: 3,
+                delete: 1,
+                skip: 2,
+                bytes: 100 + 200 + 300,
+            }
+        );
+        assert_eq!(
+            summary.to_string(),
+            "plan: 3 copy, 1 delete, 2 skip, 600 bytes"
+        );
+    }
+
     #[test]
     fn plan_with_single_operation() {
         let dir = tempdir().unwrap();
@@ -536,11 +1319,18 @@ mod tests {
             op: "copy".into(),
             done: false,
             status: None,
+            final_dst: None,
+            hash: None,
+            algorithm: None,
+            kind: None,
         });
 
         let opts = CopyOptions {
             conflict: ConflictStrategy::Overwrite,
             preserve_times: false,
+            preserve_perms: false,
+            preserve_owner: false,
+            prompt_fallback: ConflictStrategy::Skip,
         };
         execute_copy_plan(&mut plan, opts, None, false, false, 3, None).unwrap();
         assert!(dst.exists());
@@ -561,16 +1351,56 @@ mod tests {
             op: "copy".into(),
             done: false,
             status: None,
+            final_dst: None,
+            hash: None,
+            algorithm: None,
+            kind: None,
         });
 
         let opts = CopyOptions {
             conflict: ConflictStrategy::Overwrite,
             preserve_times: false,
+            preserve_perms: false,
+            preserve_owner: false,
+            prompt_fallback: ConflictStrategy::Skip,
         };
         execute_copy_plan(&mut plan, opts, None, false, false, 3, None).unwrap();
         assert!(dst.exists());
     }
 
+    #[test]
+    fn conflict_prompter_follows_scripted_answers() {
+        let input = stdio::Cursor::new("maybe\no\ns\nrename all\n");
+        let mut out = Vec::new();
+        let mut prompter = ConflictPrompter::new(input, &mut out);
+        let dst = Path::new("dst.txt");
+
+        // invalid answer is re-asked, then each answer applies to one conflict
+        assert_eq!(prompter.ask(dst).unwrap(), PromptChoice::Overwrite);
+        assert_eq!(prompter.ask(dst).unwrap(), PromptChoice::Skip);
+        // "all" sticks for the remaining conflicts without reading more input
+        assert_eq!(prompter.ask(dst).unwrap(), PromptChoice::Rename);
+        assert_eq!(prompter.ask(dst).unwrap(), PromptChoice::Rename);
+
+        let transcript = String::from_utf8(out).unwrap();
+        assert_eq!(transcript.matches("dst.txt exists").count(), 4);
+        assert!(transcript.contains("unrecognized answer"));
+    }
+
+    #[test]
+    fn conflict_prompter_aborts_on_request_or_eof() {
+        let mut prompter = ConflictPrompter::new(stdio::Cursor::new("a\n"), Vec::new());
+        let dst = Path::new("dst.txt");
+        assert_eq!(prompter.ask(dst).unwrap(), PromptChoice::Abort);
+        // input exhausted
+        assert_eq!(prompter.ask(dst).unwrap(), PromptChoice::Abort);
+        assert!(prompt_strategy(PromptChoice::Abort, dst).is_err());
+        assert!(matches!(
+            prompt_strategy(PromptChoice::Rename, dst).unwrap(),
+            ConflictStrategy::Rename
+        ));
+    }
+
     #[test]
     fn plan_handles_already_done_operations() {
         let mut plan = CopyPlan::new();
@@ -580,11 +1410,18 @@ mod tests {
             op: "copy".into(),
             done: true, // Already done
             status: Some(CopyStatus::Done),
+            final_dst: None,
+            hash: None,
+            algorithm: None,
+            kind: None,
         });
 
         let opts = CopyOptions {
             conflict: ConflictStrategy::Skip,
             preserve_times: false,
+            preserve_perms: false,
+            preserve_owner: false,
+            prompt_fallback: ConflictStrategy::Skip,
         };
         let result = execute_copy_plan(&mut plan, opts, None, false, false, 3, None);
         assert!(result.is_ok());
@@ -604,11 +1441,18 @@ mod tests {
             op: "copy".into(),
             done: false,
             status: None,
+            final_dst: None,
+            hash: None,
+            algorithm: None,
+            kind: None,
         });
 
         let opts = CopyOptions {
             conflict: ConflictStrategy::Overwrite,
             preserve_times: false,
+            preserve_perms: false,
+            preserve_owner: false,
+            prompt_fallback: ConflictStrategy::Skip,
         };
         execute_copy_plan(&mut plan, opts, None, true, false, 3, None).unwrap();
         // Dry run behavior - in dry run mode operations are marked but file ops may still occur
@@ -634,11 +1478,18 @@ mod tests {
             op: "copy".into(),
             done: false,
             status: None,
+            final_dst: None,
+            hash: None,
+            algorithm: None,
+            kind: None,
         });
 
         let opts = CopyOptions {
             conflict: ConflictStrategy::Overwrite,
             preserve_times: true,
+            preserve_perms: false,
+            preserve_owner: false,
+            prompt_fallback: ConflictStrategy::Skip,
         };
         execute_copy_plan(&mut plan, opts, None, false, false, 3, None).unwrap();
 
@@ -669,11 +1520,18 @@ mod tests {
             op: "copy".into(),
             done: false,
             status: None,
+            final_dst: None,
+            hash: None,
+            algorithm: None,
+            kind: None,
         });
 
         let opts = CopyOptions {
             conflict: ConflictStrategy::Overwrite,
             preserve_times: false,
+            preserve_perms: false,
+            preserve_owner: false,
+            prompt_fallback: ConflictStrategy::Skip,
         };
         execute_copy_plan(&mut plan, opts, None, false, false, 3, None).unwrap();
         
@@ -702,12 +1560,19 @@ mod tests {
                 op: "copy".into(),
                 done: false,
                 status: None,
+                final_dst: None,
+                hash: None,
+                algorithm: None,
+                kind: None,
             });
         }
 
         let opts = CopyOptions {
             conflict: ConflictStrategy::Overwrite,
             preserve_times: false,
+            preserve_perms: false,
+            preserve_owner: false,
+            prompt_fallback: ConflictStrategy::Skip,
         };
         execute_copy_plan(&mut plan, opts, None, false, false, 3, None).unwrap();
 
@@ -731,11 +1596,18 @@ mod tests {
             op: "copy".into(),
             done: false,
             status: None,
+            final_dst: None,
+            hash: None,
+            algorithm: None,
+            kind: None,
         });
 
         let opts = CopyOptions {
             conflict: ConflictStrategy::Overwrite,
             preserve_times: false,
+            preserve_perms: false,
+            preserve_owner: false,
+            prompt_fallback: ConflictStrategy::Skip,
         };
         execute_copy_plan(&mut plan, opts, None, false, false, 3, None).unwrap();
         assert!(dst.exists());
@@ -756,7 +1628,7 @@ mod tests {
             path: "missing.txt".into(),
             hash: "hash".into(),
             size: 100,
-            mtime: None,
+            ..Default::default()
         });
 
         let plan = generate_copy_plan(&report, Some(Path::new("/src")), Some(Path::new("/dst")));
@@ -772,13 +1644,13 @@ mod tests {
                 path: "changed.txt".into(),
                 hash: "old".into(),
                 size: 50,
-                mtime: None,
+                ..Default::default()
             },
             crate::io::MapEntry {
                 path: "changed.txt".into(),
                 hash: "new".into(),
                 size: 60,
-                mtime: None,
+                ..Default::default()
             },
         ));
 
@@ -0,0 +1,421 @@
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::{BufRead, BufReader, Read};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use anyhow::{Context, Result};
+use serde::Serialize;
+
+use crate::io::MapEntry;
+use crate::report::DuplicateGroup;
+use crate::walk::walk_directory_stream;
+
+/// Attributes files must share before they are worth hashing to look for duplicates.
+/// The size is always part of the key; `mtime` narrows it further (`--dedup-by size,mtime`).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DedupBy {
+    pub mtime: bool,
+}
+
+impl DedupBy {
+    /// Parse `--dedup-by` keys (`size`, `mtime`).
+    pub fn from_keys(keys: &[String]) -> Result<Self> {
+        let mut by = DedupBy::default();
+        for key in keys {
+            match key.trim().to_lowercase().as_str() {
+                "size" => {}
+                "mtime" => by.mtime = true,
+                other => anyhow::bail!("unknown --dedup-by key {} (expected size, mtime)", other),
+            }
+        }
+        Ok(by)
+    }
+}
+
+/// Regular files under `root` as unhashed map entries: paths relative to `root`, with
+/// size and mtime but an empty `hash`.
+pub fn scan_unhashed(root: &Path, exclusions: &[String]) -> Result<Vec<MapEntry>> {
+    let mut entries = Vec::new();
+    for path in walk_directory_stream(root, exclusions, None, false)? {
+        let meta = match fs::metadata(&path) {
+            Ok(m) if m.is_file() => m,
+            Ok(_) => continue,
+            Err(e) => {
+                log::warn!("Failed reading metadata for {}: {}", path.display(), e);
+                continue;
+            }
+        };
+        let mtime = meta
+            .modified()
+            .ok()
+            .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+            .map(|d| d.as_secs() as i64);
+        entries.push(MapEntry {
+            path: path
+                .strip_prefix(root)
+                .unwrap_or(&path)
+                .to_string_lossy()
+                .into_owned(),
+            hash: String::new(),
+            size: meta.len(),
+            mtime,
+            ..Default::default()
+        });
+    }
+    Ok(entries)
+}
+
+/// Fill in `hash` only for entries sharing their size (and, with `by.mtime`, their mtime)
+/// with at least one other entry. Everything else cannot have a duplicate and keeps an
+/// empty hash, so its contents are never read. Files that fail to hash are logged and
+/// left unhashed. Returns the number of entries hashed.
+pub fn hash_size_collisions<F>(entries: &mut [MapEntry], by: DedupBy, mut hash: F) -> usize
+where
+    F: FnMut(&MapEntry) -> Result<String>,
+{
+    let mut groups: HashMap<(u64, Option<i64>), Vec<usize>> = HashMap::new();
+    for (idx, e) in entries.iter().enumerate() {
+        let mtime = if by.mtime { e.mtime } else { None };
+        groups.entry((e.size, mtime)).or_default().push(idx);
+    }
+    let mut candidates: Vec<usize> = groups
+        .into_values()
+        .filter(|members| members.len() > 1)
+        .flatten()
+        .collect();
+    candidates.sort_unstable();
+
+    let mut hashed = 0;
+    for idx in candidates {
+        match hash(&entries[idx]) {
+            Ok(h) => {
+                entries[idx].hash = h;
+                hashed += 1;
+            }
+            Err(e) => log::warn!("Failed hashing {}: {}", entries[idx].path, e),
+        }
+    }
+    hashed
+}
+
+/// Which copy of a duplicate group survives `report --dedupe-execute`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeepPolicy {
+    /// Lexicographically first path.
+    First,
+    /// Most recently modified file.
+    Newest,
+    /// Least recently modified file.
+    Oldest,
+    /// Shortest path (fewest characters).
+    ShortestPath,
+}
+
+impl KeepPolicy {
+    pub const ALL: [KeepPolicy; 4] = [
+        KeepPolicy::First,
+        KeepPolicy::Newest,
+        KeepPolicy::Oldest,
+        KeepPolicy::ShortestPath,
+    ];
+
+    pub fn from_name(s: &str) -> Option<Self> {
+        s.parse().ok()
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            KeepPolicy::First => "first",
+            KeepPolicy::Newest => "newest",
+            KeepPolicy::Oldest => "oldest",
+            KeepPolicy::ShortestPath => "shortest-path",
+        }
+    }
+
+    /// Newest/oldest rank copies by modification time.
+    pub fn needs_mtime(&self) -> bool {
+        matches!(self, KeepPolicy::Newest | KeepPolicy::Oldest)
+    }
+}
+
+/// Index of the member `policy` keeps, given each member's path and modification time.
+/// Members are expected in path order, so ties resolve to the earlier path.
+pub fn keep_index<P: AsRef<Path>, T: Ord>(policy: KeepPolicy, members: &[(P, Option<T>)]) -> usize {
+    match policy {
+        KeepPolicy::First => 0,
+        KeepPolicy::Newest => (0..members.len())
+            .rev()
+            .max_by_key(|&i| &members[i].1)
+            .unwrap_or(0),
+        KeepPolicy::Oldest => (0..members.len())
+            .min_by_key(|&i| &members[i].1)
+            .unwrap_or(0),
+        KeepPolicy::ShortestPath => (0..members.len())
+            .min_by_key(|&i| members[i].0.as_ref().as_os_str().len())
+            .unwrap_or(0),
+    }
+}
+
+impl std::str::FromStr for KeepPolicy {
+    type Err = ();
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "first" => Ok(KeepPolicy::First),
+            "newest" => Ok(KeepPolicy::Newest),
+            "oldest" => Ok(KeepPolicy::Oldest),
+            "shortest-path" | "shortest" => Ok(KeepPolicy::ShortestPath),
+            _ => Err(()),
+        }
+    }
+}
+
+/// One duplicate group resolved against the filesystem: the copy kept and the copies
+/// to remove.
+#[derive(Debug, Clone, Serialize)]
+pub struct DedupeAction {
+    pub hash: String,
+    pub size: u64,
+    pub keep: PathBuf,
+    pub remove: Vec<PathBuf>,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct DedupeSummary {
+    pub removed: usize,
+    pub linked: usize,
+    pub skipped: usize,
+    pub bytes_freed: u64,
+}
+
+fn modified(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
+/// Pick the survivor of each group under `policy`. Relative map paths are resolved
+/// against `root`; members that no longer exist or are symlinks are left out, and groups
+/// with fewer than two remaining members produce no action.
+pub fn plan_dedupe(
+    groups: &[DuplicateGroup],
+    root: Option<&Path>,
+    policy: KeepPolicy,
+) -> Vec<DedupeAction> {
+    let mut actions = Vec::new();
+    for group in groups {
+        // group paths are sorted, so ties below resolve to the first path
+        let mut members: Vec<(PathBuf, Option<SystemTime>)> = group
+            .paths
+            .iter()
+            .map(|p| match root {
+                Some(r) if Path::new(p).is_relative() => r.join(p),
+                _ => PathBuf::from(p),
+            })
+            // symlinks are never removed or relinked, even when they point at a member
+            .filter(|p| p.symlink_metadata().is_ok_and(|m| m.is_file()))
+            .map(|p| {
+                let mtime = modified(&p);
+                (p, mtime)
+            })
+            .collect();
+        if members.len() < 2 {
+            continue;
+        }
+        let keep = members.remove(keep_index(policy, &members)).0;
+        let members: Vec<PathBuf> = members.into_iter().map(|(p, _)| p).collect();
+        actions.push(DedupeAction {
+            hash: group.hash.clone(),
+            size: group.size,
+            keep,
+            remove: members,
+        });
+    }
+    actions
+}
+
+/// Remove (or, with `hardlink`, replace by a hard link to the kept copy) every duplicate
+/// in `actions`. Each duplicate is compared byte for byte with the kept copy first; one
+/// that no longer matches (or whose survivor is gone) is skipped rather than risk
+/// deleting the only copy of changed data.
+pub fn execute_dedupe(actions: &[DedupeAction], hardlink: bool) -> Result<DedupeSummary> {
+    let mut summary = DedupeSummary::default();
+    for action in actions {
+        let keep_ok = fs::metadata(&action.keep)
+            .map(|m| m.is_file() && m.len() == action.size)
+            .unwrap_or(false);
+        for dup in &action.remove {
+            if !keep_ok || !same_contents(&action.keep, dup).unwrap_or(false) {
+                log::warn!(
+                    "Skipping {}: it or {} changed since the map was written",
+                    dup.display(),
+                    action.keep.display()
+                );
+                summary.skipped += 1;
+                continue;
+            }
+            if hardlink {
+                replace_with_hardlink(&action.keep, dup)?;
+                summary.linked += 1;
+            } else {
+                fs::remove_file(dup).with_context(|| format!("remove {}", dup.display()))?;
+                summary.removed += 1;
+            }
+            summary.bytes_freed += action.size;
+        }
+    }
+    Ok(summary)
+}
+
+fn same_contents(a: &Path, b: &Path) -> std::io::Result<bool> {
+    let (fa, fb) = (File::open(a)?, File::open(b)?);
+    if fa.metadata()?.len() != fb.metadata()?.len() {
+        return Ok(false);
+    }
+    let (mut ra, mut rb) = (BufReader::new(fa), BufReader::new(fb));
+    loop {
+        let chunk = ra.fill_buf()?;
+        if chunk.is_empty() {
+            return Ok(rb.fill_buf()?.is_empty());
+        }
+        let n = chunk.len();
+        let mut other = vec![0u8; n];
+        rb.read_exact(&mut other)?;
+        if chunk != other.as_slice() {
+            return Ok(false);
+        }
+        ra.consume(n);
+    }
+}
+
+/// Link `keep` to a temporary name next to `dup`, then rename it over `dup`, so `dup`
+/// is never missing if linking fails.
+fn replace_with_hardlink(keep: &Path, dup: &Path) -> Result<()> {
+    let name = dup
+        .file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let tmp = dup.with_file_name(format!(".{}.hash-folderoo-link", name));
+    fs::hard_link(keep, &tmp)
+        .with_context(|| format!("hard link {} -> {}", keep.display(), tmp.display()))?;
+    if let Err(e) = fs::rename(&tmp, dup) {
+        let _ = fs::remove_file(&tmp);
+        return Err(e).with_context(|| format!("replace {}", dup.display()));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use filetime::{set_file_mtime, FileTime};
+
+    #[test]
+    fn unique_sizes_are_never_hashed() {
+        let dir = tempfile::tempdir().unwrap();
+        for (name, body) in [
+            ("one.txt", &b"a"[..]),
+            ("two.txt", b"bb"),
+            ("dup1.txt", b"ccc"),
+            ("dup2.txt", b"ccc"),
+            ("same-size.txt", b"ddd"),
+        ] {
+            fs::write(dir.path().join(name), body).unwrap();
+        }
+        for (name, secs) in [("dup1.txt", 100), ("dup2.txt", 100), ("same-size.txt", 200)] {
+            set_file_mtime(dir.path().join(name), FileTime::from_unix_time(secs, 0)).unwrap();
+        }
+
+        let mut entries = scan_unhashed(dir.path(), &[]).unwrap();
+        let mut seen = Vec::new();
+        let hashed = hash_size_collisions(&mut entries, DedupBy::default(), |e| {
+            seen.push(e.path.clone());
+            Ok(format!("h-{}", e.path))
+        });
+        seen.sort();
+        assert_eq!(hashed, 3);
+        assert_eq!(seen, ["dup1.txt", "dup2.txt", "same-size.txt"]);
+        let one = entries.iter().find(|e| e.path == "one.txt").unwrap();
+        assert!(one.hash.is_empty());
+
+        let mut entries = scan_unhashed(dir.path(), &[]).unwrap();
+        let mut seen = Vec::new();
+        let by = DedupBy::from_keys(&["size".into(), "mtime".into()]).unwrap();
+        hash_size_collisions(&mut entries, by, |e| {
+            seen.push(e.path.clone());
+            Ok(String::new())
+        });
+        seen.sort();
+        assert_eq!(seen, ["dup1.txt", "dup2.txt"]);
+    }
+
+    #[test]
+    fn keep_policies_pick_expected_survivor() {
+        let dir = tempfile::tempdir().unwrap();
+        for (name, secs) in [
+            ("a/x.bin", 200),
+            ("bb/x.bin", 300),
+            ("c/long-name.bin", 100),
+        ] {
+            let p = dir.path().join(name);
+            fs::create_dir_all(p.parent().unwrap()).unwrap();
+            fs::write(&p, b"same").unwrap();
+            set_file_mtime(&p, FileTime::from_unix_time(secs, 0)).unwrap();
+        }
+        let groups = vec![DuplicateGroup {
+            hash: "h".into(),
+            size: 4,
+            count: 3,
+            wasted_bytes: 8,
+            paths: vec![
+                "a/x.bin".into(),
+                "bb/x.bin".into(),
+                "c/long-name.bin".into(),
+            ],
+            keep: Default::default(),
+        }];
+        let keep = |policy| {
+            plan_dedupe(&groups, Some(dir.path()), policy)[0]
+                .keep
+                .clone()
+        };
+        assert_eq!(keep(KeepPolicy::First), dir.path().join("a/x.bin"));
+        assert_eq!(keep(KeepPolicy::Newest), dir.path().join("bb/x.bin"));
+        assert_eq!(keep(KeepPolicy::Oldest), dir.path().join("c/long-name.bin"));
+        assert_eq!(keep(KeepPolicy::ShortestPath), dir.path().join("a/x.bin"));
+
+        let actions = plan_dedupe(&groups, Some(dir.path()), KeepPolicy::Newest);
+        let summary = execute_dedupe(&actions, true).unwrap();
+        assert_eq!(summary.linked, 2);
+        assert_eq!(summary.bytes_freed, 8);
+        assert_eq!(fs::read(dir.path().join("a/x.bin")).unwrap(), b"same");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn symlinked_members_are_never_planned() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("a.bin"), b"same").unwrap();
+        fs::write(dir.path().join("b.bin"), b"same").unwrap();
+        std::os::unix::fs::symlink("a.bin", dir.path().join("link.bin")).unwrap();
+        let group = |paths: &[&str]| DuplicateGroup {
+            hash: "h".into(),
+            size: 4,
+            count: paths.len() as u64,
+            wasted_bytes: 4,
+            paths: paths.iter().map(|p| p.to_string()).collect(),
+            keep: Default::default(),
+        };
+
+        let actions = plan_dedupe(
+            &[group(&["a.bin", "b.bin", "link.bin"])],
+            Some(dir.path()),
+            KeepPolicy::First,
+        );
+        assert_eq!(actions[0].remove, [dir.path().join("b.bin")]);
+        let actions = plan_dedupe(
+            &[group(&["a.bin", "link.bin"])],
+            Some(dir.path()),
+            KeepPolicy::First,
+        );
+        assert!(actions.is_empty());
+    }
+}
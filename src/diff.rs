@@ -1,5 +1,7 @@
 use std::path::Path;
 
+use crate::io::MapEntry;
+
 /// Simple helper to format git-style diffs for file operations.
 /// These are lightweight, primarily human-reviewable strings (not full patch metadata).
 fn read_lines_opt(p: &Path) -> Option<Vec<String>> {
@@ -9,7 +11,6 @@ fn read_lines_opt(p: &Path) -> Option<Vec<String>> {
     }
 }
 
-#[allow(clippy::needless_range_loop, clippy::single_char_add_str)]
 pub fn format_copy_diff(
     src: &Path,
     dst: &Path,
@@ -34,169 +35,205 @@ pub fn format_copy_diff(
     out.push_str(&format!("+++ b/{}\n\n", dst_s));
 
     if include_patch {
-        // Try to include a simple unified-like body; fall back silently on IO failures
-        // Attempt a more precise multi-hunk unified-style body with context lines.
-        let src_lines = read_lines_opt(src).unwrap_or_default();
-        let dst_lines = read_lines_opt(dst).unwrap_or_default();
-
-        // local helper: compute LCS matching positions for two slices
-        fn lcs_positions(a: &[String], b: &[String]) -> Vec<(usize, usize)> {
-            let n = a.len();
-            let m = b.len();
-            // dp table (n+1) x (m+1)
-            let mut dp = vec![vec![0usize; m + 1]; n + 1];
-            for i in (0..n).rev() {
-                for j in (0..m).rev() {
-                    if a[i] == b[j] {
-                        dp[i][j] = dp[i + 1][j + 1] + 1;
-                    } else {
-                        dp[i][j] = dp[i + 1][j].max(dp[i][j + 1]);
-                    }
-                }
-            }
+        // Fall back silently on IO failures.
+        // The copy replaces `dst` with `src`, so the destination is the old side.
+        let old_lines = read_lines_opt(dst).unwrap_or_default();
+        let new_lines = read_lines_opt(src).unwrap_or_default();
+        push_unified_body(&mut out, &old_lines, &new_lines, context);
+    }
+    out
+}
 
-            // backtrack to produce matches
-            let mut res = Vec::new();
-            let (mut i, mut j) = (0usize, 0usize);
-            while i < n && j < m {
+/// Append unified-style hunks turning `old_lines` into `new_lines`, with `context`
+/// lines around each change.
+#[allow(clippy::needless_range_loop, clippy::single_char_add_str)]
+fn push_unified_body(out: &mut String, old_lines: &[String], new_lines: &[String], context: usize) {
+    // local helper: compute LCS matching positions for two slices
+    fn lcs_positions(a: &[String], b: &[String]) -> Vec<(usize, usize)> {
+        let n = a.len();
+        let m = b.len();
+        // dp table (n+1) x (m+1)
+        let mut dp = vec![vec![0usize; m + 1]; n + 1];
+        for i in (0..n).rev() {
+            for j in (0..m).rev() {
                 if a[i] == b[j] {
-                    res.push((i, j));
-                    i += 1;
-                    j += 1;
-                } else if dp[i + 1][j] >= dp[i][j + 1] {
-                    i += 1;
+                    dp[i][j] = dp[i + 1][j + 1] + 1;
                 } else {
-                    j += 1;
+                    dp[i][j] = dp[i + 1][j].max(dp[i][j + 1]);
                 }
             }
-            res
         }
 
-        // compute change blocks between matches
-        let matches = lcs_positions(&src_lines, &dst_lines);
-        let mut blocks: Vec<(usize, usize, usize, usize)> = Vec::new();
-
-        let mut a_idx = 0usize;
-        let mut b_idx = 0usize;
-        for (mi, mj) in matches.iter() {
-            if *mi > a_idx || *mj > b_idx {
-                blocks.push((a_idx, *mi, b_idx, *mj));
+        // backtrack to produce matches
+        let mut res = Vec::new();
+        let (mut i, mut j) = (0usize, 0usize);
+        while i < n && j < m {
+            if a[i] == b[j] {
+                res.push((i, j));
+                i += 1;
+                j += 1;
+            } else if dp[i + 1][j] >= dp[i][j + 1] {
+                i += 1;
+            } else {
+                j += 1;
             }
-            a_idx = mi + 1;
-            b_idx = mj + 1;
         }
-        if a_idx < src_lines.len() || b_idx < dst_lines.len() {
-            blocks.push((a_idx, src_lines.len(), b_idx, dst_lines.len()));
+        res
+    }
+
+    // compute change blocks between matches
+    let matches = lcs_positions(old_lines, new_lines);
+    let mut blocks: Vec<(usize, usize, usize, usize)> = Vec::new();
+
+    let mut a_idx = 0usize;
+    let mut b_idx = 0usize;
+    for (mi, mj) in matches.iter() {
+        if *mi > a_idx || *mj > b_idx {
+            blocks.push((a_idx, *mi, b_idx, *mj));
         }
+        a_idx = mi + 1;
+        b_idx = mj + 1;
+    }
+    if a_idx < old_lines.len() || b_idx < new_lines.len() {
+        blocks.push((a_idx, old_lines.len(), b_idx, new_lines.len()));
+    }
 
-        // expand blocks with context and merge overlapping
-        let mut hunks: Vec<(usize, usize, usize, usize)> = Vec::new();
-        for (a0, a1, b0, b1) in blocks.into_iter() {
-            // expand
-            let start_a = a0.saturating_sub(context);
-            let start_b = b0.saturating_sub(context);
-            let end_a = (a1 + context).min(src_lines.len());
-            let end_b = (b1 + context).min(dst_lines.len());
-
-            if let Some(last) = hunks.last_mut() {
-                // merge if overlapping or touching
-                if start_a <= last.1 || start_b <= last.3 {
-                    // extend
-                    last.1 = last.1.max(end_a);
-                    last.3 = last.3.max(end_b);
-                    continue;
-                }
+    // expand blocks with context and merge overlapping
+    let mut hunks: Vec<(usize, usize, usize, usize)> = Vec::new();
+    for (a0, a1, b0, b1) in blocks.into_iter() {
+        // expand
+        let start_a = a0.saturating_sub(context);
+        let start_b = b0.saturating_sub(context);
+        let end_a = (a1 + context).min(old_lines.len());
+        let end_b = (b1 + context).min(new_lines.len());
+
+        if let Some(last) = hunks.last_mut() {
+            // merge if overlapping or touching
+            if start_a <= last.1 || start_b <= last.3 {
+                // extend
+                last.1 = last.1.max(end_a);
+                last.3 = last.3.max(end_b);
+                continue;
             }
-            hunks.push((start_a, end_a, start_b, end_b));
         }
+        hunks.push((start_a, end_a, start_b, end_b));
+    }
 
-        // fallback: if no hunks were generated, emit a single full-file hunk
-        if hunks.is_empty() {
-            let ha = 0usize;
-            let hb = src_lines.len();
-            let ka = 0usize;
-            let kb = dst_lines.len();
-            let old_count = hb.saturating_sub(ha);
-            let new_count = kb.saturating_sub(ka);
-            if !(old_count == 0 && new_count == 0) {
-                out.push_str(&format!(
-                    "@@ -{},{} +{},{} @@\n",
-                    ha + 1,
-                    old_count,
-                    ka + 1,
-                    new_count
-                ));
-                let old_slice = &src_lines[ha..hb];
-                let new_slice = &dst_lines[ka..kb];
-                let local_matches = lcs_positions(old_slice, new_slice);
-                let mut ai = 0usize;
-                let mut bi = 0usize;
-                for (omi, omj) in local_matches.iter() {
-                    for r in ai..*omi {
-                        out.push_str(&format!("-{}\n", old_slice[r]));
-                    }
-                    for a in bi..*omj {
-                        out.push_str(&format!("+{}\n", new_slice[a]));
-                    }
-                    out.push_str(&format!(" {}\n", old_slice[*omi]));
-                    ai = omi + 1;
-                    bi = omj + 1;
-                }
-                for r in ai..old_slice.len() {
+    // fallback: if no hunks were generated, emit a single full-file hunk
+    if hunks.is_empty() {
+        let ha = 0usize;
+        let hb = old_lines.len();
+        let ka = 0usize;
+        let kb = new_lines.len();
+        let old_count = hb.saturating_sub(ha);
+        let new_count = kb.saturating_sub(ka);
+        if !(old_count == 0 && new_count == 0) {
+            out.push_str(&format!(
+                "@@ -{},{} +{},{} @@\n",
+                ha + 1,
+                old_count,
+                ka + 1,
+                new_count
+            ));
+            let old_slice = &old_lines[ha..hb];
+            let new_slice = &new_lines[ka..kb];
+            let local_matches = lcs_positions(old_slice, new_slice);
+            let mut ai = 0usize;
+            let mut bi = 0usize;
+            for (omi, omj) in local_matches.iter() {
+                for r in ai..*omi {
                     out.push_str(&format!("-{}\n", old_slice[r]));
                 }
-                for a in bi..new_slice.len() {
+                for a in bi..*omj {
                     out.push_str(&format!("+{}\n", new_slice[a]));
                 }
-                out.push_str("\n");
+                out.push_str(&format!(" {}\n", old_slice[*omi]));
+                ai = omi + 1;
+                bi = omj + 1;
             }
-        } else {
-            for (ha, hb, ka, kb) in hunks.iter() {
-                let old_count = hb - ha;
-                let new_count = kb - ka;
-                out.push_str(&format!(
-                    "@@ -{},{} +{},{} @@\n",
-                    ha + 1,
-                    old_count,
-                    ka + 1,
-                    new_count
-                ));
-
-                // local slices
-                let old_slice = &src_lines[*ha..*hb];
-                let new_slice = &dst_lines[*ka..*kb];
-
-                // compute local LCS to drive the hunk output
-                let local_matches = lcs_positions(old_slice, new_slice);
-
-                let mut ai = 0usize;
-                let mut bi = 0usize;
-                for (omi, omj) in local_matches.iter() {
-                    // produce removed lines from ai..omi
-                    for r in ai..*omi {
-                        out.push_str(&format!("-{}\n", old_slice[r]));
-                    }
-                    // produce added lines from bi..omj
-                    for a in bi..*omj {
-                        out.push_str(&format!("+{}\n", new_slice[a]));
-                    }
-                    // matched line as context
-                    out.push_str(&format!(" {}\n", old_slice[*omi]));
-                    ai = omi + 1;
-                    bi = omj + 1;
-                }
-                // remaining tail
-                for r in ai..old_slice.len() {
+            for r in ai..old_slice.len() {
+                out.push_str(&format!("-{}\n", old_slice[r]));
+            }
+            for a in bi..new_slice.len() {
+                out.push_str(&format!("+{}\n", new_slice[a]));
+            }
+            out.push_str("\n");
+        }
+    } else {
+        for (ha, hb, ka, kb) in hunks.iter() {
+            let old_count = hb - ha;
+            let new_count = kb - ka;
+            out.push_str(&format!(
+                "@@ -{},{} +{},{} @@\n",
+                ha + 1,
+                old_count,
+                ka + 1,
+                new_count
+            ));
+
+            // local slices
+            let old_slice = &old_lines[*ha..*hb];
+            let new_slice = &new_lines[*ka..*kb];
+
+            // compute local LCS to drive the hunk output
+            let local_matches = lcs_positions(old_slice, new_slice);
+
+            let mut ai = 0usize;
+            let mut bi = 0usize;
+            for (omi, omj) in local_matches.iter() {
+                // produce removed lines from ai..omi
+                for r in ai..*omi {
                     out.push_str(&format!("-{}\n", old_slice[r]));
                 }
-                for a in bi..new_slice.len() {
+                // produce added lines from bi..omj
+                for a in bi..*omj {
                     out.push_str(&format!("+{}\n", new_slice[a]));
                 }
-
-                out.push_str("\n");
+                // matched line as context
+                out.push_str(&format!(" {}\n", old_slice[*omi]));
+                ai = omi + 1;
+                bi = omj + 1;
             }
+            // remaining tail
+            for r in ai..old_slice.len() {
+                out.push_str(&format!("-{}\n", old_slice[r]));
+            }
+            for a in bi..new_slice.len() {
+                out.push_str(&format!("+{}\n", new_slice[a]));
+            }
+
+            out.push_str("\n");
         }
     }
+}
+
+/// Diff for a `compare` pair whose contents changed, with the source as the old side.
+/// `files` are the two sides on disk; without them (map-only inputs) just the header is
+/// written, with the recorded digests and sizes.
+pub fn format_change_diff(
+    source: &MapEntry,
+    target: &MapEntry,
+    files: Option<(&Path, &Path)>,
+    context: usize,
+) -> String {
+    let abbrev = |hash: &str| hash.chars().take(12).collect::<String>();
+    let mut out = format!(
+        "diff --git a/{} b/{}\nindex {}..{}\n--- a/{0}\n+++ b/{1}\n\n",
+        source.path,
+        target.path,
+        abbrev(&source.hash),
+        abbrev(&target.hash)
+    );
+    match files.map(|(old, new)| (read_lines_opt(old), read_lines_opt(new))) {
+        Some((Some(old_lines), Some(new_lines))) => {
+            push_unified_body(&mut out, &old_lines, &new_lines, context)
+        }
+        Some(_) => out.push_str("Binary files differ\n\n"),
+        None => out.push_str(&format!(
+            "(contents not available; size {} -> {})\n\n",
+            source.size, target.size
+        )),
+    }
     out
 }
 
@@ -337,6 +374,26 @@ mod tests {
         assert!(s.contains("new file mode"));
     }
 
+    #[test]
+    fn change_diff_without_files_is_header_only() {
+        let entry = |hash: &str, size: u64| MapEntry {
+            path: "docs/readme.txt".into(),
+            hash: hash.into(),
+            size,
+            ..Default::default()
+        };
+        let s = format_change_diff(
+            &entry("0123456789abcdef", 10),
+            &entry("fedcba9876543210", 12),
+            None,
+            3,
+        );
+        assert!(s.starts_with("diff --git a/docs/readme.txt b/docs/readme.txt\n"));
+        assert!(s.contains("index 0123456789ab..fedcba987654\n"));
+        assert!(s.contains("size 10 -> 12"));
+        assert!(!s.contains("@@"));
+    }
+
     #[test]
     fn rename_diff_contains_paths() {
         let src = PathBuf::from("a/old.txt");
@@ -408,7 +465,7 @@ mod tests {
         let src = PathBuf::from(&long_path);
         let dst = PathBuf::from("short.txt");
         let diff = format_copy_diff(&src, &dst, true, None, false, 3);
-        assert!(diff.len() > 0);
+        assert!(!diff.is_empty());
     }
 
     #[test]
@@ -436,9 +493,9 @@ mod tests {
         let diff3 = format_copy_diff(&src, &dst, true, None, false, 3);
         let diff10 = format_copy_diff(&src, &dst, true, None, false, 10);
         
-        assert!(diff1.len() > 0);
-        assert!(diff3.len() > 0);
-        assert!(diff10.len() > 0);
+        assert!(!diff1.is_empty());
+        assert!(!diff3.is_empty());
+        assert!(!diff10.is_empty());
     }
 
     #[test]
@@ -449,7 +506,7 @@ mod tests {
         
         let dst = PathBuf::from("destination.txt");
         let diff = format_copy_diff(&dst, &src, true, Some(&src.to_string_lossy()), false, 3);
-        assert!(diff.len() > 0);
+        assert!(!diff.is_empty());
     }
 
     #[test]
@@ -469,7 +526,7 @@ mod tests {
         let dst = PathBuf::from("b.txt");
         let diff = format_copy_diff(&src, &dst, false, None, false, 3);
         // Should have ANSI color codes when not plain
-        assert!(diff.len() > 0);
+        assert!(!diff.is_empty());
     }
 
     #[test]
@@ -477,7 +534,7 @@ mod tests {
         let src = PathBuf::from("old.txt");
         let dst = PathBuf::from("new.txt");
         let diff = format_rename_diff(&src, &dst, false, 3);
-        assert!(diff.len() > 0);
+        assert!(!diff.is_empty());
     }
 
     #[test]
@@ -0,0 +1,144 @@
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use serde::Serialize;
+
+/// One line of the `hashmap --events-file`/`--events-fd` JSON Lines stream.
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum Event<'a> {
+    ScanStarted {
+        root: &'a str,
+        algorithm: &'a str,
+        timestamp: String,
+    },
+    FileHashed {
+        path: &'a str,
+        size: u64,
+        duration_ms: f64,
+    },
+    Error {
+        path: &'a str,
+        message: String,
+    },
+    ScanCompleted {
+        files: u64,
+        bytes: u64,
+        errors: u64,
+        duration_ms: f64,
+    },
+}
+
+/// Milliseconds with sub-millisecond precision, as recorded in events.
+pub fn millis(duration: Duration) -> f64 {
+    duration.as_secs_f64() * 1000.0
+}
+
+/// Thread-safe writer for JSONL events. Each event is flushed as it is written so a
+/// consumer tailing the stream sees it immediately; write failures are logged once and
+/// otherwise ignored so monitoring never aborts a scan.
+pub struct EventSink {
+    out: Mutex<Box<dyn Write + Send>>,
+    failed: AtomicBool,
+}
+
+impl EventSink {
+    pub fn new(out: Box<dyn Write + Send>) -> Self {
+        Self {
+            out: Mutex::new(out),
+            failed: AtomicBool::new(false),
+        }
+    }
+
+    /// Create (or truncate) `path` and stream events into it.
+    pub fn create_file(path: &Path) -> Result<Self> {
+        let file =
+            File::create(path).with_context(|| format!("create events file {}", path.display()))?;
+        Ok(Self::new(Box::new(BufWriter::new(file))))
+    }
+
+    /// Stream events into an already-open file descriptor inherited from the parent.
+    #[cfg(unix)]
+    pub fn from_fd(fd: i32) -> Result<Self> {
+        use std::os::unix::io::FromRawFd;
+        if fd <= 2 {
+            anyhow::bail!(
+                "--events-fd must not be stdin, stdout or stderr (got {})",
+                fd
+            );
+        }
+        // SAFETY: the descriptor was opened by the parent process for us to write events to,
+        // and nothing else in this process owns or closes it.
+        let file = unsafe { File::from_raw_fd(fd) };
+        Ok(Self::new(Box::new(file)))
+    }
+
+    #[cfg(not(unix))]
+    pub fn from_fd(_fd: i32) -> Result<Self> {
+        anyhow::bail!("--events-fd is only supported on unix; use --events-file")
+    }
+
+    pub fn emit(&self, event: &Event) {
+        let mut line = match serde_json::to_string(event) {
+            Ok(line) => line,
+            Err(_) => return,
+        };
+        line.push('\n');
+        let mut out = self.out.lock().unwrap();
+        if let Err(e) = out.write_all(line.as_bytes()).and_then(|_| out.flush()) {
+            if !self.failed.swap(true, Ordering::Relaxed) {
+                log::warn!("Failed writing events: {}", e);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+
+    #[derive(Clone, Default)]
+    struct Shared(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for Shared {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().write(buf)
+        }
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn events_are_tagged_json_lines() {
+        let buf = Shared::default();
+        let sink = EventSink::new(Box::new(buf.clone()));
+        sink.emit(&Event::FileHashed {
+            path: "a.txt",
+            size: 3,
+            duration_ms: 1.5,
+        });
+        sink.emit(&Event::ScanCompleted {
+            files: 1,
+            bytes: 3,
+            errors: 0,
+            duration_ms: 2.0,
+        });
+        let text = String::from_utf8(buf.0.lock().unwrap().clone()).unwrap();
+        let lines: Vec<serde_json::Value> = text
+            .lines()
+            .map(|l| serde_json::from_str(l).unwrap())
+            .collect();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0]["event"], "file_hashed");
+        assert_eq!(lines[0]["path"], "a.txt");
+        assert_eq!(lines[1]["event"], "scan_completed");
+        assert_eq!(lines[1]["files"], 1);
+    }
+}
@@ -1,7 +1,7 @@
 use anyhow::Result;
 use serde::{Deserialize, Serialize};
 use std::fs::File;
-use std::io::Read;
+use std::io::{Read, Write};
 use std::path::Path;
 use std::sync::Arc;
 
@@ -16,6 +16,9 @@ pub struct AlgorithmInfo {
     pub output_len_default: usize, // bytes
 }
 
+/// Read chunk size used by `HasherImpl::update_reader` when no explicit size is given.
+pub const DEFAULT_READ_BUFFER_SIZE: usize = 8192;
+
 pub trait HasherImpl: Send + Sync + 'static {
     fn name(&self) -> &str;
     fn info(&self) -> AlgorithmInfo;
@@ -23,8 +26,23 @@ pub trait HasherImpl: Send + Sync + 'static {
     where
         Self: Sized;
     fn update(&mut self, data: &[u8]);
+    /// Like `update`, but free to spread a large `data` over the current rayon pool.
+    /// Only algorithms with a tree mode (BLAKE3) do; the rest hash sequentially.
+    fn update_parallel(&mut self, data: &[u8]) {
+        self.update(data);
+    }
     fn update_reader(&mut self, r: &mut dyn Read) -> Result<()> {
-        let mut buf = [0u8; 8192];
+        self.update_reader_with_buffer(r, DEFAULT_READ_BUFFER_SIZE)
+    }
+    /// Like `update_reader`, but reads in chunks of `buf_size` bytes so callers can match
+    /// the memory plan's buffer size. A size of 0 falls back to `DEFAULT_READ_BUFFER_SIZE`.
+    fn update_reader_with_buffer(&mut self, r: &mut dyn Read, buf_size: usize) -> Result<()> {
+        let buf_size = if buf_size == 0 {
+            DEFAULT_READ_BUFFER_SIZE
+        } else {
+            buf_size
+        };
+        let mut buf = vec![0u8; buf_size];
         loop {
             let n = r.read(&mut buf)?;
             if n == 0 {
@@ -37,26 +55,287 @@ pub trait HasherImpl: Send + Sync + 'static {
     fn finalize_hex(&self, out_len: usize) -> String; // out_len in bytes
 }
 
+/// `std::io::Write` sink feeding everything written into a hasher, so any reader can be
+/// hashed with `std::io::copy(&mut reader, &mut HashWriter::new(hasher))`.
+pub struct HashWriter<'a> {
+    hasher: &'a mut dyn HasherImpl,
+}
+
+impl<'a> HashWriter<'a> {
+    pub fn new(hasher: &'a mut dyn HasherImpl) -> Self {
+        Self { hasher }
+    }
+}
+
+impl Write for HashWriter<'_> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.hasher.update(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Text encoding used for digests recorded in a map.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DigestEncoding {
+    #[default]
+    Hex,
+    /// URL-safe alphabet without padding.
+    Base64,
+    /// Standard alphabet with `=` padding.
+    Base64Std,
+    /// RFC 4648 alphabet with `=` padding.
+    Base32,
+}
+
+impl DigestEncoding {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "hex" => Some(Self::Hex),
+            "base64" | "base64url" => Some(Self::Base64),
+            "base64-std" => Some(Self::Base64Std),
+            "base32" => Some(Self::Base32),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Hex => "hex",
+            Self::Base64 => "base64",
+            Self::Base64Std => "base64-std",
+            Self::Base32 => "base32",
+        }
+    }
+
+    pub fn encode(&self, digest: &[u8]) -> String {
+        match self {
+            Self::Hex => hex::encode(digest),
+            Self::Base64 => data_encoding::BASE64URL_NOPAD.encode(digest),
+            Self::Base64Std => data_encoding::BASE64.encode(digest),
+            Self::Base32 => data_encoding::BASE32.encode(digest),
+        }
+    }
+
+    pub fn decode(&self, text: &str) -> Result<Vec<u8>> {
+        let bytes = match self {
+            Self::Hex => hex::decode(text)?,
+            Self::Base64 => data_encoding::BASE64URL_NOPAD.decode(text.as_bytes())?,
+            Self::Base64Std => data_encoding::BASE64.decode(text.as_bytes())?,
+            Self::Base32 => data_encoding::BASE32.decode(text.as_bytes())?,
+        };
+        Ok(bytes)
+    }
+
+    /// Re-encode a lowercase/uppercase hex digest (as produced by `finalize_hex`).
+    pub fn reencode_hex(&self, hex_digest: &str) -> Result<String> {
+        if *self == Self::Hex {
+            return Ok(hex_digest.to_string());
+        }
+        Ok(self.encode(&hex::decode(hex_digest)?))
+    }
+
+    /// Convert a digest in this encoding back to lowercase hex.
+    pub fn decode_to_hex(&self, encoded: &str) -> Result<String> {
+        if *self == Self::Hex {
+            return Ok(encoded.to_string());
+        }
+        Ok(hex::encode(self.decode(encoded)?))
+    }
+}
+
+/// True when `path` is a regular file whose metadata reports zero bytes. Its digest is
+/// that of empty input, so it need not be opened at all.
+fn is_empty_file(path: &Path) -> bool {
+    std::fs::metadata(path)
+        .map(|m| is_empty_regular_file(path, &m))
+        .unwrap_or(false)
+}
+
+/// Whether `metadata` (of `path`) describes an empty regular file whose zero size can
+/// be trusted. Files on pseudo filesystems such as procfs, sysfs or FUSE mounts often
+/// report zero bytes yet have contents, so those are always read.
+pub fn is_empty_regular_file(path: &Path, metadata: &std::fs::Metadata) -> bool {
+    metadata.is_file() && metadata.len() == 0 && !on_pseudo_filesystem(path)
+}
+
+/// Filesystem types whose reported file sizes say nothing about their contents.
+const PSEUDO_FILESYSTEMS: &[&str] = &[
+    "proc",
+    "sysfs",
+    "debugfs",
+    "tracefs",
+    "securityfs",
+    "configfs",
+    "efivarfs",
+    "pstore",
+    "bpf",
+    "cgroup",
+    "cgroup2",
+];
+
+#[cfg(target_os = "linux")]
+fn on_pseudo_filesystem(path: &Path) -> bool {
+    static MOUNTS: std::sync::OnceLock<String> = std::sync::OnceLock::new();
+    let mounts =
+        MOUNTS.get_or_init(|| std::fs::read_to_string("/proc/self/mounts").unwrap_or_default());
+    // when the path can't be resolved, don't trust its size either
+    match std::fs::canonicalize(path) {
+        Ok(path) => mount_is_pseudo(mounts, &path),
+        Err(_) => true,
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+fn on_pseudo_filesystem(_path: &Path) -> bool {
+    false
+}
+
+/// Whether the mount holding the absolute `path`, per a `/proc/self/mounts` style
+/// `table`, is a pseudo or FUSE filesystem. Without a matching mount it is assumed to
+/// be one.
+#[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+fn mount_is_pseudo(table: &str, path: &Path) -> bool {
+    let mut best: Option<(usize, &str)> = None;
+    for line in table.lines() {
+        let mut fields = line.split_whitespace();
+        let (Some(_), Some(mount_point), Some(fs_type)) =
+            (fields.next(), fields.next(), fields.next())
+        else {
+            continue;
+        };
+        let mount_point = unescape_mount_field(mount_point);
+        let depth = Path::new(&mount_point).components().count();
+        if path.starts_with(&mount_point) && best.is_none_or(|(d, _)| depth >= d) {
+            best = Some((depth, fs_type));
+        }
+    }
+    match best {
+        Some((_, fs_type)) => fs_type.starts_with("fuse") || PSEUDO_FILESYSTEMS.contains(&fs_type),
+        None => true,
+    }
+}
+
+/// Undo the octal escapes (`\040` for a space, ...) the kernel writes in mount tables.
+#[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+fn unescape_mount_field(field: &str) -> String {
+    let bytes = field.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let octal = bytes
+            .get(i + 1..i + 4)
+            .filter(|_| bytes[i] == b'\\')
+            .and_then(|d| std::str::from_utf8(d).ok())
+            .and_then(|d| u8::from_str_radix(d, 8).ok());
+        match octal {
+            Some(b) => {
+                out.push(b);
+                i += 4;
+            }
+            None => {
+                out.push(bytes[i]);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
+
 /// Stream file contents located at `path` into the provided hasher using buffers
-/// sourced from `buffer_pool`.
+/// sourced from `buffer_pool`. Empty files are not opened: the hasher is left over
+/// empty input.
 pub fn hash_path_with_pool(
     hasher: &mut dyn HasherImpl,
     path: &Path,
     buffer_pool: &Arc<BufferPool>,
 ) -> Result<()> {
+    if is_empty_file(path) {
+        return Ok(());
+    }
     let mut file = File::open(path)?;
+    hash_reader_with_pool(hasher, &mut file, buffer_pool)?;
+    Ok(())
+}
+
+/// Stream `reader` until EOF into the provided hasher using a pooled buffer.
+/// Returns the number of bytes consumed, which is useful for sources without a
+/// meaningful metadata length (FIFOs, character devices).
+pub fn hash_reader_with_pool(
+    hasher: &mut dyn HasherImpl,
+    reader: &mut dyn Read,
+    buffer_pool: &Arc<BufferPool>,
+) -> Result<u64> {
     let mut pooled = buffer_pool.get();
+    let mut total = 0u64;
     loop {
         let buf = pooled.as_mut();
-        let read = file.read(buf)?;
+        let read = reader.read(buf)?;
         if read == 0 {
             break;
         }
-        hasher.update(&buf[..read]);
+        if buffer_pool.parallel_hashing() {
+            hasher.update_parallel(&buf[..read]);
+        } else {
+            hasher.update(&buf[..read]);
+        }
+        buffer_pool.record_read(read as u64);
+        total += read as u64;
     }
+    Ok(total)
+}
+
+/// Like [`hash_path_with_pool`], but reads the file once and feeds every buffer to all
+/// of `hashers`.
+pub fn hash_path_multi_with_pool(
+    hashers: &mut [Box<dyn HasherImpl>],
+    path: &Path,
+    buffer_pool: &Arc<BufferPool>,
+) -> Result<()> {
+    if is_empty_file(path) {
+        return Ok(());
+    }
+    let mut file = File::open(path)?;
+    hash_reader_multi_with_pool(hashers, &mut file, buffer_pool)?;
     Ok(())
 }
 
+/// Like [`hash_reader_with_pool`], feeding each chunk to all of `hashers`.
+pub fn hash_reader_multi_with_pool(
+    hashers: &mut [Box<dyn HasherImpl>],
+    reader: &mut dyn Read,
+    buffer_pool: &Arc<BufferPool>,
+) -> Result<u64> {
+    let mut pooled = buffer_pool.get();
+    let mut total = 0u64;
+    loop {
+        let buf = pooled.as_mut();
+        let read = reader.read(buf)?;
+        if read == 0 {
+            break;
+        }
+        for hasher in hashers.iter_mut() {
+            if buffer_pool.parallel_hashing() {
+                hasher.update_parallel(&buf[..read]);
+            } else {
+                hasher.update(&buf[..read]);
+            }
+        }
+        buffer_pool.record_read(read as u64);
+        total += read as u64;
+    }
+    Ok(total)
+}
+
+/// Placeholder digest for `hashmap --structure-only`: the file size as 16 hex digits,
+/// so equal-sized files compare equal without their contents being read.
+pub fn size_digest(size: u64) -> String {
+    format!("{:016x}", size)
+}
+
 /// Deterministic expansion for algorithms.
 /// - For XOF algorithms, produce `out_len` bytes via the algorithm's XOF interface.
 /// - For fixed-output algorithms, produce output by computing
@@ -96,3 +375,120 @@ pub fn expand_digest(alg: &Algorithm, input: &[u8], out_len: usize) -> Vec<u8> {
     out.truncate(out_len);
     out
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn empty_file_hashes_as_empty_input_without_reading() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("empty");
+        File::create(&path).unwrap();
+        // no preallocated buffers: any read would have to allocate one
+        let pool = Arc::new(BufferPool::new(0, 64));
+        for alg in Algorithm::all() {
+            let mut reference = alg.create();
+            let len = reference.info().output_len_default;
+            reference.update_reader(&mut &b""[..]).unwrap();
+            let expected = reference.finalize_hex(len);
+
+            let mut hasher = alg.create();
+            hash_path_with_pool(hasher.as_mut(), &path, &pool).unwrap();
+            assert_eq!(hasher.finalize_hex(len), expected, "{}", alg.name());
+
+            let mut hashers = vec![alg.create()];
+            hash_path_multi_with_pool(&mut hashers, &path, &pool).unwrap();
+            assert_eq!(hashers[0].finalize_hex(len), expected, "{}", alg.name());
+        }
+        assert_eq!(pool.allocated_buffers(), 0);
+    }
+
+    #[test]
+    fn zero_sizes_on_pseudo_filesystems_are_not_trusted() {
+        let table = "/dev/sda1 / ext4 rw 0 0\n\
+                     proc /proc proc rw 0 0\n\
+                     sysfs /sys sysfs rw 0 0\n\
+                     tmpfs /run tmpfs rw 0 0\n\
+                     sshfs#host: /mnt/my\\040share fuse.sshfs rw 0 0\n";
+        assert!(!mount_is_pseudo(table, Path::new("/home/user/empty")));
+        assert!(!mount_is_pseudo(table, Path::new("/run/lock/empty")));
+        assert!(mount_is_pseudo(table, Path::new("/proc/self/status")));
+        assert!(mount_is_pseudo(table, Path::new("/sys/kernel/notes")));
+        assert!(mount_is_pseudo(table, Path::new("/mnt/my share/empty")));
+        assert!(!mount_is_pseudo(table, Path::new("/mnt/my/empty")));
+
+        #[cfg(target_os = "linux")]
+        {
+            let status = Path::new("/proc/self/status");
+            if status.exists() {
+                assert!(!is_empty_file(status));
+                let mut hasher = Algorithm::Blake3.create();
+                let pool = Arc::new(BufferPool::new(1, 4096));
+                hash_path_with_pool(hasher.as_mut(), status, &pool).unwrap();
+                let empty = Algorithm::Blake3.create();
+                assert_ne!(hasher.finalize_hex(32), empty.finalize_hex(32));
+            }
+        }
+    }
+
+    #[test]
+    fn pooled_reads_credit_bytes_chunk_by_chunk() {
+        use std::sync::atomic::{AtomicU64, Ordering};
+
+        /// Records the credited total each time the hasher asks for more input.
+        struct Watched<'a> {
+            data: &'a [u8],
+            counter: Arc<AtomicU64>,
+            seen: Vec<u64>,
+        }
+        impl Read for Watched<'_> {
+            fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+                self.seen.push(self.counter.load(Ordering::Relaxed));
+                self.data.read(buf)
+            }
+        }
+
+        let counter = Arc::new(AtomicU64::new(0));
+        let pool = Arc::new(BufferPool::new(1, 1000).with_read_counter(counter.clone()));
+        let data = vec![3u8; 2500];
+        let mut reader = Watched {
+            data: &data,
+            counter: counter.clone(),
+            seen: Vec::new(),
+        };
+        let mut hasher = Algorithm::Blake3.create();
+        hash_reader_with_pool(hasher.as_mut(), &mut reader, &pool).unwrap();
+        assert_eq!(reader.seen, [0, 1000, 2000, 2500]);
+        assert_eq!(counter.load(Ordering::Relaxed), 2500);
+    }
+
+    #[test]
+    fn io_copy_through_hash_writer_matches_update_reader() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("data.bin");
+        let data: Vec<u8> = (0..100_000u32).map(|i| (i % 251) as u8).collect();
+        std::fs::write(&path, &data).unwrap();
+        // wyhash-1024 digests depend on write boundaries; copying from a file moves 8 KiB
+        // chunks, the same as update_reader's default buffer
+        for alg in Algorithm::all() {
+            let mut reference = alg.create();
+            let len = reference.info().output_len_default;
+            reference
+                .update_reader(&mut File::open(&path).unwrap())
+                .unwrap();
+
+            let mut hasher = alg.create();
+            let mut writer = HashWriter::new(hasher.as_mut());
+            let copied = std::io::copy(&mut File::open(&path).unwrap(), &mut writer).unwrap();
+            assert_eq!(copied, data.len() as u64);
+            assert_eq!(
+                hasher.finalize_hex(len),
+                reference.finalize_hex(len),
+                "{}",
+                alg.name()
+            );
+        }
+    }
+}
@@ -1,12 +1,50 @@
 use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::fs::{self, OpenOptions};
-use std::io::Write;
-use std::path::Path;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::path::{Path, PathBuf};
+
+use crate::algorithms::AlgorithmParams;
+use crate::hash::DigestEncoding;
 
 /// Atomically write bytes to `path`.
 /// Writes to a temporary file in the same directory and then renames it into place.
 pub fn atomic_write(path: &Path, data: &[u8]) -> Result<()> {
+    atomic_write_via(path, data, None)
+}
+
+/// Make sure `path` can be written before spending time on a scan: creates missing
+/// parent directories and creates then removes a probe file next to it (and in
+/// `temp_dir`, when one is given).
+pub fn check_writable(path: &Path, temp_dir: Option<&Path>) -> Result<()> {
+    if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+        fs::create_dir_all(parent)
+            .with_context(|| format!("cannot create output directory {:?}", parent))?;
+    }
+    let file_name = path
+        .file_name()
+        .and_then(|s| s.to_str())
+        .unwrap_or("output");
+    let probe_name = format!(".{}.{}.probe", file_name, std::process::id());
+    let mut probes = vec![path.with_file_name(&probe_name)];
+    probes.extend(temp_dir.map(|dir| dir.join(&probe_name)));
+    for probe in probes {
+        OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&probe)
+            .with_context(|| format!("cannot write next to {:?} (tried {:?})", path, probe))?;
+        let _ = fs::remove_file(&probe);
+    }
+    Ok(())
+}
+
+/// [`atomic_write`] staging the temporary file in `temp_dir` instead of next to `path`.
+/// When `temp_dir` is on another filesystem the rename can't be atomic, so the staged
+/// file is copied next to `path` first and the copy renamed into place; the existing
+/// `path` is never removed before its replacement is fully written.
+pub fn atomic_write_via(path: &Path, data: &[u8], temp_dir: Option<&Path>) -> Result<()> {
     if let Some(parent) = path.parent() {
         fs::create_dir_all(parent)
             .with_context(|| format!("failed to create parent dir {:?}", parent))?;
@@ -17,8 +55,11 @@ pub fn atomic_write(path: &Path, data: &[u8]) -> Result<()> {
         .and_then(|s| s.to_str())
         .unwrap_or("tempfile");
     // temp file hidden in same dir
-    let tmp_name = format!(".{}.tmp", file_name);
-    let tmp_path = path.with_file_name(tmp_name);
+    let local_tmp = path.with_file_name(format!(".{}.tmp", file_name));
+    let tmp_path = match temp_dir {
+        Some(dir) => dir.join(format!(".{}.{}.tmp", file_name, std::process::id())),
+        None => local_tmp.clone(),
+    };
 
     {
         let mut tmp = OpenOptions::new()
@@ -33,14 +74,77 @@ pub fn atomic_write(path: &Path, data: &[u8]) -> Result<()> {
             .with_context(|| format!("sync temp file {:?}", tmp_path))?;
     }
 
-    // On Windows rename fails if target exists — remove first if present
-    if path.exists() {
-        fs::remove_file(path).with_context(|| format!("remove existing target file {:?}", path))?;
+    // The old file stays in place until the new one replaces it in a single rename
+    match replace_file(&tmp_path, path) {
+        Ok(()) => {}
+        Err(e) if temp_dir.is_some() && e.kind() == std::io::ErrorKind::CrossesDevices => {
+            log::debug!(
+                "{:?} is on another filesystem than {:?}; copying before the rename",
+                tmp_path,
+                path
+            );
+            let copied = fs::copy(&tmp_path, &local_tmp)
+                .and_then(|_| fs::File::open(&local_tmp)?.sync_all());
+            let _ = fs::remove_file(&tmp_path);
+            if let Err(e) = copied {
+                let _ = fs::remove_file(&local_tmp);
+                return Err(e)
+                    .with_context(|| format!("copy temp file {:?} -> {:?}", tmp_path, local_tmp));
+            }
+            if let Err(e) = replace_file(&local_tmp, path) {
+                let _ = fs::remove_file(&local_tmp);
+                return Err(e)
+                    .with_context(|| format!("rename temp file {:?} -> {:?}", local_tmp, path));
+            }
+        }
+        Err(e) => {
+            let _ = fs::remove_file(&tmp_path);
+            return Err(e)
+                .with_context(|| format!("rename temp file {:?} -> {:?}", tmp_path, path));
+        }
+    }
+
+    Ok(())
+}
+
+/// Rename `from` over `to`. Rename replaces an existing file on Unix (and, through
+/// `std`, on Windows); elsewhere a target that blocks the rename is removed first.
+fn replace_file(from: &Path, to: &Path) -> std::io::Result<()> {
+    match fs::rename(from, to) {
+        #[cfg(not(unix))]
+        Err(e) if e.kind() != std::io::ErrorKind::CrossesDevices && to.is_file() => {
+            fs::remove_file(to)?;
+            fs::rename(from, to)
+        }
+        result => result,
+    }
+}
+
+/// Parse an octal permission mode such as `600` or `0o644`.
+pub fn parse_output_mode(s: &str) -> Result<u32> {
+    let digits = s.trim().trim_start_matches("0o");
+    let mode = u32::from_str_radix(digits, 8)
+        .with_context(|| format!("invalid --output-mode {:?}: expected an octal mode", s))?;
+    if mode > 0o7777 {
+        anyhow::bail!("invalid --output-mode {:?}: mode is larger than 7777", s);
     }
+    Ok(mode)
+}
 
-    fs::rename(&tmp_path, path)
-        .with_context(|| format!("rename temp file {:?} -> {:?}", tmp_path, path))?;
+/// Set the permission bits of an already written output file.
+#[cfg(unix)]
+pub fn set_output_mode(path: &Path, mode: u32) -> Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+    fs::set_permissions(path, fs::Permissions::from_mode(mode))
+        .with_context(|| format!("set mode {:o} on {:?}", mode, path))
+}
 
+#[cfg(not(unix))]
+pub fn set_output_mode(path: &Path, _mode: u32) -> Result<()> {
+    log::warn!(
+        "--output-mode is only supported on unix; leaving permissions of {:?} unchanged",
+        path
+    );
     Ok(())
 }
 
@@ -50,24 +154,247 @@ pub fn write_json<T: ?Sized + Serialize>(path: &Path, value: &T) -> Result<()> {
     atomic_write(path, &data)
 }
 
+/// Column order of a CSV map; headerless maps are read with these names.
+pub const MAP_CSV_COLUMNS: [&str; 4] = ["path", "hash", "size", "mtime"];
+
+/// Every column a CSV map header may name, in any order.
+const MAP_CSV_HEADER_NAMES: [&str; 8] = [
+    "path",
+    "hash",
+    "size",
+    "mtime",
+    "inode",
+    "hashes",
+    "kind",
+    "algorithm",
+];
+
+/// CSV dialect for maps (`hashmap --csv-delimiter`/`--csv-no-header`/`--csv-crlf`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CsvOptions {
+    pub delimiter: u8,
+    /// Write (or expect) a header row naming the columns.
+    pub header: bool,
+    /// End records with `\r\n` instead of `\n`.
+    pub crlf: bool,
+}
+
+impl Default for CsvOptions {
+    fn default() -> Self {
+        Self {
+            delimiter: b',',
+            header: true,
+            crlf: false,
+        }
+    }
+}
+
+impl CsvOptions {
+    /// Parse a `--csv-delimiter` value: a single ASCII character, or `tab`/`\t`.
+    pub fn parse_delimiter(s: &str) -> Result<u8> {
+        match s {
+            "tab" | "\\t" | "\t" => Ok(b'\t'),
+            _ if s.len() == 1 && s.is_ascii() && s != "\"" && s != "\n" && s != "\r" => {
+                Ok(s.as_bytes()[0])
+            }
+            _ => anyhow::bail!(
+                "invalid CSV delimiter {:?}: expected a single ASCII character or `tab`",
+                s
+            ),
+        }
+    }
+
+    /// Guess the dialect of a CSV map from its first line: the most frequent of `,`, `;`,
+    /// tab and `|` is the delimiter, and a first row made only of map column names
+    /// (in any order) is a header row.
+    pub fn detect(first_line: &str) -> Self {
+        let line = first_line.trim_start_matches('\u{feff}');
+        let delimiter = [b',', b';', b'\t', b'|']
+            .into_iter()
+            .map(|d| (line.bytes().filter(|b| *b == d).count(), d))
+            .filter(|(count, _)| *count > 0)
+            .max_by_key(|(count, d)| (*count, *d == b','))
+            .map(|(_, d)| d)
+            .unwrap_or(b',');
+        let header = line
+            .trim_end_matches('\r')
+            .split(delimiter as char)
+            .map(|field| field.trim().trim_matches('"'))
+            .all(|field| MAP_CSV_HEADER_NAMES.contains(&field));
+        Self {
+            delimiter,
+            header,
+            crlf: first_line.ends_with('\r'),
+        }
+    }
+
+    /// [`CsvOptions::detect`] on the first line of the file at `path`.
+    pub fn detect_file(path: &Path) -> Result<Self> {
+        let file = fs::File::open(path).with_context(|| format!("open csv {:?}", path))?;
+        let mut first = String::new();
+        BufReader::new(file)
+            .read_line(&mut first)
+            .with_context(|| format!("read csv {:?}", path))?;
+        Ok(Self::detect(first.trim_end_matches('\n')))
+    }
+
+    pub fn writer<W: Write>(&self, out: W) -> csv::Writer<W> {
+        csv::WriterBuilder::new()
+            .delimiter(self.delimiter)
+            .has_headers(self.header)
+            .terminator(if self.crlf {
+                csv::Terminator::CRLF
+            } else {
+                csv::Terminator::Any(b'\n')
+            })
+            .from_writer(out)
+    }
+
+    pub fn reader<R: Read>(&self, input: R) -> csv::Reader<R> {
+        csv::ReaderBuilder::new()
+            .delimiter(self.delimiter)
+            .has_headers(self.header)
+            .from_reader(input)
+    }
+}
+
 /// Serialize `records` to CSV and atomically write to `path`.
 pub fn write_csv<T: Serialize>(path: &Path, records: &[T]) -> Result<()> {
-    let mut wtr = csv::Writer::from_writer(vec![]);
+    write_csv_with(path, records, &CsvOptions::default())
+}
+
+/// [`write_csv`] in the dialect described by `opts`.
+pub fn write_csv_with<T: Serialize>(path: &Path, records: &[T], opts: &CsvOptions) -> Result<()> {
+    atomic_write(path, &to_csv(records, opts)?)
+}
+
+/// Serialize `records` to CSV bytes in the dialect described by `opts`.
+pub fn to_csv<T: Serialize>(records: &[T], opts: &CsvOptions) -> Result<Vec<u8>> {
+    let mut wtr = opts.writer(vec![]);
     for rec in records {
         wtr.serialize(rec).context("serialize csv record")?;
     }
-    let data = wtr.into_inner().context("finalize csv writer")?;
-    atomic_write(path, &data)
+    wtr.into_inner().context("finalize csv writer")
+}
+
+/// Serialize map `entries` to CSV bytes; see [`write_map_csv`].
+pub fn map_to_csv(entries: &[MapEntry], opts: &CsvOptions) -> Result<Vec<u8>> {
+    let mut out = Vec::new();
+    write_map_csv(&mut out, entries, opts)?;
+    Ok(out)
+}
+
+/// Write map `entries` as CSV. Optional columns (`mtime`, `inode`, `kind`, `algorithm`)
+/// are written when any entry has a value, with empty cells for the rest, so every row
+/// has the same width.
+pub fn write_map_csv<W: Write>(out: W, entries: &[MapEntry], opts: &CsvOptions) -> Result<()> {
+    let has = |f: fn(&MapEntry) -> bool| entries.iter().any(f);
+    let mtime = has(|e| e.mtime.is_some());
+    let inode = has(|e| e.inode.is_some());
+    let kind = has(|e| e.kind.is_some());
+    let algorithm = has(|e| e.algorithm.is_some());
+
+    let mut wtr = opts.writer(out);
+    if opts.header {
+        let mut header = vec!["path", "hash", "size"];
+        header.extend(mtime.then_some("mtime"));
+        header.extend(inode.then_some("inode"));
+        header.extend(kind.then_some("kind"));
+        header.extend(algorithm.then_some("algorithm"));
+        wtr.write_record(&header).context("write csv header")?;
+    }
+    for e in entries {
+        let mut record = vec![e.path.clone(), e.hash.clone(), e.size.to_string()];
+        if mtime {
+            record.push(e.mtime.map(|t| t.to_string()).unwrap_or_default());
+        }
+        if inode {
+            record.push(e.inode.clone().unwrap_or_default());
+        }
+        if kind {
+            record.push(match e.kind {
+                Some(EntryKind::Symlink) => "symlink".to_string(),
+                None => String::new(),
+            });
+        }
+        if algorithm {
+            record.push(e.algorithm.clone().unwrap_or_default());
+        }
+        wtr.write_record(&record).context("serialize csv record")?;
+    }
+    wtr.flush().context("finalize csv writer")?;
+    Ok(())
+}
+
+/// Map format implied by an output path's extension: `json`, `csv`, `ndjson` (`.ndjson` or
+/// `.jsonl`) or `sums`. `None` for other or missing extensions.
+pub fn format_for_path(path: &Path) -> Option<&'static str> {
+    let ext = path.extension()?.to_str()?.to_ascii_lowercase();
+    match ext.as_str() {
+        "json" => Some("json"),
+        "csv" => Some("csv"),
+        "ndjson" | "jsonl" => Some("ndjson"),
+        "sums" => Some("sums"),
+        _ => None,
+    }
+}
+
+/// Serialize `entries` as NDJSON, one `MapEntry` object per line.
+pub fn to_ndjson(entries: &[MapEntry]) -> Result<Vec<u8>> {
+    let mut data = Vec::new();
+    write_ndjson(&mut data, entries)?;
+    Ok(data)
+}
+
+/// Write `entries` to `out` as JSON Lines, one entry object per line.
+pub fn write_ndjson<W: Write>(out: &mut W, entries: &[MapEntry]) -> Result<()> {
+    for e in entries {
+        serde_json::to_writer(&mut *out, e).context("serialize ndjson line")?;
+        out.write_all(b"\n").context("write ndjson line")?;
+    }
+    Ok(())
+}
+
+/// Render `entries` as a coreutils-style sums file listing each entry's full map path.
+/// With a `tag`, lines use the BSD tagged layout (see [`format_bsd_line`]).
+pub fn to_sums(entries: &[MapEntry], tag: Option<&str>) -> String {
+    entries
+        .iter()
+        .map(|e| sums_line(tag, &e.hash, &e.path))
+        .collect()
 }
 
 /// MapEntry used for persistent maps (json/csv) and for in-memory comparisons.
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Default)]
 pub struct MapEntry {
     pub path: String,
     pub hash: String,
     pub size: u64,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub mtime: Option<i64>,
+    /// `<st_dev>:<st_ino>` of the file, recorded with `--coalesce-hardlinks` so hard links
+    /// to the same data can be told apart from real duplicates.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub inode: Option<String>,
+    /// Digest per algorithm for multi-algorithm maps (`--algorithms`); `hash` holds the
+    /// first algorithm's digest.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hashes: Option<BTreeMap<String, String>>,
+    /// What `hash` was computed over when it isn't the file's contents.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub kind: Option<EntryKind>,
+    /// Algorithm of `hash`, written as a CSV column (`--csv-include-algorithm`, and on
+    /// multi-algorithm CSV maps, which list one row per file and algorithm).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub algorithm: Option<String>,
+}
+
+/// Non-regular entries recorded in a map.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum EntryKind {
+    /// A symlink hashed by its target path (`hashmap --hash-symlink-target-path`).
+    Symlink,
 }
 
 /// Load a map from a JSON file. Accepts either:
@@ -79,8 +406,26 @@ pub fn load_map_from_json(path: &Path) -> Result<Vec<MapEntry>> {
 
     // Try object with entries first
     if let Some(entries) = v.get("entries") {
-        let entries_parsed: Vec<MapEntry> =
+        let mut entries_parsed: Vec<MapEntry> =
             serde_json::from_value(entries.clone()).context("deserialize entries")?;
+        // Digests stored as base64/base32 are normalized to hex so maps compare by value;
+        // the per-algorithm `hashes` were encoded alongside `hash` and are decoded too
+        let encoding = header_digest_encoding(&v)?;
+        if encoding != DigestEncoding::Hex {
+            for e in &mut entries_parsed {
+                let path = &e.path;
+                let decode = |digest: &mut String| -> Result<()> {
+                    *digest = encoding.decode_to_hex(digest).with_context(|| {
+                        format!("decode {} digest for {:?}", encoding.name(), path)
+                    })?;
+                    Ok(())
+                };
+                decode(&mut e.hash)?;
+                for digest in e.hashes.iter_mut().flat_map(|h| h.values_mut()) {
+                    decode(digest)?;
+                }
+            }
+        }
         return Ok(entries_parsed);
     }
 
@@ -98,15 +443,501 @@ pub fn load_map_from_json(path: &Path) -> Result<Vec<MapEntry>> {
     Ok(entries_parsed)
 }
 
-/// Load a map from CSV file. Expects headers matching MapEntry fields.
+/// Digest encoding recorded in a JSON map header (`algorithm.params.digest_encoding`),
+/// hex when absent.
+pub fn header_digest_encoding(map: &serde_json::Value) -> Result<DigestEncoding> {
+    match map
+        .pointer("/algorithm/params/digest_encoding")
+        .and_then(|e| e.as_str())
+    {
+        Some(name) => DigestEncoding::from_name(name)
+            .ok_or_else(|| anyhow::anyhow!("unknown digest encoding {:?} in map header", name)),
+        None => Ok(DigestEncoding::Hex),
+    }
+}
+
+/// Short digest of everything that decides a map's hash values: algorithm, digest
+/// length in bytes, algorithm parameters and the tool's major version. Recorded as the
+/// JSON header `fingerprint`; maps whose fingerprints differ can't be compared.
+pub fn map_fingerprint(algorithm: &str, out_len: usize, params: &AlgorithmParams) -> String {
+    let key = format!(
+        "hash-folderoo/{}\0{}\0{}\0{:?}\0{:?}",
+        env!("CARGO_PKG_VERSION_MAJOR"),
+        algorithm.to_ascii_lowercase(),
+        out_len,
+        params.block_size,
+        params.customization
+    );
+    blake3::hash(key.as_bytes()).to_hex()[..16].to_string()
+}
+
+/// The `fingerprint` recorded in a JSON map header, if any.
+pub fn load_map_fingerprint(path: &Path) -> Result<Option<String>> {
+    let s = std::fs::read_to_string(path).with_context(|| format!("read json {:?}", path))?;
+    let v: serde_json::Value = match serde_json::from_str(&s) {
+        Ok(v) => v,
+        Err(_) => return Ok(None),
+    };
+    Ok(v.get("fingerprint")
+        .and_then(|f| f.as_str())
+        .map(|f| f.to_string()))
+}
+
+/// Read the `root` recorded in a JSON map header, if any.
+/// Returns `None` for CSV maps, bare arrays, or headers without a root.
+pub fn load_map_root(path: &Path) -> Result<Option<String>> {
+    let s = std::fs::read_to_string(path).with_context(|| format!("read json {:?}", path))?;
+    let v: serde_json::Value = match serde_json::from_str(&s) {
+        Ok(v) => v,
+        Err(_) => return Ok(None),
+    };
+    Ok(v.get("root")
+        .and_then(|r| r.as_str())
+        .filter(|r| !r.is_empty())
+        .map(|r| r.to_string()))
+}
+
+/// Load a map from CSV file, detecting its dialect from the first line (see
+/// [`CsvOptions::detect`]). Header rows name MapEntry fields; headerless maps list
+/// [`MAP_CSV_COLUMNS`] in order.
 pub fn load_map_from_csv(path: &Path) -> Result<Vec<MapEntry>> {
-    let mut rdr = csv::Reader::from_path(path).with_context(|| format!("open csv {:?}", path))?;
-    let mut out = Vec::new();
-    for result in rdr.deserialize() {
-        let rec: MapEntry = result.context("deserialize csv record")?;
-        out.push(rec);
+    load_map_from_csv_with(path, &CsvOptions::detect_file(path)?)
+}
+
+/// Load a CSV map written in the dialect described by `opts`. Rows of a
+/// multi-algorithm map are folded back into one entry per path (see
+/// [`fold_algorithm_rows`]).
+pub fn load_map_from_csv_with(path: &Path, opts: &CsvOptions) -> Result<Vec<MapEntry>> {
+    let rows = csv_entries(path, opts)?.collect::<Result<Vec<_>>>()?;
+    Ok(fold_algorithm_rows(rows))
+}
+
+/// CSV rows carrying an `algorithm` column: one row per entry, or, for entries with
+/// per-algorithm `hashes`, one row per algorithm with `primary` first.
+pub fn algorithm_rows(entries: &[MapEntry], primary: &str) -> Vec<MapEntry> {
+    let mut rows = Vec::with_capacity(entries.len());
+    for e in entries {
+        let row = |algorithm: &str, hash: &str| MapEntry {
+            hash: hash.to_string(),
+            hashes: None,
+            algorithm: Some(algorithm.to_string()),
+            ..e.clone()
+        };
+        match &e.hashes {
+            Some(hashes) => {
+                rows.push(row(primary, &e.hash));
+                for (name, digest) in hashes.iter().filter(|(name, _)| *name != primary) {
+                    rows.push(row(name, digest));
+                }
+            }
+            None => rows.push(row(primary, &e.hash)),
+        }
+    }
+    rows
+}
+
+/// Undo [`algorithm_rows`] for maps naming more than one algorithm: rows sharing a path
+/// become one entry whose `hashes` holds every digest and whose `hash` is the digest of
+/// the first row's algorithm. Maps with one algorithm (or none recorded) are returned
+/// unchanged.
+pub fn fold_algorithm_rows(rows: Vec<MapEntry>) -> Vec<MapEntry> {
+    let primary = match rows.first().and_then(|r| r.algorithm.clone()) {
+        Some(primary) => primary,
+        None => return rows,
+    };
+    if rows
+        .iter()
+        .all(|r| r.algorithm.as_deref().is_none_or(|a| a == primary))
+    {
+        return rows;
+    }
+    let mut entries: Vec<MapEntry> = Vec::new();
+    let mut index: std::collections::HashMap<String, usize> = std::collections::HashMap::new();
+    for row in rows {
+        let algorithm = row.algorithm.clone().unwrap_or_else(|| primary.clone());
+        let i = *index.entry(row.path.clone()).or_insert_with(|| {
+            entries.push(MapEntry {
+                hash: String::new(),
+                hashes: Some(BTreeMap::new()),
+                algorithm: Some(primary.clone()),
+                ..row.clone()
+            });
+            entries.len() - 1
+        });
+        let entry = &mut entries[i];
+        if algorithm == primary {
+            entry.hash = row.hash.clone();
+        }
+        entry
+            .hashes
+            .get_or_insert_with(BTreeMap::new)
+            .insert(algorithm, row.hash);
+    }
+    entries
+}
+
+fn csv_entries(
+    path: &Path,
+    opts: &CsvOptions,
+) -> Result<Box<dyn Iterator<Item = Result<MapEntry>>>> {
+    let file = fs::File::open(path).with_context(|| format!("open csv {:?}", path))?;
+    let mut rdr = opts.reader(file);
+    let headers = if opts.header {
+        rdr.headers().context("read csv header")?.clone()
+    } else {
+        csv::StringRecord::from(MAP_CSV_COLUMNS.to_vec())
+    };
+    Ok(Box::new(rdr.into_records().map(move |record| {
+        record
+            .context("read csv record")?
+            .deserialize(Some(&headers))
+            .context("deserialize csv record")
+    })))
+}
+
+/// Row-by-row [`fold_algorithm_rows`] for streamed CSV maps: the consecutive rows
+/// [`algorithm_rows`] writes for one path, each naming its algorithm, become one entry
+/// whose `hash` is the first row's digest.
+struct FoldedRows<I: Iterator<Item = Result<MapEntry>>> {
+    rows: std::iter::Peekable<I>,
+}
+
+impl<I: Iterator<Item = Result<MapEntry>>> Iterator for FoldedRows<I> {
+    type Item = Result<MapEntry>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut entry = match self.rows.next()? {
+            Ok(entry) => entry,
+            Err(e) => return Some(Err(e)),
+        };
+        let primary = match &entry.algorithm {
+            Some(primary) => primary.clone(),
+            None => return Some(Ok(entry)),
+        };
+        while let Some(Ok(row)) = self.rows.next_if(
+            |next| matches!(next, Ok(row) if row.path == entry.path && row.algorithm.is_some()),
+        ) {
+            let hashes = entry
+                .hashes
+                .get_or_insert_with(|| BTreeMap::from([(primary.clone(), entry.hash.clone())]));
+            hashes.insert(row.algorithm.unwrap_or_default(), row.hash);
+        }
+        Some(Ok(entry))
+    }
+}
+
+/// Stream entries from a map without loading it whole. NDJSON maps (`.ndjson`/`.jsonl`,
+/// one `MapEntry` object per line; blank lines ignored) and CSV maps are supported.
+pub fn stream_map_entries(path: &Path) -> Result<Box<dyn Iterator<Item = Result<MapEntry>>>> {
+    let ext = path
+        .extension()
+        .and_then(|e| e.to_str())
+        .map(|e| e.to_ascii_lowercase());
+    match ext.as_deref() {
+        Some("ndjson") | Some("jsonl") => {
+            let file = fs::File::open(path).with_context(|| format!("open ndjson {:?}", path))?;
+            let lines = BufReader::new(file).lines().enumerate();
+            Ok(Box::new(lines.filter_map(|(idx, line)| {
+                match line {
+                    Ok(l) if l.trim().is_empty() => None,
+                    Ok(l) => Some(
+                        serde_json::from_str(&l)
+                            .with_context(|| format!("deserialize ndjson line {}", idx + 1)),
+                    ),
+                    Err(e) => Some(Err(e).context("read ndjson line")),
+                }
+            })))
+        }
+        Some("csv") => Ok(Box::new(FoldedRows {
+            rows: csv_entries(path, &CsvOptions::detect_file(path)?)?.peekable(),
+        })),
+        _ => anyhow::bail!(
+            "{:?} cannot be streamed; use an NDJSON (.ndjson/.jsonl) or CSV map",
+            path
+        ),
+    }
+}
+
+/// Load an NDJSON map (one `MapEntry` object per line).
+pub fn load_map_from_ndjson(path: &Path) -> Result<Vec<MapEntry>> {
+    stream_map_entries(path)?.collect()
+}
+
+/// `name` escaped the way coreutils `*sum` tools do: backslash, newline and carriage
+/// return become `\\`, `\n` and `\r`. `None` when the name needs no escaping (the line
+/// then carries no leading `\`).
+fn escape_sums_name(name: &str) -> Option<String> {
+    if !name.contains(['\\', '\n', '\r']) {
+        return None;
+    }
+    Some(
+        name.replace('\\', "\\\\")
+            .replace('\n', "\\n")
+            .replace('\r', "\\r"),
+    )
+}
+
+/// One line of a coreutils-style sums file: `<hash>  <name>`. As `sha256sum` does, a
+/// name containing a backslash, newline or carriage return is escaped and the line
+/// prefixed with `\`.
+pub fn format_sums_line(hash: &str, name: &str) -> String {
+    match escape_sums_name(name) {
+        Some(escaped) => format!("\\{}  {}\n", hash, escaped),
+        None => format!("{}  {}\n", hash, name),
     }
-    Ok(out)
+}
+
+/// Tag naming `algorithm` in BSD-style sums lines, as `*sum --tag` writes `SHA256` or
+/// `MD5`: the name in upper case, except `BLAKE2b`, spelled the way `b2sum` does.
+pub fn bsd_tag(algorithm: &str) -> String {
+    if algorithm.eq_ignore_ascii_case("blake2b") {
+        return "BLAKE2b".to_string();
+    }
+    algorithm.to_ascii_uppercase()
+}
+
+/// One line of a BSD-style (`*sum --tag`) sums file: `<TAG> (<name>) = <hash>`, escaped
+/// like [`format_sums_line`].
+pub fn format_bsd_line(tag: &str, hash: &str, name: &str) -> String {
+    match escape_sums_name(name) {
+        Some(escaped) => format!("\\{} ({}) = {}\n", tag, escaped, hash),
+        None => format!("{} ({}) = {}\n", tag, name, hash),
+    }
+}
+
+fn sums_line(tag: Option<&str>, hash: &str, name: &str) -> String {
+    match tag {
+        Some(tag) => format_bsd_line(tag, hash, name),
+        None => format_sums_line(hash, name),
+    }
+}
+
+/// Parse one sums line in either layout into `(tag, hash, name)`; the tag is only present
+/// on BSD-style lines. Escaped names are unescaped and a `*` binary-mode marker dropped.
+/// `None` for lines that match neither layout.
+pub fn parse_sums_line(line: &str) -> Option<(Option<String>, String, String)> {
+    let (escaped, line) = match line.strip_prefix('\\') {
+        Some(rest) => (true, rest),
+        None => (false, line),
+    };
+    let unescape = |name: &str| {
+        if !escaped {
+            return name.to_string();
+        }
+        let mut out = String::with_capacity(name.len());
+        let mut chars = name.chars();
+        while let Some(c) = chars.next() {
+            match (c, chars.clone().next()) {
+                ('\\', Some('\\')) => {
+                    out.push('\\');
+                    chars.next();
+                }
+                ('\\', Some('n')) => {
+                    out.push('\n');
+                    chars.next();
+                }
+                ('\\', Some('r')) => {
+                    out.push('\r');
+                    chars.next();
+                }
+                _ => out.push(c),
+            }
+        }
+        out
+    };
+    let is_digest = |h: &str| !h.is_empty() && !h.contains(char::is_whitespace);
+
+    // BSD: `TAG (name) = hash`; the name may itself contain `) = `, so split at the last one
+    if let Some((head, hash)) = line.rsplit_once(") = ") {
+        if let Some((tag, name)) = head.split_once(" (") {
+            if !tag.is_empty() && !tag.contains(char::is_whitespace) && is_digest(hash) {
+                return Some((Some(tag.to_string()), hash.to_string(), unescape(name)));
+            }
+        }
+    }
+    let (hash, rest) = line.split_once(' ')?;
+    let name = rest.strip_prefix(' ').or_else(|| rest.strip_prefix('*'))?;
+    if !is_digest(hash) || name.is_empty() {
+        return None;
+    }
+    Some((None, hash.to_string(), unescape(name)))
+}
+
+/// Whether `path` holds a sums file, judged by its first non-blank line: a BSD tagged
+/// line, or a GNU line whose digest is hex. Lets sums files named like `SHA256SUMS` or
+/// `CHECKSUMS.txt` load without a `.sums` extension.
+pub fn looks_like_sums(path: &Path) -> bool {
+    let Ok(file) = fs::File::open(path) else {
+        return false;
+    };
+    let first = BufReader::new(file)
+        .lines()
+        .map_while(Result::ok)
+        .find(|l| !l.trim().is_empty());
+    let Some(line) = first else {
+        return false;
+    };
+    match parse_sums_line(line.strip_suffix('\r').unwrap_or(&line)) {
+        Some((Some(_), _, _)) => true,
+        Some((None, hash, _)) => hash.len() % 2 == 0 && hash.bytes().all(|b| b.is_ascii_hexdigit()),
+        None => false,
+    }
+}
+
+/// Load a sums file written by `hashmap --format sums` or a `*sum` tool, in GNU or BSD
+/// (`--tag`) layout. Sums files record no sizes or mtimes, so entries carry size 0; tagged
+/// lines record their algorithm in lower case.
+pub fn load_map_from_sums(path: &Path) -> Result<Vec<MapEntry>> {
+    let text = fs::read_to_string(path).with_context(|| format!("read sums {:?}", path))?;
+    let mut entries = Vec::new();
+    for (idx, line) in text.lines().enumerate() {
+        let line = line.strip_suffix('\r').unwrap_or(line);
+        if line.trim().is_empty() {
+            continue;
+        }
+        let (tag, hash, name) = parse_sums_line(line).with_context(|| {
+            format!(
+                "line {} of {:?} is not a GNU or BSD sums line",
+                idx + 1,
+                path
+            )
+        })?;
+        entries.push(MapEntry {
+            path: name,
+            hash,
+            size: 0,
+            algorithm: tag.map(|t| t.to_ascii_lowercase()),
+            ..Default::default()
+        });
+    }
+    Ok(entries)
+}
+
+/// Write a sums file for `entries` into every directory that directly contains one of
+/// them. Entry paths are resolved against `base`; each file lists only its directory's
+/// own files by name, in BSD layout when `tag` is given. Returns the number of sums files
+/// written.
+pub fn write_per_dir_sums(
+    entries: &[MapEntry],
+    base: &Path,
+    file_name: &str,
+    tag: Option<&str>,
+) -> Result<usize> {
+    let mut by_dir: std::collections::BTreeMap<&Path, Vec<&MapEntry>> =
+        std::collections::BTreeMap::new();
+    for e in entries {
+        let parent = Path::new(&e.path).parent().unwrap_or(Path::new(""));
+        by_dir.entry(parent).or_default().push(e);
+    }
+    for (dir, files) in &by_dir {
+        let mut body = String::new();
+        for e in files {
+            let name = Path::new(&e.path)
+                .file_name()
+                .map(|n| n.to_string_lossy().into_owned())
+                .unwrap_or_else(|| e.path.clone());
+            body.push_str(&sums_line(tag, &e.hash, &name));
+        }
+        let target = base.join(dir).join(file_name);
+        atomic_write(&target, body.as_bytes())
+            .with_context(|| format!("write sums file {:?}", target))?;
+    }
+    Ok(by_dir.len())
+}
+
+/// Rewrite `\` separators in entry paths as `/`, for maps written on Windows (or by
+/// tools that keep native separators) to compare against `/`-separated maps.
+pub fn normalize_separators(entries: &mut [MapEntry]) {
+    for e in entries.iter_mut() {
+        if e.path.contains('\\') {
+            e.path = e.path.replace('\\', "/");
+        }
+    }
+}
+
+/// Cut every hex digest down to its first `bytes` bytes (`compare --compare-prefix-bytes`).
+/// Digests that aren't hex (base64 or base32 maps) or are shorter than the prefix are
+/// rejected, since cutting them would not compare the same bytes on both sides.
+pub fn truncate_hashes(entries: &mut [MapEntry], bytes: usize) -> Result<()> {
+    for e in entries.iter_mut() {
+        if e.hash.len() % 2 != 0 || !e.hash.bytes().all(|b| b.is_ascii_hexdigit()) {
+            anyhow::bail!(
+                "digest of {} is not hex; prefix comparison needs hex-encoded maps",
+                e.path
+            );
+        }
+        if e.hash.len() < bytes * 2 {
+            anyhow::bail!(
+                "digest of {} is only {} bytes, shorter than the {}-byte prefix",
+                e.path,
+                e.hash.len() / 2,
+                bytes
+            );
+        }
+        e.hash.truncate(bytes * 2);
+    }
+    Ok(())
+}
+
+/// Whether any entry records an absolute path (`hashmap --absolute-paths` maps and
+/// directory inputs do).
+pub fn has_absolute_paths(entries: &[MapEntry]) -> bool {
+    entries.iter().any(|e| Path::new(&e.path).is_absolute())
+}
+
+/// Rewrite absolute entry paths under `root` as `/`-separated root-relative paths, the
+/// form `hashmap` records by default. Paths outside `root` are left alone.
+pub fn relativize_paths(entries: &mut [MapEntry], root: &Path) {
+    for e in entries.iter_mut() {
+        if let Ok(rel) = Path::new(&e.path).strip_prefix(root) {
+            e.path = rel
+                .components()
+                .collect::<PathBuf>()
+                .to_string_lossy()
+                .replace('\\', "/");
+        }
+    }
+}
+
+/// Paths listed more than once in `entries`, in first-seen order.
+pub fn duplicate_paths(entries: &[MapEntry]) -> Vec<&str> {
+    let mut seen = std::collections::HashSet::new();
+    let mut dupes = Vec::new();
+    for e in entries {
+        if !seen.insert(e.path.as_str()) && !dupes.contains(&e.path.as_str()) {
+            dupes.push(e.path.as_str());
+        }
+    }
+    dupes
+}
+
+/// Reject a loaded map that lists the same path twice: lookups keyed by path would
+/// silently keep only one of the entries. With `allow` set, each offending path is
+/// logged as a warning instead.
+pub fn check_unique_paths(entries: &[MapEntry], source: &str, allow: bool) -> Result<()> {
+    let dupes = duplicate_paths(entries);
+    if dupes.is_empty() {
+        return Ok(());
+    }
+    if allow {
+        for path in &dupes {
+            log::warn!("{}: path {:?} is listed more than once", source, path);
+        }
+        return Ok(());
+    }
+    let shown: Vec<String> = dupes.iter().take(5).map(|p| format!("{:?}", p)).collect();
+    let more = if dupes.len() > shown.len() {
+        format!(" and {} more", dupes.len() - shown.len())
+    } else {
+        String::new()
+    };
+    anyhow::bail!(
+        "{} lists {} path(s) more than once: {}{} (use --allow-duplicate-paths to continue anyway)",
+        source,
+        dupes.len(),
+        shown.join(", "),
+        more
+    )
 }
 
 #[cfg(test)]
@@ -114,6 +945,44 @@ mod tests {
     use super::*;
     use tempfile::tempdir;
 
+    #[test]
+    fn check_writable_creates_parents_and_cleans_up() {
+        let dir = tempdir().unwrap();
+        let target = dir.path().join("a").join("b").join("map.json");
+        check_writable(&target, None).unwrap();
+        assert_eq!(fs::read_dir(target.parent().unwrap()).unwrap().count(), 0);
+
+        let blocker = dir.path().join("file");
+        fs::write(&blocker, b"x").unwrap();
+        assert!(check_writable(&blocker.join("map.json"), None).is_err());
+    }
+
+    #[test]
+    fn atomic_write_via_temp_dir_leaves_only_the_target() {
+        let dir = tempdir().unwrap();
+        let staging = dir.path().join("staging");
+        fs::create_dir_all(&staging).unwrap();
+        let target = dir.path().join("out").join("map.json");
+        fs::create_dir_all(target.parent().unwrap()).unwrap();
+        fs::write(&target, b"old").unwrap();
+
+        atomic_write_via(&target, b"new contents", Some(&staging)).unwrap();
+        assert_eq!(fs::read(&target).unwrap(), b"new contents");
+        assert_eq!(fs::read_dir(&staging).unwrap().count(), 0);
+        assert_eq!(fs::read_dir(target.parent().unwrap()).unwrap().count(), 1);
+    }
+
+    #[test]
+    fn atomic_write_via_keeps_the_old_file_when_staging_fails() {
+        let dir = tempdir().unwrap();
+        let target = dir.path().join("map.json");
+        fs::write(&target, b"old").unwrap();
+
+        let missing = dir.path().join("no-such-dir");
+        assert!(atomic_write_via(&target, b"new", Some(&missing)).is_err());
+        assert_eq!(fs::read(&target).unwrap(), b"old");
+    }
+
     #[test]
     fn roundtrip_json_array() {
         let dir = tempdir().unwrap();
@@ -123,13 +992,13 @@ mod tests {
                 path: "a".into(),
                 hash: "h1".into(),
                 size: 1,
-                mtime: None,
+                ..Default::default()
             },
             MapEntry {
                 path: "b".into(),
                 hash: "h2".into(),
                 size: 2,
-                mtime: None,
+                ..Default::default()
             },
         ];
         write_json(&p, &v).unwrap();
@@ -146,13 +1015,13 @@ mod tests {
                 path: "a".into(),
                 hash: "h1".into(),
                 size: 1,
-                mtime: None,
+                ..Default::default()
             },
             MapEntry {
                 path: "b".into(),
                 hash: "h2".into(),
                 size: 2,
-                mtime: None,
+                ..Default::default()
             },
         ];
         write_csv(&p, &v).unwrap();
@@ -160,6 +1029,59 @@ mod tests {
         assert_eq!(loaded, v);
     }
 
+    #[test]
+    fn csv_algorithm_column_roundtrips() {
+        let dir = tempdir().unwrap();
+        let entry = |path: &str, hash: &str, hashes: Option<&[(&str, &str)]>| MapEntry {
+            path: path.into(),
+            hash: hash.into(),
+            size: 3,
+            mtime: Some(7),
+            hashes: hashes.map(|h| {
+                h.iter()
+                    .map(|(a, d)| (a.to_string(), d.to_string()))
+                    .collect()
+            }),
+            ..Default::default()
+        };
+
+        // single algorithm: the column is written and read back per entry
+        let p = dir.path().join("single.csv");
+        let rows = algorithm_rows(&[entry("a", "aa", None)], "blake3");
+        write_csv(&p, &rows).unwrap();
+        let text = fs::read_to_string(&p).unwrap();
+        assert_eq!(text, "path,hash,size,mtime,algorithm\na,aa,3,7,blake3\n");
+        let loaded = load_map_from_csv(&p).unwrap();
+        assert_eq!(loaded[0].algorithm.as_deref(), Some("blake3"));
+        assert_eq!(loaded[0].hashes, None);
+
+        // multi-algorithm: one row per algorithm, folded back into `hashes`
+        let p = dir.path().join("multi.csv");
+        let multi = [
+            entry("a", "a3", Some(&[("blake3", "a3"), ("md5", "a5")])),
+            entry("b", "b3", Some(&[("blake3", "b3"), ("md5", "b5")])),
+        ];
+        write_csv(&p, &algorithm_rows(&multi, "blake3")).unwrap();
+        assert_eq!(fs::read_to_string(&p).unwrap().lines().count(), 5);
+        let loaded = load_map_from_csv(&p).unwrap();
+        assert_eq!(loaded.len(), 2);
+        for (got, want) in loaded.iter().zip(&multi) {
+            assert_eq!(got.hash, want.hash);
+            assert_eq!(got.hashes, want.hashes);
+        }
+        let streamed = stream_map_entries(&p)
+            .unwrap()
+            .collect::<Result<Vec<_>>>()
+            .unwrap();
+        assert_eq!(streamed, loaded);
+
+        // without the column nothing changes
+        let p = dir.path().join("plain.csv");
+        write_csv(&p, &[entry("a", "aa", None)]).unwrap();
+        let loaded = load_map_from_csv(&p).unwrap();
+        assert_eq!(loaded, [entry("a", "aa", None)]);
+    }
+
     #[test]
     fn json_handles_empty_array() {
         let dir = tempdir().unwrap();
@@ -189,6 +1111,7 @@ mod tests {
             hash: "abc123".into(),
             size: 100,
             mtime: Some(1234567890),
+            ..Default::default()
         }];
         write_json(&p, &v).unwrap();
         let loaded = load_map_from_json(&p).unwrap();
@@ -204,6 +1127,7 @@ mod tests {
             hash: "hash\"with\"quotes".into(),
             size: 999,
             mtime: Some(9999999),
+            ..Default::default()
         }];
         write_csv(&p, &v).unwrap();
         let loaded = load_map_from_csv(&p).unwrap();
@@ -218,7 +1142,7 @@ mod tests {
             path: "文件名.txt".into(),
             hash: "🔥hash🔥".into(),
             size: 42,
-            mtime: None,
+            ..Default::default()
         }];
         write_json(&p, &v).unwrap();
         let loaded = load_map_from_json(&p).unwrap();
@@ -234,6 +1158,7 @@ mod tests {
             hash: "хеш".into(),
             size: 777,
             mtime: Some(1000),
+            ..Default::default()
         }];
         write_csv(&p, &v).unwrap();
         let loaded = load_map_from_csv(&p).unwrap();
@@ -250,6 +1175,7 @@ mod tests {
                 hash: format!("hash_{}", i),
                 size: i as u64,
                 mtime: Some(i as i64),
+                ..Default::default()
             })
             .collect();
         write_json(&p, &v).unwrap();
@@ -269,6 +1195,7 @@ mod tests {
                 hash: format!("hash_{}", i),
                 size: i as u64,
                 mtime: Some(i as i64),
+                ..Default::default()
             })
             .collect();
         write_csv(&p, &v).unwrap();
@@ -284,7 +1211,7 @@ mod tests {
             path: "test.txt".into(),
             hash: "hash123".into(),
             size: 100,
-            mtime: None,
+            ..Default::default()
         }];
         write_json(&p, &v).unwrap();
         let loaded = load_map_from_json(&p).unwrap();
@@ -299,13 +1226,64 @@ mod tests {
             path: "test.txt".into(),
             hash: "hash123".into(),
             size: 100,
-            mtime: None,
+            ..Default::default()
         }];
         write_csv(&p, &v).unwrap();
         let loaded = load_map_from_csv(&p).unwrap();
         assert_eq!(loaded[0].mtime, None);
     }
 
+    #[test]
+    fn csv_detects_a_header_with_reordered_columns() {
+        let dir = tempdir().unwrap();
+        let p = dir.path().join("reordered.csv");
+        fs::write(&p, "hash,path,size,mtime\nh1,a.txt,1,1700000000\n").unwrap();
+        assert!(CsvOptions::detect_file(&p).unwrap().header);
+        let loaded = load_map_from_csv(&p).unwrap();
+        assert_eq!(loaded.len(), 1);
+        assert_eq!(loaded[0].path, "a.txt");
+        assert_eq!(loaded[0].hash, "h1");
+        assert_eq!(loaded[0].mtime, Some(1_700_000_000));
+
+        assert!(!CsvOptions::detect("a.txt,h1,1,1700000000").header);
+    }
+
+    #[test]
+    fn csv_semicolon_headerless_crlf_roundtrip() {
+        let dir = tempdir().unwrap();
+        let p = dir.path().join("excel.csv");
+        let v = vec![
+            MapEntry {
+                path: "dir/a,b.txt".into(),
+                hash: "h1".into(),
+                size: 1,
+                mtime: Some(1_700_000_000),
+                ..Default::default()
+            },
+            MapEntry {
+                path: "c.txt".into(),
+                hash: "h2".into(),
+                size: 2,
+                mtime: Some(1_700_000_001),
+                ..Default::default()
+            },
+        ];
+        let opts = CsvOptions {
+            delimiter: b';',
+            header: false,
+            crlf: true,
+        };
+        write_csv_with(&p, &v, &opts).unwrap();
+        assert_eq!(
+            fs::read_to_string(&p).unwrap(),
+            "dir/a,b.txt;h1;1;1700000000\r\nc.txt;h2;2;1700000001\r\n"
+        );
+        assert_eq!(load_map_from_csv_with(&p, &opts).unwrap(), v);
+        // the dialect is also picked up without being told
+        assert_eq!(CsvOptions::detect_file(&p).unwrap(), opts);
+        assert_eq!(load_map_from_csv(&p).unwrap(), v);
+    }
+
     #[test]
     fn json_handles_very_long_paths() {
         let dir = tempdir().unwrap();
@@ -315,7 +1293,7 @@ mod tests {
             path: long_path.clone(),
             hash: "hash".into(),
             size: 1,
-            mtime: None,
+            ..Default::default()
         }];
         write_json(&p, &v).unwrap();
         let loaded = load_map_from_json(&p).unwrap();
@@ -331,7 +1309,7 @@ mod tests {
             path: "file.txt".into(),
             hash: long_hash.clone(),
             size: 1,
-            mtime: None,
+            ..Default::default()
         }];
         write_json(&p, &v).unwrap();
         let loaded = load_map_from_json(&p).unwrap();
@@ -346,7 +1324,7 @@ mod tests {
             path: "empty.txt".into(),
             hash: "empty_hash".into(),
             size: 0,
-            mtime: None,
+            ..Default::default()
         }];
         write_json(&p, &v).unwrap();
         let loaded = load_map_from_json(&p).unwrap();
@@ -361,7 +1339,7 @@ mod tests {
             path: "huge.txt".into(),
             hash: "hash".into(),
             size: u64::MAX,
-            mtime: None,
+            ..Default::default()
         }];
         write_json(&p, &v).unwrap();
         let loaded = load_map_from_json(&p).unwrap();
@@ -392,4 +1370,124 @@ mod tests {
         let result = load_map_from_json(&p);
         assert!(result.is_err());
     }
+
+    #[test]
+    fn repeated_path_is_rejected_unless_allowed() {
+        let dir = tempdir().unwrap();
+        let p = dir.path().join("dupes.csv");
+        std::fs::write(
+            &p,
+            "path,hash,size\na.txt,00ff,1\nb.txt,0011,1\na.txt,ff00,1\n",
+        )
+        .unwrap();
+        let loaded = load_map_from_csv(&p).unwrap();
+        assert_eq!(duplicate_paths(&loaded), vec!["a.txt"]);
+
+        let err = check_unique_paths(&loaded, "dupes.csv", false).unwrap_err();
+        let msg = err.to_string();
+        assert!(msg.contains("\"a.txt\""), "{}", msg);
+        assert!(msg.contains("--allow-duplicate-paths"), "{}", msg);
+        assert!(check_unique_paths(&loaded, "dupes.csv", true).is_ok());
+    }
+
+    #[test]
+    fn sums_line_escapes_like_coreutils() {
+        assert_eq!(format_sums_line("ab12", "a.txt"), "ab12  a.txt\n");
+        assert_eq!(
+            format_sums_line("ab12", "odd\\name\nx"),
+            "\\ab12  odd\\\\name\\nx\n"
+        );
+    }
+
+    #[test]
+    fn bsd_sums_roundtrip_through_loader() {
+        let dir = tempdir().unwrap();
+        let entries: Vec<MapEntry> = ["a.txt", "sub/b (1).txt", "odd\\name\nx"]
+            .iter()
+            .zip(["ab12", "cd34", "ef56"])
+            .map(|(path, hash)| MapEntry {
+                path: path.to_string(),
+                hash: hash.to_string(),
+                size: 0,
+                ..Default::default()
+            })
+            .collect();
+
+        let text = to_sums(&entries, Some(&bsd_tag("blake3")));
+        assert!(text.starts_with("BLAKE3 (a.txt) = ab12\n"), "{}", text);
+        assert!(
+            text.contains("\\BLAKE3 (odd\\\\name\\nx) = ef56\n"),
+            "{}",
+            text
+        );
+        let p = dir.path().join("map.sums");
+        fs::write(&p, &text).unwrap();
+        let loaded = load_map_from_sums(&p).unwrap();
+        assert_eq!(loaded.len(), 3);
+        for (got, want) in loaded.iter().zip(&entries) {
+            assert_eq!((&got.path, &got.hash), (&want.path, &want.hash));
+            assert_eq!(got.algorithm.as_deref(), Some("blake3"));
+        }
+
+        // GNU lines (including `*` binary markers) still load, without an algorithm
+        fs::write(&p, format!("{}cd34 *bin.dat\n", to_sums(&entries, None))).unwrap();
+        let loaded = load_map_from_sums(&p).unwrap();
+        let pairs: Vec<(&str, &str)> = loaded
+            .iter()
+            .map(|e| (e.path.as_str(), e.hash.as_str()))
+            .collect();
+        assert_eq!(
+            pairs,
+            [
+                ("a.txt", "ab12"),
+                ("sub/b (1).txt", "cd34"),
+                ("odd\\name\nx", "ef56"),
+                ("bin.dat", "cd34")
+            ]
+        );
+        assert!(loaded.iter().all(|e| e.algorithm.is_none()));
+
+        fs::write(&p, "not a sums line\n").unwrap();
+        assert!(load_map_from_sums(&p).is_err());
+    }
+
+    #[test]
+    fn sums_names_escape_carriage_returns_and_sniff_by_content() {
+        assert_eq!(format_sums_line("ab12", "a\rb"), "\\ab12  a\\rb\n");
+        assert_eq!(
+            format_bsd_line(&bsd_tag("blake2b"), "ab12", "a\rb"),
+            "\\BLAKE2b (a\\rb) = ab12\n"
+        );
+        assert_eq!(
+            parse_sums_line("\\BLAKE2b (a\\rb) = ab12"),
+            Some((Some("BLAKE2b".into()), "ab12".into(), "a\rb".into()))
+        );
+
+        let dir = tempdir().unwrap();
+        let sniff = |name: &str, text: &str| {
+            let p = dir.path().join(name);
+            fs::write(&p, text).unwrap();
+            looks_like_sums(&p)
+        };
+        assert!(sniff("SHA256SUMS", "\nab12  a.txt\n"));
+        assert!(sniff("CHECKSUMS.txt", "BLAKE3 (a b.txt) = ab12\r\n"));
+        assert!(!sniff("map", "{\"entries\": []}\n"));
+        assert!(!sniff("rows", "path,hash,size\na  b.txt,ab12,3\n"));
+        assert!(!sniff("words", "hello  world\n"));
+        assert!(!sniff("empty", ""));
+    }
+
+    #[test]
+    fn truncate_hashes_needs_long_enough_hex() {
+        let entry = |hash: &str| MapEntry {
+            path: "a".into(),
+            hash: hash.into(),
+            ..Default::default()
+        };
+        let mut entries = vec![entry("00112233aabb")];
+        truncate_hashes(&mut entries, 2).unwrap();
+        assert_eq!(entries[0].hash, "0011");
+        assert!(truncate_hashes(&mut [entry("0011")], 4).is_err());
+        assert!(truncate_hashes(&mut [entry("ABEiM6q7")], 2).is_err());
+    }
 }
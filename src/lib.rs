@@ -4,24 +4,37 @@ pub mod cli;
 pub mod compare;
 pub mod config;
 pub mod copy;
+pub mod dedupe;
 pub mod diff;
+pub mod events;
 pub mod hash;
 pub mod io;
+pub mod map;
 pub mod memory;
 pub mod pipeline;
+#[cfg(feature = "url")]
+pub mod remote;
 pub mod removempty;
 pub mod renamer;
 pub mod report;
 pub mod utils;
+pub mod verify;
 pub mod walk;
 
 pub use config::RuntimeConfig;
 pub use hash::{AlgorithmInfo, HasherImpl};
+pub use map::{hash_directory, HashOptions};
 pub use memory::{BufferPool, MemoryMode};
 pub use pipeline::Pipeline;
-pub use removempty::remove_empty_directories;
+pub use removempty::{
+    remove_empty_directories, remove_empty_directories_with_options, RemoveEmptyOptions,
+    RemoveEmptySummary,
+};
 pub use renamer::rename_files;
+pub use renamer::rename_files_with;
 pub use renamer::rename_files_with_options;
+pub use renamer::RenameOptions;
+pub use renamer::RenameSummary;
 
 pub use bench::run_benchmark;
 pub use report::generate_report;
@@ -1,25 +1,42 @@
+use std::collections::{BTreeMap, HashSet};
 use std::fs::File;
-use std::io::{BufReader, Write};
+use std::io::{BufReader, Read, Write};
 use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
 use std::sync::{Arc, Mutex};
 use std::time::{Duration, Instant, UNIX_EPOCH};
 
+use anyhow::Context;
 use chrono::Utc;
 use clap::Parser;
-use globset::{Glob, GlobSetBuilder};
 use log::{info, warn};
 use serde::Serialize;
 
-use hash_folderoo::algorithms::Algorithm;
-use hash_folderoo::cli::Cli;
+use hash_folderoo::algorithms::{parallelhash, Algorithm, AlgorithmParams};
+use hash_folderoo::cli::{Cli, HashmapArgs};
 use hash_folderoo::compare as compare_mod;
 use hash_folderoo::config;
 use hash_folderoo::copy;
-use hash_folderoo::hash::hash_path_with_pool;
+use hash_folderoo::dedupe::{DedupBy, KeepPolicy};
+use hash_folderoo::diff;
+use hash_folderoo::events::{self, Event, EventSink};
+use hash_folderoo::hash::{
+    hash_reader_multi_with_pool, hash_reader_with_pool, is_empty_regular_file, size_digest,
+    DigestEncoding, HasherImpl,
+};
 use hash_folderoo::io;
-use hash_folderoo::memory::MemoryMode;
-use hash_folderoo::pipeline::Pipeline;
-use hash_folderoo::utils::setup_logging;
+use hash_folderoo::map;
+use hash_folderoo::memory::{recommend_config, BufferPool, MemoryMode, MemoryPlan};
+use hash_folderoo::pipeline::{
+    validate_progress_template, Pipeline, ProgressTarget, DEFAULT_ETA_SMOOTHING,
+};
+use hash_folderoo::report::{self, ReportOptions};
+use hash_folderoo::utils::{
+    canonical_input_path, classify_path_error, dedupe_input_paths, long_path, normalize_lexically,
+    portable_path, setup_logging, ErrorBudget, ErrorTally,
+};
+use hash_folderoo::verify;
+use hash_folderoo::walk;
 
 fn format_entry_path(path: &Path, strip_prefix: Option<&Path>, root: &Path) -> String {
     let logical = strip_prefix
@@ -37,11 +54,16 @@ fn print_algorithm_list() {
     for alg in Algorithm::all() {
         let info = alg.create().info();
         println!(
-            "- {name:<10} default_len: {len:>3} bytes  cryptographic: {crypto}  xof: {xof}",
+            "- {name:<10} default_len: {len:>3} bytes  cryptographic: {crypto}  xof: {xof}{note}",
             name = info.name,
             len = info.output_len_default,
             crypto = if info.is_cryptographic { "yes" } else { "no" },
-            xof = if info.supports_xof { "yes" } else { "no" }
+            xof = if info.supports_xof { "yes" } else { "no" },
+            note = if alg.is_deprecated() {
+                "  (deprecated: legacy interop only)"
+            } else {
+                ""
+            }
         );
     }
 }
@@ -51,8 +73,51 @@ struct MapHeader {
     version: u8,
     generated_by: &'static str,
     timestamp: String,
-    root: String,
+    root: Option<String>,
     algorithm: AlgorithmMeta,
+    /// `io::map_fingerprint` of the algorithm settings, checked by `compare`
+    fingerprint: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    truncated: Option<Truncation>,
+    /// `false` for `--structure-only` maps, whose hashes are size placeholders
+    #[serde(skip_serializing_if = "Option::is_none")]
+    content_hashed: Option<bool>,
+}
+
+/// Why a scan ended before every file was hashed.
+#[derive(Serialize)]
+struct Truncation {
+    reason: &'static str,
+    limit_bytes: u64,
+    hashed_bytes: u64,
+}
+
+/// Complete JSON map document: header fields followed by the entries.
+#[derive(Serialize)]
+struct MapOutput<'a> {
+    #[serde(flatten)]
+    header: &'a MapHeader,
+    entries: &'a [io::MapEntry],
+}
+
+/// `hashmap --explain-plan --format json` output.
+#[derive(Serialize)]
+struct PlanExplanation<'a> {
+    #[serde(flatten)]
+    plan: &'a MemoryPlan,
+    total_buffer_bytes: u64,
+}
+
+/// `hashmap --run-summary` output.
+#[derive(Serialize)]
+struct RunSummary<'a> {
+    files: u64,
+    bytes: u64,
+    elapsed_ms: f64,
+    errors: u64,
+    algorithm: &'a str,
+    #[serde(flatten)]
+    plan: MemoryPlan,
 }
 
 #[derive(Serialize)]
@@ -67,395 +132,1287 @@ struct FileTiming {
     duration: Duration,
 }
 
-fn build_exclude_set(patterns: &[String]) -> anyhow::Result<Option<globset::GlobSet>> {
-    if patterns.is_empty() {
-        return Ok(None);
-    }
-    let mut builder = GlobSetBuilder::new();
-    for p in patterns {
-        let g = Glob::new(p)?;
-        builder.add(g);
+/// Root directory associated with a compare/copydiff input: the directory itself, or the
+/// `root` recorded in a JSON map header.
+fn input_root(input: &Path) -> Option<PathBuf> {
+    if input.is_dir() {
+        return Some(input.canonicalize().unwrap_or_else(|_| input.to_path_buf()));
     }
-    Ok(Some(builder.build()?))
+    io::load_map_root(input).ok().flatten().map(PathBuf::from)
 }
 
-fn main() -> anyhow::Result<()> {
-    setup_logging();
-
-    let cli = Cli::parse();
-    if cli.alg_list {
-        print_algorithm_list();
-        return Ok(());
+/// When a side records absolute paths (`hashmap --absolute-paths`, directory inputs),
+/// rewrite it relative to its root so both sides line up by path. Two such sides (e.g.
+/// two directories) are each made relative to their own root.
+fn align_path_styles(
+    src: &mut [io::MapEntry],
+    source: &Path,
+    tgt: &mut [io::MapEntry],
+    target: &Path,
+) {
+    match (io::has_absolute_paths(src), io::has_absolute_paths(tgt)) {
+        (true, true) => {
+            // Without both roots the absolute paths are the best common ground
+            if let (Some(src_root), Some(tgt_root)) = (input_root(source), input_root(target)) {
+                io::relativize_paths(src, &src_root);
+                io::relativize_paths(tgt, &tgt_root);
+            }
+        }
+        (true, false) => relativize_to_root(src, source),
+        (false, true) => relativize_to_root(tgt, target),
+        (false, false) => {}
     }
+}
 
-    let mut runtime_cfg = config::load_runtime_config(cli.config.as_deref())?;
-    config::apply_env_overrides(&mut runtime_cfg);
-    runtime_cfg.validate()?;
-
-    match &cli.command {
-        Some(hash_folderoo::cli::Commands::Hashmap(args)) => {
-            let runtime_cfg = runtime_cfg.clone();
-            // Note: Phase 1 CLI doesn't include all previous flags (e.g. strip-prefix, xof-length).
-            // Where applicable the runtime config can still provide defaults.
+/// Refuse a `--baseline` map whose digests can't sit next to this run's: one recorded
+/// with a different fingerprint (algorithm, digest length or parameters) or algorithm,
+/// or holding digests of another length or encoding.
+fn check_baseline(
+    baseline: &Path,
+    entries: &[io::MapEntry],
+    fingerprint: &str,
+    algorithm: &str,
+    out_len: usize,
+    encoding: DigestEncoding,
+) -> anyhow::Result<()> {
+    let refuse = |why: String| {
+        anyhow::anyhow!(
+            "baseline {} {}; hash the tree in full instead",
+            baseline.display(),
+            why
+        )
+    };
+    if let Some(recorded) = io::load_map_fingerprint(baseline)? {
+        if recorded != fingerprint {
+            return Err(refuse(format!(
+                "was hashed with different algorithm settings (fingerprint {}, this run {})",
+                recorded, fingerprint
+            )));
+        }
+    } else if let Some((name, _)) = verify::load_map_algorithm(baseline)? {
+        if !name.eq_ignore_ascii_case(algorithm) {
+            return Err(refuse(format!(
+                "was hashed with {}, this run uses {}",
+                name, algorithm
+            )));
+        }
+    }
+    if let Some(entry) = entries.iter().find(|e| !e.hash.is_empty()) {
+        if encoding.decode(&entry.hash).map(|d| d.len()).ok() != Some(out_len) {
+            return Err(refuse(format!(
+                "holds digests that aren't {}-byte {} values",
+                out_len,
+                encoding.name()
+            )));
+        }
+    }
+    Ok(())
+}
 
-            // CLI args override config
-            let path = args
-                .path
-                .as_ref()
-                .map(|p| p.clone().to_string_lossy().into_owned())
-                .or_else(|| runtime_cfg.general.as_ref().and_then(|g| g.path.clone()))
-                .ok_or_else(|| {
-                    anyhow::anyhow!("--path is required (CLI flag or config general.path)")
-                })?;
+fn relativize_to_root(entries: &mut [io::MapEntry], input: &Path) {
+    match input_root(input) {
+        Some(root) => io::relativize_paths(entries, &root),
+        None => warn!(
+            "{} records absolute paths but no root; paths will not line up with the other side",
+            input.display()
+        ),
+    }
+}
 
-            let output = args
-                .output
+/// Run one `hashmap` scan and write its map; returns the entries as written so
+/// `--interval` can diff successive scans.
+fn run_hashmap(
+    args: &HashmapArgs,
+    runtime_cfg: &config::RuntimeConfig,
+) -> anyhow::Result<Vec<io::MapEntry>> {
+    // Note: Phase 1 CLI doesn't include all previous flags (e.g. strip-prefix, xof-length).
+    // Where applicable the runtime config can still provide defaults.
+
+    // CLI args override config
+    let path = args
+        .path
+        .as_ref()
+        .map(|p| p.clone().to_string_lossy().into_owned())
+        .or_else(|| runtime_cfg.general.as_ref().and_then(|g| g.path.clone()))
+        .ok_or_else(|| anyhow::anyhow!("--path is required (CLI flag or config general.path)"))?;
+
+    let output = args
+        .output
+        .as_ref()
+        .map(|p| p.as_path().to_string_lossy().into_owned())
+        .or_else(|| runtime_cfg.general.as_ref().and_then(|g| g.output.clone()));
+
+    let alg = args
+        .algorithm
+        .as_deref()
+        .or_else(|| {
+            runtime_cfg
+                .algorithm
                 .as_ref()
-                .map(|p| p.as_path().to_string_lossy().into_owned())
-                .or_else(|| runtime_cfg.general.as_ref().and_then(|g| g.output.clone()));
+                .and_then(|a| a.name.as_deref())
+        })
+        .unwrap_or("blake3");
+    // `--algorithm all` / `--algorithms a,b`: each file is read once into every hasher
+    let multi_names: Vec<String> = if !args.algorithms.is_empty() {
+        args.algorithms.clone()
+    } else if alg.eq_ignore_ascii_case("all") {
+        Algorithm::list().into_iter().map(str::to_string).collect()
+    } else {
+        Vec::new()
+    };
+    let alg = if alg.eq_ignore_ascii_case("all") {
+        "blake3"
+    } else {
+        multi_names.first().map(String::as_str).unwrap_or(alg)
+    };
+
+    let strip_prefix: Option<PathBuf> = args.strip_prefix.clone().or_else(|| {
+        runtime_cfg
+            .general
+            .as_ref()
+            .and_then(|g| g.strip_prefix.clone().map(PathBuf::from))
+    });
+
+    let mut excludes = runtime_cfg
+        .general
+        .as_ref()
+        .and_then(|g| g.exclude.clone())
+        .unwrap_or_default();
+    if !args.exclude.is_empty() {
+        excludes.extend(args.exclude.clone());
+    }
+    // Sums files from an earlier --per-dir-output run are not content
+    if let Some(name) = &args.per_dir_output {
+        excludes.push(format!("**/{}", name));
+    }
 
-            let alg = args
-                .algorithm
-                .as_deref()
-                .or_else(|| {
-                    runtime_cfg
-                        .algorithm
-                        .as_ref()
-                        .and_then(|a| a.name.as_deref())
-                })
-                .unwrap_or("blake3");
+    let depth = args
+        .depth
+        .or_else(|| runtime_cfg.general.as_ref().and_then(|g| g.depth));
+
+    let follow_symlinks = if args.follow_symlinks {
+        true
+    } else {
+        runtime_cfg
+            .general
+            .as_ref()
+            .and_then(|g| g.follow_symlinks)
+            .unwrap_or(false)
+    };
+
+    let hash_symlink_targets = args.hash_symlink_target_path;
+    if hash_symlink_targets && follow_symlinks {
+        anyhow::bail!("--hash-symlink-target-path can't be used while following symlinks");
+    }
 
-            let xof_len = args
-                .xof_length
-                .or_else(|| runtime_cfg.algorithm.as_ref().and_then(|a| a.xof_length));
+    let show_progress = if args.progress {
+        true
+    } else {
+        runtime_cfg
+            .general
+            .as_ref()
+            .and_then(|g| g.progress)
+            .unwrap_or(false)
+    };
+
+    if show_progress && args.progress_to_stdout && output.is_none() {
+        warn!(
+            "--progress-to-stdout without --output mixes the progress bar into the map on stdout"
+        );
+    }
+    if let Some(template) = &args.progress_template {
+        validate_progress_template(template)?;
+    }
+    let output_mode = args
+        .output_mode
+        .as_deref()
+        .map(io::parse_output_mode)
+        .transpose()?;
+    if output_mode.is_some() && output.is_none() {
+        warn!("--output-mode has no effect when the map is written to stdout");
+    }
+    let checksum_alg = match args.with_checksum.as_deref() {
+        Some("") => Some(None),
+        Some(name) => Some(Some(
+            Algorithm::from_name(name)
+                .inspect(Algorithm::warn_if_deprecated)
+                .ok_or_else(|| anyhow::anyhow!("unknown algorithm {} for --with-checksum", name))?,
+        )),
+        None => None,
+    };
+
+    let dry_run = if args.dry_run {
+        true
+    } else {
+        runtime_cfg
+            .general
+            .as_ref()
+            .and_then(|g| g.dry_run)
+            .unwrap_or(false)
+    };
+
+    let sort_by_hash = match args.sort.as_deref().map(str::to_lowercase).as_deref() {
+        Some("hash") => true,
+        Some("path") | None => false,
+        Some(other) => anyhow::bail!("unknown --sort key {} (expected path or hash)", other),
+    };
+    let upper_hex = match args.hex_case.as_deref().map(str::to_lowercase).as_deref() {
+        Some("upper") => true,
+        Some("lower") | None => false,
+        Some(other) => anyhow::bail!("unknown --hex-case {} (expected lower or upper)", other),
+    };
+    let eta_smoothing = args.progress_eta_smoothing.unwrap_or(DEFAULT_ETA_SMOOTHING);
+    if !(eta_smoothing > 0.0 && eta_smoothing <= 1.0) {
+        anyhow::bail!(
+            "--progress-eta-smoothing must be greater than 0 and at most 1 (got {})",
+            eta_smoothing
+        );
+    }
+    if let Some(rate) = args.max_error_rate {
+        if !(0.0..=100.0).contains(&rate) {
+            anyhow::bail!(
+                "--max-error-rate must be a percentage from 0 to 100 (got {})",
+                rate
+            );
+        }
+    }
+    let error_budget = Arc::new(ErrorBudget::new(args.max_errors, args.max_error_rate));
+    let bsd_style = match args
+        .checksum_style
+        .as_deref()
+        .map(str::to_lowercase)
+        .as_deref()
+    {
+        Some("bsd") => true,
+        Some("gnu") | None => false,
+        Some(other) => anyhow::bail!("unknown --checksum-style {} (expected gnu or bsd)", other),
+    };
+
+    // Fail before the scan rather than after hours of hashing
+    if let Some(p) = output.as_deref().filter(|_| !dry_run && !args.explain_plan) {
+        io::check_writable(Path::new(p), args.temp_dir.as_deref())?;
+    }
 
-            let strip_prefix: Option<PathBuf> = args.strip_prefix.clone().or_else(|| {
-                runtime_cfg
-                    .general
-                    .as_ref()
-                    .and_then(|g| g.strip_prefix.clone().map(PathBuf::from))
-            });
+    if !args.silent {
+        info!("Computing hashmap for {} using alg {}", path, alg);
+    }
 
-            let mut excludes = runtime_cfg
+    let alg_enum = match Algorithm::from_name(alg) {
+        Some(a) => a,
+        None => {
+            warn!("Unknown algorithm {}, falling back to blake3", alg);
+            Algorithm::Blake3
+        }
+    };
+    alg_enum.warn_if_deprecated();
+    let bsd_tag = bsd_style.then(|| io::bsd_tag(alg_enum.name()));
+
+    // Probe to determine default out length
+    let alg_info = alg_enum.create().info();
+    // --xof-length, else the config's length for this algorithm, else its global one
+    let xof_len = args.xof_length.or_else(|| {
+        runtime_cfg
+            .algorithm
+            .as_ref()
+            .and_then(|a| a.xof_length_for(alg_enum))
+    });
+    if xof_len.is_some() && !alg_info.supports_xof && !args.force_expand {
+        anyhow::bail!(
+            "algorithm {} does not support --xof-length (use --force-expand to opt-in to non-native expansion)",
+            alg_info.name
+        );
+    }
+    if xof_len.is_some() && !alg_info.supports_xof && args.force_expand {
+        warn!(
+            "algorithm {} does not natively support XOF; proceeding with deterministic expansion (non-standard)",
+            alg_info.name
+        );
+    }
+    let default_out = alg_info.output_len_default;
+    let out_len = xof_len.unwrap_or(default_out);
+
+    // Handle output format: `auto` (default) follows the --output extension, falling
+    // back to json for stdout and unrecognised extensions
+    let requested_format = args
+        .format
+        .as_deref()
+        .or_else(|| {
+            runtime_cfg
                 .general
                 .as_ref()
-                .and_then(|g| g.exclude.clone())
-                .unwrap_or_default();
-            if !args.exclude.is_empty() {
-                excludes.extend(args.exclude.clone());
-            }
-
-            let depth = args
-                .depth
-                .or_else(|| runtime_cfg.general.as_ref().and_then(|g| g.depth));
+                .and_then(|g| g.format.as_deref())
+        })
+        .unwrap_or("auto")
+        .to_lowercase();
+    let inferred_format = output
+        .as_deref()
+        .and_then(|p| io::format_for_path(Path::new(p)));
+    let format = if requested_format == "auto" {
+        inferred_format.unwrap_or("json").to_string()
+    } else {
+        if let Some(inferred) = inferred_format.filter(|f| *f != requested_format) {
+            warn!(
+                "--format {} contradicts the {} extension of {}; writing {} anyway (use --format auto to follow the extension)",
+                requested_format,
+                inferred,
+                output.as_deref().unwrap_or_default(),
+                requested_format
+            );
+        }
+        requested_format
+    };
+
+    let csv_options = io::CsvOptions {
+        delimiter: args
+            .csv_delimiter
+            .as_deref()
+            .map(io::CsvOptions::parse_delimiter)
+            .transpose()?
+            .unwrap_or(b','),
+        header: !args.csv_no_header,
+        crlf: args.csv_crlf,
+    };
+    if csv_options != io::CsvOptions::default() && format != "csv" {
+        warn!("--csv-* options only apply to CSV maps, not {}", format);
+    }
+    if args.checksum_style.is_some() && format != "sums" && args.per_dir_output.is_none() {
+        warn!(
+            "--checksum-style only applies to sums files, not {}",
+            format
+        );
+    }
 
-            let follow_symlinks = if args.follow_symlinks {
-                true
-            } else {
-                runtime_cfg
-                    .general
-                    .as_ref()
-                    .and_then(|g| g.follow_symlinks)
-                    .unwrap_or(false)
-            };
+    let mut extra_algs: Vec<Algorithm> = Vec::new();
+    for name in &multi_names {
+        let extra = Algorithm::from_name(name)
+            .ok_or_else(|| anyhow::anyhow!("unknown algorithm {} in --algorithms", name))?;
+        extra.warn_if_deprecated();
+        if extra.name() != alg_enum.name() && !extra_algs.iter().any(|a| a.name() == extra.name()) {
+            extra_algs.push(extra);
+        }
+    }
+    // Extra algorithms take --xof-length (or the config's length for them) and the
+    // algorithm parameters just like the first one
+    let mut extra_lens: Vec<usize> = Vec::with_capacity(extra_algs.len());
+    for extra in &extra_algs {
+        let info = extra.create().info();
+        let len = args.xof_length.or_else(|| {
+            runtime_cfg
+                .algorithm
+                .as_ref()
+                .and_then(|a| a.xof_length_for(*extra))
+        });
+        if len.is_some() && !info.supports_xof && !args.force_expand {
+            anyhow::bail!(
+                "algorithm {} in --algorithms does not support --xof-length (use --force-expand to opt-in to non-native expansion)",
+                info.name
+            );
+        }
+        extra_lens.push(len.unwrap_or(info.output_len_default));
+    }
+    if !extra_algs.is_empty() && (format == "sums" || args.per_dir_output.is_some()) {
+        anyhow::bail!(
+            "sums files hold one digest per file; --algorithms needs JSON, NDJSON or CSV output"
+        );
+    }
+    if !extra_algs.is_empty() && format == "csv" && !csv_options.header {
+        anyhow::bail!(
+            "multi-algorithm CSV maps need the header row to name their algorithm column"
+        );
+    }
+    if hash_symlink_targets && matches!(format.as_str(), "csv" | "sums") {
+        anyhow::bail!(
+            "--hash-symlink-target-path needs JSON or NDJSON output to mark symlink entries"
+        );
+    }
 
-            let show_progress = if args.progress {
-                true
-            } else {
-                runtime_cfg
-                    .general
-                    .as_ref()
-                    .and_then(|g| g.progress)
-                    .unwrap_or(false)
-            };
+    let mut alg_params = AlgorithmParams::default();
+    if let Some(block_size) = args.block_size {
+        if !matches!(alg_enum, Algorithm::ParallelHash256) {
+            anyhow::bail!(
+                "--block-size only applies to parallelhash256, not {}",
+                alg_info.name
+            );
+        }
+        alg_params.block_size = Some(block_size);
+    } else if let Some(block_size) = runtime_cfg.algorithm.as_ref().and_then(|a| a.block_size) {
+        if matches!(alg_enum, Algorithm::ParallelHash256) {
+            alg_params.block_size = Some(block_size);
+        } else {
+            warn!(
+                "algorithm.block_size is ignored for {} (only parallelhash256 uses it)",
+                alg_info.name
+            );
+        }
+    }
+    if let Some(block_size) = alg_params.block_size {
+        parallelhash::validate_block_size(block_size)?;
+    }
+    if let Some(custom) = &args.customization {
+        if !alg_enum.supports_customization() {
+            anyhow::bail!(
+                "--customization only applies to k12 and parallelhash256, not {}",
+                alg_info.name
+            );
+        }
+        alg_params.customization = Some(custom.clone());
+    } else if let Some(custom) = runtime_cfg
+        .algorithm
+        .as_ref()
+        .and_then(|a| a.customization.clone())
+    {
+        if alg_enum.supports_customization() {
+            alg_params.customization = Some(custom);
+        } else {
+            warn!(
+                "algorithm.customization is ignored for {} (only k12 and parallelhash256 use it)",
+                alg_info.name
+            );
+        }
+    }
+    alg_params.customization = alg_params.customization.filter(|c| !c.is_empty());
+
+    // Determine memory mode from CLI/config (defaults to Balanced)
+    let mem_mode_str = args
+        .mem_mode
+        .as_deref()
+        .or_else(|| runtime_cfg.memory.as_ref().and_then(|m| m.mode.as_deref()))
+        .unwrap_or("balanced");
+    let mode = MemoryMode::from_name(mem_mode_str);
+
+    let threads_override = args
+        .threads
+        .or_else(|| runtime_cfg.general.as_ref().and_then(|g| g.threads));
+
+    let max_ram_override = args
+        .max_ram
+        .or_else(|| runtime_cfg.memory.as_ref().and_then(|m| m.max_ram));
+
+    let digest_encoding = match args.digest_encoding.as_deref() {
+        Some(name) => DigestEncoding::from_name(name).ok_or_else(|| {
+            anyhow::anyhow!(
+                "unknown digest encoding {} (expected hex, base64, base64-std, base32)",
+                name
+            )
+        })?,
+        None => DigestEncoding::Hex,
+    };
+    if digest_encoding != DigestEncoding::Hex && args.hex_case.is_some() {
+        anyhow::bail!("--hex-case only applies to hex digests");
+    }
 
-            let dry_run = if args.dry_run {
-                true
-            } else {
-                runtime_cfg
-                    .general
-                    .as_ref()
-                    .and_then(|g| g.dry_run)
-                    .unwrap_or(false)
+    if args.explain_plan {
+        let plan = recommend_config(mode, threads_override, max_ram_override)?;
+        if args.format.as_deref() == Some("json") {
+            let explained = PlanExplanation {
+                plan: &plan,
+                total_buffer_bytes: plan.total_buffer_bytes(),
             };
+            println!("{}", serde_json::to_string_pretty(&explained)?);
+        } else {
+            println!(
+                "mode:         {}",
+                format!("{:?}", plan.mode).to_lowercase()
+            );
+            println!("threads:      {}", plan.threads);
+            println!("buffer_size:  {} bytes", plan.buffer_size);
+            println!("num_buffers:  {}", plan.num_buffers);
+            println!(
+                "total:        {:.2} MiB",
+                plan.total_buffer_bytes() as f64 / (1024.0 * 1024.0)
+            );
+            println!("prefetch:     {}", plan.prefetch_listing);
+        }
+        return Ok(Vec::new());
+    }
 
-            if !args.silent {
-                info!("Computing hashmap for {} using alg {}", path, alg);
+    // Byte budget: the worker tallies hashed bytes and raises `stop` once it is
+    // exceeded; `budget_spent` tells that apart from stops for errors
+    let hashed_bytes = Arc::new(AtomicU64::new(0));
+    let stop = Arc::new(AtomicBool::new(false));
+    let budget_spent = Arc::new(AtomicBool::new(false));
+    let max_total_bytes = args.max_total_bytes;
+
+    // The plan the pipeline settles on, for --run-summary
+    let run_plan: Arc<Mutex<Option<MemoryPlan>>> = Arc::new(Mutex::new(None));
+
+    // Create pipeline with chosen memory mode
+    let pipeline = Pipeline::new(mode)
+        .with_threads(threads_override)
+        .with_max_ram(max_ram_override)
+        .with_io_threads(args.io_threads)
+        .with_hash_threads(args.hash_threads)
+        .with_large_file_threshold(args.large_file_threshold)
+        .with_one_file_system(args.one_file_system)
+        .with_extensions(args.ext.clone())
+        .with_symlinks(hash_symlink_targets)
+        .with_max_files(args.max_files)
+        .with_exclude_case_insensitive(args.exclude_case_insensitive)
+        .with_progress_target(if args.progress_to_stdout {
+            ProgressTarget::Stdout
+        } else {
+            ProgressTarget::Stderr
+        })
+        .with_progress_refresh(args.progress_refresh.map(Duration::from_millis))
+        .with_progress_plain(args.progress_plain)
+        .with_force_progress(args.force_progress)
+        .with_progress_template(args.progress_template.clone())
+        .with_eta_smoothing(Some(eta_smoothing))
+        .with_fail_fast(args.fail_fast)
+        .with_stop_signal(stop.clone())
+        .with_on_listed({
+            let error_budget = error_budget.clone();
+            move |total| error_budget.set_total(total)
+        })
+        .with_on_plan({
+            let run_plan = run_plan.clone();
+            move |plan| *run_plan.lock().unwrap() = Some(*plan)
+        });
+
+    let fingerprint = io::map_fingerprint(
+        &alg_info.name,
+        out_len,
+        &AlgorithmParams {
+            block_size: matches!(alg_enum, Algorithm::ParallelHash256).then(|| {
+                alg_params
+                    .block_size
+                    .unwrap_or(parallelhash::DEFAULT_BLOCK_SIZE)
+            }),
+            customization: alg_params.customization.clone(),
+        },
+    );
+
+    // Results of the run: filled by the branch below that does the hashing
+    let mut entries: Vec<io::MapEntry> = Vec::new();
+    let mut timings: Vec<FileTiming> = Vec::new();
+
+    // Worker closure: hash a single file and push MapEntry into shared vector
+    let alg_for_worker = alg_enum;
+    let params_for_worker = alg_params.clone();
+    let extra_for_worker: Vec<(Algorithm, usize)> =
+        extra_algs.iter().copied().zip(extra_lens).collect();
+    let scan_root = PathBuf::from(&path);
+    let mut canonical_root =
+        std::fs::canonicalize(&scan_root).unwrap_or_else(|_| scan_root.clone());
+    if args.long_paths {
+        canonical_root = long_path(&canonical_root);
+    }
+    // Entry paths are relative to the scanned directory, or to the parent when
+    // --path names a single file, so a one-file map is keyed by its basename
+    let entries_base = if canonical_root.is_dir() {
+        canonical_root.clone()
+    } else {
+        canonical_root
+            .parent()
+            .map(Path::to_path_buf)
+            .unwrap_or_else(|| canonical_root.clone())
+    };
+    if depth == Some(0) && canonical_root.is_dir() {
+        anyhow::bail!(
+            "--depth 0 covers only the root directory itself, so no files would be hashed (use --depth 1 for the files directly inside)"
+        );
+    }
+    // The resume marker is compared against root-relative paths during the walk
+    let resume_after = match &args.resume_from {
+        Some(marker) if marker.is_absolute() => {
+            let marker = canonical_input_path(marker);
+            match marker.strip_prefix(&canonical_root) {
+                Ok(rel) => Some(rel.to_path_buf()),
+                Err(_) => anyhow::bail!(
+                    "--resume-from {} is not inside {}",
+                    marker.display(),
+                    canonical_root.display()
+                ),
             }
+        }
+        Some(marker) => Some(normalize_lexically(marker)),
+        None => None,
+    };
+    let pipeline = pipeline.with_resume_after(resume_after);
+    let strip_prefix_abs = strip_prefix.as_ref().map(|p| {
+        let candidate = if p.is_absolute() {
+            p.clone()
+        } else {
+            canonical_root.join(p)
+        };
+        std::fs::canonicalize(&candidate).unwrap_or(candidate)
+    });
+
+    let out_len_inner = out_len;
+
+    let root_for_worker = entries_base.clone();
+    let strip_for_worker = strip_prefix_abs.clone();
+    let portable_paths = args.portable_paths;
+    let structure_only = args.structure_only;
+    let coalesce_hardlinks = args.coalesce_hardlinks;
+    let fail_fast = args.fail_fast;
+    let absolute_paths = args.absolute_paths;
+    let hashed_bytes_worker = hashed_bytes.clone();
+    let stop_worker = stop.clone();
+    let budget_spent_worker = budget_spent.clone();
+    let errors = Arc::new(ErrorTally::default());
+    let errors_worker = errors.clone();
+    let budget_worker = error_budget.clone();
+
+    let events = match (&args.events_file, args.events_fd) {
+        (Some(path), _) => Some(Arc::new(EventSink::create_file(path)?)),
+        (None, Some(fd)) => Some(Arc::new(EventSink::from_fd(fd)?)),
+        (None, None) => None,
+    };
+    let scan_started = Instant::now();
+    if let Some(sink) = &events {
+        sink.emit(&Event::ScanStarted {
+            root: &canonical_root.to_string_lossy(),
+            algorithm: &alg_info.name,
+            timestamp: Utc::now().to_rfc3339(),
+        });
+    }
+    let events_worker = events.clone();
+
+    // `source` is the file's contents when a split pipeline's I/O stage already
+    // opened it; otherwise the worker opens the file itself. Each hashed file
+    // yields its entry and timing, collected per thread by the pipeline.
+    let worker = move |path_buf: PathBuf,
+                       source: Option<&mut dyn Read>,
+                       buffer_pool: Arc<hash_folderoo::memory::BufferPool>|
+          -> anyhow::Result<Option<(io::MapEntry, FileTiming)>> {
+        // Exclusions are applied once by the walker; only files (and, with
+        // --hash-symlink-target-path, symlinks) reach the worker
+        let link_target = if hash_symlink_targets {
+            path_buf
+                .symlink_metadata()
+                .is_ok_and(|m| m.file_type().is_symlink())
+                .then(|| std::fs::read_link(&path_buf))
+                .transpose()
+        } else {
+            Ok(None)
+        };
+        let (link_target, metadata) = match link_target {
+            Ok(Some(target)) => (Some(target), path_buf.symlink_metadata()),
+            Ok(None) => (None, path_buf.metadata()),
+            Err(e) => (None, Err(e)),
+        };
+        // Only process files
+        let metadata = match metadata {
+            Ok(m) if m.is_file() || link_target.is_some() => Some(m),
+            Ok(_) => return Ok(None),
+            Err(e) => {
+                let e = classify_path_error(e.into(), &path_buf);
+                warn!("Failed reading {}: {}", path_buf.display(), e);
+                if let Some(sink) = &events_worker {
+                    sink.emit(&Event::Error {
+                        path: &path_buf.to_string_lossy(),
+                        message: e.to_string(),
+                    });
+                }
+                errors_worker.record(&e);
+                if budget_worker.fail() {
+                    stop_worker.store(true, Ordering::Relaxed);
+                }
+                return if fail_fast { Err(e) } else { Ok(None) };
+            }
+        };
+
+        let mut rel = if absolute_paths {
+            path_buf.to_string_lossy().into_owned()
+        } else {
+            format_entry_path(&path_buf, strip_for_worker.as_deref(), &root_for_worker)
+        };
+        if portable_paths {
+            rel = portable_path(&rel);
+        }
 
-            let alg_enum = match Algorithm::from_name(alg) {
-                Some(a) => a,
-                None => {
-                    warn!("Unknown algorithm {}, falling back to blake3", alg);
-                    Algorithm::Blake3
+        let size = metadata.as_ref().map(|m| m.len()).unwrap_or(0);
+        let mtime = metadata
+            .as_ref()
+            .and_then(|m| m.modified().ok())
+            .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+            .map(|dur| dur.as_secs() as i64);
+        let start = Instant::now();
+        let (hash, hashes) = if structure_only {
+            (size_digest(size), None)
+        } else {
+            let mut hashers: Vec<Box<dyn HasherImpl>> =
+                std::iter::once(alg_for_worker.create_with(&params_for_worker))
+                    .chain(
+                        extra_for_worker
+                            .iter()
+                            .map(|(alg, _)| alg.create_with(&params_for_worker)),
+                    )
+                    .collect();
+            let hashed = match (&link_target, source) {
+                // A symlink's digest covers the target path, not what it points to
+                (Some(target), _) => {
+                    for hasher in hashers.iter_mut() {
+                        hasher.update(target.as_os_str().as_encoded_bytes());
+                    }
+                    Ok(())
+                }
+                (None, Some(reader)) => {
+                    hash_reader_multi_with_pool(&mut hashers, reader, &buffer_pool).map(|_| ())
+                }
+                // An empty file's digest is that of empty input; don't open it
+                (None, None)
+                    if metadata
+                        .as_ref()
+                        .is_some_and(|m| is_empty_regular_file(&path_buf, m)) =>
+                {
+                    Ok(())
                 }
+                (None, None) => File::open(&path_buf)
+                    .map_err(Into::into)
+                    .and_then(|mut file| {
+                        hash_reader_multi_with_pool(&mut hashers, &mut file, &buffer_pool)
+                            .map(|_| ())
+                    }),
             };
-
-            // Probe to determine default out length
-            let alg_info = alg_enum.create().info();
-            if xof_len.is_some() && !alg_info.supports_xof && !args.force_expand {
-                anyhow::bail!(
-                    "algorithm {} does not support --xof-length (use --force-expand to opt-in to non-native expansion)",
-                    alg_info.name
-                );
+            match hashed {
+                Ok(()) => {
+                    let hash = hashers[0].finalize_hex(out_len_inner);
+                    let hashes = (!extra_for_worker.is_empty()).then(|| {
+                        let mut all = BTreeMap::new();
+                        all.insert(alg_for_worker.name().to_string(), hash.clone());
+                        for ((alg, len), hasher) in extra_for_worker.iter().zip(&hashers[1..]) {
+                            all.insert(alg.name().to_string(), hasher.finalize_hex(*len));
+                        }
+                        all
+                    });
+                    (hash, hashes)
+                }
+                Err(e) => {
+                    let e = classify_path_error(e, &path_buf);
+                    warn!("Failed hashing {}: {}", path_buf.display(), e);
+                    if let Some(sink) = &events_worker {
+                        sink.emit(&Event::Error {
+                            path: &path_buf.to_string_lossy(),
+                            message: e.to_string(),
+                        });
+                    }
+                    errors_worker.record(&e);
+                    if budget_worker.fail() {
+                        stop_worker.store(true, Ordering::Relaxed);
+                    }
+                    return if fail_fast { Err(e) } else { Ok(None) };
+                }
             }
-            if xof_len.is_some() && !alg_info.supports_xof && args.force_expand {
-                warn!(
-                    "algorithm {} does not natively support XOF; proceeding with deterministic expansion (non-standard)",
-                    alg_info.name
+        };
+        let elapsed = start.elapsed();
+        if let Some(limit) = max_total_bytes {
+            let total = hashed_bytes_worker.fetch_add(size, Ordering::Relaxed) + size;
+            if total > limit {
+                budget_spent_worker.store(true, Ordering::Relaxed);
+                stop_worker.store(true, Ordering::Relaxed);
+            }
+        }
+        let me = io::MapEntry {
+            path: rel,
+            hash,
+            size,
+            mtime,
+            inode: metadata
+                .as_ref()
+                .filter(|_| coalesce_hardlinks)
+                .and_then(walk::hardlink_key),
+            hashes,
+            kind: link_target.as_ref().map(|_| io::EntryKind::Symlink),
+            ..Default::default()
+        };
+        if let Some(sink) = &events_worker {
+            sink.emit(&Event::FileHashed {
+                path: &me.path,
+                size,
+                duration_ms: events::millis(elapsed),
+            });
+        }
+        let timing = FileTiming {
+            path: me.path.clone(),
+            duration: elapsed,
+        };
+        Ok(Some((me, timing)))
+    };
+
+    // Run the pipeline, or stream a single special file (device/FIFO) directly
+    let processed = if walk::is_special_file(&scan_root) {
+        if !args.allow_special {
+            anyhow::bail!(
+                "{} is not a regular file or directory (use --allow-special to hash it as a stream)",
+                scan_root.display()
+            );
+        }
+        let plan = recommend_config(mode, threads_override, max_ram_override)?;
+        *run_plan.lock().unwrap() = Some(MemoryPlan {
+            threads: 1,
+            num_buffers: 1,
+            ..plan
+        });
+        let pool = Arc::new(BufferPool::new(1, plan.buffer_size));
+        let mut reader = File::open(&scan_root)
+            .with_context(|| format!("open special file {}", scan_root.display()))?;
+        let mut hasher = alg_enum.create_with(&alg_params);
+        let start = Instant::now();
+        let size = hash_reader_with_pool(hasher.as_mut(), &mut reader, &pool)
+            .with_context(|| format!("read special file {}", scan_root.display()))?;
+        let mut rel = if args.absolute_paths {
+            canonical_root.to_string_lossy().into_owned()
+        } else {
+            format_entry_path(&canonical_root, strip_prefix_abs.as_deref(), &entries_base)
+        };
+        if args.portable_paths {
+            rel = portable_path(&rel);
+        }
+        if let Some(sink) = &events {
+            sink.emit(&Event::FileHashed {
+                path: &rel,
+                size,
+                duration_ms: events::millis(start.elapsed()),
+            });
+        }
+        timings.push(FileTiming {
+            path: rel.clone(),
+            duration: start.elapsed(),
+        });
+        entries.push(io::MapEntry {
+            path: rel,
+            hash: hasher.finalize_hex(out_len),
+            size,
+            ..Default::default()
+        });
+        1
+    } else {
+        // Incremental update (`--changed-from`): start from the baseline and hash only
+        // the listed paths
+        let pipeline = match &args.changed_from {
+            Some(list_path) => {
+                let baseline = args
+                    .baseline
+                    .as_ref()
+                    .ok_or_else(|| anyhow::anyhow!("--changed-from requires --baseline <map>"))?;
+                if !baseline.is_file() {
+                    anyhow::bail!("baseline map not found: {}", baseline.display());
+                }
+                let baseline_entries = compare_mod::get_map_from_input(
+                    &baseline.to_string_lossy(),
+                    &compare_mod::DirectoryHashing::new(alg_enum),
+                )?;
+                io::check_unique_paths(
+                    &baseline_entries,
+                    &baseline.to_string_lossy(),
+                    args.allow_duplicate_paths,
+                )?;
+                check_baseline(
+                    baseline,
+                    &baseline_entries,
+                    &fingerprint,
+                    &alg_info.name,
+                    out_len,
+                    digest_encoding,
+                )?;
+                let list = std::fs::read_to_string(list_path)
+                    .with_context(|| format!("read change list {}", list_path.display()))?;
+
+                let mut listed: Vec<PathBuf> = list
+                    .lines()
+                    .map(str::trim)
+                    .filter(|line| !line.is_empty() && !line.starts_with('#'))
+                    .map(|line| entries_base.join(line))
+                    .collect();
+                // Entries are keyed relative to the root, so a path outside it has no
+                // place in the map
+                if let Some(outside) = listed
+                    .iter()
+                    .find(|p| !normalize_lexically(p).starts_with(&entries_base))
+                {
+                    anyhow::bail!(
+                        "{} in {} is outside {}",
+                        outside.display(),
+                        list_path.display(),
+                        entries_base.display()
+                    );
+                }
+                if !args.no_dedupe_inputs {
+                    // `./a`, `a` and an absolute spelling of the same file are hashed once
+                    let before = listed.len();
+                    listed = dedupe_input_paths(listed);
+                    if listed.len() < before {
+                        info!(
+                            "Dropped {} repeated paths from the change list",
+                            before - listed.len()
+                        );
+                    }
+                }
+
+                let mut changed_keys: HashSet<String> = HashSet::new();
+                for abs in &listed {
+                    let mut key = if args.absolute_paths {
+                        abs.to_string_lossy().into_owned()
+                    } else {
+                        format_entry_path(abs, strip_prefix_abs.as_deref(), &entries_base)
+                    };
+                    if args.portable_paths {
+                        key = portable_path(&key);
+                    }
+                    changed_keys.insert(key);
+                }
+
+                // Carry over every baseline entry that isn't being rehashed; listed paths
+                // that no longer exist simply drop out of the map.
+                entries.extend(
+                    baseline_entries
+                        .into_iter()
+                        .filter(|e| !changed_keys.contains(&e.path)),
                 );
+                listed.retain(|abs| abs.is_file());
+                pipeline.with_paths(listed)
             }
-            let default_out = alg_info.output_len_default;
-            let out_len = xof_len.unwrap_or(default_out);
+            None => pipeline,
+        };
+        let (processed, results) = if pipeline.is_split() {
+            pipeline.run_split_collect(
+                &canonical_root,
+                &excludes,
+                depth,
+                follow_symlinks,
+                show_progress,
+                move |path, reader, pool| worker(path, Some(reader), pool),
+            )
+        } else {
+            pipeline.run_collect(
+                &canonical_root,
+                &excludes,
+                depth,
+                follow_symlinks,
+                show_progress,
+                move |path, pool| worker(path, None, pool),
+            )
+        }
+        .map_err(|e| anyhow::anyhow!("pipeline error: {}", e))?;
+        entries.reserve(results.len());
+        timings.reserve(results.len());
+        for (entry, timing) in results {
+            entries.push(entry);
+            timings.push(timing);
+        }
+        processed
+    };
 
-            let exclude_set = build_exclude_set(&excludes)?;
+    if !args.silent {
+        info!("Processed {} files", processed);
+    }
+    if let Some(sink) = &events {
+        sink.emit(&Event::ScanCompleted {
+            files: entries.len() as u64,
+            bytes: entries.iter().map(|e| e.size).sum(),
+            errors: errors.total() as u64,
+            duration_ms: events::millis(scan_started.elapsed()),
+        });
+    }
+    if args.ignore_errors_summary {
+        if let Some(summary) = errors.summary() {
+            warn!("{}", summary);
+        }
+    }
+    if let Some(reason) = error_budget.check(processed as u64) {
+        anyhow::bail!(
+            "aborted: {} ({}); no map written",
+            reason,
+            errors.summary().unwrap_or_default()
+        );
+    }
+    if args.fail_on_empty && entries.is_empty() {
+        anyhow::bail!(
+            "no files were hashed under {} (--fail-on-empty); no map written",
+            scan_root.display()
+        );
+    }
 
-            // Determine memory mode from CLI/config (defaults to Balanced)
-            let mem_mode_str = args
-                .mem_mode
-                .as_deref()
-                .or_else(|| runtime_cfg.memory.as_ref().and_then(|m| m.mode.as_deref()))
-                .unwrap_or("balanced");
-            let mode = MemoryMode::from_name(mem_mode_str);
-
-            let threads_override = args
-                .threads
-                .or_else(|| runtime_cfg.general.as_ref().and_then(|g| g.threads));
-
-            let max_ram_override = args
-                .max_ram
-                .or_else(|| runtime_cfg.memory.as_ref().and_then(|m| m.max_ram));
-
-            // Create pipeline with chosen memory mode
-            let pipeline = Pipeline::new(mode)
-                .with_threads(threads_override)
-                .with_max_ram(max_ram_override);
-
-            // Shared vector to collect results from workers
-            let entries: Arc<Mutex<Vec<io::MapEntry>>> = Arc::new(Mutex::new(Vec::new()));
-            let timings: Arc<Mutex<Vec<FileTiming>>> = Arc::new(Mutex::new(Vec::new()));
-
-            // Worker closure: hash a single file and push MapEntry into shared vector
-            let alg_for_worker = alg_enum;
-            let entries_clone = entries.clone();
-            let scan_root = PathBuf::from(&path);
-            let canonical_root =
-                std::fs::canonicalize(&scan_root).unwrap_or_else(|_| scan_root.clone());
-            let strip_prefix_abs = strip_prefix.as_ref().map(|p| {
-                let candidate = if p.is_absolute() {
-                    p.clone()
-                } else {
-                    canonical_root.join(p)
-                };
-                std::fs::canonicalize(&candidate).unwrap_or(candidate)
-            });
+    let mut timings_vec = timings;
+    timings_vec.sort_by_key(|t| std::cmp::Reverse(t.duration));
+    if !timings_vec.is_empty() && !args.silent && args.show_slowest > 0 {
+        info!("Top slowest files:");
+        for timing in timings_vec.iter().take(args.show_slowest) {
+            info!("  {:>8.3?} {}", timing.duration, timing.path);
+        }
+    }
+    if let Some(path) = &args.show_slowest_json {
+        let rows: Vec<serde_json::Value> = timings_vec
+            .iter()
+            .map(|t| {
+                serde_json::json!({
+                    "path": t.path,
+                    "duration_ms": events::millis(t.duration),
+                })
+            })
+            .collect();
+        io::write_json(path, &rows)
+            .with_context(|| format!("write timings to {}", path.display()))?;
+    }
+    // Written with the plan the run used, once the map itself is in place
+    let run_summary = match &args.run_summary {
+        Some(path) => {
+            let plan = match run_plan.lock().unwrap().take() {
+                Some(plan) => plan,
+                None => recommend_config(mode, threads_override, max_ram_override)?,
+            };
+            let summary = RunSummary {
+                files: processed as u64,
+                bytes: entries.iter().map(|e| e.size).sum(),
+                elapsed_ms: events::millis(scan_started.elapsed()),
+                errors: errors.total() as u64,
+                algorithm: &alg_info.name,
+                plan,
+            };
+            Some((path, summary))
+        }
+        None => None,
+    };
+
+    // Build header + entries for output
+    let mut algorithm_params = serde_json::Map::new();
+    if let Some(len) = xof_len {
+        algorithm_params.insert("xof_length".into(), len.into());
+    }
+    if matches!(alg_enum, Algorithm::ParallelHash256) {
+        let block_size = alg_params
+            .block_size
+            .unwrap_or(parallelhash::DEFAULT_BLOCK_SIZE);
+        algorithm_params.insert("block_size".into(), block_size.into());
+    }
+    if let Some(custom) = &alg_params.customization {
+        algorithm_params.insert("customization".into(), custom.clone().into());
+    }
+    if !extra_algs.is_empty() {
+        let names: Vec<&str> = std::iter::once(alg_enum.name())
+            .chain(extra_algs.iter().map(Algorithm::name))
+            .collect();
+        algorithm_params.insert("algorithms".into(), names.into());
+    }
+    if digest_encoding != DigestEncoding::Hex {
+        algorithm_params.insert("digest_encoding".into(), digest_encoding.name().into());
+    }
+    let algorithm_params = if algorithm_params.is_empty() {
+        None
+    } else {
+        Some(serde_json::Value::Object(algorithm_params))
+    };
+
+    let header = MapHeader {
+        version: 1,
+        generated_by: "hash-folderoo",
+        timestamp: Utc::now().to_rfc3339(),
+        // Portable maps must not carry an OS-specific absolute root
+        root: if args.portable_paths {
+            None
+        } else {
+            Some(entries_base.to_string_lossy().into_owned())
+        },
+        algorithm: AlgorithmMeta {
+            name: alg_info.name.clone(),
+            params: algorithm_params,
+        },
+        fingerprint,
+        truncated: max_total_bytes
+            .filter(|_| budget_spent.load(Ordering::Relaxed))
+            .map(|limit| Truncation {
+                reason: "max_total_bytes",
+                limit_bytes: limit,
+                hashed_bytes: hashed_bytes.load(Ordering::Relaxed),
+            }),
+        content_hashed: args.structure_only.then_some(false),
+    };
+    if let Some(t) = &header.truncated {
+        warn!(
+            "Byte budget of {} reached after {} bytes; remaining files were not hashed",
+            t.limit_bytes, t.hashed_bytes
+        );
+    }
 
-            let exclude_set_clone = exclude_set.clone();
-            let out_len_inner = out_len;
+    let mut entries_vec = entries;
 
-            let timings_clone = timings.clone();
-            let root_for_worker = canonical_root.clone();
-            let strip_for_worker = strip_prefix_abs.clone();
+    // Sort entries by path for deterministic output
+    entries_vec.sort_by(|a, b| a.path.cmp(&b.path));
 
-            let worker = move |path_buf: PathBuf,
-                               buffer_pool: Arc<hash_folderoo::memory::BufferPool>|
-                  -> anyhow::Result<()> {
-                // Apply excludes (path-based) if set; note: pipeline already walks with exclusions but double-check
-                if let Some(gs) = &exclude_set_clone {
-                    if gs.is_match(&path_buf) {
-                        return Ok(());
-                    }
-                }
+    if args.print_root {
+        let root = map::merkle_root(&entries_vec, alg_enum, &alg_params, out_len);
+        println!("{}", root);
+        write_run_summary(run_summary)?;
+        return Ok(entries_vec);
+    }
 
-                // Only process files
-                if !path_buf.is_file() {
-                    return Ok(());
-                }
+    let upper = upper_hex && digest_encoding == DigestEncoding::Hex;
+    let encode = |digest: &mut String| -> anyhow::Result<()> {
+        if digest_encoding != DigestEncoding::Hex {
+            *digest = digest_encoding.reencode_hex(digest)?;
+        } else if upper {
+            digest.make_ascii_uppercase();
+        } else {
+            digest.make_ascii_lowercase();
+        }
+        Ok(())
+    };
+    for e in entries_vec.iter_mut() {
+        encode(&mut e.hash)?;
+        for digest in e.hashes.iter_mut().flat_map(|h| h.values_mut()) {
+            encode(digest)?;
+        }
+    }
+    if sort_by_hash {
+        entries_vec.sort_by(|a, b| a.hash.cmp(&b.hash).then_with(|| a.path.cmp(&b.path)));
+    }
 
-                let rel =
-                    format_entry_path(&path_buf, strip_for_worker.as_deref(), &root_for_worker);
+    if matches!(format.as_str(), "csv" | "ndjson" | "sums")
+        && digest_encoding != DigestEncoding::Hex
+    {
+        warn!(
+            "{} maps have no header to record --digest-encoding {}; loaders will not decode these digests",
+            format.to_uppercase(),
+            digest_encoding.name()
+        );
+    }
 
-                let metadata = path_buf.metadata().ok();
-                let size = metadata.as_ref().map(|m| m.len()).unwrap_or(0);
-                let mtime = metadata
-                    .as_ref()
-                    .and_then(|m| m.modified().ok())
-                    .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
-                    .map(|dur| dur.as_secs() as i64);
-                let mut hasher = alg_for_worker.create();
-                let start = Instant::now();
-                let hash = match hash_path_with_pool(hasher.as_mut(), &path_buf, &buffer_pool) {
-                    Ok(()) => hasher.finalize_hex(out_len_inner),
-                    Err(e) => {
-                        warn!("Failed hashing {}: {}", path_buf.display(), e);
-                        return Ok(());
-                    }
-                };
-                let elapsed = start.elapsed();
-                let me = io::MapEntry {
-                    path: rel,
-                    hash,
-                    size,
-                    mtime,
-                };
-                timings_clone.lock().unwrap().push(FileTiming {
-                    path: me.path.clone(),
-                    duration: elapsed,
-                });
-                let mut guard = entries_clone.lock().unwrap();
-                guard.push(me);
-                Ok(())
-            };
+    if dry_run {
+        info!(
+            "Dry-run complete: hashed {} files (results not written)",
+            entries_vec.len()
+        );
+        write_run_summary(run_summary)?;
+        return Ok(entries_vec);
+    }
 
-            // Run the pipeline
-            let processed = pipeline
-                .run(
-                    &scan_root,
-                    &excludes,
-                    depth,
-                    follow_symlinks,
-                    show_progress,
-                    worker,
-                )
-                .map_err(|e| anyhow::anyhow!("pipeline error: {}", e))?;
+    if let Some(name) = &args.per_dir_output {
+        let base = strip_prefix_abs.as_deref().unwrap_or(&entries_base);
+        let written = io::write_per_dir_sums(&entries_vec, base, name, bsd_tag.as_deref())?;
+        if !args.silent {
+            info!("Wrote {} per-directory {} files", written, name);
+        }
+        if output.is_none() {
+            write_run_summary(run_summary)?;
+            return Ok(entries_vec);
+        }
+    }
 
-            if !args.silent {
-                info!("Processed {} files", processed);
+    // CSV has no header object, so the algorithm goes on each row when asked for, and
+    // multi-algorithm maps list one row per file and algorithm
+    let algorithm_rows = (format == "csv"
+        && (args.csv_include_algorithm || !extra_algs.is_empty()))
+    .then(|| io::algorithm_rows(&entries_vec, alg_enum.name()));
+    let csv_records = algorithm_rows.as_deref().unwrap_or(&entries_vec);
+
+    let out = MapOutput {
+        header: &header,
+        entries: &entries_vec,
+    };
+    match (output, format.as_str()) {
+        (Some(p), fmt) => {
+            let data = match fmt {
+                "csv" => io::map_to_csv(csv_records, &csv_options)?,
+                "ndjson" => io::to_ndjson(&entries_vec)?,
+                "sums" => io::to_sums(&entries_vec, bsd_tag.as_deref()).into_bytes(),
+                other => {
+                    if other != "json" {
+                        warn!("Unknown format {}, falling back to json", other);
+                    }
+                    serde_json::to_vec_pretty(&out).context("serialize json")?
+                }
+            };
+            // A sidecar must never describe a map it doesn't sit next to, so the old one
+            // goes first and the new one is only written once the map is in place
+            let sidecar = checksum_alg.map(|alg| {
+                let alg = alg.unwrap_or(alg_enum);
+                (alg, PathBuf::from(format!("{}.{}", p, alg.name())))
+            });
+            if let Some((_, sidecar_path)) = sidecar.as_ref().filter(|(_, s)| s.exists()) {
+                std::fs::remove_file(sidecar_path)
+                    .with_context(|| format!("remove stale checksum {:?}", sidecar_path))?;
             }
-
-            let mut timings_vec = timings.lock().unwrap().clone();
-            if !timings_vec.is_empty() && !args.silent {
-                timings_vec.sort_by(|a, b| b.duration.cmp(&a.duration));
-                info!("Top slowest files:");
-                for timing in timings_vec.iter().take(5) {
-                    info!("  {:>8.3?} {}", timing.duration, timing.path);
+            io::atomic_write_via(Path::new(&p), &data, args.temp_dir.as_deref())?;
+            if let Some(mode) = output_mode {
+                io::set_output_mode(Path::new(&p), mode)?;
+            }
+            if let Some((alg, sidecar_path)) = sidecar {
+                let mut hasher = alg.create();
+                hasher.update(&data);
+                let digest = hasher.finalize_hex(hasher.info().output_len_default);
+                let name = Path::new(&p)
+                    .file_name()
+                    .map(|n| n.to_string_lossy().into_owned())
+                    .unwrap_or_default();
+                let line = io::format_sums_line(&digest, &name);
+                io::atomic_write_via(&sidecar_path, line.as_bytes(), args.temp_dir.as_deref())?;
+                if let Some(mode) = output_mode {
+                    io::set_output_mode(&sidecar_path, mode)?;
                 }
             }
+        }
+        (None, fmt) => {
+            // Buffer piped output rather than paying a syscall per record or line
+            let mut stdout = std::io::BufWriter::new(std::io::stdout().lock());
+            match fmt {
+                "csv" => {
+                    io::write_map_csv(&mut stdout, csv_records, &csv_options)?;
+                }
+                "ndjson" => io::write_ndjson(&mut stdout, &entries_vec)?,
+                "sums" => {
+                    stdout.write_all(io::to_sums(&entries_vec, bsd_tag.as_deref()).as_bytes())?
+                }
+                other => {
+                    if other != "json" {
+                        warn!("Unknown format {}, falling back to json", other);
+                    }
+                    serde_json::to_writer_pretty(&mut stdout, &out)?;
+                }
+            }
+            stdout.flush()?;
+        }
+    }
+    write_run_summary(run_summary)?;
+    Ok(entries_vec)
+}
 
-            // Build header + entries for output
-            let algorithm_params = xof_len.map(|len| serde_json::json!({ "xof_length": len }));
-
-            let header = MapHeader {
-                version: 1,
-                generated_by: "hash-folderoo",
-                timestamp: Utc::now().to_rfc3339(),
-                root: canonical_root.to_string_lossy().into_owned(),
-                algorithm: AlgorithmMeta {
-                    name: alg_info.name.clone(),
-                    params: algorithm_params,
-                },
-            };
+/// Write `hashmap --run-summary`, once the map (or whatever the run produces instead) is
+/// in place.
+fn write_run_summary(summary: Option<(&PathBuf, RunSummary)>) -> anyhow::Result<()> {
+    if let Some((path, summary)) = summary {
+        io::write_json(path, &summary)
+            .with_context(|| format!("write run summary to {}", path.display()))?;
+    }
+    Ok(())
+}
 
-            let mut entries_vec = entries.lock().unwrap().clone();
+fn main() -> anyhow::Result<()> {
+    setup_logging();
 
-            // Sort entries by path for deterministic output
-            entries_vec.sort_by(|a, b| a.path.cmp(&b.path));
+    let cli = Cli::parse();
+    if cli.alg_list {
+        print_algorithm_list();
+        return Ok(());
+    }
 
-            // Handle output format: json (default) or csv
-            let format = args
-                .format
-                .as_deref()
-                .or_else(|| {
-                    runtime_cfg
-                        .general
-                        .as_ref()
-                        .and_then(|g| g.format.as_deref())
-                })
-                .unwrap_or("json")
-                .to_lowercase();
+    let mut runtime_cfg = config::load_runtime_config(cli.config.as_deref())?;
+    config::apply_env_overrides(&mut runtime_cfg);
+    runtime_cfg.validate()?;
 
-            if dry_run {
-                info!(
-                    "Dry-run complete: hashed {} files (results not written)",
-                    entries_vec.len()
-                );
-                return Ok(());
+    match &cli.command {
+        Some(hash_folderoo::cli::Commands::Hashmap(args)) => {
+            if args.interval == Some(0) {
+                anyhow::bail!("--interval must be at least 1 second");
             }
-
-            match (output, format.as_str()) {
-                (Some(p), "json") => {
-                    // create combined object
-                    #[derive(Serialize)]
-                    struct Out<'a> {
-                        version: u8,
-                        generated_by: &'static str,
-                        timestamp: String,
-                        root: String,
-                        algorithm: &'a AlgorithmMeta,
-                        entries: &'a [io::MapEntry],
-                    }
-
-                    let out = Out {
-                        version: header.version,
-                        generated_by: header.generated_by,
-                        timestamp: header.timestamp.clone(),
-                        root: header.root.clone(),
-                        algorithm: &header.algorithm,
-                        entries: &entries_vec,
-                    };
-                    io::write_json(Path::new(&p), &out).map_err(|e| anyhow::anyhow!(e))?;
-                }
-                (Some(p), "csv") => {
-                    io::write_csv(Path::new(&p), &entries_vec).map_err(|e| anyhow::anyhow!(e))?;
-                }
-                (Some(p), other) => {
-                    warn!("Unknown format {}, falling back to json", other);
-                    #[derive(Serialize)]
-                    struct Out<'a> {
-                        version: u8,
-                        generated_by: &'static str,
-                        timestamp: String,
-                        root: String,
-                        algorithm: &'a AlgorithmMeta,
-                        entries: &'a [io::MapEntry],
-                    }
-                    let out = Out {
-                        version: header.version,
-                        generated_by: header.generated_by,
-                        timestamp: header.timestamp.clone(),
-                        root: header.root.clone(),
-                        algorithm: &header.algorithm,
-                        entries: &entries_vec,
-                    };
-                    io::write_json(Path::new(&p), &out).map_err(|e| anyhow::anyhow!(e))?;
-                }
-                (None, "json") => {
-                    let mut stdout = std::io::stdout();
-                    let s = serde_json::to_vec_pretty(&serde_json::json!({
-                        "version": header.version,
-                        "generated_by": header.generated_by,
-                        "timestamp": header.timestamp,
-                        "root": header.root,
-                        "algorithm": {
-                            "name": header.algorithm.name,
-                            "params": header.algorithm.params,
-                        },
-                        "entries": entries_vec,
-                    }))?;
-                    stdout.write_all(&s)?;
-                }
-                (None, "csv") => {
-                    let mut wtr = csv::Writer::from_writer(std::io::stdout());
-                    for rec in &entries_vec {
-                        wtr.serialize(rec)?;
-                    }
-                    wtr.flush()?;
-                }
-                (None, other) => {
-                    warn!("Unknown format {}, falling back to json", other);
-                    let mut stdout = std::io::stdout();
-                    let s = serde_json::to_vec_pretty(&serde_json::json!({
-                        "version": header.version,
-                        "generated_by": header.generated_by,
-                        "timestamp": header.timestamp,
-                        "root": header.root,
-                        "algorithm": {
-                            "name": header.algorithm.name,
-                            "params": header.algorithm.params,
-                        },
-                        "entries": entries_vec,
-                    }))?;
-                    stdout.write_all(&s)?;
+            let mut previous = run_hashmap(args, &runtime_cfg)?;
+            if let Some(interval) = args.interval.filter(|_| !args.explain_plan) {
+                let mut cycle = 1;
+                while args.max_cycles.is_none_or(|max| cycle < max) {
+                    std::thread::sleep(Duration::from_secs(interval));
+                    cycle += 1;
+                    let current = run_hashmap(args, &runtime_cfg)?;
+                    let churn = compare_mod::compare_maps(previous, current.clone());
+                    info!(
+                        "Rescan {}: {} added, {} removed, {} changed, {} moved",
+                        cycle,
+                        churn.new.len(),
+                        churn.missing.len(),
+                        churn.changed.len(),
+                        churn.moved.len()
+                    );
+                    previous = current;
                 }
             }
         }
@@ -465,25 +1422,58 @@ fn main() -> anyhow::Result<()> {
                 .as_ref()
                 .map(|p| p.to_string_lossy().into_owned())
                 .ok_or_else(|| anyhow::anyhow!("--source is required"))?;
+            // With --self-dupes the target defaults to the source itself
             let target = args
                 .target
                 .as_ref()
                 .map(|p| p.to_string_lossy().into_owned())
+                .or_else(|| args.self_dupes.then(|| source.clone()))
                 .ok_or_else(|| anyhow::anyhow!("--target is required"))?;
+            // Remote maps are downloaded first so their headers are checked like local ones
+            let local_source = compare_mod::localize_input(&source)?;
+            let local_target = if target == source {
+                None
+            } else {
+                Some(compare_mod::localize_input(&target)?)
+            };
+            let source = local_source.path().to_string();
+            let target = local_target
+                .as_ref()
+                .map_or_else(|| source.clone(), |t| t.path().to_string());
 
-            let compare_alg = args
-                .algorithm
-                .as_deref()
-                .and_then(Algorithm::from_name)
-                .unwrap_or_else(|| {
-                    if let Some(name) = args.algorithm.as_deref() {
-                        warn!(
-                            "Unknown algorithm {} for compare; falling back to blake3",
-                            name
-                        );
-                    }
-                    Algorithm::Blake3
-                });
+            let requested_alg = args.algorithm.as_deref().and_then(|name| {
+                let alg = Algorithm::from_name(name).inspect(Algorithm::warn_if_deprecated);
+                if alg.is_none() {
+                    warn!("Unknown algorithm {} for compare; ignoring it", name);
+                }
+                alg
+            });
+            let on_mismatch = match args.algorithm_mismatch.as_deref() {
+                None => compare_mod::AlgorithmMismatch::Error,
+                Some(name) => compare_mod::AlgorithmMismatch::from_name(name).ok_or_else(|| {
+                    anyhow::anyhow!(
+                        "unknown --algorithm-mismatch {} (expected error or rehash)",
+                        name
+                    )
+                })?,
+            };
+            let categories = compare_mod::parse_categories(&args.only)?;
+
+            if args.streaming {
+                compare_mod::check_streaming_algorithms(
+                    Path::new(&source),
+                    Path::new(&target),
+                    args.force_compare,
+                )?;
+                compare_mod::write_streaming_report(
+                    Path::new(&source),
+                    Path::new(&target),
+                    args.output.as_deref(),
+                    args.format.as_deref().unwrap_or("json"),
+                    &categories,
+                )?;
+                return Ok(());
+            }
 
             if !args.format.as_deref().unwrap_or("json").is_empty() {
                 // noop; format will be used below
@@ -493,17 +1483,124 @@ fn main() -> anyhow::Result<()> {
                 // noop; output will be used below
             }
 
-            let src_map = compare_mod::get_map_from_input(&source, compare_alg)
-                .map_err(|e| anyhow::anyhow!(e))?;
-            let tgt_map = compare_mod::get_map_from_input(&target, compare_alg)
+            // Digest lengths are expected to differ under --compare-prefix-bytes; those
+            // fingerprints are checked once both maps are loaded
+            if args.compare_prefix_bytes.is_none() {
+                compare_mod::check_fingerprints(
+                    Path::new(&source),
+                    Path::new(&target),
+                    args.force_compare,
+                )?;
+            }
+            let compare_alg = compare_mod::resolve_algorithm(
+                &[Path::new(&source), Path::new(&target)],
+                requested_alg,
+                on_mismatch,
+            )?;
+            if let Some(bytes) = args.compare_prefix_bytes {
+                if bytes == 0 {
+                    anyhow::bail!("--compare-prefix-bytes must be greater than zero");
+                }
+                warn!(
+                    "Comparing only the first {} bytes of each digest; this is only valid for prefix-stable XOFs such as blake3, shake256 or k12",
+                    bytes
+                );
+            }
+            let mut src_map = compare_mod::get_map_from_input(&source, &compare_alg)
                 .map_err(|e| anyhow::anyhow!(e))?;
+            if args.normalize_separators {
+                io::normalize_separators(&mut src_map);
+            }
+            // digest length before any prefix cut, for the fingerprint check
+            let src_len = src_map.first().map_or(0, |e| e.hash.len() / 2);
+            if let Some(bytes) = args.compare_prefix_bytes {
+                io::truncate_hashes(&mut src_map, bytes)
+                    .with_context(|| format!("--compare-prefix-bytes on {}", source))?;
+            }
+            io::check_unique_paths(&src_map, &source, args.allow_duplicate_paths)?;
+            let tgt_map = if target == source {
+                src_map.clone()
+            } else {
+                let mut tgt_map = compare_mod::get_map_from_input(&target, &compare_alg)
+                    .map_err(|e| anyhow::anyhow!(e))?;
+                if args.normalize_separators {
+                    io::normalize_separators(&mut tgt_map);
+                }
+                if let Some(bytes) = args.compare_prefix_bytes {
+                    compare_mod::check_prefix_fingerprints(
+                        Path::new(&source),
+                        src_len,
+                        Path::new(&target),
+                        tgt_map.first().map_or(0, |e| e.hash.len() / 2),
+                        args.force_compare,
+                    )?;
+                    io::truncate_hashes(&mut tgt_map, bytes)
+                        .with_context(|| format!("--compare-prefix-bytes on {}", target))?;
+                }
+                io::check_unique_paths(&tgt_map, &target, args.allow_duplicate_paths)?;
+                align_path_styles(
+                    &mut src_map,
+                    Path::new(&source),
+                    &mut tgt_map,
+                    Path::new(&target),
+                );
+                tgt_map
+            };
 
-            let report = compare_mod::compare_maps(src_map, tgt_map);
+            let self_duplicates = if args.self_dupes {
+                report::find_duplicates(&src_map, true)
+            } else {
+                Vec::new()
+            };
+            let mut report = compare_mod::compare_maps(src_map, tgt_map);
+            report.self_duplicates = self_duplicates;
+            report.retain_categories(&categories);
 
             let format = args.format.as_deref().unwrap_or("json");
             let out_path = args.output.as_deref();
 
-            compare_mod::write_report(&report, out_path, format).map_err(|e| anyhow::anyhow!(e))?;
+            if args.git_diff {
+                let source_root = input_root(Path::new(&source));
+                let target_root = input_root(Path::new(&target));
+                let mut patch = String::new();
+                for (s, t) in &report.changed {
+                    let files = source_root
+                        .as_ref()
+                        .zip(target_root.as_ref())
+                        .map(|(sr, tr)| (sr.join(&s.path), tr.join(&t.path)))
+                        .filter(|(sf, tf)| sf.is_file() && tf.is_file());
+                    let files = files.as_ref().map(|(sf, tf)| (sf.as_path(), tf.as_path()));
+                    patch.push_str(&diff::format_change_diff(
+                        s,
+                        t,
+                        files,
+                        args.git_diff_context,
+                    ));
+                }
+                match args.git_diff_output.as_deref() {
+                    Some(p) => io::atomic_write(p, patch.as_bytes())?,
+                    None => print!("{}", patch),
+                }
+                // A diff on stdout stands in for the report there
+                if args.git_diff_output.is_none()
+                    && out_path.is_none()
+                    && args.split_output.is_none()
+                {
+                    return Ok(());
+                }
+            }
+
+            if let Some(dir) = args.split_output.as_deref() {
+                let written = compare_mod::write_split_report(&report, dir, format)?;
+                info!(
+                    "Wrote {} category files to {}",
+                    written.len(),
+                    dir.display()
+                );
+            } else {
+                compare_mod::write_report(&report, out_path, format)
+                    .map_err(|e| anyhow::anyhow!(e))?;
+            }
         }
         Some(hash_folderoo::cli::Commands::Copydiff(args)) => {
             // Load plan from file if provided, otherwise generate by running a comparison
@@ -511,6 +1608,7 @@ fn main() -> anyhow::Result<()> {
                 .algorithm
                 .as_deref()
                 .and_then(Algorithm::from_name)
+                .inspect(Algorithm::warn_if_deprecated)
                 .unwrap_or_else(|| {
                     if let Some(name) = args.algorithm.as_deref() {
                         warn!(
@@ -544,33 +1642,71 @@ fn main() -> anyhow::Result<()> {
                     .ok_or_else(|| {
                         anyhow::anyhow!("--target is required when --plan is not provided")
                     })?;
-
-                let src_map = compare_mod::get_map_from_input(&source, copy_alg)
+                let local_source = compare_mod::localize_input(&source)?;
+                let local_target = compare_mod::localize_input(&target)?;
+                let (source, target) = (local_source.path(), local_target.path());
+                // Map inputs were hashed with the algorithm in their header; the plan
+                // records it next to each source digest for --verify-only
+                let plan_alg = compare_mod::resolve_algorithm(
+                    &[Path::new(source), Path::new(target)],
+                    args.algorithm.is_some().then_some(copy_alg),
+                    compare_mod::AlgorithmMismatch::Error,
+                )?;
+
+                let mut src_map = compare_mod::get_map_from_input(source, &plan_alg)
                     .map_err(|e| anyhow::anyhow!(e))?;
-                let tgt_map = compare_mod::get_map_from_input(&target, copy_alg)
+                let mut tgt_map = compare_mod::get_map_from_input(target, &plan_alg)
                     .map_err(|e| anyhow::anyhow!(e))?;
+                io::check_unique_paths(&src_map, source, args.allow_duplicate_paths)?;
+                io::check_unique_paths(&tgt_map, target, args.allow_duplicate_paths)?;
+                align_path_styles(
+                    &mut src_map,
+                    Path::new(source),
+                    &mut tgt_map,
+                    Path::new(target),
+                );
                 let report = compare_mod::compare_maps(src_map, tgt_map);
 
-                // If the provided source/target are directories, pass them as roots to help construct dst paths
-                let source_root = args.source.as_ref().and_then(|p| {
-                    if p.exists() && p.is_dir() {
-                        Some(p.as_path())
-                    } else {
-                        None
-                    }
-                });
-                let target_root = args.target.as_ref().and_then(|p| {
-                    if p.exists() && p.is_dir() {
-                        Some(p.as_path())
-                    } else {
-                        None
-                    }
-                });
+                // Directories act as their own roots; map files contribute the root recorded in
+                // their header so relative entry paths can be resolved back to real files.
+                let source_root = input_root(Path::new(source));
+                let target_root = input_root(Path::new(target));
 
-                copy::generate_copy_plan(&report, source_root, target_root)
+                copy::generate_copy_plan(&report, source_root.as_deref(), target_root.as_deref())
+                    .with_algorithm(plan_alg.algorithm)
             };
 
-            if args.execute {
+            if args.verify_only {
+                let results = copy::verify_copy_plan(&plan, copy_alg);
+                let count = |status: copy::VerifyStatus| {
+                    results.iter().filter(|r| r.status == status).count()
+                };
+                for r in &results {
+                    match &r.error {
+                        Some(e) => println!("{:<14} {} ({})", r.status.name(), r.dst, e),
+                        None => println!("{:<14} {}", r.status.name(), r.dst),
+                    }
+                }
+                println!(
+                    "verify: {} ok, {} mismatch, {} missing, {} source-missing, {} skipped, {} error",
+                    count(copy::VerifyStatus::Ok),
+                    count(copy::VerifyStatus::Mismatch),
+                    count(copy::VerifyStatus::Missing),
+                    count(copy::VerifyStatus::SourceMissing),
+                    count(copy::VerifyStatus::Skipped),
+                    count(copy::VerifyStatus::Error)
+                );
+                let failed = results.len()
+                    - count(copy::VerifyStatus::Ok)
+                    - count(copy::VerifyStatus::Skipped);
+                if failed > 0 {
+                    anyhow::bail!(
+                        "{} of {} planned destinations do not match",
+                        failed,
+                        results.len()
+                    );
+                }
+            } else if args.execute {
                 let conflict =
                     copy::ConflictStrategy::from_name(&args.conflict).unwrap_or_else(|| {
                         warn!(
@@ -579,9 +1715,23 @@ fn main() -> anyhow::Result<()> {
                         );
                         copy::ConflictStrategy::Overwrite
                     });
+                let prompt_fallback =
+                    match copy::ConflictStrategy::from_name(&args.conflict_fallback) {
+                        Some(copy::ConflictStrategy::Prompt) | None => {
+                            warn!(
+                                "Invalid conflict fallback {}; defaulting to skip",
+                                args.conflict_fallback
+                            );
+                            copy::ConflictStrategy::Skip
+                        }
+                        Some(s) => s,
+                    };
                 let opts = copy::CopyOptions {
                     conflict,
                     preserve_times: args.preserve_times,
+                    preserve_perms: args.preserve_perms,
+                    preserve_owner: args.preserve_owner,
+                    prompt_fallback,
                 };
                 // when resuming we persist updates back to the plan file so progress is maintained
                 let persist_path = if args.resume {
@@ -600,6 +1750,15 @@ fn main() -> anyhow::Result<()> {
                     args.git_diff_output.as_deref(),
                 )
                 .map_err(|e| anyhow::anyhow!(e))?;
+            } else if let Some(format) = &args.summary {
+                let summary = copy::summarize_plan(&plan);
+                match format.to_lowercase().as_str() {
+                    "text" => println!("{}", summary),
+                    "json" => println!("{}", serde_json::to_string(&summary)?),
+                    other => {
+                        anyhow::bail!("unknown --summary format {} (expected text or json)", other)
+                    }
+                }
             } else {
                 // default to dry-run output
                 copy::dry_run_copy_plan(
@@ -617,17 +1776,30 @@ fn main() -> anyhow::Result<()> {
                 .as_ref()
                 .map(|p| p.to_string_lossy().into_owned())
                 .ok_or_else(|| anyhow::anyhow!("--path is required"))?;
-            hash_folderoo::remove_empty_directories(
+            let opts = hash_folderoo::RemoveEmptyOptions {
+                dry_run: args.dry_run,
+                min_depth: args.min_empty_depth,
+                excludes: args.exclude.clone(),
+                git_diff: args.git_diff,
+                git_diff_body: args.git_diff_body,
+                git_diff_context: args.git_diff_context,
+                git_diff_output: args.git_diff_output.clone(),
+                one_file_system: args.one_file_system,
+                strict: args.strict,
+            };
+            let summary = hash_folderoo::remove_empty_directories_with_options(
                 std::path::Path::new(&path),
-                args.dry_run,
-                args.min_empty_depth,
-                &args.exclude,
-                args.git_diff,
-                args.git_diff_body,
-                args.git_diff_context,
-                args.git_diff_output.as_deref(),
+                &opts,
             )
             .map_err(|e| anyhow::anyhow!("removempty error: {}", e))?;
+            if summary.failed > 0 {
+                anyhow::bail!(
+                    "removempty: {} director{} could not be removed ({} removed)",
+                    summary.failed,
+                    if summary.failed == 1 { "y" } else { "ies" },
+                    summary.removed
+                );
+            }
         }
         Some(hash_folderoo::cli::Commands::Renamer(args)) => {
             let path = args
@@ -641,23 +1813,39 @@ fn main() -> anyhow::Result<()> {
                 anyhow::bail!("either --map or --pattern is required for renamer");
             }
 
-            let map_path = args.map.as_deref();
-            let pattern = args.pattern.as_deref();
-            let replace = args.replace.as_deref();
-
-            hash_folderoo::rename_files_with_options(
-                std::path::Path::new(&path),
-                pattern,
-                replace,
-                map_path,
-                args.regex,
-                args.dry_run,
-                args.git_diff,
-                args.git_diff_body,
-                args.git_diff_context,
-                args.git_diff_output.as_deref(),
-            )
-            .map_err(|e| anyhow::anyhow!("renamer error: {}", e))?;
+            let dedupe_alg = args
+                .dedupe_on_collision
+                .as_deref()
+                .map(|name| {
+                    Algorithm::from_name(name)
+                        .inspect(Algorithm::warn_if_deprecated)
+                        .ok_or_else(|| {
+                            anyhow::anyhow!("unknown --dedupe-on-collision algorithm {}", name)
+                        })
+                })
+                .transpose()?;
+
+            let opts = hash_folderoo::RenameOptions {
+                pattern: args.pattern.clone(),
+                replace: args.replace.clone(),
+                map: args.map.clone(),
+                regex: args.regex,
+                dry_run: args.dry_run,
+                git_diff: args.git_diff,
+                git_diff_body: args.git_diff_body,
+                git_diff_context: args.git_diff_context,
+                git_diff_output: args.git_diff_output.clone(),
+                dedupe_on_collision: dedupe_alg,
+            };
+            let summary = hash_folderoo::rename_files_with(std::path::Path::new(&path), &opts)
+                .map_err(|e| anyhow::anyhow!("renamer error: {}", e))?;
+            if !args.silent {
+                if args.dry_run {
+                    println!("{}", summary);
+                } else {
+                    println!("{}; {} renamed", summary, summary.renamed);
+                }
+            }
         }
         Some(hash_folderoo::cli::Commands::Benchmark(args)) => {
             let alg = args.algorithm.as_deref().unwrap_or("blake3");
@@ -687,8 +1875,151 @@ fn main() -> anyhow::Result<()> {
                 args.include.clone()
             };
             let top_n = args.top_n.unwrap_or(5);
-            hash_folderoo::generate_report(&input, format, &include, top_n)
-                .map_err(|e| anyhow::anyhow!(e))?;
+            let opts = ReportOptions {
+                include_empty: args.include_empty,
+                allow_duplicate_paths: args.allow_duplicate_paths,
+                dedup_by: DedupBy::from_keys(&args.dedup_by)?,
+                human_sizes: args.human,
+                compound_ext: args.compound_ext,
+                min_dupe_copies: args.min_dupe_copies,
+                min_dupe_size: args.min_dupe_size,
+            };
+            if args.tree {
+                report::generate_tree(&input, &opts)?;
+            } else if args.only_duplicates {
+                report::generate_only_duplicates(&input, format, &opts)?;
+            } else if args.dedupe_execute {
+                let keep = args.keep.as_deref().unwrap_or("first");
+                let policy = KeepPolicy::from_name(keep).ok_or_else(|| {
+                    anyhow::anyhow!(
+                        "unknown --keep policy {} (expected first, newest, oldest, shortest-path)",
+                        keep
+                    )
+                })?;
+                report::generate_dedupe(
+                    &input,
+                    args.root.as_deref(),
+                    policy,
+                    args.hardlink,
+                    args.yes,
+                    &opts,
+                )?;
+            } else if let Some(since) = &args.since {
+                report::generate_churn_report(
+                    &since.to_string_lossy(),
+                    &input,
+                    format,
+                    top_n,
+                    &opts,
+                )?;
+            } else {
+                report::generate_report_with_options(&input, format, &include, top_n, &opts)
+                    .map_err(|e| anyhow::anyhow!(e))?;
+            }
+        }
+        Some(hash_folderoo::cli::Commands::VerifyMap(args)) => {
+            let input = args
+                .input
+                .as_ref()
+                .ok_or_else(|| anyhow::anyhow!("--input is required"))?;
+            let check = verify::verify_map_file(input)?;
+            for issue in &check.issues {
+                eprintln!("{}", issue);
+            }
+            if !check.issues.is_empty() {
+                anyhow::bail!(
+                    "{}: {} problem(s) found in {} entries",
+                    input.display(),
+                    check.issues.len(),
+                    check.entries
+                );
+            }
+            println!("{}: OK ({} entries)", input.display(), check.entries);
+
+            if let Some(root) = &args.path {
+                let (name, params) = match verify::load_map_algorithm(input)? {
+                    Some((name, params)) if args.algorithm.is_none() => (name, params),
+                    _ => (
+                        args.algorithm
+                            .clone()
+                            .unwrap_or_else(|| "blake3".to_string()),
+                        AlgorithmParams::default(),
+                    ),
+                };
+                let alg = Algorithm::from_name(&name)
+                    .ok_or_else(|| anyhow::anyhow!("unknown algorithm {}", name))?;
+                let entries = compare_mod::get_map_from_input(
+                    &input.to_string_lossy(),
+                    &compare_mod::DirectoryHashing::new(alg),
+                )?;
+                let content = verify::verify_contents(
+                    &entries,
+                    root,
+                    args.rehash_on_size_change_only,
+                    |path, entry| {
+                        let mut hasher = alg.create_with(&params);
+                        if entry.kind == Some(io::EntryKind::Symlink) {
+                            let target = std::fs::read_link(path)?;
+                            hasher.update(target.as_os_str().as_encoded_bytes());
+                        } else {
+                            let mut file = std::fs::File::open(path)?;
+                            hasher.update_reader(&mut file)?;
+                        }
+                        // the recorded digest's length fixes the XOF output length
+                        Ok(hasher.finalize_hex(entry.hash.len() / 2))
+                    },
+                );
+                for path in &content.modified {
+                    println!("modified: {}", path);
+                }
+                for path in &content.missing {
+                    println!("missing: {}", path);
+                }
+                for problem in &content.unreadable {
+                    eprintln!("unreadable: {}", problem);
+                }
+                if !content.is_clean() {
+                    anyhow::bail!(
+                        "{}: {} modified, {} missing, {} unreadable ({} hashed)",
+                        root.display(),
+                        content.modified.len(),
+                        content.missing.len(),
+                        content.unreadable.len(),
+                        content.hashed
+                    );
+                }
+                println!(
+                    "{}: OK ({} files match, {} hashed)",
+                    root.display(),
+                    content.ok,
+                    content.hashed
+                );
+            }
+        }
+        #[cfg(feature = "url")]
+        Some(hash_folderoo::cli::Commands::Hash(args)) => {
+            let name = args.algorithm.as_deref().unwrap_or("blake3");
+            let alg = Algorithm::from_name(name)
+                .ok_or_else(|| anyhow::anyhow!("unknown algorithm {}", name))?;
+            alg.warn_if_deprecated();
+            let out_len = alg.create().info().output_len_default;
+            let fetched = hash_folderoo::remote::hash_url(
+                &args.url,
+                alg,
+                &AlgorithmParams::default(),
+                out_len,
+            )?;
+            match fetched.content_length {
+                Some(len) => info!(
+                    "{}: {} bytes (Content-Length {})",
+                    fetched.final_url, fetched.bytes, len
+                ),
+                None => info!(
+                    "{}: {} bytes (no Content-Length)",
+                    fetched.final_url, fetched.bytes
+                ),
+            }
+            println!("{}  {}", fetched.digest, args.url);
         }
         None => {
             println!("Run with --help for usage");
@@ -0,0 +1,172 @@
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use anyhow::{Context, Result};
+
+use crate::algorithms::{Algorithm, AlgorithmParams};
+use crate::hash::hash_path_with_pool;
+use crate::io::MapEntry;
+use crate::memory::{BufferPool, MemoryMode};
+use crate::pipeline::Pipeline;
+
+/// Settings for [`hash_directory`].
+#[derive(Debug, Clone)]
+pub struct HashOptions {
+    pub algorithm: Algorithm,
+    pub params: AlgorithmParams,
+    /// Digest length in bytes (defaults to the algorithm's own).
+    pub output_len: Option<usize>,
+    pub mode: MemoryMode,
+    /// Glob patterns, relative to the root, to leave out.
+    pub exclusions: Vec<String>,
+    pub max_depth: Option<usize>,
+    pub follow_symlinks: bool,
+    pub show_progress: bool,
+    /// Record paths as walked instead of relative to the root.
+    pub absolute_paths: bool,
+}
+
+impl Default for HashOptions {
+    fn default() -> Self {
+        Self {
+            algorithm: Algorithm::Blake3,
+            params: AlgorithmParams::default(),
+            output_len: None,
+            mode: MemoryMode::Balanced,
+            exclusions: Vec::new(),
+            max_depth: None,
+            follow_symlinks: false,
+            show_progress: false,
+            absolute_paths: false,
+        }
+    }
+}
+
+/// Hash every file under `root` and return the map entries sorted by path.
+/// Paths are relative to `root` (or to its parent when `root` is a single file) and use
+/// `/` separators, as in maps written by `hashmap`. Files that fail to hash are logged
+/// and left out.
+pub fn hash_directory(root: &Path, opts: &HashOptions) -> Result<Vec<MapEntry>> {
+    let out_len = opts
+        .output_len
+        .unwrap_or_else(|| opts.algorithm.create().info().output_len_default);
+    let base = if root.is_dir() {
+        root.to_path_buf()
+    } else {
+        root.parent().map(Path::to_path_buf).unwrap_or_default()
+    };
+
+    let algorithm = opts.algorithm;
+    let params = opts.params.clone();
+    let absolute_paths = opts.absolute_paths;
+    let worker = move |path: PathBuf, pool: Arc<BufferPool>| -> Result<Option<MapEntry>> {
+        let metadata = path.metadata().ok();
+        let size = metadata.as_ref().map(|m| m.len()).unwrap_or(0);
+        let mtime = metadata
+            .as_ref()
+            .and_then(|m| m.modified().ok())
+            .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+            .map(|dur| dur.as_secs() as i64);
+        let mut hasher = algorithm.create_with(&params);
+        hash_path_with_pool(hasher.as_mut(), &path, &pool)
+            .with_context(|| format!("hash {}", path.display()))?;
+        let rel = if absolute_paths {
+            path.to_string_lossy().into_owned()
+        } else {
+            path.strip_prefix(&base)
+                .unwrap_or(&path)
+                .to_string_lossy()
+                .replace('\\', "/")
+        };
+        Ok(Some(MapEntry {
+            path: rel,
+            hash: hasher.finalize_hex(out_len),
+            size,
+            mtime,
+            ..Default::default()
+        }))
+    };
+
+    let (_, mut entries) = Pipeline::new(opts.mode)
+        .run_collect(
+            root,
+            &opts.exclusions,
+            opts.max_depth,
+            opts.follow_symlinks,
+            opts.show_progress,
+            worker,
+        )
+        .context("running pipeline to build map")?;
+    entries.sort_by(|a, b| a.path.cmp(&b.path));
+    Ok(entries)
+}
+
+/// Merkle root of `entries` (sorted by path) for `hashmap --print-root`. Each leaf hashes
+/// `0x00 path 0x00 digest`, then neighbouring nodes are hashed pairwise as
+/// `0x01 left right` until one is left; an odd node out moves up a level unchanged.
+/// An empty map yields the hash of no input.
+pub fn merkle_root(
+    entries: &[MapEntry],
+    algorithm: Algorithm,
+    params: &AlgorithmParams,
+    out_len: usize,
+) -> String {
+    let digest = |parts: &[&[u8]]| {
+        let mut hasher = algorithm.create_with(params);
+        for part in parts {
+            hasher.update(part);
+        }
+        hasher.finalize_hex(out_len)
+    };
+    let mut level: Vec<String> = entries
+        .iter()
+        .map(|e| {
+            let hash = e.hash.to_ascii_lowercase();
+            digest(&[&[0], e.path.as_bytes(), &[0], hash.as_bytes()])
+        })
+        .collect();
+    if level.is_empty() {
+        return digest(&[]);
+    }
+    while level.len() > 1 {
+        level = level
+            .chunks(2)
+            .map(|pair| match pair {
+                [left, right] => digest(&[&[1], left.as_bytes(), right.as_bytes()]),
+                [single] => single.clone(),
+                _ => unreachable!(),
+            })
+            .collect();
+    }
+    level.pop().unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs::{create_dir_all, write};
+    use tempfile::tempdir;
+
+    #[test]
+    fn hash_directory_returns_sorted_relative_entries() {
+        let dir = tempdir().unwrap();
+        create_dir_all(dir.path().join("sub")).unwrap();
+        write(dir.path().join("b.txt"), b"bee").unwrap();
+        write(dir.path().join("sub").join("a.txt"), b"hello").unwrap();
+        write(dir.path().join("skip.log"), b"log").unwrap();
+
+        let opts = HashOptions {
+            exclusions: vec!["*.log".to_string()],
+            ..Default::default()
+        };
+        let entries = hash_directory(dir.path(), &opts).unwrap();
+        let paths: Vec<&str> = entries.iter().map(|e| e.path.as_str()).collect();
+        assert_eq!(paths, ["b.txt", "sub/a.txt"]);
+        assert_eq!(entries[1].size, 5);
+        assert_eq!(
+            entries[1].hash,
+            blake3::hash(b"hello").to_hex().as_str(),
+            "default options hash with BLAKE3"
+        );
+    }
+}
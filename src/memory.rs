@@ -1,13 +1,16 @@
-use anyhow::Result;
+use anyhow::{Context, Result};
 use log::warn;
-use std::sync::atomic::{AtomicUsize, Ordering};
+use serde::Serialize;
+use std::path::Path;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
 use std::sync::{Arc, Mutex};
 use std::thread;
 use std::time::Duration;
 use sysinfo::{System, SystemExt};
 
 /// Memory usage modes for the hashing engine.
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
 pub enum MemoryMode {
     Stream,
     Balanced,
@@ -49,6 +52,10 @@ struct BufferPoolState {
 #[derive(Clone)]
 pub struct BufferPool {
     state: Arc<BufferPoolState>,
+    /// Bytes read into this pool's buffers, when someone is watching (byte progress).
+    bytes_read: Option<Arc<AtomicU64>>,
+    /// Readers should hash each buffer with `HasherImpl::update_parallel`.
+    parallel_hashing: bool,
 }
 
 impl BufferPool {
@@ -69,6 +76,34 @@ impl BufferPool {
         };
         Self {
             state: Arc::new(state),
+            bytes_read: None,
+            parallel_hashing: false,
+        }
+    }
+
+    /// Mark buffers from this pool as large enough to hash in parallel (see
+    /// `HasherImpl::update_parallel`); used by the pipeline's large-file group.
+    pub fn with_parallel_hashing(mut self, enabled: bool) -> Self {
+        self.parallel_hashing = enabled;
+        self
+    }
+
+    /// Whether buffers from this pool should be hashed in parallel.
+    pub fn parallel_hashing(&self) -> bool {
+        self.parallel_hashing
+    }
+
+    /// Add the bytes reported through [`BufferPool::record_read`] to `counter`.
+    pub fn with_read_counter(mut self, counter: Arc<AtomicU64>) -> Self {
+        self.bytes_read = Some(counter);
+        self
+    }
+
+    /// Credit `bytes` just read into one of this pool's buffers, so byte progress moves
+    /// chunk by chunk instead of once per file.
+    pub fn record_read(&self, bytes: u64) {
+        if let Some(counter) = &self.bytes_read {
+            counter.fetch_add(bytes, Ordering::Relaxed);
         }
     }
 
@@ -215,17 +250,142 @@ impl Drop for PooledBuffer {
     }
 }
 
-/// Detect total system RAM in bytes. Uses sysinfo.
+/// Environment variable that overrides detected RAM (bytes), for hosts where detection
+/// is wrong or unavailable.
+pub const ASSUME_RAM_ENV: &str = "HASH_FOLDEROO_ASSUME_RAM";
+
+/// Detect total system RAM in bytes: `HASH_FOLDEROO_ASSUME_RAM` when set, else sysinfo,
+/// else `/proc/meminfo` (Linux) or `sysctl hw.memsize` (macOS).
 pub fn detect_system_ram_bytes() -> Result<u64> {
+    match assumed_ram_bytes() {
+        Some(bytes) => Ok(bytes),
+        None => host_ram_bytes(),
+    }
+}
+
+/// RAM to plan against: `HASH_FOLDEROO_ASSUME_RAM` as given when set, else the detected
+/// host RAM capped by this process's cgroup memory limit.
+pub fn available_ram_bytes() -> Result<u64> {
+    match assumed_ram_bytes() {
+        Some(bytes) => Ok(bytes),
+        None => Ok(ram_within_cgroup(host_ram_bytes()?, cgroup_memory_limit())),
+    }
+}
+
+/// `HASH_FOLDEROO_ASSUME_RAM`, when set to a positive byte count.
+fn assumed_ram_bytes() -> Option<u64> {
+    let value = std::env::var(ASSUME_RAM_ENV).ok()?;
+    match value.trim().parse::<u64>() {
+        Ok(bytes) if bytes > 0 => Some(bytes),
+        _ => {
+            warn!(
+                "ignoring {}={:?}: expected a byte count",
+                ASSUME_RAM_ENV, value
+            );
+            None
+        }
+    }
+}
+
+fn host_ram_bytes() -> Result<u64> {
     let mut sys = System::new();
-    // Refresh memory to get up-to-date values.
     sys.refresh_memory();
-    // sys.total_memory() returns KB according to sysinfo docs; convert to bytes.
-    let kb = sys.total_memory();
-    Ok(kb * 1024)
+    // sysinfo reports bytes, and 0 where it cannot read the total.
+    let bytes = sys.total_memory();
+    if bytes > 0 {
+        return Ok(bytes);
+    }
+    os_ram_bytes().context("could not detect system RAM")
+}
+
+#[cfg(target_os = "linux")]
+fn os_ram_bytes() -> Option<u64> {
+    parse_meminfo_total(&std::fs::read_to_string("/proc/meminfo").ok()?)
+}
+
+#[cfg(target_os = "macos")]
+fn os_ram_bytes() -> Option<u64> {
+    let out = std::process::Command::new("sysctl")
+        .args(["-n", "hw.memsize"])
+        .output()
+        .ok()?;
+    String::from_utf8_lossy(&out.stdout).trim().parse().ok()
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos")))]
+fn os_ram_bytes() -> Option<u64> {
+    None
+}
+
+/// Memory limit of the cgroup this process runs in (v2 `memory.max`, else v1
+/// `memory.limit_in_bytes`), or `None` when unlimited or not on Linux. The cgroup is
+/// taken from `/proc/self/cgroup`, and the lowest limit between it and the root applies.
+pub fn cgroup_memory_limit() -> Option<u64> {
+    if cfg!(target_os = "linux") {
+        let own = std::fs::read_to_string("/proc/self/cgroup").unwrap_or_default();
+        cgroup_limit_in(Path::new("/sys/fs/cgroup"), &own)
+    } else {
+        None
+    }
 }
 
-#[derive(Debug, Clone, Copy)]
+/// [`cgroup_memory_limit`] read from a cgroup filesystem mounted at `root`, for a process
+/// whose `/proc/self/cgroup` reads `own`. Without one, the mount root itself is checked.
+fn cgroup_limit_in(root: &Path, own: &str) -> Option<u64> {
+    let own = if own.trim().is_empty() {
+        "0::/\n0:memory:/"
+    } else {
+        own
+    };
+    let mut lowest: Option<u64> = None;
+    for line in own.lines() {
+        // `<id>:<controllers>:<path>`; v2 lists no controllers, v1 a comma-separated set
+        let mut fields = line.splitn(3, ':');
+        let (Some(_), Some(controllers), Some(path)) =
+            (fields.next(), fields.next(), fields.next())
+        else {
+            continue;
+        };
+        let (hierarchy, file) = if controllers.is_empty() {
+            (root.to_path_buf(), "memory.max")
+        } else if controllers.split(',').any(|c| c == "memory") {
+            (root.join("memory"), "memory.limit_in_bytes")
+        } else {
+            continue;
+        };
+        let mut cgroup = Some(Path::new(path.trim_start_matches('/')));
+        while let Some(dir) = cgroup {
+            // v2 writes "max" when unlimited; v1 a page-aligned i64::MAX, which no host exceeds.
+            let limit = std::fs::read_to_string(hierarchy.join(dir).join(file))
+                .ok()
+                .and_then(|l| l.trim().parse::<u64>().ok());
+            if let Some(limit) = limit {
+                lowest = Some(lowest.map_or(limit, |l| l.min(limit)));
+            }
+            cgroup = dir.parent();
+        }
+    }
+    lowest
+}
+
+/// RAM to plan against: the host's, capped by the cgroup limit when one is set.
+fn ram_within_cgroup(host_ram: u64, cgroup_limit: Option<u64>) -> u64 {
+    cgroup_limit.map_or(host_ram, |limit| host_ram.min(limit))
+}
+
+/// `MemTotal` from `/proc/meminfo` contents, in bytes.
+#[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+fn parse_meminfo_total(meminfo: &str) -> Option<u64> {
+    let line = meminfo.lines().find(|l| l.starts_with("MemTotal:"))?;
+    let mut fields = line["MemTotal:".len()..].split_whitespace();
+    let value: u64 = fields.next()?.parse().ok()?;
+    match fields.next() {
+        Some("kB") | None => value.checked_mul(1024),
+        _ => None,
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize)]
 pub struct MemoryPlan {
     pub mode: MemoryMode,
     pub threads: usize,
@@ -241,12 +401,26 @@ impl MemoryPlan {
 }
 
 /// Recommend configuration (threads, buffer_size, num_buffers) based on RAM and MemoryMode.
+/// Detected RAM is capped by the cgroup memory limit so containers stay within theirs;
+/// an explicit `HASH_FOLDEROO_ASSUME_RAM` is used as given.
 pub fn recommend_config(
     mode: MemoryMode,
     threads_override: Option<usize>,
     max_ram_override: Option<u64>,
 ) -> Result<MemoryPlan> {
-    let detected_ram = detect_system_ram_bytes().unwrap_or(2 * 1024 * 1024 * 1024);
+    let detected_ram = available_ram_bytes().unwrap_or_else(|e| {
+        warn!("{}; assuming 2 GiB", e);
+        ram_within_cgroup(2 * 1024 * 1024 * 1024, cgroup_memory_limit())
+    });
+    plan_for_ram(mode, threads_override, max_ram_override, detected_ram)
+}
+
+fn plan_for_ram(
+    mode: MemoryMode,
+    threads_override: Option<usize>,
+    max_ram_override: Option<u64>,
+    detected_ram: u64,
+) -> Result<MemoryPlan> {
     let ram_budget = max_ram_override.unwrap_or(detected_ram).max(64 * 1024) as u128;
 
     // Determine number of logical CPUs available
@@ -295,6 +469,8 @@ pub fn recommend_config(
         num_buffers = std::cmp::max(1, scaled_buffers);
     }
 
+    // Never run more workers than buffers: a worker finding the pool empty would
+    // allocate past the budget, defeating the memory cap.
     if num_buffers < threads {
         threads = num_buffers.max(1);
     }
@@ -361,7 +537,7 @@ mod tests {
         // Edge case: pool with 0 max buffers should still allow allocation
         let pool = BufferPool::new(0, 1024);
         let buf = pool.get();
-        assert!(buf.as_slice().len() > 0);
+        assert!(!buf.as_slice().is_empty());
     }
 
     #[test]
@@ -507,6 +683,78 @@ mod tests {
         let _b = pool.get();
     }
 
+    #[test]
+    fn meminfo_total_is_read_in_bytes() {
+        let meminfo = "MemTotal:       16318412 kB\nMemFree:         1048576 kB\n";
+        assert_eq!(parse_meminfo_total(meminfo), Some(16318412 * 1024));
+        assert_eq!(parse_meminfo_total("MemFree: 1 kB\n"), None);
+        assert_eq!(parse_meminfo_total("MemTotal: lots kB\n"), None);
+    }
+
+    #[test]
+    fn cgroup_limit_caps_the_plan() {
+        let host_ram = 64 * 1024 * 1024 * 1024;
+        let v2 = tempfile::tempdir().unwrap();
+        std::fs::write(v2.path().join("memory.max"), "max\n").unwrap();
+        assert_eq!(cgroup_limit_in(v2.path(), ""), None);
+        std::fs::write(v2.path().join("memory.max"), "1048576\n").unwrap();
+        let limit = cgroup_limit_in(v2.path(), "0::/\n");
+        assert_eq!(limit, Some(1024 * 1024));
+
+        let v1 = tempfile::tempdir().unwrap();
+        std::fs::create_dir(v1.path().join("memory")).unwrap();
+        std::fs::write(v1.path().join("memory/memory.limit_in_bytes"), "2097152\n").unwrap();
+        assert_eq!(cgroup_limit_in(v1.path(), ""), Some(2 * 1024 * 1024));
+
+        let capped = plan_for_ram(
+            MemoryMode::Booster,
+            None,
+            None,
+            ram_within_cgroup(host_ram, limit),
+        )
+        .unwrap();
+        assert!(capped.total_buffer_bytes() <= 1024 * 1024);
+        let uncapped = plan_for_ram(
+            MemoryMode::Booster,
+            None,
+            None,
+            ram_within_cgroup(host_ram, None),
+        )
+        .unwrap();
+        assert!(uncapped.total_buffer_bytes() > capped.total_buffer_bytes());
+    }
+
+    #[test]
+    fn cgroup_limit_follows_the_process_cgroup() {
+        let v2 = tempfile::tempdir().unwrap();
+        let own = v2.path().join("system.slice/job.service");
+        std::fs::create_dir_all(&own).unwrap();
+        std::fs::write(v2.path().join("system.slice/memory.max"), "4194304\n").unwrap();
+        std::fs::write(own.join("memory.max"), "max\n").unwrap();
+        // a parent's limit applies to the processes below it
+        assert_eq!(
+            cgroup_limit_in(v2.path(), "0::/system.slice/job.service\n"),
+            Some(4 * 1024 * 1024)
+        );
+        std::fs::write(own.join("memory.max"), "1048576\n").unwrap();
+        assert_eq!(
+            cgroup_limit_in(v2.path(), "0::/system.slice/job.service\n"),
+            Some(1024 * 1024)
+        );
+        // a sibling's limit doesn't
+        assert_eq!(cgroup_limit_in(v2.path(), "0::/user.slice\n"), None);
+
+        let v1 = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(v1.path().join("memory/docker/abc")).unwrap();
+        std::fs::write(
+            v1.path().join("memory/docker/abc/memory.limit_in_bytes"),
+            "2097152\n",
+        )
+        .unwrap();
+        let own = "12:cpu,cpuacct:/docker/abc\n7:memory:/docker/abc\n";
+        assert_eq!(cgroup_limit_in(v1.path(), own), Some(2 * 1024 * 1024));
+    }
+
     #[test]
     fn recommend_config_zero_threads_defaults() {
         // Edge case: if somehow zero threads requested
@@ -1,14 +1,29 @@
-use std::path::PathBuf;
-use std::sync::Arc;
+use std::fs::File;
+use std::io::{self, IsTerminal, Read};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 
 use anyhow::{Context, Result};
-use crossbeam_channel::unbounded;
-use indicatif::{ProgressBar, ProgressStyle};
+use crossbeam_channel::{bounded, unbounded, Receiver, Sender};
+use indicatif::{HumanBytes, HumanDuration, ProgressBar, ProgressDrawTarget, ProgressStyle};
 use rayon::ThreadPoolBuilder;
 
-use crate::memory::{recommend_config, BufferPool, MemoryMode};
+use crate::memory::{recommend_config, BufferPool, MemoryMode, MemoryPlan};
 use crate::walk;
 
+/// Stream the progress bar is drawn on. Stderr by default so a map written to stdout
+/// is never interleaved with progress output.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ProgressTarget {
+    #[default]
+    Stderr,
+    Stdout,
+}
+
+type PlanCallback = Box<dyn Fn(&MemoryPlan) + Send + Sync>;
+
 /// A simple hashing pipeline that connects a producer (directory walker)
 /// to multiple worker threads that process files.
 ///
@@ -18,6 +33,26 @@ pub struct Pipeline {
     pub mode: MemoryMode,
     threads_override: Option<usize>,
     max_ram_override: Option<u64>,
+    stop: Option<Arc<AtomicBool>>,
+    fail_fast: bool,
+    io_threads: Option<usize>,
+    hash_threads: Option<usize>,
+    one_file_system: bool,
+    resume_after: Option<PathBuf>,
+    extensions: Vec<String>,
+    symlinks: bool,
+    max_files: Option<u64>,
+    exclude_case_insensitive: bool,
+    progress_target: ProgressTarget,
+    progress_refresh: Option<Duration>,
+    progress_plain: bool,
+    force_progress: bool,
+    progress_template: Option<String>,
+    eta_smoothing: Option<f64>,
+    on_listed: Option<Box<dyn Fn(u64) + Send + Sync>>,
+    on_plan: Option<PlanCallback>,
+    large_file_threshold: Option<u64>,
+    paths: Option<Vec<PathBuf>>,
 }
 
 impl Pipeline {
@@ -26,6 +61,26 @@ impl Pipeline {
             mode,
             threads_override: None,
             max_ram_override: None,
+            stop: None,
+            fail_fast: false,
+            io_threads: None,
+            hash_threads: None,
+            one_file_system: false,
+            resume_after: None,
+            extensions: Vec::new(),
+            symlinks: false,
+            max_files: None,
+            exclude_case_insensitive: false,
+            progress_target: ProgressTarget::Stderr,
+            progress_refresh: None,
+            progress_plain: false,
+            force_progress: false,
+            progress_template: None,
+            eta_smoothing: None,
+            on_listed: None,
+            on_plan: None,
+            large_file_threshold: None,
+            paths: None,
         }
     }
 
@@ -39,28 +94,165 @@ impl Pipeline {
         self
     }
 
-    /// Run the pipeline over `root` using `exclusions`.
-    ///
-    /// `worker` is called for every file and must be Send + Sync + 'static.
-    /// Returns the number of files processed.
-    pub fn run<F>(
+    /// Stop handing out new files once `stop` is set (e.g. by the worker when a budget
+    /// is reached). Files already being processed still finish.
+    pub fn with_stop_signal(mut self, stop: Arc<AtomicBool>) -> Self {
+        self.stop = Some(stop);
+        self
+    }
+
+    /// Stop at the first worker error and return it from `run`/`run_split`, instead of
+    /// logging it and carrying on. Files already being processed still finish.
+    pub fn with_fail_fast(mut self, fail_fast: bool) -> Self {
+        self.fail_fast = fail_fast;
+        self
+    }
+
+    /// Stay on the root's filesystem instead of descending into other mounts.
+    pub fn with_one_file_system(mut self, enabled: bool) -> Self {
+        self.one_file_system = enabled;
+        self
+    }
+
+    /// Walk in sorted order and skip files up to and including the root-relative
+    /// `marker` (see [`walk::WalkStream::resume_after`]).
+    pub fn with_resume_after(mut self, marker: Option<PathBuf>) -> Self {
+        self.resume_after = marker;
+        self
+    }
+
+    /// Only hash files with one of these extensions (see [`walk::WalkStream::extensions`]).
+    pub fn with_extensions(mut self, extensions: Vec<String>) -> Self {
+        self.extensions = extensions;
+        self
+    }
+
+    /// Hand symlinks to the worker instead of skipping them (see
+    /// [`walk::WalkStream::symlinks`]).
+    pub fn with_symlinks(mut self, enabled: bool) -> Self {
+        self.symlinks = enabled;
+        self
+    }
+
+    /// Match exclusion globs ignoring case (see [`walk::WalkStream::exclude_case_insensitive`]).
+    pub fn with_exclude_case_insensitive(mut self, enabled: bool) -> Self {
+        self.exclude_case_insensitive = enabled;
+        self
+    }
+
+    /// Fail the run when the walk finds more than `max_files` files: before anything is
+    /// hashed when the listing is prefetched, or as soon as the count is passed when
+    /// streaming.
+    pub fn with_max_files(mut self, max_files: Option<u64>) -> Self {
+        self.max_files = max_files;
+        self
+    }
+
+    pub fn with_progress_target(mut self, target: ProgressTarget) -> Self {
+        self.progress_target = target;
+        self
+    }
+
+    /// Redraw the progress bar at most once per `refresh` (and tick the spinner at that
+    /// cadence) instead of indicatif's default rate.
+    pub fn with_progress_refresh(mut self, refresh: Option<Duration>) -> Self {
+        self.progress_refresh = refresh.filter(|d| !d.is_zero());
+        self
+    }
+
+    /// Replace the redrawn bar with a plain `progress: ...` line every refresh interval
+    /// ([`DEFAULT_PLAIN_INTERVAL`] unless set), for CI logs without ANSI support.
+    pub fn with_progress_plain(mut self, plain: bool) -> Self {
+        self.progress_plain = plain;
+        self
+    }
+
+    /// Draw the bar even when its stream is not a terminal or `CI` is set; otherwise
+    /// such runs fall back to plain progress lines.
+    pub fn with_force_progress(mut self, force: bool) -> Self {
+        self.force_progress = force;
+        self
+    }
+
+    /// Draw the bar with a custom indicatif template (see [`validate_progress_template`]).
+    pub fn with_progress_template(mut self, template: Option<String>) -> Self {
+        self.progress_template = template;
+        self
+    }
+
+    /// Track bytes hashed and show a throughput and ETA, smoothed as an exponential moving
+    /// average with weight `alpha` (see [`ThroughputEma`]), as the progress message.
+    pub fn with_eta_smoothing(mut self, alpha: Option<f64>) -> Self {
+        self.eta_smoothing = alpha;
+        self
+    }
+
+    /// Call `on_listed` with the file count when the listing is prefetched, before any
+    /// file reaches a worker, so callers can size their result buffers up front.
+    pub fn with_on_listed(mut self, on_listed: impl Fn(u64) + Send + Sync + 'static) -> Self {
+        self.on_listed = Some(Box::new(on_listed));
+        self
+    }
+
+    /// Call `on_plan` with the memory plan the run actually uses, once its workers are
+    /// sized. A split run reports its reader and hasher threads together and the
+    /// buffers it allocated.
+    pub fn with_on_plan(mut self, on_plan: impl Fn(&MemoryPlan) + Send + Sync + 'static) -> Self {
+        self.on_plan = Some(Box::new(on_plan));
+        self
+    }
+
+    /// Split file reading from hashing: `io_threads` open files and fill buffers while
+    /// `hash_threads` consume them. Either defaults to the memory plan's thread count.
+    /// Only used by [`Pipeline::run_split`].
+    pub fn with_io_threads(mut self, io_threads: Option<usize>) -> Self {
+        self.io_threads = io_threads.filter(|t| *t > 0);
+        self
+    }
+
+    pub fn with_hash_threads(mut self, hash_threads: Option<usize>) -> Self {
+        self.hash_threads = hash_threads.filter(|t| *t > 0);
+        self
+    }
+
+    /// Hash files of at least `threshold` bytes on their own worker group, so a few huge
+    /// files can't hold up the small ones queued behind them. That group reads with
+    /// [`LARGE_BUFFER_FACTOR`]-times larger buffers and hashes each one in parallel across
+    /// its threads (see `HasherImpl::update_parallel`). Ignored, with a warning, when the
+    /// memory plan is too small to split. Only used by [`Pipeline::run_collect`].
+    pub fn with_large_file_threshold(mut self, threshold: Option<u64>) -> Self {
+        self.large_file_threshold = threshold.filter(|t| *t > 0);
+        self
+    }
+
+    /// Hand exactly these files to the workers instead of walking the root
+    /// (`hashmap --changed-from`). Only paths the walk itself would yield are hashed:
+    /// exclusions, extensions, depth and symlink handling apply as they do to a walk.
+    pub fn with_paths(mut self, paths: Vec<PathBuf>) -> Self {
+        self.paths = Some(paths);
+        self
+    }
+
+    /// True when either `--io-threads` or `--hash-threads` was requested.
+    pub fn is_split(&self) -> bool {
+        self.io_threads.is_some() || self.hash_threads.is_some()
+    }
+
+    /// Plan the run, start the walker and return the receiving end of the path channel.
+    /// With `large_file_threshold` (and a plan with room for two groups), files of at
+    /// least that size go to a second channel.
+    fn dispatch(
         &self,
-        root: impl AsRef<std::path::Path>,
+        root: &Path,
         exclusions: &[String],
         max_depth: Option<usize>,
         follow_symlinks: bool,
         show_progress: bool,
-        worker: F,
-    ) -> Result<usize>
-    where
-        F: Fn(PathBuf, Arc<BufferPool>) -> Result<()> + Send + Sync + 'static,
-    {
+        large_file_threshold: Option<u64>,
+    ) -> Result<Dispatch> {
         // Decide threads and buffer configuration from memory mode
         let plan = recommend_config(self.mode, self.threads_override, self.max_ram_override)
             .context("failed to get recommended config")?;
-        let threads = plan.threads;
-        let buf_size = plan.buffer_size;
-        let num_buffers = plan.num_buffers;
         log::info!(
             "Memory plan {:?}: threads={}, buffers={} (~{:.2} MiB)",
             plan.mode,
@@ -68,74 +260,322 @@ impl Pipeline {
             plan.num_buffers,
             plan.total_buffer_bytes() as f64 / (1024.0 * 1024.0)
         );
+        let size_classes = match large_file_threshold {
+            Some(threshold) => match size_class_groups(&plan) {
+                Some(groups) => Some((threshold, groups)),
+                None => {
+                    log::warn!(
+                        "--large-file-threshold needs at least 2 threads and {} buffers; the plan has {} and {}, so all files share one worker group",
+                        LARGE_BUFFER_FACTOR + 1,
+                        plan.threads,
+                        plan.num_buffers
+                    );
+                    None
+                }
+            },
+            None => None,
+        };
 
-        // Build buffer pool
-        let buffer_pool = Arc::new(BufferPool::new(num_buffers, buf_size));
-
-        let root_buf = root.as_ref().to_path_buf();
+        // Sizes come from the walker's one stat per file, and only when something needs
+        // them: the ETA's byte total, or routing by size class
+        let byte_total = show_progress && self.eta_smoothing.is_some() && plan.prefetch_listing;
+        let sized = byte_total || size_classes.is_some();
         let walker_stream =
-            walk::walk_directory_stream(&root_buf, exclusions, max_depth, follow_symlinks)
-                .context("walk directory")?;
+            walk::walk_directory_stream(root, exclusions, max_depth, follow_symlinks)
+                .context("walk directory")?
+                .exclude_case_insensitive(self.exclude_case_insensitive)?
+                .one_file_system(self.one_file_system)
+                .resume_after(self.resume_after.clone())
+                .extensions(&self.extensions)
+                .symlinks(self.symlinks);
+        let listing: Box<dyn Iterator<Item = (PathBuf, Option<u64>)> + Send> =
+            if let Some(paths) = &self.paths {
+                // Listed paths are held to the walk's filters, so they can't add entries
+                // a full scan wouldn't produce
+                let paths: Vec<PathBuf> = paths
+                    .iter()
+                    .filter(|p| {
+                        let admitted = walker_stream.admits(p);
+                        if !admitted {
+                            log::debug!("{} is filtered out of the walk; skipping", p.display());
+                        }
+                        admitted
+                    })
+                    .cloned()
+                    .collect();
+                let paths = paths.into_iter();
+                if sized {
+                    Box::new(paths.map(|p| {
+                        let size = std::fs::metadata(&p).map(|m| m.len()).ok();
+                        (p, size)
+                    }))
+                } else {
+                    Box::new(paths.map(|p| (p, None)))
+                }
+            } else if sized {
+                Box::new(walker_stream.with_sizes().map(|(p, size)| (p, Some(size))))
+            } else {
+                Box::new(walker_stream.map(|p| (p, None)))
+            };
 
-        let mut streaming_iter: Option<walk::WalkStream> = None;
+        let mut streaming_iter = None;
+        let mut total_bytes = None;
         let (files, total_files) = if plan.prefetch_listing {
-            let collected: Vec<PathBuf> = walker_stream.collect();
+            let collected: Vec<(PathBuf, Option<u64>)> = listing.collect();
             let total = collected.len() as u64;
+            if byte_total {
+                total_bytes = Some(collected.iter().filter_map(|(_, size)| *size).sum());
+            }
+            if let Some(max) = self.max_files.filter(|max| total > *max) {
+                anyhow::bail!("{}", too_many_files(root, max));
+            }
+            if let Some(on_listed) = &self.on_listed {
+                on_listed(total);
+            }
             (Some(collected), total)
         } else {
-            streaming_iter = Some(walker_stream);
+            streaming_iter = Some(listing);
             (None, 0)
         };
 
-        let pb = if show_progress {
+        let mut plain = None;
+        let plain_progress = self.progress_plain
+            || (!self.force_progress && !progress_is_interactive(self.progress_target));
+        let pb = if show_progress && plain_progress {
+            let bar = ProgressBar::hidden();
+            if plan.prefetch_listing {
+                bar.set_length(total_files);
+            }
+            plain = Some(PlainProgress::start(
+                bar.clone(),
+                self.progress_refresh.unwrap_or(DEFAULT_PLAIN_INTERVAL),
+                self.progress_target,
+            ));
+            bar
+        } else if show_progress {
             let bar = if plan.prefetch_listing {
                 ProgressBar::new(total_files)
             } else {
                 ProgressBar::new_spinner()
             };
-            bar.set_style(
-                ProgressStyle::with_template(if plan.prefetch_listing {
+            let template = self
+                .progress_template
+                .as_deref()
+                .unwrap_or(if plan.prefetch_listing {
                     "{spinner:.green} [{elapsed_precise}] {bar:40.cyan/blue} {pos}/{len} {msg}"
                 } else {
                     "{spinner:.green} [{elapsed_precise}] {msg}"
-                })
-                .unwrap_or_else(|_| ProgressStyle::default_bar()),
+                });
+            bar.set_style(
+                ProgressStyle::with_template(template)
+                    .unwrap_or_else(|_| ProgressStyle::default_bar()),
             );
+            // indicatif caps redraws in Hz; intervals over a second redraw once a second
+            let hz = self
+                .progress_refresh
+                .map(|d| (1000 / d.as_millis().max(1)).clamp(1, 255) as u8)
+                .unwrap_or(20);
+            // a term-like target draws even when the stream is not a terminal
+            bar.set_draw_target(match (self.progress_target, self.force_progress) {
+                (ProgressTarget::Stderr, false) => ProgressDrawTarget::stderr_with_hz(hz),
+                (ProgressTarget::Stdout, false) => ProgressDrawTarget::stdout_with_hz(hz),
+                (ProgressTarget::Stderr, true) => {
+                    ProgressDrawTarget::term_like_with_hz(Box::new(console::Term::stderr()), hz)
+                }
+                (ProgressTarget::Stdout, true) => {
+                    ProgressDrawTarget::term_like_with_hz(Box::new(console::Term::stdout()), hz)
+                }
+            });
+            if let Some(refresh) = self.progress_refresh {
+                bar.enable_steady_tick(refresh);
+            }
             bar.set_message("hashing files");
             bar
         } else {
             ProgressBar::hidden()
         };
+        let eta = self.eta_smoothing.filter(|_| show_progress).map(|alpha| {
+            let interval = self.progress_refresh.unwrap_or(DEFAULT_ETA_INTERVAL);
+            EtaProgress::start(pb.clone(), total_bytes, alpha, interval)
+        });
+
+        // Set by a fail-fast worker, or by the producer when --max-files is exceeded
+        let first_error = Arc::new(Mutex::new(None));
 
         // Channel to feed file paths to workers
         let (tx, rx) = unbounded::<PathBuf>();
+        let (large_tx, large_paths) = match size_classes {
+            Some((threshold, (small, large))) => {
+                let (large_tx, large_rx) = unbounded::<PathBuf>();
+                (Some((large_tx, threshold)), Some((large_rx, small, large)))
+            }
+            None => (None, None),
+        };
+        let tx = PathRouter {
+            small: tx,
+            large: large_tx,
+        };
+
+        let stop = self
+            .stop
+            .clone()
+            .unwrap_or_else(|| Arc::new(AtomicBool::new(false)));
 
         // Producer: send all file paths then close the channel
         if let Some(files) = files {
-            let tx = tx.clone();
+            let stop = stop.clone();
             std::thread::spawn(move || {
-                for f in files {
-                    if tx.send(f).is_err() {
+                for (f, size) in files {
+                    if stop.load(Ordering::Relaxed) || tx.send(f, size).is_err() {
                         break;
                     }
                 }
             });
         } else if let Some(stream) = streaming_iter.take() {
-            let tx = tx.clone();
+            let stop = stop.clone();
+            let first_error = first_error.clone();
+            let max_files = self.max_files;
+            let root = root.to_path_buf();
             std::thread::spawn(move || {
-                for f in stream {
-                    if tx.send(f).is_err() {
+                for (listed, (f, size)) in (1u64..).zip(stream) {
+                    if let Some(max) = max_files.filter(|max| listed > *max) {
+                        let err = anyhow::anyhow!("{}", too_many_files(&root, max));
+                        record_first_error(&first_error, &stop, err);
+                        break;
+                    }
+                    if stop.load(Ordering::Relaxed) || tx.send(f, size).is_err() {
                         break;
                     }
                 }
             });
         }
 
-        // Drop the original sender so the channel is closed once the producer finishes.
-        drop(tx);
+        Ok(Dispatch {
+            plan,
+            paths: rx,
+            large_paths,
+            total_files,
+            pb,
+            plain,
+            eta,
+            stop,
+            first_error,
+        })
+    }
+
+    /// Run the pipeline over `root` using `exclusions`.
+    ///
+    /// `worker` is called for every file and must be Send + Sync + 'static.
+    /// Returns the number of files processed.
+    pub fn run<F>(
+        &self,
+        root: impl AsRef<Path>,
+        exclusions: &[String],
+        max_depth: Option<usize>,
+        follow_symlinks: bool,
+        show_progress: bool,
+        worker: F,
+    ) -> Result<usize>
+    where
+        F: Fn(PathBuf, Arc<BufferPool>) -> Result<()> + Send + Sync + 'static,
+    {
+        self.run_collect(
+            root,
+            exclusions,
+            max_depth,
+            follow_symlinks,
+            show_progress,
+            move |path, pool| worker(path, pool).map(|()| None::<()>),
+        )
+        .map(|(processed, _)| processed)
+    }
+
+    /// Like [`Pipeline::run`], but collects what `worker` returns. Each worker thread
+    /// keeps its results in its own `Vec`, merged once all threads are done, so no lock
+    /// is taken per file. Returns the number of files processed and the merged results
+    /// (in no particular order).
+    pub fn run_collect<T, F>(
+        &self,
+        root: impl AsRef<Path>,
+        exclusions: &[String],
+        max_depth: Option<usize>,
+        follow_symlinks: bool,
+        show_progress: bool,
+        worker: F,
+    ) -> Result<(usize, Vec<T>)>
+    where
+        T: Send + 'static,
+        F: Fn(PathBuf, Arc<BufferPool>) -> Result<Option<T>> + Send + Sync + 'static,
+    {
+        let Dispatch {
+            plan,
+            paths: rx,
+            large_paths,
+            total_files,
+            pb,
+            plain,
+            eta,
+            stop,
+            first_error,
+        } = self.dispatch(
+            root.as_ref(),
+            exclusions,
+            max_depth,
+            follow_symlinks,
+            show_progress,
+            self.large_file_threshold,
+        )?;
+        let threads = plan.threads;
+        if let Some(on_plan) = &self.on_plan {
+            on_plan(&plan);
+        }
+
+        // Worker groups, each draining its own channel with its own buffer pool
+        let groups = match large_paths {
+            Some((large_rx, small, large)) => {
+                log::info!(
+                    "Size classes: {} workers for files of {} bytes or more ({} buffers), {} for the rest ({} buffers)",
+                    large.threads,
+                    self.large_file_threshold.unwrap_or_default(),
+                    HumanBytes(large.buffer_size as u64),
+                    small.threads,
+                    HumanBytes(small.buffer_size as u64)
+                );
+                vec![(rx, small), (large_rx, large)]
+            }
+            None => vec![(
+                rx,
+                WorkerGroup {
+                    threads,
+                    num_buffers: plan.num_buffers,
+                    buffer_size: plan.buffer_size,
+                    parallel: false,
+                },
+            )],
+        };
+
+        // A parallel group hashes inside a rayon pool of its own size, so one large file
+        // in flight is spread over every thread of the group
+        let groups = groups
+            .into_iter()
+            .map(|(rx, group)| {
+                let hash_pool = group
+                    .parallel
+                    .then(|| {
+                        ThreadPoolBuilder::new()
+                            .num_threads(group.threads)
+                            .thread_name(|i| format!("large-hash-{}", i))
+                            .build()
+                            .context("build large-file hashing pool")
+                    })
+                    .transpose()?;
+                Ok((rx, group, hash_pool.map(Arc::new)))
+            })
+            .collect::<Result<Vec<_>>>()?;
 
         // Wrap worker in Arc so it can be cloned into threads
         let worker = Arc::new(worker);
+        let mut results = Vec::with_capacity(total_files as usize);
 
         // Build rayon thread pool with configured number of threads
         let pool = ThreadPoolBuilder::new()
@@ -146,38 +586,665 @@ impl Pipeline {
 
         // Start workers inside the rayon pool
         pool.install(|| {
-            // spawn worker tasks equal to the number of threads
+            // spawn worker tasks equal to each group's number of threads
             let mut handles = Vec::with_capacity(threads);
-            for _ in 0..threads {
-                let rx = rx.clone();
-                let worker = worker.clone();
-                let pool_clone = buffer_pool.clone();
-                let pb = pb.clone();
-                // Each rayon task loops over the shared receiver
-                handles.push(std::thread::spawn(move || {
-                    // Iterate until channel closes
-                    for path in rx.iter() {
-                        // Enforce soft backpressure: if allocated buffers exceed budget, yield briefly
-                        if pool_clone.allocated_buffers() > pool_clone.max_buffers() {
-                            std::thread::sleep(std::time::Duration::from_millis(5));
+            for (rx, group, hash_pool) in &groups {
+                let mut buffer_pool = BufferPool::new(group.num_buffers, group.buffer_size)
+                    .with_parallel_hashing(group.parallel);
+                if let Some(eta) = &eta {
+                    buffer_pool = buffer_pool.with_read_counter(eta.bytes.clone());
+                }
+                let buffer_pool = Arc::new(buffer_pool);
+                for _ in 0..group.threads {
+                    let hash_pool = hash_pool.clone();
+                    let rx = rx.clone();
+                    let worker = worker.clone();
+                    let pool_clone = buffer_pool.clone();
+                    let pb = pb.clone();
+                    let stop = stop.clone();
+                    let first_error = first_error.clone();
+                    let fail_fast = self.fail_fast;
+                    // Each rayon task loops over the shared receiver
+                    handles.push(std::thread::spawn(move || {
+                        let mut local = Vec::new();
+                        // Iterate until channel closes (or a stop is requested)
+                        for path in rx.iter() {
+                            if stop.load(Ordering::Relaxed) {
+                                break;
+                            }
+                            // Enforce soft backpressure: if allocated buffers exceed budget, yield briefly
+                            if pool_clone.allocated_buffers() > pool_clone.max_buffers() {
+                                std::thread::sleep(std::time::Duration::from_millis(5));
+                            }
+                            let outcome = match &hash_pool {
+                                Some(hash_pool) => {
+                                    hash_pool.install(|| (worker)(path, pool_clone.clone()))
+                                }
+                                None => (worker)(path, pool_clone.clone()),
+                            };
+                            match outcome {
+                                Ok(item) => local.extend(item),
+                                Err(e) => {
+                                    if fail_fast {
+                                        record_first_error(&first_error, &stop, e);
+                                        break;
+                                    }
+                                    log::warn!("worker error: {:?}", e);
+                                }
+                            }
+                            pb.inc(1);
+                        }
+                        local
+                    }));
+                }
+            }
+
+            // Wait for all spawned threads to finish and merge their results
+            for h in handles {
+                if let Ok(local) = h.join() {
+                    results.extend(local);
+                }
+            }
+        });
+
+        finish_progress(&pb, plain, eta, &first_error)?;
+        Ok((pb.position() as usize, results))
+    }
+
+    /// Like [`Pipeline::run`], but with separate I/O and hashing stages.
+    ///
+    /// I/O threads open each file and read it into pooled buffers; hash threads receive
+    /// the file as a [`ChunkReader`] and pass it to `worker`. Each file stays with one
+    /// hash thread, and at most [`CHUNKS_IN_FLIGHT`] buffers per file are queued ahead
+    /// of it. Open/read failures surface as read errors from the `ChunkReader`.
+    pub fn run_split<F>(
+        &self,
+        root: impl AsRef<Path>,
+        exclusions: &[String],
+        max_depth: Option<usize>,
+        follow_symlinks: bool,
+        show_progress: bool,
+        worker: F,
+    ) -> Result<usize>
+    where
+        F: Fn(PathBuf, &mut dyn Read, Arc<BufferPool>) -> Result<()> + Send + Sync + 'static,
+    {
+        self.run_split_collect(
+            root,
+            exclusions,
+            max_depth,
+            follow_symlinks,
+            show_progress,
+            move |path, reader, pool| worker(path, reader, pool).map(|()| None::<()>),
+        )
+        .map(|(processed, _)| processed)
+    }
+
+    /// [`Pipeline::run_split`] collecting per-thread results like [`Pipeline::run_collect`].
+    pub fn run_split_collect<T, F>(
+        &self,
+        root: impl AsRef<Path>,
+        exclusions: &[String],
+        max_depth: Option<usize>,
+        follow_symlinks: bool,
+        show_progress: bool,
+        worker: F,
+    ) -> Result<(usize, Vec<T>)>
+    where
+        T: Send + 'static,
+        F: Fn(PathBuf, &mut dyn Read, Arc<BufferPool>) -> Result<Option<T>> + Send + Sync + 'static,
+    {
+        let Dispatch {
+            plan,
+            paths,
+            large_paths: _,
+            total_files,
+            pb,
+            plain,
+            eta,
+            stop,
+            first_error,
+        } = self.dispatch(
+            root.as_ref(),
+            exclusions,
+            max_depth,
+            follow_symlinks,
+            show_progress,
+            None,
+        )?;
+        let requested = (
+            self.io_threads.unwrap_or(plan.threads),
+            self.hash_threads.unwrap_or(plan.threads),
+        );
+        let (io_threads, hash_threads) =
+            fit_split_threads(requested.0, requested.1, plan.num_buffers);
+        if (io_threads, hash_threads) != requested {
+            log::warn!(
+                "Split pipeline reduced to io_threads={}, hash_threads={} to stay within {} buffers",
+                io_threads,
+                hash_threads,
+                plan.num_buffers
+            );
+        }
+        log::info!(
+            "Split pipeline: io_threads={}, hash_threads={}",
+            io_threads,
+            hash_threads
+        );
+
+        let num_buffers = plan
+            .num_buffers
+            .max(split_buffers_needed(io_threads, hash_threads));
+        if let Some(on_plan) = &self.on_plan {
+            on_plan(&MemoryPlan {
+                threads: io_threads + hash_threads,
+                num_buffers,
+                ..plan
+            });
+        }
+        let mut buffer_pool = BufferPool::new(num_buffers, plan.buffer_size);
+        if let Some(eta) = &eta {
+            buffer_pool = buffer_pool.with_read_counter(eta.bytes.clone());
+        }
+        let buffer_pool = Arc::new(buffer_pool);
+        let (job_tx, job_rx) = unbounded::<(PathBuf, ChunkReader)>();
+
+        let mut readers = Vec::with_capacity(io_threads);
+        for i in 0..io_threads {
+            let paths = paths.clone();
+            let job_tx = job_tx.clone();
+            let pool = buffer_pool.clone();
+            let stop = stop.clone();
+            let handle = std::thread::Builder::new()
+                .name(format!("io-worker-{}", i))
+                .spawn(move || {
+                    for path in paths.iter() {
+                        if stop.load(Ordering::Relaxed) {
+                            break;
                         }
-                        if let Err(e) = (worker)(path, pool_clone.clone()) {
-                            log::warn!("worker error: {:?}", e);
+                        let (chunk_tx, chunk_rx) = bounded(CHUNKS_IN_FLIGHT);
+                        let reader = ChunkReader::new(chunk_rx, pool.clone());
+                        if job_tx.send((path.clone(), reader)).is_err() {
+                            break;
+                        }
+                        fill_chunks(&path, &pool, &chunk_tx);
+                    }
+                })
+                .context("spawn io worker")?;
+            readers.push(handle);
+        }
+        drop(paths);
+        // Close the job channel once every reader is done.
+        drop(job_tx);
+
+        let worker = Arc::new(worker);
+        let mut hashers = Vec::with_capacity(hash_threads);
+        for i in 0..hash_threads {
+            let job_rx = job_rx.clone();
+            let worker = worker.clone();
+            let pool = buffer_pool.clone();
+            let pb = pb.clone();
+            let stop = stop.clone();
+            let first_error = first_error.clone();
+            let fail_fast = self.fail_fast;
+            let handle = std::thread::Builder::new()
+                .name(format!("hash-worker-{}", i))
+                .spawn(move || {
+                    let mut local = Vec::new();
+                    for (path, mut reader) in job_rx.iter() {
+                        if stop.load(Ordering::Relaxed) {
+                            break;
+                        }
+                        match (worker)(path, &mut reader, pool.clone()) {
+                            Ok(item) => local.extend(item),
+                            Err(e) => {
+                                if fail_fast {
+                                    record_first_error(&first_error, &stop, e);
+                                    break;
+                                }
+                                log::warn!("worker error: {:?}", e);
+                            }
                         }
                         pb.inc(1);
                     }
-                }));
+                    local
+                })
+                .context("spawn hash worker")?;
+            hashers.push(handle);
+        }
+        drop(job_rx);
+
+        let mut results = Vec::with_capacity(total_files as usize);
+        for h in hashers {
+            if let Ok(local) = h.join() {
+                results.extend(local);
             }
+        }
+        for h in readers {
+            let _ = h.join();
+        }
 
-            // Wait for all spawned threads to finish
-            for h in handles {
-                let _ = h.join();
+        finish_progress(&pb, plain, eta, &first_error)?;
+        Ok((pb.position() as usize, results))
+    }
+}
+
+/// Keep the first error of a `--fail-fast` run and tell the producer to stop.
+fn record_first_error(slot: &Mutex<Option<anyhow::Error>>, stop: &AtomicBool, err: anyhow::Error) {
+    stop.store(true, Ordering::Relaxed);
+    let mut slot = slot.lock().unwrap();
+    if slot.is_none() {
+        *slot = Some(err);
+    }
+}
+
+fn too_many_files(root: &Path, max: u64) -> String {
+    format!(
+        "{} has more than {} files (--max-files); check that the root is right",
+        root.display(),
+        max
+    )
+}
+
+/// Whether a redrawn bar suits `target`: it must be a terminal, and `CI` must be unset
+/// (or `false`/`0`), since CI logs record every redraw.
+fn progress_is_interactive(target: ProgressTarget) -> bool {
+    let ci = std::env::var("CI")
+        .is_ok_and(|v| !v.is_empty() && v != "0" && !v.eq_ignore_ascii_case("false"));
+    let terminal = match target {
+        ProgressTarget::Stderr => io::stderr().is_terminal(),
+        ProgressTarget::Stdout => io::stdout().is_terminal(),
+    };
+    terminal && !ci
+}
+
+/// Finish the progress display, then surface the error that aborted a fail-fast run.
+fn finish_progress(
+    pb: &ProgressBar,
+    plain: Option<PlainProgress>,
+    eta: Option<EtaProgress>,
+    first_error: &Mutex<Option<anyhow::Error>>,
+) -> Result<()> {
+    if let Some(eta) = eta {
+        eta.finish();
+    }
+    // Plain lines end with the last throughput, not the bar's closing message
+    if let Some(plain) = plain {
+        plain.finish();
+    }
+    let failed = first_error.lock().unwrap().take();
+    if failed.is_some() {
+        pb.abandon_with_message("failed");
+    } else {
+        pb.finish_with_message("done");
+    }
+    match failed {
+        Some(e) => Err(e),
+        None => Ok(()),
+    }
+}
+
+/// Buffers an I/O thread may queue for a single file before it waits on the hasher.
+pub const CHUNKS_IN_FLIGHT: usize = 2;
+
+/// Buffers the split pipeline can hold at once: every reader may hold its queued chunks
+/// plus the one being filled, and every hasher one read buffer.
+fn split_buffers_needed(io_threads: usize, hash_threads: usize) -> usize {
+    io_threads * (CHUNKS_IN_FLIGHT + 1) + hash_threads
+}
+
+/// Shrink split-pipeline thread counts until their buffers fit in `budget`, so explicit
+/// `--io-threads`/`--hash-threads` can't outgrow the memory plan. One reader and one
+/// hasher always remain, even if that alone exceeds the budget.
+fn fit_split_threads(io_threads: usize, hash_threads: usize, budget: usize) -> (usize, usize) {
+    let budget = budget.max(split_buffers_needed(1, 1));
+    let (mut io, mut hash) = (io_threads.max(1), hash_threads.max(1));
+    while split_buffers_needed(io, hash) > budget {
+        // trim whichever side holds more buffers
+        if io > 1 && (hash == 1 || io * (CHUNKS_IN_FLIGHT + 1) >= hash) {
+            io -= 1;
+        } else {
+            hash -= 1;
+        }
+    }
+    (io, hash)
+}
+
+/// Check a `--progress-template` up front so a typo fails the run instead of silently
+/// falling back to the default bar.
+pub fn validate_progress_template(template: &str) -> Result<()> {
+    ProgressStyle::with_template(template)
+        .map(|_| ())
+        .with_context(|| format!("invalid --progress-template {:?}", template))
+}
+
+/// How much larger the large-file group's buffers are than the plan's.
+pub const LARGE_BUFFER_FACTOR: usize = 4;
+
+/// Threads and buffers of one worker group in [`Pipeline::run_collect`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct WorkerGroup {
+    threads: usize,
+    num_buffers: usize,
+    buffer_size: usize,
+    /// Hash each buffer across the group's threads.
+    parallel: bool,
+}
+
+/// Split the plan into a small-file and a large-file group. The large group gets a
+/// quarter of the threads (at least one), each with one buffer [`LARGE_BUFFER_FACTOR`]
+/// times the plan's size, paid for out of the small group's buffers. Together the groups
+/// never exceed the plan's threads or buffers; `None` when the plan can't give each
+/// group a worker and a buffer per worker.
+fn size_class_groups(plan: &MemoryPlan) -> Option<(WorkerGroup, WorkerGroup)> {
+    if plan.threads < 2 {
+        return None;
+    }
+    let large_threads = (plan.threads / 4).max(1);
+    let small_threads = plan.threads - large_threads;
+    let small_buffers = plan
+        .num_buffers
+        .checked_sub(large_threads * LARGE_BUFFER_FACTOR)
+        .filter(|buffers| *buffers >= small_threads)?;
+    let small = WorkerGroup {
+        threads: small_threads,
+        num_buffers: small_buffers,
+        buffer_size: plan.buffer_size,
+        parallel: false,
+    };
+    let large = WorkerGroup {
+        threads: large_threads,
+        num_buffers: large_threads,
+        buffer_size: plan.buffer_size * LARGE_BUFFER_FACTOR,
+        parallel: true,
+    };
+    Some((small, large))
+}
+
+/// Producer end of the path channels, routing files by size when size classes are on.
+struct PathRouter {
+    small: Sender<PathBuf>,
+    large: Option<(Sender<PathBuf>, u64)>,
+}
+
+impl PathRouter {
+    /// Send `path` to its group; `size` is the walker's, known whenever size classes are on.
+    fn send(
+        &self,
+        path: PathBuf,
+        size: Option<u64>,
+    ) -> Result<(), crossbeam_channel::SendError<PathBuf>> {
+        match (&self.large, size) {
+            (Some((large, threshold)), Some(size)) if size >= *threshold => large.send(path),
+            _ => self.small.send(path),
+        }
+    }
+}
+
+/// Shared state produced by [`Pipeline::dispatch`].
+struct Dispatch {
+    plan: MemoryPlan,
+    paths: Receiver<PathBuf>,
+    /// Files at or over the large-file threshold and the two worker groups, when size
+    /// classes are on.
+    large_paths: Option<(Receiver<PathBuf>, WorkerGroup, WorkerGroup)>,
+    /// Files in a prefetched listing (0 when streaming).
+    total_files: u64,
+    pb: ProgressBar,
+    plain: Option<PlainProgress>,
+    eta: Option<EtaProgress>,
+    stop: Arc<AtomicBool>,
+    first_error: Arc<Mutex<Option<anyhow::Error>>>,
+}
+
+/// Interval between plain progress lines when no refresh rate is given.
+pub const DEFAULT_PLAIN_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Background thread printing the (hidden) bar's position as plain lines.
+struct PlainProgress {
+    done: Arc<AtomicBool>,
+    handle: std::thread::JoinHandle<()>,
+}
+
+impl PlainProgress {
+    fn start(pb: ProgressBar, interval: Duration, target: ProgressTarget) -> Self {
+        let done = Arc::new(AtomicBool::new(false));
+        let done_thread = done.clone();
+        let handle = std::thread::spawn(move || {
+            let started = Instant::now();
+            let mut next = interval;
+            loop {
+                let finished = done_thread.load(Ordering::Relaxed);
+                if finished || started.elapsed() >= next {
+                    let mut line = match pb.length() {
+                        Some(len) => format!(
+                            "progress: {}/{} files [{}s]",
+                            pb.position(),
+                            len,
+                            started.elapsed().as_secs()
+                        ),
+                        None => format!(
+                            "progress: {} files [{}s]",
+                            pb.position(),
+                            started.elapsed().as_secs()
+                        ),
+                    };
+                    let message = pb.message();
+                    if !message.is_empty() {
+                        line = format!("{} {}", line, message);
+                    }
+                    match target {
+                        ProgressTarget::Stderr => eprintln!("{}", line),
+                        ProgressTarget::Stdout => println!("{}", line),
+                    }
+                    next += interval;
+                }
+                if finished {
+                    break;
+                }
+                std::thread::sleep(interval.min(Duration::from_millis(100)));
             }
         });
+        Self { done, handle }
+    }
 
-        pb.finish_with_message("done");
+    /// Print the final count and stop the thread.
+    fn finish(self) {
+        self.done.store(true, Ordering::Relaxed);
+        let _ = self.handle.join();
+    }
+}
 
-        Ok(pb.position() as usize)
+/// Interval between throughput samples when no refresh rate is given.
+pub const DEFAULT_ETA_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Default weight of the newest sample in the smoothed throughput.
+pub const DEFAULT_ETA_SMOOTHING: f64 = 0.3;
+
+/// Exponential moving average of throughput. Each sample moves the estimate `alpha` of
+/// the way towards the latest rate: a large `alpha` follows changes quickly, a small one
+/// rides out bursts of tiny or huge files.
+#[derive(Debug, Clone, Copy)]
+pub struct ThroughputEma {
+    alpha: f64,
+    rate: Option<f64>,
+}
+
+impl ThroughputEma {
+    pub fn new(alpha: f64) -> Self {
+        Self { alpha, rate: None }
+    }
+
+    /// Fold in `bytes` done over `elapsed` and return the smoothed bytes per second. The
+    /// first sample is taken as is.
+    pub fn update(&mut self, bytes: u64, elapsed: Duration) -> f64 {
+        let secs = elapsed.as_secs_f64();
+        if secs <= 0.0 {
+            return self.rate.unwrap_or(0.0);
+        }
+        let sample = bytes as f64 / secs;
+        let rate = match self.rate {
+            Some(prev) => prev + self.alpha * (sample - prev),
+            None => sample,
+        };
+        self.rate = Some(rate);
+        rate
+    }
+
+    /// Smoothed bytes per second, once a sample has been taken.
+    pub fn rate(&self) -> Option<f64> {
+        self.rate
+    }
+
+    /// Time left for `remaining` bytes at the smoothed rate; `None` before the first
+    /// sample or while stalled.
+    pub fn eta(&self, remaining: u64) -> Option<Duration> {
+        self.rate
+            .filter(|r| *r > 0.0)
+            .map(|r| Duration::from_secs_f64(remaining as f64 / r))
+    }
+}
+
+/// Bytes hashed so far, and a background thread showing their smoothed throughput and
+/// the ETA as the bar's message.
+struct EtaProgress {
+    bytes: Arc<AtomicU64>,
+    done: Arc<AtomicBool>,
+    handle: std::thread::JoinHandle<()>,
+}
+
+impl EtaProgress {
+    fn start(pb: ProgressBar, total_bytes: Option<u64>, alpha: f64, interval: Duration) -> Self {
+        let bytes = Arc::new(AtomicU64::new(0));
+        let done = Arc::new(AtomicBool::new(false));
+        let (bytes_thread, done_thread) = (bytes.clone(), done.clone());
+        let handle = std::thread::spawn(move || {
+            let mut ema = ThroughputEma::new(alpha);
+            let (mut last_at, mut last_bytes) = (Instant::now(), 0);
+            while !done_thread.load(Ordering::Relaxed) {
+                std::thread::sleep(interval.min(Duration::from_millis(100)));
+                if last_at.elapsed() < interval {
+                    continue;
+                }
+                let now = bytes_thread.load(Ordering::Relaxed);
+                let rate = ema.update(now - last_bytes, last_at.elapsed());
+                (last_at, last_bytes) = (Instant::now(), now);
+                let mut message = format!("{}/s", HumanBytes(rate as u64));
+                if let Some(eta) = total_bytes.and_then(|t| ema.eta(t.saturating_sub(now))) {
+                    message = format!("{}, ETA {}", message, HumanDuration(eta));
+                }
+                pb.set_message(message);
+            }
+        });
+        Self {
+            bytes,
+            done,
+            handle,
+        }
+    }
+
+    fn finish(self) {
+        self.done.store(true, Ordering::Relaxed);
+        let _ = self.handle.join();
+    }
+}
+
+/// Read `path` into full pool-sized chunks and send them to the hash stage. Chunks are
+/// only short at EOF, so hashers see the same update boundaries as a direct read.
+fn fill_chunks(path: &Path, pool: &BufferPool, chunks: &Sender<io::Result<Vec<u8>>>) {
+    let mut file = match File::open(path) {
+        Ok(f) => f,
+        Err(e) => {
+            let _ = chunks.send(Err(e));
+            return;
+        }
+    };
+    loop {
+        let mut buf = pool.get().into_inner();
+        match read_full(&mut file, &mut buf) {
+            Ok(0) => {
+                pool.put(buf);
+                return;
+            }
+            Ok(n) => {
+                let at_eof = n < buf.len();
+                buf.truncate(n);
+                // A closed channel means the hasher gave up on this file.
+                if chunks.send(Ok(buf)).is_err() || at_eof {
+                    return;
+                }
+            }
+            Err(e) => {
+                pool.put(buf);
+                let _ = chunks.send(Err(e));
+                return;
+            }
+        }
+    }
+}
+
+fn read_full(file: &mut File, buf: &mut [u8]) -> io::Result<usize> {
+    let mut filled = 0;
+    while filled < buf.len() {
+        match file.read(&mut buf[filled..]) {
+            Ok(0) => break,
+            Ok(n) => filled += n,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(filled)
+}
+
+/// Reads one file's contents as they arrive from an I/O thread, returning each
+/// consumed buffer to the pool.
+pub struct ChunkReader {
+    chunks: Receiver<io::Result<Vec<u8>>>,
+    current: Vec<u8>,
+    pos: usize,
+    pool: Arc<BufferPool>,
+}
+
+impl ChunkReader {
+    fn new(chunks: Receiver<io::Result<Vec<u8>>>, pool: Arc<BufferPool>) -> Self {
+        Self {
+            chunks,
+            current: Vec::new(),
+            pos: 0,
+            pool,
+        }
+    }
+
+    fn recycle(&mut self) {
+        let used = std::mem::take(&mut self.current);
+        if used.capacity() > 0 {
+            self.pool.put(used);
+        }
+        self.pos = 0;
+    }
+}
+
+impl Read for ChunkReader {
+    fn read(&mut self, out: &mut [u8]) -> io::Result<usize> {
+        while self.pos >= self.current.len() {
+            self.recycle();
+            match self.chunks.recv() {
+                Ok(Ok(chunk)) => self.current = chunk,
+                Ok(Err(e)) => return Err(e),
+                // Reader finished: EOF
+                Err(_) => return Ok(0),
+            }
+        }
+        let n = out.len().min(self.current.len() - self.pos);
+        out[..n].copy_from_slice(&self.current[self.pos..self.pos + n]);
+        self.pos += n;
+        Ok(n)
+    }
+}
+
+impl Drop for ChunkReader {
+    fn drop(&mut self) {
+        self.recycle();
     }
 }
 
@@ -185,6 +1252,7 @@ impl Pipeline {
 mod tests {
     use super::*;
     use std::fs::{create_dir_all, write};
+    use std::sync::atomic::AtomicU64;
     use std::sync::{Arc, Mutex};
     use tempfile::tempdir;
 
@@ -212,6 +1280,81 @@ mod tests {
         assert_eq!(*seen.lock().unwrap(), 2);
     }
 
+    #[test]
+    fn pipeline_runs_with_custom_progress_refresh() {
+        let dir = tempdir().unwrap();
+        let root = dir.path().join("root");
+        create_dir_all(&root).unwrap();
+        for i in 0..5 {
+            write(root.join(format!("f{}.txt", i)), b"data").unwrap();
+        }
+
+        for plain in [false, true] {
+            let processed = Pipeline::new(MemoryMode::Balanced)
+                .with_progress_refresh(Some(Duration::from_millis(10)))
+                .with_progress_plain(plain)
+                .run(&root, &[], None, false, true, |_path, _pool| Ok(()))
+                .unwrap();
+            assert_eq!(processed, 5);
+        }
+    }
+
+    #[test]
+    fn pipeline_runs_with_custom_progress_template() {
+        let dir = tempdir().unwrap();
+        let root = dir.path().join("root");
+        create_dir_all(&root).unwrap();
+        write(root.join("a.txt"), b"a").unwrap();
+        write(root.join("b.txt"), b"b").unwrap();
+
+        let template = "{pos} files hashed";
+        validate_progress_template(template).unwrap();
+        assert!(validate_progress_template("{pos:>abc}").is_err());
+        let processed = Pipeline::new(MemoryMode::Balanced)
+            .with_progress_template(Some(template.to_string()))
+            .run(&root, &[], None, false, true, |_path, _pool| Ok(()))
+            .unwrap();
+        assert_eq!(processed, 2);
+    }
+
+    #[test]
+    fn throughput_ema_smooths_a_known_series() {
+        let mut ema = ThroughputEma::new(0.5);
+        assert_eq!(ema.eta(100), None);
+        let second = Duration::from_secs(1);
+        let rates: Vec<f64> = [100, 200, 100, 0]
+            .iter()
+            .map(|&bytes| ema.update(bytes, second))
+            .collect();
+        assert_eq!(rates, [100.0, 150.0, 125.0, 62.5]);
+        assert_eq!(
+            ema.update(50, Duration::ZERO),
+            62.5,
+            "empty intervals are ignored"
+        );
+        assert_eq!(ema.eta(125), Some(Duration::from_secs(2)));
+    }
+
+    #[test]
+    fn byte_progress_pipeline_completes() {
+        let dir = tempdir().unwrap();
+        let root = dir.path().join("root");
+        create_dir_all(&root).unwrap();
+        for i in 0..5 {
+            write(root.join(format!("{}.bin", i)), vec![0u8; 4096 * (i + 1)]).unwrap();
+        }
+
+        let processed = Pipeline::new(MemoryMode::Balanced)
+            .with_eta_smoothing(Some(0.5))
+            .with_progress_refresh(Some(Duration::from_millis(10)))
+            .run(&root, &[], None, false, true, |_path, _pool| {
+                std::thread::sleep(Duration::from_millis(15));
+                Ok(())
+            })
+            .unwrap();
+        assert_eq!(processed, 5);
+    }
+
     #[test]
     fn pipeline_handles_empty_directory() {
         let dir = tempdir().unwrap();
@@ -267,6 +1410,341 @@ mod tests {
         assert_eq!(processed, 2); // Only include.txt and also_include.md
     }
 
+    #[test]
+    fn pipeline_excludes_filter_before_worker() {
+        let dir = tempdir().unwrap();
+        let root = dir.path().join("exclude_once");
+        create_dir_all(root.join("skip")).unwrap();
+        write(root.join("keep.txt"), b"yes").unwrap();
+        write(root.join("skip").join("a.txt"), b"no").unwrap();
+        write(root.join("drop.log"), b"no").unwrap();
+
+        let pipeline = Pipeline::new(MemoryMode::Balanced);
+        let excludes = vec!["skip/**".to_string(), "*.log".to_string()];
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let seen_clone = seen.clone();
+
+        let processed = pipeline
+            .run(&root, &excludes, None, false, false, move |path, _pool| {
+                seen_clone.lock().unwrap().push(path);
+                Ok(())
+            })
+            .unwrap();
+
+        // the worker is only handed files that survived the walker's single filter pass
+        let seen = seen.lock().unwrap();
+        assert_eq!(processed, 1);
+        assert_eq!(seen.len(), 1);
+        assert!(seen[0].ends_with("keep.txt"));
+    }
+
+    #[test]
+    fn large_files_are_hashed_by_the_large_file_group() {
+        let dir = tempdir().unwrap();
+        let root = dir.path().join("size_classes");
+        create_dir_all(&root).unwrap();
+        for i in 0..20 {
+            write(root.join(format!("small{}.txt", i)), b"tiny").unwrap();
+        }
+        for i in 0..2 {
+            write(root.join(format!("large{}.bin", i)), vec![7u8; 300_000]).unwrap();
+        }
+
+        let plan = recommend_config(MemoryMode::Balanced, Some(4), None).unwrap();
+        let (processed, seen) = Pipeline::new(MemoryMode::Balanced)
+            .with_threads(Some(4))
+            .with_large_file_threshold(Some(100_000))
+            .run_collect(&root, &[], None, false, false, |path, pool| {
+                let name = path.file_name().unwrap().to_string_lossy().into_owned();
+                let in_hash_pool = rayon::current_thread_index().is_some();
+                Ok(Some((
+                    name,
+                    pool.buf_size(),
+                    pool.parallel_hashing(),
+                    in_hash_pool,
+                )))
+            })
+            .unwrap();
+
+        assert_eq!(processed, 22);
+        assert_eq!(seen.len(), 22);
+        for (name, buf_size, parallel, in_hash_pool) in &seen {
+            let large = name.starts_with("large");
+            let expected = if large {
+                plan.buffer_size * LARGE_BUFFER_FACTOR
+            } else {
+                plan.buffer_size
+            };
+            assert_eq!(*buf_size, expected, "{} read with the wrong buffers", name);
+            assert_eq!((*parallel, *in_hash_pool), (large, large), "{}", name);
+        }
+    }
+
+    #[test]
+    fn size_class_groups_stay_within_the_plan() {
+        let plan = MemoryPlan {
+            mode: MemoryMode::Balanced,
+            threads: 1,
+            buffer_size: 1024,
+            num_buffers: 2,
+            prefetch_listing: true,
+        };
+        assert_eq!(size_class_groups(&plan), None, "one thread can't be split");
+
+        let plan = MemoryPlan {
+            threads: 2,
+            num_buffers: LARGE_BUFFER_FACTOR,
+            ..plan
+        };
+        assert_eq!(
+            size_class_groups(&plan),
+            None,
+            "no buffer left for small files"
+        );
+
+        let plan = MemoryPlan {
+            threads: 2,
+            num_buffers: LARGE_BUFFER_FACTOR + 1,
+            ..plan
+        };
+        let (small, large) = size_class_groups(&plan).unwrap();
+        assert_eq!((small.threads, large.threads), (1, 1));
+        assert_eq!(small.num_buffers, 1);
+        assert_eq!(large.buffer_size, 1024 * LARGE_BUFFER_FACTOR);
+
+        let plan = MemoryPlan {
+            threads: 8,
+            num_buffers: 32,
+            ..plan
+        };
+        let (small, large) = size_class_groups(&plan).unwrap();
+        assert_eq!((small.threads, large.threads), (6, 2));
+        assert_eq!(small.num_buffers, 32 - 2 * LARGE_BUFFER_FACTOR);
+        assert!(large.parallel && !small.parallel);
+        assert!(
+            small.num_buffers * small.buffer_size + large.num_buffers * large.buffer_size
+                <= plan.num_buffers * plan.buffer_size
+        );
+    }
+
+    #[test]
+    fn pipeline_stop_signal_halts_dispatch() {
+        let dir = tempdir().unwrap();
+        let root = dir.path().join("stop");
+        create_dir_all(&root).unwrap();
+        for i in 0..20 {
+            write(root.join(format!("f{}.txt", i)), b"x").unwrap();
+        }
+
+        let stop = Arc::new(AtomicBool::new(false));
+        let pipeline = Pipeline::new(MemoryMode::Balanced)
+            .with_threads(Some(1))
+            .with_stop_signal(stop.clone());
+        let processed = pipeline
+            .run(&root, &[], None, false, false, move |_path, _pool| {
+                stop.store(true, Ordering::Relaxed);
+                Ok(())
+            })
+            .unwrap();
+
+        assert_eq!(processed, 1);
+    }
+
+    #[test]
+    fn constrained_booster_stays_within_buffer_budget() {
+        let dir = tempdir().unwrap();
+        let root = dir.path().join("root");
+        create_dir_all(&root).unwrap();
+        for i in 0..64 {
+            write(root.join(format!("f{}.txt", i)), b"data").unwrap();
+        }
+
+        // 4 MiB of 1 MiB Booster buffers cannot feed 64 threads
+        let max_ram = 4 * 1024 * 1024;
+        let plan = recommend_config(MemoryMode::Booster, Some(64), Some(max_ram)).unwrap();
+        assert_eq!(plan.num_buffers, 4);
+        assert!(plan.threads <= plan.num_buffers);
+
+        let peak = Arc::new(Mutex::new((0usize, 0usize)));
+        let threads = Arc::new(Mutex::new(std::collections::HashSet::new()));
+        let (peak_w, threads_w) = (peak.clone(), threads.clone());
+        let processed = Pipeline::new(MemoryMode::Booster)
+            .with_threads(Some(64))
+            .with_max_ram(Some(max_ram))
+            .run(&root, &[], None, false, false, move |_path, pool| {
+                let _buf = pool.get();
+                threads_w
+                    .lock()
+                    .unwrap()
+                    .insert(std::thread::current().id());
+                std::thread::sleep(Duration::from_millis(2));
+                let mut p = peak_w.lock().unwrap();
+                p.0 = p.0.max(pool.allocated_buffers());
+                p.1 = pool.max_buffers();
+                Ok(())
+            })
+            .unwrap();
+        assert_eq!(processed, 64);
+        let (peak_allocated, max_buffers) = *peak.lock().unwrap();
+        assert!(
+            peak_allocated <= max_buffers,
+            "{} > {}",
+            peak_allocated,
+            max_buffers
+        );
+        assert!(threads.lock().unwrap().len() <= plan.num_buffers);
+
+        // the split pipeline trims explicit thread counts to the same budget
+        assert_eq!(fit_split_threads(8, 8, 12), (2, 6));
+        assert_eq!(fit_split_threads(8, 8, 0), (1, 1));
+        assert!(split_buffers_needed(2, 6) <= 12);
+    }
+
+    #[test]
+    fn error_budget_stops_the_run_once_crossed() {
+        let dir = tempdir().unwrap();
+        let root = dir.path().join("root");
+        create_dir_all(&root).unwrap();
+        for i in 0..4 {
+            write(root.join(format!("ok-{}.txt", i)), b"ok").unwrap();
+        }
+        for i in 0..6 {
+            write(root.join(format!("bad-{}.txt", i)), b"x").unwrap();
+        }
+
+        let run = |budget: Arc<crate::utils::ErrorBudget>| {
+            let stop = Arc::new(AtomicBool::new(false));
+            let (stop_w, budget_w) = (stop.clone(), budget.clone());
+            Pipeline::new(MemoryMode::Balanced)
+                .with_threads(Some(1))
+                .with_stop_signal(stop)
+                .with_on_listed(move |total| budget.set_total(total))
+                .run(&root, &[], None, false, false, move |path, _pool| {
+                    let name = path.file_name().unwrap().to_string_lossy().into_owned();
+                    // a failing file is tallied and skipped, as hashmap does
+                    if name.starts_with("bad") && budget_w.fail() {
+                        stop_w.store(true, Ordering::Relaxed);
+                    }
+                    Ok(())
+                })
+                .unwrap()
+        };
+
+        let budget = Arc::new(crate::utils::ErrorBudget::new(Some(2), None));
+        let processed = run(budget.clone());
+        // the third failure stops the run, before the last of the six bad files
+        assert!(processed <= 8, "processed {}", processed);
+        let reason = budget.check(processed as u64).unwrap();
+        assert_eq!(reason, "3 files failed, more than --max-errors 2");
+
+        let budget = Arc::new(crate::utils::ErrorBudget::new(None, Some(50.0)));
+        let processed = run(budget.clone());
+        let reason = budget.check(processed as u64).unwrap();
+        assert_eq!(
+            reason,
+            "6 of 10 files failed, more than --max-error-rate 50%"
+        );
+
+        let budget = Arc::new(crate::utils::ErrorBudget::new(Some(6), Some(60.0)));
+        assert_eq!(run(budget.clone()), 10);
+        assert!(budget.check(10).is_none(), "6 of 10 is within both limits");
+    }
+
+    #[test]
+    fn fail_fast_returns_first_worker_error() {
+        let dir = tempdir().unwrap();
+        let root = dir.path().join("root");
+        create_dir_all(&root).unwrap();
+        for i in 0..20 {
+            write(root.join(format!("f{:02}.txt", i)), b"ok").unwrap();
+        }
+        write(root.join("bad-1.txt"), b"x").unwrap();
+        write(root.join("bad-2.txt"), b"x").unwrap();
+
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let seen_w = seen.clone();
+        let result = Pipeline::new(MemoryMode::Balanced)
+            .with_threads(Some(1))
+            .with_fail_fast(true)
+            .run(&root, &[], None, false, false, move |path, _pool| {
+                let name = path.file_name().unwrap().to_string_lossy().into_owned();
+                seen_w.lock().unwrap().push(name.clone());
+                if name.starts_with("bad") {
+                    anyhow::bail!("cannot hash {}", name);
+                }
+                Ok(())
+            });
+
+        let err = result.unwrap_err();
+        assert!(err.to_string().starts_with("cannot hash bad-"));
+        let seen = seen.lock().unwrap();
+        // the run stopped at the first bad file, so the second was never reached
+        assert!(seen.last().unwrap().starts_with("bad"));
+        assert_eq!(seen.iter().filter(|n| n.starts_with("bad")).count(), 1);
+        assert!(seen.len() < 22);
+
+        let split = Pipeline::new(MemoryMode::Balanced)
+            .with_io_threads(Some(2))
+            .with_hash_threads(Some(2))
+            .with_fail_fast(true)
+            .run_split(&root, &[], None, false, false, |path, _reader, _pool| {
+                if path
+                    .file_name()
+                    .unwrap()
+                    .to_string_lossy()
+                    .starts_with("bad")
+                {
+                    anyhow::bail!("cannot hash {}", path.display());
+                }
+                Ok(())
+            });
+        assert!(split.is_err());
+    }
+
+    #[test]
+    fn pipeline_split_threads_hash_correctly() {
+        let dir = tempdir().unwrap();
+        let root = dir.path().join("split");
+        create_dir_all(root.join("sub")).unwrap();
+        // Sizes straddle the 64 KiB stream-mode buffer so files span several chunks.
+        let sizes = [0usize, 1, 65_536, 65_537, 300_001];
+        let mut expected = std::collections::HashMap::new();
+        for (i, size) in sizes.iter().enumerate() {
+            let data: Vec<u8> = (0..*size).map(|b| (b * 7 + i) as u8).collect();
+            let name = format!("sub/f{}.bin", i);
+            write(root.join(&name), &data).unwrap();
+            expected.insert(root.join(&name), blake3::hash(&data).to_hex().to_string());
+        }
+
+        for (io_threads, hash_threads) in [(4, 1), (1, 3)] {
+            let pipeline = Pipeline::new(MemoryMode::Stream)
+                .with_io_threads(Some(io_threads))
+                .with_hash_threads(Some(hash_threads));
+            assert!(pipeline.is_split());
+            let seen = Arc::new(Mutex::new(std::collections::HashMap::new()));
+            let seen_clone = seen.clone();
+            let processed = pipeline
+                .run_split(
+                    &root,
+                    &[],
+                    None,
+                    false,
+                    false,
+                    move |path, reader, _pool| {
+                        let mut hasher = blake3::Hasher::new();
+                        std::io::copy(reader, &mut hasher)?;
+                        let digest = hasher.finalize().to_hex().to_string();
+                        seen_clone.lock().unwrap().insert(path, digest);
+                        Ok(())
+                    },
+                )
+                .unwrap();
+
+            assert_eq!(processed, sizes.len());
+            assert_eq!(*seen.lock().unwrap(), expected);
+        }
+    }
+
     #[test]
     fn pipeline_nested_directories() {
         let dir = tempdir().unwrap();
@@ -308,7 +1786,7 @@ mod tests {
         write(root.join("file.txt"), b"data").unwrap();
 
         let pipeline = Pipeline::new(MemoryMode::Balanced);
-        let result = pipeline.run(&root, &[], None, false, true, |_path, _pool| {
+        let _result = pipeline.run(&root, &[], None, false, true, |_path, _pool| {
             Err(anyhow::anyhow!("Simulated error"))
         });
 
@@ -364,6 +1842,132 @@ mod tests {
         assert_eq!(*counter.lock().unwrap(), 100);
     }
 
+    #[test]
+    fn collected_results_match_shared_vec() {
+        let dir = tempdir().unwrap();
+        let root = dir.path().join("collect");
+        create_dir_all(root.join("sub")).unwrap();
+        for i in 0..300 {
+            let parent = if i % 3 == 0 {
+                root.join("sub")
+            } else {
+                root.clone()
+            };
+            write(parent.join(format!("f{}.txt", i)), i.to_string()).unwrap();
+        }
+        write(root.join("skip.log"), b"log").unwrap();
+
+        // the pre-sharding approach: every worker pushes into one locked Vec
+        let shared: Arc<Mutex<Vec<PathBuf>>> = Arc::new(Mutex::new(Vec::new()));
+        let shared_worker = shared.clone();
+        Pipeline::new(MemoryMode::Balanced)
+            .run(&root, &[], None, false, false, move |path, _pool| {
+                shared_worker.lock().unwrap().push(path);
+                Ok(())
+            })
+            .unwrap();
+        let mut expected = shared.lock().unwrap().clone();
+        expected.retain(|p| p.extension().is_some_and(|e| e == "txt"));
+        expected.sort();
+
+        let keep_txt = |path: PathBuf| path.extension().is_some_and(|e| e == "txt").then_some(path);
+        let (processed, mut collected) = Pipeline::new(MemoryMode::Balanced)
+            .with_threads(Some(4))
+            .run_collect(&root, &[], None, false, false, move |path, _pool| {
+                Ok(keep_txt(path))
+            })
+            .unwrap();
+        collected.sort();
+        assert_eq!(processed, 301);
+        assert_eq!(collected, expected);
+
+        let (processed, mut split) = Pipeline::new(MemoryMode::Balanced)
+            .with_io_threads(Some(2))
+            .with_hash_threads(Some(3))
+            .run_split_collect(
+                &root,
+                &[],
+                None,
+                false,
+                false,
+                move |path, _reader, _pool| Ok(keep_txt(path)),
+            )
+            .unwrap();
+        split.sort();
+        assert_eq!(processed, 301);
+        assert_eq!(split, expected);
+    }
+
+    #[test]
+    fn on_listed_presizes_results_before_workers_run() {
+        let dir = tempdir().unwrap();
+        let root = dir.path().join("presized");
+        create_dir_all(&root).unwrap();
+        for i in 0..500 {
+            write(root.join(format!("file_{}.txt", i)), b"x").unwrap();
+        }
+
+        let results: Arc<Mutex<Vec<PathBuf>>> = Arc::new(Mutex::new(Vec::new()));
+        let reserved = Arc::new(AtomicU64::new(0));
+        let pipeline = Pipeline::new(MemoryMode::Balanced).with_on_listed({
+            let results = results.clone();
+            let reserved = reserved.clone();
+            move |files| {
+                let mut results = results.lock().unwrap();
+                results.reserve(files as usize);
+                reserved.store(results.capacity() as u64, Ordering::SeqCst);
+            }
+        });
+        let results_worker = results.clone();
+        let processed = pipeline
+            .run(&root, &[], None, false, false, move |path, _pool| {
+                results_worker.lock().unwrap().push(path);
+                Ok(())
+            })
+            .unwrap();
+
+        assert_eq!(processed, 500);
+        let results = results.lock().unwrap();
+        assert_eq!(results.len(), 500);
+        // every push fit in the reserved allocation
+        assert!(reserved.load(Ordering::SeqCst) >= 500);
+        assert_eq!(results.capacity() as u64, reserved.load(Ordering::SeqCst));
+    }
+
+    #[test]
+    fn on_plan_reports_the_plan_the_run_used() {
+        let dir = tempdir().unwrap();
+        let root = dir.path().join("planned");
+        create_dir_all(&root).unwrap();
+        write(root.join("file.txt"), b"x").unwrap();
+
+        let seen: Arc<Mutex<Vec<MemoryPlan>>> = Arc::new(Mutex::new(Vec::new()));
+        let record = {
+            let seen = seen.clone();
+            move |plan: &MemoryPlan| seen.lock().unwrap().push(*plan)
+        };
+        Pipeline::new(MemoryMode::Balanced)
+            .with_threads(Some(3))
+            .with_on_plan(record.clone())
+            .run(&root, &[], None, false, false, |_path, _pool| Ok(()))
+            .unwrap();
+        Pipeline::new(MemoryMode::Balanced)
+            .with_threads(Some(3))
+            .with_io_threads(Some(1))
+            .with_hash_threads(Some(2))
+            .with_on_plan(record)
+            .run_split_collect(&root, &[], None, false, false, |_path, _reader, _pool| {
+                Ok(Some(()))
+            })
+            .unwrap();
+
+        let seen = seen.lock().unwrap();
+        assert_eq!(seen.len(), 2);
+        assert_eq!(seen[0].threads, 3);
+        assert_eq!(seen[1].threads, 1 + 2);
+        assert!(seen[1].num_buffers >= split_buffers_needed(1, 2));
+    }
+
     #[test]
     fn pipeline_with_thread_override() {
         let dir = tempdir().unwrap();
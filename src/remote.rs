@@ -0,0 +1,152 @@
+use std::io::{Read, Write};
+use std::path::Path;
+
+use anyhow::{Context, Result};
+
+use crate::algorithms::{Algorithm, AlgorithmParams};
+use crate::io::{self, MapEntry};
+
+/// Result of [`hash_url`].
+#[derive(Debug, Clone)]
+pub struct UrlDigest {
+    pub digest: String,
+    /// Bytes of response body hashed.
+    pub bytes: u64,
+    /// `Content-Length` announced by the server, when it sent one.
+    pub content_length: Option<u64>,
+    /// URL the body came from after following redirects.
+    pub final_url: String,
+}
+
+/// Counts the bytes passing through so the body size is known without buffering it.
+struct CountingReader<R> {
+    inner: R,
+    count: u64,
+}
+
+impl<R: Read> Read for CountingReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        self.count += n as u64;
+        Ok(n)
+    }
+}
+
+/// GET `url` (following redirects) and stream the response body through `algorithm`
+/// without writing it to disk. Fails on HTTP error statuses and on bodies shorter or
+/// longer than the announced `Content-Length`.
+pub fn hash_url(
+    url: &str,
+    algorithm: Algorithm,
+    params: &AlgorithmParams,
+    out_len: usize,
+) -> Result<UrlDigest> {
+    let response = ureq::get(url)
+        .call()
+        .with_context(|| format!("fetch {}", url))?;
+    let final_url = response.get_url().to_string();
+    let content_length = response
+        .header("Content-Length")
+        .and_then(|len| len.trim().parse::<u64>().ok());
+
+    let mut reader = CountingReader {
+        inner: response.into_reader(),
+        count: 0,
+    };
+    let mut hasher = algorithm.create_with(params);
+    hasher
+        .update_reader(&mut reader)
+        .with_context(|| format!("read response body of {}", final_url))?;
+    if let Some(expected) = content_length.filter(|&len| len != reader.count) {
+        anyhow::bail!(
+            "{} announced {} bytes but sent {}; the download was cut short",
+            final_url,
+            expected,
+            reader.count
+        );
+    }
+    Ok(UrlDigest {
+        digest: hasher.finalize_hex(out_len),
+        bytes: reader.count,
+        content_length,
+        final_url,
+    })
+}
+
+/// Map format of a download: the URL path's extension, else the `Content-Type`, else JSON.
+fn remote_map_format(url_path: &str, content_type: &str) -> &'static str {
+    if let Some(format) = io::format_for_path(Path::new(url_path)) {
+        return format;
+    }
+    let content_type = content_type.to_ascii_lowercase();
+    if content_type.contains("ndjson") || content_type.contains("jsonl") {
+        "ndjson"
+    } else if content_type.contains("csv") {
+        "csv"
+    } else if content_type.starts_with("text/plain") {
+        "sums"
+    } else {
+        "json"
+    }
+}
+
+/// GET the map at `url` and stream the body into a temporary file whose extension
+/// names the map format, so it can be inspected and loaded like a local map. The file
+/// is removed when the returned handle is dropped.
+pub fn stage_map(url: &str) -> Result<tempfile::NamedTempFile> {
+    let response = ureq::get(url)
+        .call()
+        .with_context(|| format!("fetch {}", url))?;
+    let final_url = response.get_url().to_string();
+    let url_path = final_url
+        .split(['?', '#'])
+        .next()
+        .unwrap_or_default()
+        .to_string();
+    let format = remote_map_format(&url_path, response.content_type());
+    let mut staged = tempfile::Builder::new()
+        .prefix("hash-folderoo-")
+        .suffix(&format!(".{}", format))
+        .tempfile()
+        .context("create a temporary file for the remote map")?;
+    std::io::copy(&mut response.into_reader(), staged.as_file_mut())
+        .with_context(|| format!("download {} to {:?}", final_url, staged.path()))?;
+    staged
+        .as_file_mut()
+        .flush()
+        .with_context(|| format!("write {:?}", staged.path()))?;
+    Ok(staged)
+}
+
+/// GET the map at `url` and load it like a local map file (see [`stage_map`]).
+pub fn fetch_map(url: &str) -> Result<Vec<MapEntry>> {
+    let staged = stage_map(url)?;
+    let format = io::format_for_path(staged.path()).unwrap_or("json");
+    let loaded = match format {
+        "csv" => io::load_map_from_csv(staged.path()),
+        "ndjson" => io::load_map_from_ndjson(staged.path()),
+        "sums" => io::load_map_from_sums(staged.path()),
+        _ => io::load_map_from_json(staged.path()),
+    };
+    loaded.with_context(|| format!("load {} map from {}", format, url))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn remote_format_prefers_extension_then_content_type() {
+        assert_eq!(remote_map_format("/maps/a.csv", "application/json"), "csv");
+        assert_eq!(
+            remote_map_format("/maps/latest", "text/csv; charset=utf-8"),
+            "csv"
+        );
+        assert_eq!(
+            remote_map_format("/maps/latest", "application/x-ndjson"),
+            "ndjson"
+        );
+        assert_eq!(remote_map_format("/maps/latest", "text/plain"), "sums");
+        assert_eq!(remote_map_format("/maps/latest", ""), "json");
+    }
+}
@@ -1,13 +1,62 @@
-use anyhow::Result;
+use anyhow::{Context, Result};
 use globset::{Glob, GlobSet};
 use log::warn;
 use std::fs;
 use std::io::Write;
-use std::path::Path;
+use std::path::{Path, PathBuf};
+
+use crate::walk;
+
+/// Outcome of [`remove_empty_directories_with_options`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RemoveEmptySummary {
+    /// Directories removed (or, in a dry run, that would be removed).
+    pub removed: usize,
+    /// Directories that could not be read or removed; always 0 with `strict`, which
+    /// returns the first such error instead.
+    pub failed: usize,
+}
+
+/// Settings for [`remove_empty_directories_with_options`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RemoveEmptyOptions {
+    pub dry_run: bool,
+    /// Minimum depth at which directories may be removed.
+    pub min_depth: Option<usize>,
+    /// Glob patterns (relative to the root) of directories to skip removal.
+    pub excludes: Vec<String>,
+    pub git_diff: bool,
+    pub git_diff_body: bool,
+    pub git_diff_context: usize,
+    pub git_diff_output: Option<PathBuf>,
+    /// Leave directories on another device than the root alone.
+    pub one_file_system: bool,
+    /// Abort on the first directory that can't be read or removed.
+    pub strict: bool,
+}
+
+impl Default for RemoveEmptyOptions {
+    fn default() -> Self {
+        Self {
+            dry_run: false,
+            min_depth: None,
+            excludes: Vec::new(),
+            git_diff: false,
+            git_diff_body: false,
+            git_diff_context: 3,
+            git_diff_output: None,
+            one_file_system: false,
+            strict: false,
+        }
+    }
+}
 
 /// Remove empty directories in `path` using post-order traversal.
 /// `min_depth` controls the minimum depth at which directories may be removed.
 /// `excludes` is a list of glob patterns (relative to `path`) to skip removal.
+/// With `one_file_system`, directories on another device than `path` are left alone.
+/// The first directory that can't be read or removed aborts the run; use
+/// [`remove_empty_directories_with_options`] to count such failures instead.
 #[allow(clippy::too_many_arguments)]
 pub fn remove_empty_directories(
     path: &Path,
@@ -18,14 +67,59 @@ pub fn remove_empty_directories(
     git_diff_body: bool,
     git_diff_context: usize,
     git_diff_output: Option<&Path>,
+    one_file_system: bool,
 ) -> Result<()> {
+    let opts = RemoveEmptyOptions {
+        dry_run,
+        min_depth,
+        excludes: excludes.to_vec(),
+        git_diff,
+        git_diff_body,
+        git_diff_context,
+        git_diff_output: git_diff_output.map(Path::to_path_buf),
+        one_file_system,
+        strict: true,
+    };
+    remove_empty_directories_with_options(path, &opts).map(|_| ())
+}
+
+/// Like [`remove_empty_directories`], with its settings in `opts`. Directories that
+/// can't be read or removed are logged and counted in the summary, and the rest of the
+/// tree is still processed; `opts.strict` aborts on the first such error.
+pub fn remove_empty_directories_with_options(
+    path: &Path,
+    opts: &RemoveEmptyOptions,
+) -> Result<RemoveEmptySummary> {
+    remove_empty_directories_with(path, opts, &|p| fs::remove_dir(p))
+}
+
+/// [`remove_empty_directories_with_options`] with the directory removal injected, so
+/// tests can simulate directories that refuse to go away.
+fn remove_empty_directories_with(
+    path: &Path,
+    opts: &RemoveEmptyOptions,
+    remove_dir: &dyn Fn(&Path) -> std::io::Result<()>,
+) -> Result<RemoveEmptySummary> {
+    let RemoveEmptyOptions {
+        dry_run,
+        min_depth,
+        ref excludes,
+        git_diff,
+        git_diff_body,
+        git_diff_context,
+        ref git_diff_output,
+        one_file_system,
+        strict,
+    } = *opts;
+    let git_diff_output = git_diff_output.as_deref();
+    let mut summary = RemoveEmptySummary::default();
     if !path.exists() {
         warn!("Path {} does not exist, nothing to do", path.display());
-        return Ok(());
+        return Ok(summary);
     }
     if path.is_file() {
         warn!("Path {} is a file, nothing to do", path.display());
-        return Ok(());
+        return Ok(summary);
     }
 
     let globset = if excludes.is_empty() {
@@ -40,6 +134,14 @@ pub fn remove_empty_directories(
 
     let root = path.to_path_buf();
     let min_allowed = min_depth.unwrap_or(0);
+    let root_dev = if !one_file_system {
+        None
+    } else if cfg!(unix) {
+        walk::device_id(path)
+    } else {
+        warn!("--one-file-system is only supported on Unix; ignoring");
+        None
+    };
 
     #[allow(clippy::too_many_arguments, clippy::only_used_in_recursion)]
     fn helper(
@@ -53,12 +155,29 @@ pub fn remove_empty_directories(
         depth: usize,
         min_allowed: usize,
         excludes: &Option<GlobSet>,
+        root_dev: Option<u64>,
+        strict: bool,
+        remove_dir: &dyn Fn(&Path) -> std::io::Result<()>,
+        summary: &mut RemoveEmptySummary,
     ) -> Result<bool> {
         let mut is_empty = true;
-        for entry in fs::read_dir(p)? {
-            let e = entry?;
+        let listing = match fs::read_dir(p).and_then(|rd| rd.collect::<std::io::Result<Vec<_>>>()) {
+            Ok(listing) => listing,
+            Err(e) if !strict => {
+                warn!("Failed reading {}: {}", p.display(), e);
+                summary.failed += 1;
+                return Ok(false);
+            }
+            Err(e) => return Err(e).with_context(|| format!("read directory {}", p.display())),
+        };
+        for e in listing {
             let pth = e.path();
             if pth.is_dir() {
+                // A mount point counts as content: never recurse into or remove it
+                if walk::crosses_device(root_dev, walk::device_id(&pth)) {
+                    is_empty = false;
+                    continue;
+                }
                 let child_empty = helper(
                     &pth,
                     dry_run,
@@ -70,6 +189,10 @@ pub fn remove_empty_directories(
                     depth + 1,
                     min_allowed,
                     excludes,
+                    root_dev,
+                    strict,
+                    remove_dir,
+                    summary,
                 )?;
                 if !child_empty {
                     is_empty = false;
@@ -109,6 +232,7 @@ pub fn remove_empty_directories(
                 } else {
                     println!("Would remove empty directory: {}", p.display());
                 }
+                summary.removed += 1;
             } else {
                 if git_diff {
                     let diff = crate::diff::format_remove_dir_diff(p);
@@ -132,7 +256,15 @@ pub fn remove_empty_directories(
                 } else {
                     println!("Removing empty directory: {}", p.display());
                 }
-                fs::remove_dir(p)?;
+                match remove_dir(p) {
+                    Ok(()) => summary.removed += 1,
+                    Err(e) if !strict => {
+                        warn!("Failed removing {}: {}", p.display(), e);
+                        summary.failed += 1;
+                        return Ok(false);
+                    }
+                    Err(e) => return Err(e).with_context(|| format!("remove {}", p.display())),
+                }
             }
             return Ok(true);
         }
@@ -155,8 +287,12 @@ pub fn remove_empty_directories(
         0,
         min_allowed,
         &globset,
+        root_dev,
+        strict,
+        remove_dir,
+        &mut summary,
     )?;
-    Ok(())
+    Ok(summary)
 }
 
 #[cfg(test)]
@@ -182,6 +318,7 @@ mod tests {
             false,
             3,
             None,
+            false,
         )
         .unwrap();
         assert!(root.join("a").exists());
@@ -189,4 +326,44 @@ mod tests {
         assert!(root.join("keep").exists());
         assert!(root.join("top_empty").exists());
     }
+
+    #[test]
+    fn removal_failures_are_counted_and_traversal_continues() {
+        let dir = tempdir().unwrap();
+        let root = dir.path().join("root");
+        for sub in ["a", "locked", "z"] {
+            create_dir_all(root.join(sub).join("inner")).unwrap();
+        }
+        let locked = root.join("locked");
+        let refuse = |p: &Path| {
+            if p == locked {
+                Err(std::io::Error::from(std::io::ErrorKind::PermissionDenied))
+            } else {
+                fs::remove_dir(p)
+            }
+        };
+
+        let opts = RemoveEmptyOptions {
+            min_depth: Some(1),
+            ..Default::default()
+        };
+        let summary = remove_empty_directories_with(&root, &opts, &refuse).unwrap();
+        assert_eq!(summary.failed, 1);
+        assert_eq!(summary.removed, 5);
+        assert!(locked.exists());
+        assert!(!locked.join("inner").exists());
+        assert!(!root.join("a").exists());
+        assert!(!root.join("z").exists());
+
+        create_dir_all(root.join("z")).unwrap();
+        let strict = remove_empty_directories_with(
+            &root,
+            &RemoveEmptyOptions {
+                strict: true,
+                ..opts
+            },
+            &refuse,
+        );
+        assert!(strict.is_err());
+    }
 }
@@ -1,9 +1,42 @@
-use anyhow::Result;
+use anyhow::{Context, Result};
 use log::{info, warn};
+use std::collections::HashSet;
 use std::io::Write;
 use std::path::{Path, PathBuf};
 use walkdir::WalkDir;
 
+use crate::algorithms::Algorithm;
+
+/// Totals for one renamer run, printed as its final summary line.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RenameSummary {
+    /// Every rename the pattern or map produced.
+    pub planned: usize,
+    /// Planned renames whose target already existed.
+    pub skipped_existing: usize,
+    /// Planned renames whose target another planned rename also claims (all but the first).
+    pub collisions: usize,
+    /// Renames actually performed (0 for a dry run).
+    pub renamed: usize,
+    /// Sources dropped (or, in a dry run, to be dropped) by `--dedupe-on-collision`
+    /// because the existing target has the same content.
+    pub deduped: usize,
+}
+
+impl std::fmt::Display for RenameSummary {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} renames planned, {} skipped (target exists), {} collisions",
+            self.planned, self.skipped_existing, self.collisions
+        )?;
+        if self.deduped > 0 {
+            write!(f, ", {} identical to their target", self.deduped)?;
+        }
+        Ok(())
+    }
+}
+
 /// Rename files under `path` according to a simple pattern.
 /// Pattern format: "old->new" (replace occurrences of `old` in filenames with `new`).
 /// If pattern does not contain "->", treat it as `old` and replace with empty string.
@@ -30,6 +63,8 @@ pub fn rename_files(path: &Path, pattern: &str, dry_run: bool) -> Result<()> {
 /// - `map` file (CSV or JSON) containing mapping pairs {src,dst} or two-column CSV
 /// - `regex` flag: treat pattern as a regex and apply `replace` substitution on filenames
 /// - `dry_run` and `git_diff` output options
+///
+/// See [`rename_files_with`] for the run's summary and the remaining options.
 #[allow(clippy::too_many_arguments)]
 pub fn rename_files_with_options(
     path: &Path,
@@ -43,13 +78,81 @@ pub fn rename_files_with_options(
     git_diff_context: usize,
     git_diff_output: Option<&Path>,
 ) -> Result<()> {
+    let opts = RenameOptions {
+        pattern: pattern.map(str::to_string),
+        replace: replace.map(str::to_string),
+        map: map.map(Path::to_path_buf),
+        regex,
+        dry_run,
+        git_diff,
+        git_diff_body,
+        git_diff_context,
+        git_diff_output: git_diff_output.map(Path::to_path_buf),
+        dedupe_on_collision: None,
+    };
+    rename_files_with(path, &opts).map(|_| ())
+}
+
+/// Settings for [`rename_files_with`].
+#[derive(Debug, Clone)]
+pub struct RenameOptions {
+    /// `old->new` replacement, or a regex when `regex` is set.
+    pub pattern: Option<String>,
+    /// Regex substitution for `pattern`.
+    pub replace: Option<String>,
+    /// CSV or JSON file of `src,dst` pairs; takes precedence over `pattern`.
+    pub map: Option<PathBuf>,
+    pub regex: bool,
+    pub dry_run: bool,
+    pub git_diff: bool,
+    pub git_diff_body: bool,
+    pub git_diff_context: usize,
+    pub git_diff_output: Option<PathBuf>,
+    /// When a target already exists, hash both files with this algorithm and delete the
+    /// source if they match instead of skipping it.
+    pub dedupe_on_collision: Option<Algorithm>,
+}
+
+impl Default for RenameOptions {
+    fn default() -> Self {
+        Self {
+            pattern: None,
+            replace: None,
+            map: None,
+            regex: false,
+            dry_run: false,
+            git_diff: false,
+            git_diff_body: false,
+            git_diff_context: 3,
+            git_diff_output: None,
+            dedupe_on_collision: None,
+        }
+    }
+}
+
+/// Rename files under `path` as [`rename_files_with_options`] does, with every setting
+/// in `opts`. Returns the run's [`RenameSummary`].
+pub fn rename_files_with(path: &Path, opts: &RenameOptions) -> Result<RenameSummary> {
+    let pattern = opts.pattern.as_deref();
+    let replace = opts.replace.as_deref();
+    let map = opts.map.as_deref();
+    let git_diff_output = opts.git_diff_output.as_deref();
+    let RenameOptions {
+        regex,
+        dry_run,
+        git_diff,
+        git_diff_body,
+        git_diff_context,
+        dedupe_on_collision,
+        ..
+    } = *opts;
     if !path.exists() {
         warn!("Path {} does not exist, nothing to do", path.display());
-        return Ok(());
+        return Ok(RenameSummary::default());
     }
     if path.is_file() {
         warn!("Path {} is a file; nothing to do", path.display());
-        return Ok(());
+        return Ok(RenameSummary::default());
     }
 
     // parse mappings
@@ -135,12 +238,46 @@ pub fn rename_files_with_options(
         }
     } else {
         warn!("No mapping / pattern provided for renamer; nothing to do");
-        return Ok(());
+        return Ok(RenameSummary::default());
     }
 
     if plan.is_empty() {
         info!("No files to rename");
-        return Ok(());
+        return Ok(RenameSummary::default());
+    }
+
+    let mut summary = RenameSummary {
+        planned: plan.len(),
+        ..Default::default()
+    };
+    let mut claimed = HashSet::new();
+    // Sources whose existing target has identical content (--dedupe-on-collision)
+    let mut identical: HashSet<&Path> = HashSet::new();
+    for (s, d) in &plan {
+        // A target that is the source itself (a case-only rename on a case-insensitive
+        // filesystem, a hardlink or a symlink to it) is never a duplicate to delete
+        if d.exists() && !is_same_file(s, d) {
+            let same = match dedupe_on_collision {
+                Some(alg) => same_content(alg, s, d).unwrap_or_else(|e| {
+                    warn!(
+                        "Could not compare {} with {}: {:#}",
+                        s.display(),
+                        d.display(),
+                        e
+                    );
+                    false
+                }),
+                None => false,
+            };
+            if same {
+                identical.insert(s);
+                summary.deduped += 1;
+            } else {
+                summary.skipped_existing += 1;
+            }
+        } else if !claimed.insert(d) {
+            summary.collisions += 1;
+        }
     }
 
     println!("Planned renames:");
@@ -169,9 +306,18 @@ pub fn rename_files_with_options(
         }
     }
 
+    for (s, d) in plan.iter().filter(|(s, _)| identical.contains(s.as_path())) {
+        println!(
+            "{} {} (identical to existing {})",
+            if dry_run { "Would remove" } else { "Removing" },
+            s.display(),
+            d.display()
+        );
+    }
+
     if dry_run {
         println!("Dry-run mode; not performing renames");
-        return Ok(());
+        return Ok(summary);
     }
 
     // Perform transactional renames: move each source to a temporary name under the
@@ -183,7 +329,10 @@ pub fn rename_files_with_options(
     let pid = std::process::id();
     // Stage 1: move sources -> tmp names
     for (s, d) in &plan {
-        if d.exists() {
+        if identical.contains(s.as_path()) {
+            continue;
+        }
+        if d.exists() && !is_same_file(s, d) {
             warn!("Target exists, skipping: {}", d.display());
             continue;
         }
@@ -254,7 +403,7 @@ pub fn rename_files_with_options(
                         }
                     }
                 }
-                return Ok(());
+                return Ok(summary);
             }
         }
     }
@@ -281,6 +430,7 @@ pub fn rename_files_with_options(
         }
         match std::fs::rename(tmp, dst) {
             Ok(_) => {
+                summary.renamed += 1;
                 info!("Committed rename {} -> {}", orig.display(), dst.display());
                 if git_diff {
                     let diff =
@@ -326,12 +476,60 @@ pub fn rename_files_with_options(
                         }
                     }
                 }
-                return Ok(());
+                return Ok(summary);
             }
         }
     }
 
-    Ok(())
+    // Duplicates go only once every rename is committed, and only while their target is
+    // still a different file with the same content
+    for (s, d) in plan.iter().filter(|(s, _)| identical.contains(s.as_path())) {
+        if is_same_file(s, d) || !d.exists() {
+            warn!(
+                "Keeping {}: {} is no longer a separate copy",
+                s.display(),
+                d.display()
+            );
+            continue;
+        }
+        if let Err(e) = std::fs::remove_file(s) {
+            warn!("Failed removing duplicate {}: {}", s.display(), e);
+        }
+    }
+
+    Ok(summary)
+}
+
+/// Whether `a` and `b` name the same file (following symlinks): same device and inode on
+/// Unix, same canonical path elsewhere. Paths that can't be resolved count as the same so
+/// nothing is deleted on doubt.
+fn is_same_file(a: &Path, b: &Path) -> bool {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::MetadataExt;
+        match (a.metadata(), b.metadata()) {
+            (Ok(ma), Ok(mb)) => ma.dev() == mb.dev() && ma.ino() == mb.ino(),
+            _ => true,
+        }
+    }
+    #[cfg(not(unix))]
+    {
+        match (a.canonicalize(), b.canonicalize()) {
+            (Ok(ca), Ok(cb)) => ca == cb,
+            _ => true,
+        }
+    }
+}
+
+/// Whether `a` and `b` hash the same under `alg`.
+fn same_content(alg: Algorithm, a: &Path, b: &Path) -> Result<bool> {
+    let digest = |p: &Path| -> Result<String> {
+        let mut hasher = alg.create();
+        let mut file = std::fs::File::open(p).with_context(|| format!("open {}", p.display()))?;
+        hasher.update_reader(&mut file)?;
+        Ok(hasher.finalize_hex(hasher.info().output_len_default))
+    };
+    Ok(a.metadata()?.len() == b.metadata()?.len() && digest(a)? == digest(b)?)
 }
 
 #[cfg(test)]
@@ -463,6 +661,145 @@ mod tests {
         assert_eq!(std::fs::read_to_string(root.join("fileX.txt")).unwrap(), "existing");
     }
 
+    #[test]
+    fn summary_counts_skips_and_collisions() {
+        let dir = tempdir().unwrap();
+        let root = dir.path().join("root");
+        create_dir_all(&root).unwrap();
+        for name in ["a.txt", "b.txt", "c.txt", "d.txt", "taken.txt"] {
+            write(root.join(name), name).unwrap();
+        }
+        let map_file = dir.path().join("map.csv");
+        std::fs::write(
+            &map_file,
+            "src,dst\na.txt,alpha.txt\nb.txt,taken.txt\nc.txt,gamma.txt\nd.txt,gamma.txt\n",
+        )
+        .unwrap();
+        let run = |dry_run: bool| {
+            rename_files_with(
+                &root,
+                &RenameOptions {
+                    map: Some(map_file.clone()),
+                    dry_run,
+                    ..Default::default()
+                },
+            )
+            .unwrap()
+        };
+
+        let planned = run(true);
+        assert_eq!(
+            planned,
+            RenameSummary {
+                planned: 4,
+                skipped_existing: 1,
+                collisions: 1,
+                renamed: 0,
+                deduped: 0,
+            }
+        );
+        assert_eq!(
+            planned.to_string(),
+            "4 renames planned, 1 skipped (target exists), 1 collisions"
+        );
+
+        let done = run(false);
+        assert_eq!(done.renamed, 2);
+        assert!(root.join("alpha.txt").exists());
+        assert!(root.join("gamma.txt").exists());
+        assert!(root.join("b.txt").exists());
+    }
+
+    #[test]
+    fn dedupe_on_collision_removes_only_identical_sources() {
+        let dir = tempdir().unwrap();
+        let root = dir.path().join("root");
+        create_dir_all(&root).unwrap();
+        write(root.join("copy-same.txt"), b"shared").unwrap();
+        write(root.join("same.txt"), b"shared").unwrap();
+        write(root.join("copy-other.txt"), b"mine").unwrap();
+        write(root.join("other.txt"), b"theirs").unwrap();
+
+        let run = |dry_run: bool| {
+            rename_files_with(
+                &root,
+                &RenameOptions {
+                    pattern: Some("copy-->".to_string()),
+                    dry_run,
+                    dedupe_on_collision: Some(Algorithm::Blake3),
+                    ..Default::default()
+                },
+            )
+            .unwrap()
+        };
+
+        let planned = run(true);
+        assert_eq!((planned.deduped, planned.skipped_existing), (1, 1));
+        assert!(
+            root.join("copy-same.txt").exists(),
+            "dry run removed a file"
+        );
+
+        let done = run(false);
+        assert_eq!(
+            (done.deduped, done.skipped_existing, done.renamed),
+            (1, 1, 0)
+        );
+        assert!(!root.join("copy-same.txt").exists());
+        assert_eq!(std::fs::read(root.join("same.txt")).unwrap(), b"shared");
+        assert_eq!(std::fs::read(root.join("copy-other.txt")).unwrap(), b"mine");
+        assert_eq!(std::fs::read(root.join("other.txt")).unwrap(), b"theirs");
+    }
+
+    #[test]
+    fn dedupe_on_collision_keeps_a_case_only_rename() {
+        // On case-insensitive filesystems `A.txt` already "exists" as `a.txt` itself
+        let dir = tempdir().unwrap();
+        let root = dir.path().join("root");
+        create_dir_all(&root).unwrap();
+        write(root.join("a.txt"), b"only copy").unwrap();
+
+        let summary = rename_files_with(
+            &root,
+            &RenameOptions {
+                pattern: Some("a.txt->A.txt".to_string()),
+                dedupe_on_collision: Some(Algorithm::Blake3),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        assert_eq!(summary.deduped, 0);
+        assert_eq!(std::fs::read(root.join("A.txt")).unwrap(), b"only copy");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn dedupe_on_collision_never_deletes_a_linked_source() {
+        let dir = tempdir().unwrap();
+        let root = dir.path().join("root");
+        create_dir_all(&root).unwrap();
+        write(root.join("copy-hard.txt"), b"hard").unwrap();
+        std::fs::hard_link(root.join("copy-hard.txt"), root.join("hard.txt")).unwrap();
+        write(root.join("copy-soft.txt"), b"soft").unwrap();
+        std::os::unix::fs::symlink(root.join("copy-soft.txt"), root.join("soft.txt")).unwrap();
+
+        let summary = rename_files_with(
+            &root,
+            &RenameOptions {
+                pattern: Some("copy-->".to_string()),
+                dedupe_on_collision: Some(Algorithm::Blake3),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        assert_eq!(summary.deduped, 0);
+        assert_eq!(std::fs::read(root.join("hard.txt")).unwrap(), b"hard");
+        assert_eq!(std::fs::read(root.join("soft.txt")).unwrap(), b"soft");
+        // the hardlinked pair is left alone; the symlink is replaced by the file it named
+        assert!(root.join("copy-hard.txt").exists());
+        assert!(!root.join("soft.txt").is_symlink());
+    }
+
     #[test]
     fn transactional_rename_creates_subdirs() {
         // Test that renamer creates necessary subdirectories
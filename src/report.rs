@@ -1,7 +1,862 @@
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, BinaryHeap, HashMap};
 use std::fs;
-use std::path::Path;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
 
-use anyhow::Result;
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::algorithms::Algorithm;
+use crate::compare;
+use crate::dedupe::{self, DedupBy, KeepPolicy};
+use crate::hash::hash_path_with_pool;
+use crate::io::{self, MapEntry};
+use crate::memory::{BufferPool, MemoryMode};
+use crate::pipeline::Pipeline;
+use crate::utils::format_bytes;
+use crate::walk::walk_directory_stream;
+
+/// Options tweaking how a map report is computed.
+#[derive(Debug, Clone, Default)]
+pub struct ReportOptions {
+    /// Group zero-byte files as duplicates. Off by default: every empty file shares the
+    /// same hash, which would otherwise drown out actionable duplicate groups.
+    pub include_empty: bool,
+    /// Warn instead of failing when the map lists a path more than once.
+    pub allow_duplicate_paths: bool,
+    /// Prefilter key when reporting on a directory: only files sharing it get hashed.
+    pub dedup_by: DedupBy,
+    /// Print sizes in text output as KiB/MiB/... instead of exact byte counts.
+    pub human_sizes: bool,
+    /// Count `.tar.gz` and similar double extensions as one type in `top_extensions`.
+    pub compound_ext: bool,
+    /// Only list duplicate groups with at least this many copies.
+    pub min_dupe_copies: Option<u64>,
+    /// Only list duplicate groups whose files are at least this many bytes each.
+    pub min_dupe_size: Option<u64>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ExtensionStat {
+    pub extension: String,
+    pub files: u64,
+    pub bytes: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ReportStats {
+    pub total_files: u64,
+    pub total_bytes: u64,
+    pub unique_hashes: u64,
+    pub empty_files: u64,
+    pub duplicate_groups: u64,
+    pub wasted_bytes: u64,
+    pub top_extensions: Vec<ExtensionStat>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DuplicateGroup {
+    pub hash: String,
+    pub size: u64,
+    pub count: u64,
+    pub wasted_bytes: u64,
+    pub paths: Vec<String>,
+    /// Path each `--keep` policy would keep; newest/oldest only when every copy has an mtime.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub keep: BTreeMap<String, String>,
+}
+
+/// File count and bytes under one directory of a map, `du`-style.
+#[derive(Debug, Clone, Serialize)]
+pub struct DirSummary {
+    /// Directory path as recorded in the map; `.` for the map root.
+    pub path: String,
+    /// Files directly inside the directory.
+    pub files: u64,
+    pub bytes: u64,
+    /// Files anywhere below the directory.
+    pub recursive_files: u64,
+    pub recursive_bytes: u64,
+}
+
+/// Summary of a hash map: the sections requested via `--include`.
+#[derive(Debug, Clone, Serialize)]
+pub struct MapReport {
+    pub version: String,
+    pub map_source: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stats: Option<ReportStats>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub duplicates: Option<Vec<DuplicateGroup>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub largest_files: Option<Vec<MapEntry>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub dir_summary: Option<Vec<DirSummary>>,
+}
+
+/// The path each [`KeepPolicy`] would keep among path-sorted `members`. Time-based
+/// policies are left out unless every member carries an mtime.
+pub fn keep_annotations(members: &[(&str, Option<i64>)]) -> BTreeMap<String, String> {
+    let all_timed = members.iter().all(|(_, mtime)| mtime.is_some());
+    KeepPolicy::ALL
+        .iter()
+        .filter(|policy| all_timed || !policy.needs_mtime())
+        .map(|policy| {
+            let idx = dedupe::keep_index(*policy, members);
+            (policy.name().to_string(), members[idx].0.to_string())
+        })
+        .collect()
+}
+
+/// Group entries sharing a hash into duplicate groups, largest waste first.
+/// Zero-byte files are left out unless `include_empty` is set, as are entries the size
+/// prefilter left unhashed and symlinks hashed by their target path.
+pub fn find_duplicates(entries: &[MapEntry], include_empty: bool) -> Vec<DuplicateGroup> {
+    let mut by_hash: HashMap<String, Vec<&MapEntry>> = HashMap::new();
+    for e in entries {
+        // a link hashed by its target path holds no data to deduplicate
+        if (e.size == 0 && !include_empty) || e.hash.is_empty() || e.kind.is_some() {
+            continue;
+        }
+        by_hash
+            .entry(e.hash.to_ascii_lowercase())
+            .or_default()
+            .push(e);
+    }
+    let mut groups: Vec<DuplicateGroup> = by_hash
+        .into_iter()
+        .filter_map(|(hash, members)| {
+            // hard links sharing an inode are one physical file and waste nothing
+            let mut inodes: Vec<&str> = members.iter().filter_map(|e| e.inode.as_deref()).collect();
+            let linked = inodes.len();
+            inodes.sort_unstable();
+            inodes.dedup();
+            let physical = (members.len() - linked + inodes.len()) as u64;
+            if physical < 2 {
+                return None;
+            }
+            let size = members[0].size;
+            let count = members.len() as u64;
+            let mut members: Vec<(&str, Option<i64>)> =
+                members.iter().map(|e| (e.path.as_str(), e.mtime)).collect();
+            members.sort();
+            Some(DuplicateGroup {
+                hash,
+                size,
+                count,
+                wasted_bytes: size * (physical - 1),
+                paths: members.iter().map(|(p, _)| p.to_string()).collect(),
+                keep: keep_annotations(&members),
+            })
+        })
+        .collect();
+    groups.sort_by(|a, b| {
+        b.wasted_bytes
+            .cmp(&a.wasted_bytes)
+            .then(b.count.cmp(&a.count))
+            .then_with(|| a.hash.cmp(&b.hash))
+    });
+    groups
+}
+
+/// The `top_n` largest entries, biggest first with ties broken by path. One pass with a
+/// bounded min-heap, so huge maps are neither copied nor fully sorted.
+pub fn largest_entries(entries: &[MapEntry], top_n: usize) -> Vec<MapEntry> {
+    // Ordering key: larger size wins, then the lexicographically smaller path
+    fn key(e: &MapEntry) -> (u64, Reverse<&str>) {
+        (e.size, Reverse(e.path.as_str()))
+    }
+    let mut heap = BinaryHeap::with_capacity(top_n.min(entries.len()) + 1);
+    for (idx, e) in entries.iter().enumerate() {
+        if top_n == 0 {
+            break;
+        }
+        if heap.len() < top_n {
+            heap.push(Reverse((key(e), idx)));
+        } else if let Some(Reverse((smallest, _))) = heap.peek() {
+            if key(e) > *smallest {
+                heap.pop();
+                heap.push(Reverse((key(e), idx)));
+            }
+        }
+    }
+    heap.into_sorted_vec()
+        .into_iter()
+        .map(|Reverse((_, idx))| entries[idx].clone())
+        .collect()
+}
+
+/// Components of a map entry's path, whichever separator the map used, without empty
+/// or `.` segments.
+fn path_components(path: &str) -> Vec<&str> {
+    path.split(['/', '\\'])
+        .filter(|c| !c.is_empty() && *c != ".")
+        .collect()
+}
+
+/// Per-directory totals built from the entry paths alone, keyed by the directory's
+/// components joined with `/` (`""` for the map root). Shared by `dir-summary` and
+/// `--tree` so both agree on what a directory holds.
+fn aggregate_dirs(entries: &[MapEntry]) -> HashMap<String, DirSummary> {
+    let mut totals: HashMap<String, DirSummary> = HashMap::new();
+    for e in entries {
+        let components = path_components(&e.path);
+        let parent_len = components.len().saturating_sub(1);
+        for depth in 0..=parent_len {
+            let key = components[..depth].join("/");
+            let slot = totals.entry(key).or_insert_with_key(|key| DirSummary {
+                path: if key.is_empty() {
+                    ".".to_string()
+                } else {
+                    key.clone()
+                },
+                files: 0,
+                bytes: 0,
+                recursive_files: 0,
+                recursive_bytes: 0,
+            });
+            if depth == parent_len {
+                slot.files += 1;
+                slot.bytes += e.size;
+            }
+            slot.recursive_files += 1;
+            slot.recursive_bytes += e.size;
+        }
+    }
+    totals
+}
+
+/// Per-directory totals, the `top_n` largest by recursive size first (ties by path).
+pub fn summarize_dirs(entries: &[MapEntry], top_n: usize) -> Vec<DirSummary> {
+    let mut dirs: Vec<DirSummary> = aggregate_dirs(entries).into_values().collect();
+    dirs.sort_by(|a, b| {
+        b.recursive_bytes
+            .cmp(&a.recursive_bytes)
+            .then_with(|| a.path.cmp(&b.path))
+    });
+    dirs.truncate(top_n);
+    dirs
+}
+
+/// Double extensions counted as one type with `--compound-ext`.
+const COMPOUND_EXTENSIONS: &[&str] = &["tar.gz", "tar.bz2", "tar.xz", "tar.zst", "tar.lz4"];
+
+fn extension_of(path: &str, compound: bool) -> String {
+    let path = Path::new(path);
+    if compound {
+        let name = path
+            .file_name()
+            .map(|n| n.to_string_lossy().to_lowercase())
+            .unwrap_or_default();
+        if let Some(ext) = COMPOUND_EXTENSIONS
+            .iter()
+            .find(|ext| name.len() > ext.len() + 1 && name.ends_with(&format!(".{}", ext)))
+        {
+            return ext.to_string();
+        }
+    }
+    path.extension()
+        .map(|e| e.to_string_lossy().to_lowercase())
+        .unwrap_or_default()
+}
+
+fn compute_stats(
+    entries: &[MapEntry],
+    duplicates: &[DuplicateGroup],
+    top_n: usize,
+    compound_ext: bool,
+) -> ReportStats {
+    let mut hashes: Vec<String> = entries
+        .iter()
+        .filter(|e| !e.hash.is_empty())
+        .map(|e| e.hash.to_ascii_lowercase())
+        .collect();
+    hashes.sort_unstable();
+    hashes.dedup();
+    // entries left unhashed by the size prefilter are unique by construction
+    let unhashed = entries.iter().filter(|e| e.hash.is_empty()).count();
+
+    let mut by_ext: HashMap<String, (u64, u64)> = HashMap::new();
+    for e in entries {
+        let slot = by_ext
+            .entry(extension_of(&e.path, compound_ext))
+            .or_default();
+        slot.0 += 1;
+        slot.1 += e.size;
+    }
+    let mut top_extensions: Vec<ExtensionStat> = by_ext
+        .into_iter()
+        .map(|(extension, (files, bytes))| ExtensionStat {
+            extension,
+            files,
+            bytes,
+        })
+        .collect();
+    top_extensions.sort_by(|a, b| {
+        b.bytes
+            .cmp(&a.bytes)
+            .then(b.files.cmp(&a.files))
+            .then_with(|| a.extension.cmp(&b.extension))
+    });
+    top_extensions.truncate(top_n);
+
+    ReportStats {
+        total_files: entries.len() as u64,
+        total_bytes: entries.iter().map(|e| e.size).sum(),
+        unique_hashes: (hashes.len() + unhashed) as u64,
+        empty_files: entries.iter().filter(|e| e.size == 0).count() as u64,
+        duplicate_groups: duplicates.len() as u64,
+        wasted_bytes: duplicates.iter().map(|g| g.wasted_bytes).sum(),
+        top_extensions,
+    }
+}
+
+/// Build a report over map `entries`. `include` selects sections (`stats`, `duplicates`,
+/// `largest`, `dir-summary`); top lists are capped at `top_n` entries.
+pub fn build_map_report(
+    entries: &[MapEntry],
+    map_source: &str,
+    include: &[String],
+    top_n: usize,
+    opts: &ReportOptions,
+) -> MapReport {
+    let wants = |name: &str| include.iter().any(|s| s.trim().eq_ignore_ascii_case(name));
+    let duplicates = find_duplicates(entries, opts.include_empty);
+
+    let stats =
+        wants("stats").then(|| compute_stats(entries, &duplicates, top_n, opts.compound_ext));
+    let largest_files = wants("largest").then(|| largest_entries(entries, top_n));
+    let dir_summary = wants("dir-summary").then(|| summarize_dirs(entries, top_n));
+    let duplicates = wants("duplicates").then(|| {
+        duplicates
+            .into_iter()
+            .filter(|g| meets_dupe_thresholds(g, opts))
+            .take(top_n)
+            .collect()
+    });
+
+    MapReport {
+        version: "1".to_string(),
+        map_source: map_source.to_string(),
+        stats,
+        duplicates,
+        largest_files,
+        dir_summary,
+    }
+}
+
+/// Whether `group` passes `--min-dupe-copies` and `--min-dupe-size`.
+fn meets_dupe_thresholds(group: &DuplicateGroup, opts: &ReportOptions) -> bool {
+    opts.min_dupe_copies.is_none_or(|min| group.count >= min)
+        && opts.min_dupe_size.is_none_or(|min| group.size >= min)
+}
+
+/// Text for a byte count: `format_bytes` when `human`, otherwise the exact number.
+fn size_text(bytes: u64, human: bool) -> String {
+    if human {
+        format_bytes(bytes)
+    } else {
+        bytes.to_string()
+    }
+}
+
+/// Like [`size_text`] but with a `bytes` unit on exact numbers.
+fn bytes_text(bytes: u64, human: bool) -> String {
+    if human {
+        format_bytes(bytes)
+    } else {
+        format!("{} bytes", bytes)
+    }
+}
+
+/// Signed [`size_text`] with an explicit `+`/`-`.
+fn delta_text(bytes: i64, human: bool) -> String {
+    if human {
+        let sign = if bytes < 0 { '-' } else { '+' };
+        format!("{}{}", sign, format_bytes(bytes.unsigned_abs()))
+    } else {
+        format!("{:+}", bytes)
+    }
+}
+
+/// Human-readable rendering of a map report; `human` prints sizes as KiB/MiB/...
+pub fn render_map_report_text(report: &MapReport, human: bool) -> String {
+    let mut out = format!("Report for {}\n", report.map_source);
+    if let Some(stats) = &report.stats {
+        out.push_str("\nStats\n");
+        out.push_str(&format!("  Total files:      {}\n", stats.total_files));
+        out.push_str(&format!(
+            "  Total bytes:      {}\n",
+            size_text(stats.total_bytes, human)
+        ));
+        out.push_str(&format!("  Unique hashes:    {}\n", stats.unique_hashes));
+        out.push_str(&format!("  Empty files:      {}\n", stats.empty_files));
+        out.push_str(&format!(
+            "  Duplicate groups: {} ({} wasted)\n",
+            stats.duplicate_groups,
+            bytes_text(stats.wasted_bytes, human)
+        ));
+        if !stats.top_extensions.is_empty() {
+            out.push_str("\nTop extensions\n");
+            for ext in &stats.top_extensions {
+                let name = if ext.extension.is_empty() {
+                    "(none)".to_string()
+                } else {
+                    format!(".{}", ext.extension)
+                };
+                out.push_str(&format!(
+                    "  {:<12} {:>8} files {:>14}{}\n",
+                    name,
+                    ext.files,
+                    size_text(ext.bytes, human),
+                    if human { "" } else { " bytes" }
+                ));
+            }
+        }
+    }
+    if let Some(groups) = &report.duplicates {
+        out.push_str("\nDuplicates\n");
+        if groups.is_empty() {
+            out.push_str("  (none)\n");
+        }
+        for g in groups {
+            out.push_str(&format!(
+                "  {} x{} size {} wasted {}\n",
+                g.hash,
+                g.count,
+                size_text(g.size, human),
+                size_text(g.wasted_bytes, human)
+            ));
+            for p in &g.paths {
+                out.push_str(&format!("    {}\n", p));
+            }
+        }
+    }
+    if let Some(largest) = &report.largest_files {
+        out.push_str("\nLargest files\n");
+        for e in largest {
+            out.push_str(&format!("  {:>14}  {}\n", size_text(e.size, human), e.path));
+        }
+    }
+    if let Some(dirs) = &report.dir_summary {
+        out.push_str("\nDirectories (recursive)\n");
+        for d in dirs {
+            out.push_str(&format!(
+                "  {:>14} {:>8} files  {}\n",
+                size_text(d.recursive_bytes, human),
+                d.recursive_files,
+                d.path
+            ));
+        }
+    }
+    out
+}
+
+/// One directory of the `--tree` view: subdirectories by name, then its own files.
+#[derive(Default)]
+struct TreeDir<'a> {
+    dirs: BTreeMap<&'a str, TreeDir<'a>>,
+    files: Vec<(&'a str, &'a MapEntry)>,
+}
+
+impl<'a> TreeDir<'a> {
+    fn insert(&mut self, components: &[&'a str], entry: &'a MapEntry) {
+        match components {
+            [] => {}
+            [name] => self.files.push((name, entry)),
+            [dir, rest @ ..] => self.dirs.entry(dir).or_default().insert(rest, entry),
+        }
+    }
+
+    fn render(
+        &self,
+        prefix: &str,
+        depth: usize,
+        totals: &HashMap<String, DirSummary>,
+        human: bool,
+        out: &mut String,
+    ) {
+        let indent = "  ".repeat(depth);
+        for (name, dir) in &self.dirs {
+            let key = if prefix.is_empty() {
+                name.to_string()
+            } else {
+                format!("{}/{}", prefix, name)
+            };
+            let bytes = totals.get(&key).map_or(0, |t| t.recursive_bytes);
+            out.push_str(&format!(
+                "{}{}/  {}\n",
+                indent,
+                name,
+                size_text(bytes, human)
+            ));
+            dir.render(&key, depth + 1, totals, human, out);
+        }
+        let mut files = self.files.clone();
+        files.sort_by(|a, b| a.0.cmp(b.0));
+        for (name, e) in files {
+            // directory scans leave files without a size collision unhashed
+            let line = format!(
+                "{}{}  {}  {}",
+                indent,
+                name,
+                size_text(e.size, human),
+                e.hash
+            );
+            out.push_str(line.trim_end());
+            out.push('\n');
+        }
+    }
+}
+
+/// Indented directory tree of the entry paths for `report --tree`: each directory
+/// shows its recursive size, each file its size and hash.
+pub fn render_tree(entries: &[MapEntry], source: &str, human: bool) -> String {
+    let totals = aggregate_dirs(entries);
+    let mut root = TreeDir::default();
+    for e in entries {
+        root.insert(&path_components(&e.path), e);
+    }
+    let root_bytes = totals.get("").map_or(0, |t| t.recursive_bytes);
+    let mut out = format!("{}  {}\n", source, size_text(root_bytes, human));
+    root.render("", 1, &totals, human, &mut out);
+    out
+}
+
+/// Print the `--tree` view of a map (JSON or CSV) or of a directory scanned in place.
+pub fn generate_tree(input: &str, opts: &ReportOptions) -> Result<()> {
+    let in_path = Path::new(input);
+    let entries = if in_path.is_dir() {
+        scan_directory(in_path, opts.dedup_by)?
+    } else {
+        let entries = try_load_map(in_path)
+            .ok_or_else(|| anyhow::anyhow!("{} is not a hash map (JSON or CSV)", input))?;
+        io::check_unique_paths(&entries, input, opts.allow_duplicate_paths)?;
+        entries
+    };
+    print!("{}", render_tree(&entries, input, opts.human_sizes));
+    Ok(())
+}
+
+/// A file whose content changed between two scans.
+#[derive(Debug, Clone, Serialize)]
+pub struct ChurnedFile {
+    pub path: String,
+    pub old_size: u64,
+    pub new_size: u64,
+    pub delta_bytes: i64,
+}
+
+/// Churn between an older and a newer map of the same tree (`report --since`).
+#[derive(Debug, Clone, Serialize)]
+pub struct ChurnReport {
+    pub version: String,
+    pub old_map: String,
+    pub new_map: String,
+    pub added: u64,
+    pub removed: u64,
+    pub changed: u64,
+    pub moved: u64,
+    pub unchanged: u64,
+    pub bytes_before: u64,
+    pub bytes_after: u64,
+    pub net_bytes: i64,
+    pub top_changed: Vec<ChurnedFile>,
+}
+
+/// Summarize what changed from `old` to `new` using `compare_maps`. The largest changed
+/// files (by new size) are listed, capped at `top_n`.
+pub fn build_churn_report(
+    old: Vec<MapEntry>,
+    new: Vec<MapEntry>,
+    old_map: &str,
+    new_map: &str,
+    top_n: usize,
+) -> ChurnReport {
+    let bytes_before: u64 = old.iter().map(|e| e.size).sum();
+    let bytes_after: u64 = new.iter().map(|e| e.size).sum();
+    let cmp = compare::compare_maps(old, new);
+
+    let mut top_changed: Vec<ChurnedFile> = cmp
+        .changed
+        .iter()
+        .map(|(src, tgt)| ChurnedFile {
+            path: tgt.path.clone(),
+            old_size: src.size,
+            new_size: tgt.size,
+            delta_bytes: tgt.size as i64 - src.size as i64,
+        })
+        .collect();
+    top_changed.sort_by(|a, b| {
+        b.new_size
+            .cmp(&a.new_size)
+            .then_with(|| a.path.cmp(&b.path))
+    });
+    top_changed.truncate(top_n);
+
+    ChurnReport {
+        version: "1".to_string(),
+        old_map: old_map.to_string(),
+        new_map: new_map.to_string(),
+        added: cmp.new.len() as u64,
+        removed: cmp.missing.len() as u64,
+        changed: cmp.changed.len() as u64,
+        moved: cmp.moved.len() as u64,
+        unchanged: cmp.identical.len() as u64,
+        bytes_before,
+        bytes_after,
+        net_bytes: bytes_after as i64 - bytes_before as i64,
+        top_changed,
+    }
+}
+
+/// Human-readable rendering of a churn report; `human` prints sizes as KiB/MiB/...
+pub fn render_churn_report_text(report: &ChurnReport, human: bool) -> String {
+    let mut out = format!("Churn from {} to {}\n", report.old_map, report.new_map);
+    out.push_str(&format!("  Added:      {}\n", report.added));
+    out.push_str(&format!("  Removed:    {}\n", report.removed));
+    out.push_str(&format!("  Changed:    {}\n", report.changed));
+    out.push_str(&format!("  Moved:      {}\n", report.moved));
+    out.push_str(&format!("  Unchanged:  {}\n", report.unchanged));
+    out.push_str(&format!(
+        "  Bytes:      {} -> {} ({})\n",
+        size_text(report.bytes_before, human),
+        size_text(report.bytes_after, human),
+        delta_text(report.net_bytes, human)
+    ));
+    if !report.top_changed.is_empty() {
+        out.push_str("\nLargest changed files\n");
+        for f in &report.top_changed {
+            out.push_str(&format!(
+                "  {:>14}  {:>14}  {}\n",
+                size_text(f.new_size, human),
+                delta_text(f.delta_bytes, human),
+                f.path
+            ));
+        }
+    }
+    out
+}
+
+/// Print the churn between map `since` (older) and map `input` (newer) as text or JSON.
+pub fn generate_churn_report(
+    since: &str,
+    input: &str,
+    format: &str,
+    top_n: usize,
+    opts: &ReportOptions,
+) -> Result<()> {
+    let load = |p: &str| -> Result<Vec<MapEntry>> {
+        let path = Path::new(p);
+        if !path.exists() {
+            anyhow::bail!("input map not found: {}", p);
+        }
+        let entries = try_load_map(path)
+            .ok_or_else(|| anyhow::anyhow!("{} is not a hash map (JSON or CSV)", p))?;
+        io::check_unique_paths(&entries, p, opts.allow_duplicate_paths)?;
+        Ok(entries)
+    };
+    let old = load(since)?;
+    let new = load(input)?;
+    let report = build_churn_report(old, new, since, input, top_n);
+    match format.to_lowercase().as_str() {
+        "json" => println!("{}", serde_json::to_string_pretty(&report)?),
+        "text" => print!("{}", render_churn_report_text(&report, opts.human_sizes)),
+        other => anyhow::bail!(
+            "unsupported format for --since: {} (use text or json)",
+            other
+        ),
+    }
+    Ok(())
+}
+
+/// `report --dedupe-execute`: find duplicate groups in map `input` and keep one file per
+/// group under `policy`. Without `execute` this only prints the plan.
+pub fn generate_dedupe(
+    input: &str,
+    root: Option<&Path>,
+    policy: KeepPolicy,
+    hardlink: bool,
+    execute: bool,
+    opts: &ReportOptions,
+) -> Result<()> {
+    let in_path = Path::new(input);
+    let entries = try_load_map(in_path)
+        .ok_or_else(|| anyhow::anyhow!("{} is not a hash map (JSON or CSV)", input))?;
+    io::check_unique_paths(&entries, input, opts.allow_duplicate_paths)?;
+    let root = match root {
+        Some(r) => Some(r.to_path_buf()),
+        None => io::load_map_root(in_path)
+            .ok()
+            .flatten()
+            .map(std::path::PathBuf::from),
+    };
+    if root.is_none() && entries.iter().any(|e| Path::new(&e.path).is_relative()) {
+        anyhow::bail!(
+            "{} records relative paths but no root; pass --root <dir> to locate the files",
+            input
+        );
+    }
+
+    let groups = find_duplicates(&entries, opts.include_empty);
+    let actions = dedupe::plan_dedupe(&groups, root.as_deref(), policy);
+    let verb = if hardlink { "link" } else { "delete" };
+    for action in &actions {
+        println!("keep   {}", action.keep.display());
+        for dup in &action.remove {
+            println!("{:<6} {}", verb, dup.display());
+        }
+    }
+    let count: usize = actions.iter().map(|a| a.remove.len()).sum();
+    let bytes: u64 = actions.iter().map(|a| a.size * a.remove.len() as u64).sum();
+    if !execute {
+        println!(
+            "Dry run: {} duplicate(s) ({}) would be {}; pass --yes to apply",
+            count,
+            bytes_text(bytes, opts.human_sizes),
+            if hardlink { "hard-linked" } else { "deleted" }
+        );
+        return Ok(());
+    }
+    let summary = dedupe::execute_dedupe(&actions, hardlink)?;
+    println!(
+        "Removed {}, linked {}, skipped {}; {} freed",
+        summary.removed,
+        summary.linked,
+        summary.skipped,
+        bytes_text(summary.bytes_freed, opts.human_sizes)
+    );
+    Ok(())
+}
+
+/// Entries for every file under `dir`, hashing (BLAKE3) only the files whose size collides
+/// with another file's, so a tree of mostly unique sizes is barely read.
+fn scan_directory(dir: &Path, by: DedupBy) -> Result<Vec<MapEntry>> {
+    let mut entries = dedupe::scan_unhashed(dir, &[])?;
+    let alg = Algorithm::Blake3;
+    let hashed = dedupe::hash_size_collisions(&mut entries, by, |e| {
+        let mut hasher = alg.create();
+        let mut file = fs::File::open(dir.join(&e.path))?;
+        hasher.update_reader(&mut file)?;
+        Ok(hasher.finalize_hex(32))
+    });
+    log::info!(
+        "Hashed {} of {} files (the rest have no size collision)",
+        hashed,
+        entries.len()
+    );
+    Ok(entries)
+}
+
+/// Duplicate groups under `dir` without building a map of the tree (`report
+/// --only-duplicates`). A first walk only counts file sizes (and mtimes with
+/// `--dedup-by mtime`); a second one hashes (BLAKE3), in parallel through the pipeline,
+/// just the files whose key another file shares. Unique files are never kept in memory.
+pub fn find_directory_duplicates(dir: &Path, opts: &ReportOptions) -> Result<Vec<DuplicateGroup>> {
+    let by = opts.dedup_by;
+    let key = move |meta: &fs::Metadata| (meta.len(), mtime_secs(meta).filter(|_| by.mtime));
+    let mut counts: HashMap<(u64, Option<i64>), u64> = HashMap::new();
+    for path in walk_directory_stream(dir, &[], None, false)? {
+        match fs::metadata(&path) {
+            Ok(meta) if meta.is_file() && (meta.len() > 0 || opts.include_empty) => {
+                *counts.entry(key(&meta)).or_default() += 1;
+            }
+            Ok(_) => {}
+            Err(e) => log::warn!("Failed reading metadata for {}: {}", path.display(), e),
+        }
+    }
+    let files: u64 = counts.values().sum();
+    counts.retain(|_, n| *n > 1);
+    let candidates = Arc::new(counts);
+
+    let base = dir.to_path_buf();
+    let alg = Algorithm::Blake3;
+    let worker = move |path: PathBuf, pool: Arc<BufferPool>| -> Result<Option<MapEntry>> {
+        let meta = fs::metadata(&path).with_context(|| format!("stat {}", path.display()))?;
+        if !meta.is_file() || !candidates.contains_key(&key(&meta)) {
+            return Ok(None);
+        }
+        let mut hasher = alg.create();
+        hash_path_with_pool(hasher.as_mut(), &path, &pool)
+            .with_context(|| format!("hash {}", path.display()))?;
+        Ok(Some(MapEntry {
+            path: path
+                .strip_prefix(&base)
+                .unwrap_or(&path)
+                .to_string_lossy()
+                .replace('\\', "/"),
+            hash: hasher.finalize_hex(32),
+            size: meta.len(),
+            mtime: mtime_secs(&meta),
+            ..Default::default()
+        }))
+    };
+    let (_, hashed) = Pipeline::new(MemoryMode::Balanced)
+        .run_collect(dir, &[], None, false, false, worker)
+        .context("hashing duplicate candidates")?;
+    log::info!(
+        "Hashed {} of {} files (the rest have no size collision)",
+        hashed.len(),
+        files
+    );
+    Ok(find_duplicates(&hashed, opts.include_empty)
+        .into_iter()
+        .filter(|g| meets_dupe_thresholds(g, opts))
+        .collect())
+}
+
+fn mtime_secs(meta: &fs::Metadata) -> Option<i64> {
+    meta.modified()
+        .ok()
+        .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+        .map(|d| d.as_secs() as i64)
+}
+
+/// `report --only-duplicates`: print every duplicate group of directory (or map) `input`
+/// and nothing else.
+pub fn generate_only_duplicates(input: &str, format: &str, opts: &ReportOptions) -> Result<()> {
+    let in_path = Path::new(input);
+    let groups = if in_path.is_dir() {
+        find_directory_duplicates(in_path, opts)?
+    } else {
+        let entries = try_load_map(in_path)
+            .ok_or_else(|| anyhow::anyhow!("{} is not a hash map (JSON or CSV)", input))?;
+        io::check_unique_paths(&entries, input, opts.allow_duplicate_paths)?;
+        find_duplicates(&entries, opts.include_empty)
+            .into_iter()
+            .filter(|g| meets_dupe_thresholds(g, opts))
+            .collect()
+    };
+    let report = MapReport {
+        version: "1".to_string(),
+        map_source: input.to_string(),
+        stats: None,
+        duplicates: Some(groups),
+        largest_files: None,
+        dir_summary: None,
+    };
+    match format.to_lowercase().as_str() {
+        "json" => println!("{}", serde_json::to_string_pretty(&report)?),
+        "text" => print!("{}", render_map_report_text(&report, opts.human_sizes)),
+        other => anyhow::bail!("--only-duplicates supports text or json, not {}", other),
+    }
+    Ok(())
+}
+
+/// Load map entries from a JSON or CSV hash map, returning `None` when the input is
+/// not a map (e.g. a benchmark report).
+fn try_load_map(path: &Path) -> Option<Vec<MapEntry>> {
+    let is_csv = path
+        .extension()
+        .map(|e| e.eq_ignore_ascii_case("csv"))
+        .unwrap_or(false);
+    if is_csv {
+        io::load_map_from_csv(path).ok()
+    } else {
+        io::load_map_from_json(path).ok()
+    }
+}
 
 /// Render a simple HTML view for a benchmark JSON report produced by
 /// `run_benchmark_and_save`. The JSON is embedded in a <pre> block with
@@ -29,21 +884,53 @@ pub fn render_json_to_html(input_json: &Path, out_html: &Path) -> Result<()> {
 
 /// Generate a report from a saved JSON report file. This matches the
 /// library-level export expected by the CLI: `generate_report(input, format, include, top_n)`.
+/// Hash maps (JSON or CSV) get a map report (stats, duplicates, largest files) printed as
+/// text or, with `format == "json"`, as structured JSON.
 /// For `format == "html"` a sidecar HTML file is written next to the input JSON.
-/// For `format == "json"` we print an enriched JSON that includes a `total_files` key.
+/// For other inputs and `format == "json"` we print an enriched JSON that includes a `total_files` key.
 /// For other formats we simply print the JSON (placeholder simple behavior).
-pub fn generate_report(
+pub fn generate_report(input: &str, format: &str, include: &[String], top_n: usize) -> Result<()> {
+    generate_report_with_options(input, format, include, top_n, &ReportOptions::default())
+}
+
+/// Same as `generate_report` with explicit map report options.
+pub fn generate_report_with_options(
     input: &str,
     format: &str,
-    _include: &Vec<String>,
-    _top_n: usize,
+    include: &[String],
+    top_n: usize,
+    opts: &ReportOptions,
 ) -> Result<()> {
     let in_path = Path::new(input);
     if !in_path.exists() {
         anyhow::bail!("input report not found: {}", input);
     }
 
-    match format.to_lowercase().as_str() {
+    let format = format.to_lowercase();
+    if in_path.is_dir() {
+        let entries = scan_directory(in_path, opts.dedup_by)?;
+        let report = build_map_report(&entries, input, include, top_n, opts);
+        match format.as_str() {
+            "json" => println!("{}", serde_json::to_string_pretty(&report)?),
+            "text" => print!("{}", render_map_report_text(&report, opts.human_sizes)),
+            other => anyhow::bail!("report on a directory supports text or json, not {}", other),
+        }
+        return Ok(());
+    }
+    if format != "html" {
+        if let Some(entries) = try_load_map(in_path) {
+            io::check_unique_paths(&entries, input, opts.allow_duplicate_paths)?;
+            let report = build_map_report(&entries, input, include, top_n, opts);
+            if format == "json" {
+                println!("{}", serde_json::to_string_pretty(&report)?);
+            } else {
+                print!("{}", render_map_report_text(&report, opts.human_sizes));
+            }
+            return Ok(());
+        }
+    }
+
+    match format.as_str() {
         "html" => {
             let out = in_path.with_extension("html");
             render_json_to_html(in_path, &out)?;
@@ -149,7 +1036,7 @@ mod tests {
         let result = generate_report(
             in_path.to_str().unwrap(),
             "json",
-            &vec![],
+            &[],
             10,
         );
         assert!(result.is_ok());
@@ -173,7 +1060,7 @@ mod tests {
         let result = generate_report(
             in_path.to_str().unwrap(),
             "html",
-            &vec![],
+            &[],
             10,
         );
         assert!(result.is_ok());
@@ -192,12 +1079,347 @@ mod tests {
         let result = generate_report(
             "/nonexistent/path/to/file.json",
             "html",
-            &vec![],
+            &[],
             10,
         );
         assert!(result.is_err());
     }
 
+    fn entry(path: &str, hash: &str, size: u64) -> MapEntry {
+        MapEntry {
+            path: path.to_string(),
+            hash: hash.to_string(),
+            size,
+            ..Default::default()
+        }
+    }
+
+    fn sections() -> Vec<String> {
+        vec![
+            "stats".to_string(),
+            "duplicates".to_string(),
+            "largest".to_string(),
+        ]
+    }
+
+    #[test]
+    fn duplicate_groups_name_the_copy_each_policy_keeps() {
+        let timed = |path: &str, mtime: i64| MapEntry {
+            mtime: Some(mtime),
+            ..entry(path, "aa", 10)
+        };
+        let entries = vec![
+            timed("archive/2019/photo.jpg", 100),
+            timed("b/photo.jpg", 300),
+            timed("photos/photo.jpg", 200),
+        ];
+        let groups = find_duplicates(&entries, false);
+        let keep = &groups[0].keep;
+        assert_eq!(keep["newest"], "b/photo.jpg");
+        assert_eq!(keep["oldest"], "archive/2019/photo.jpg");
+        assert_eq!(keep["first"], "archive/2019/photo.jpg");
+        assert_eq!(keep["shortest-path"], "b/photo.jpg");
+
+        // without mtimes only the path-based policies are annotated
+        let untimed = vec![entry("x.bin", "bb", 4), entry("y.bin", "bb", 4)];
+        let keep = &find_duplicates(&untimed, false)[0].keep;
+        assert_eq!(keep.keys().collect::<Vec<_>>(), ["first", "shortest-path"]);
+    }
+
+    #[test]
+    fn symlinks_hashed_by_target_are_not_duplicates() {
+        let link = |path: &str| MapEntry {
+            kind: Some(crate::io::EntryKind::Symlink),
+            ..entry(path, "aa", 12)
+        };
+        let entries = vec![link("l1"), link("l2"), entry("file.bin", "aa", 12)];
+        assert!(find_duplicates(&entries, false).is_empty());
+    }
+
+    #[test]
+    fn tree_indents_nested_paths_and_sums_directories() {
+        let entries = vec![
+            entry("top.txt", "aa", 1),
+            entry("a/b/two.bin", "cc", 100),
+            entry("a/one.bin", "bb", 10),
+            entry("a/b/c/three.bin", "dd", 1000),
+        ];
+        let text = render_tree(&entries, "map.json", false);
+        assert_eq!(
+            text,
+            "map.json  1111\n\
+             \x20 a/  1110\n\
+             \x20   b/  1100\n\
+             \x20     c/  1000\n\
+             \x20       three.bin  1000  dd\n\
+             \x20     two.bin  100  cc\n\
+             \x20   one.bin  10  bb\n\
+             \x20 top.txt  1  aa\n"
+        );
+    }
+
+    #[test]
+    fn tree_and_dir_summary_agree_on_directory_sizes() {
+        let entries = vec![
+            entry("./a/one.bin", "bb", 10),
+            entry("a\\b\\two.bin", "cc", 100),
+            entry("a/b/three.bin", "dd", 1000),
+        ];
+        let dirs = summarize_dirs(&entries, 10);
+        let sizes: Vec<(&str, u64)> = dirs
+            .iter()
+            .map(|d| (d.path.as_str(), d.recursive_bytes))
+            .collect();
+        assert_eq!(sizes, [(".", 1110), ("a", 1110), ("a/b", 1100)]);
+        let text = render_tree(&entries, "map.json", false);
+        assert!(text.starts_with("map.json  1110\n  a/  1110\n    b/  1100\n"));
+    }
+
+    #[test]
+    fn dupe_thresholds_keep_only_large_and_frequent_groups() {
+        let mut entries = Vec::new();
+        // (hash, size, copies)
+        for (hash, size, copies) in [
+            ("aa", 1000, 5), // qualifies
+            ("bb", 1000, 2), // too few copies
+            ("cc", 10, 9),   // too small
+            ("dd", 4000, 3), // qualifies, most waste
+            ("ee", 500, 3),  // exactly at both limits
+        ] {
+            for i in 0..copies {
+                entries.push(entry(&format!("{}/{}.bin", hash, i), hash, size));
+            }
+        }
+        let opts = ReportOptions {
+            min_dupe_copies: Some(3),
+            min_dupe_size: Some(500),
+            ..Default::default()
+        };
+        let report = build_map_report(&entries, "map.json", &sections(), 10, &opts);
+        let groups: Vec<(&str, u64)> = report
+            .duplicates
+            .as_ref()
+            .unwrap()
+            .iter()
+            .map(|g| (g.hash.as_str(), g.wasted_bytes))
+            .collect();
+        assert_eq!(groups, [("dd", 8000), ("aa", 4000), ("ee", 1000)]);
+        // stats still describe every group
+        assert_eq!(report.stats.unwrap().duplicate_groups, 5);
+    }
+
+    #[test]
+    fn compound_ext_counts_tar_gz_apart_from_gz() {
+        let entries = vec![
+            entry("backup.tar.gz", "aa", 100),
+            entry("logs/app.log.gz", "bb", 10),
+            entry("other.TAR.GZ", "cc", 50),
+        ];
+        let exts = |compound_ext: bool| {
+            let opts = ReportOptions {
+                compound_ext,
+                ..Default::default()
+            };
+            let report = build_map_report(&entries, "map.json", &sections(), 5, &opts);
+            let mut exts: Vec<(String, u64)> = report
+                .stats
+                .unwrap()
+                .top_extensions
+                .into_iter()
+                .map(|e| (e.extension, e.files))
+                .collect();
+            exts.sort();
+            exts
+        };
+        assert_eq!(exts(false), [("gz".to_string(), 3)]);
+        assert_eq!(
+            exts(true),
+            [("gz".to_string(), 1), ("tar.gz".to_string(), 2)]
+        );
+    }
+
+    #[test]
+    fn dir_summary_totals_bytes_recursively() {
+        let entries = vec![
+            entry("top.txt", "aa", 1),
+            entry("a/one.bin", "bb", 10),
+            entry("a/b/two.bin", "cc", 100),
+            entry("a/b/c/three.bin", "dd", 1000),
+            entry("d/four.bin", "ee", 5),
+        ];
+        let report = build_map_report(
+            &entries,
+            "map.json",
+            &["dir-summary".to_string()],
+            10,
+            &ReportOptions::default(),
+        );
+        let dirs = report.dir_summary.unwrap();
+        let totals: Vec<(&str, u64, u64, u64)> = dirs
+            .iter()
+            .map(|d| {
+                (
+                    d.path.as_str(),
+                    d.recursive_bytes,
+                    d.recursive_files,
+                    d.bytes,
+                )
+            })
+            .collect();
+        assert_eq!(
+            totals,
+            [
+                (".", 1116, 5, 1),
+                ("a", 1110, 3, 10),
+                ("a/b", 1100, 2, 100),
+                ("a/b/c", 1000, 1, 1000),
+                ("d", 5, 1, 5),
+            ]
+        );
+        assert_eq!(summarize_dirs(&entries, 2).len(), 2);
+    }
+
+    #[test]
+    fn empty_files_excluded_from_duplicates_by_default() {
+        let empty = "af1349b9f5f9a1a6a0404dea36dcc9499bcb25c9adc112b7cc9a93cae41f3262";
+        let entries = vec![
+            entry("e1.txt", empty, 0),
+            entry("e2.txt", empty, 0),
+            entry("e3.txt", empty, 0),
+            entry("e4.txt", empty, 0),
+            entry("a/data.bin", "aa", 10),
+            entry("b/data.bin", "aa", 10),
+            entry("unique.txt", "bb", 5),
+        ];
+
+        let report = build_map_report(
+            &entries,
+            "map.json",
+            &sections(),
+            5,
+            &ReportOptions::default(),
+        );
+        let dupes = report.duplicates.unwrap();
+        assert_eq!(dupes.len(), 1);
+        assert_eq!(dupes[0].hash, "aa");
+        assert_eq!(dupes[0].paths, vec!["a/data.bin", "b/data.bin"]);
+        assert_eq!(dupes[0].wasted_bytes, 10);
+
+        // empties are still counted in the stats
+        let stats = report.stats.unwrap();
+        assert_eq!(stats.total_files, 7);
+        assert_eq!(stats.empty_files, 4);
+        assert_eq!(stats.duplicate_groups, 1);
+        assert_eq!(stats.wasted_bytes, 10);
+
+        let opts = ReportOptions {
+            include_empty: true,
+            ..Default::default()
+        };
+        let report = build_map_report(&entries, "map.json", &sections(), 5, &opts);
+        let dupes = report.duplicates.unwrap();
+        assert_eq!(dupes.len(), 2);
+        assert!(dupes.iter().any(|g| g.hash == empty && g.count == 4));
+    }
+
+    #[test]
+    fn map_report_lists_largest_and_respects_sections() {
+        let entries = vec![
+            entry("small.txt", "a", 1),
+            entry("big.iso", "b", 300),
+            entry("mid.txt", "c", 20),
+        ];
+        let report = build_map_report(
+            &entries,
+            "m",
+            &["largest".to_string()],
+            2,
+            &ReportOptions::default(),
+        );
+        assert!(report.stats.is_none());
+        assert!(report.duplicates.is_none());
+        let largest = report.largest_files.unwrap();
+        assert_eq!(largest.len(), 2);
+        assert_eq!(largest[0].path, "big.iso");
+        assert_eq!(largest[1].path, "mid.txt");
+
+        let report = build_map_report(&entries, "m", &sections(), 5, &ReportOptions::default());
+        let text = render_map_report_text(&report, true);
+        assert!(text.contains("Total files:      3"));
+        assert!(text.contains(".txt"));
+        // without --human the labels and exact counts read as they always have
+        let total: u64 = entries.iter().map(|e| e.size).sum();
+        let text = render_map_report_text(&report, false);
+        assert!(text.contains(&format!("Total bytes:      {}\n", total)));
+        assert!(text.contains(" bytes wasted)"));
+    }
+
+    #[test]
+    fn largest_entries_matches_full_sort() {
+        // xorshift keeps the "random" map reproducible; few distinct sizes force ties
+        let mut state = 0x2545_f491_4f6c_dd1du64;
+        let mut next = || {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            state
+        };
+        let entries: Vec<MapEntry> = (0..500)
+            .map(|i| {
+                let r = next();
+                entry(&format!("f{:03}-{}", r % 1000, i), "h", r % 37)
+            })
+            .collect();
+        for top_n in [0, 1, 5, 37, 499, 500, 800] {
+            let mut sorted = entries.clone();
+            sorted.sort_by(|a, b| b.size.cmp(&a.size).then_with(|| a.path.cmp(&b.path)));
+            sorted.truncate(top_n);
+            let heap: Vec<(String, u64)> = largest_entries(&entries, top_n)
+                .into_iter()
+                .map(|e| (e.path, e.size))
+                .collect();
+            let expected: Vec<(String, u64)> =
+                sorted.into_iter().map(|e| (e.path, e.size)).collect();
+            assert_eq!(heap, expected, "top_n = {}", top_n);
+        }
+    }
+
+    #[test]
+    fn churn_report_counts_and_net_bytes() {
+        let old = vec![
+            entry("keep.txt", "k", 10),
+            entry("grow.log", "g1", 100),
+            entry("shrink.db", "s1", 50),
+            entry("gone.tmp", "x", 30),
+            entry("old/name.bin", "m", 7),
+        ];
+        let new = vec![
+            entry("keep.txt", "k", 10),
+            entry("grow.log", "g2", 400),
+            entry("shrink.db", "s2", 20),
+            entry("fresh.txt", "f", 5),
+            entry("new/name.bin", "m", 7),
+        ];
+        let report = build_churn_report(old, new, "old.json", "new.json", 1);
+        assert_eq!(report.added, 1);
+        assert_eq!(report.removed, 1);
+        assert_eq!(report.changed, 2);
+        assert_eq!(report.moved, 1);
+        assert_eq!(report.unchanged, 1);
+        assert_eq!(report.bytes_before, 197);
+        assert_eq!(report.bytes_after, 442);
+        assert_eq!(report.net_bytes, 245);
+        assert_eq!(report.top_changed.len(), 1);
+        assert_eq!(report.top_changed[0].path, "grow.log");
+        assert_eq!(report.top_changed[0].delta_bytes, 300);
+
+        let text = render_churn_report_text(&report, false);
+        assert!(text.contains("Added:      1"));
+        assert!(text.contains("(+245)"));
+        let text = render_churn_report_text(&report, true);
+        assert!(text.contains("197 B -> 442 B (+245 B)"));
+    }
+
     #[test]
     fn render_empty_json() {
         let tmp = env::temp_dir();
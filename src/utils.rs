@@ -1,5 +1,9 @@
 use indicatif::ProgressBar;
+use std::collections::BTreeMap;
 use std::env;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
 use thiserror::Error;
 
 /// Initialize logging (simple wrapper around env_logger)
@@ -8,7 +12,10 @@ pub fn setup_logging() {
         // Default to info if user hasn't set RUST_LOG
         env::set_var("RUST_LOG", "info");
     }
-    env_logger::init();
+    // Logs always go to stderr so they never mix with maps or reports printed on stdout
+    env_logger::Builder::from_default_env()
+        .target(env_logger::Target::Stderr)
+        .init();
 }
 
 /// Simple progress-bar helper (placeholder for later phases)
@@ -16,6 +23,289 @@ pub fn new_progress(len: u64) -> ProgressBar {
     ProgressBar::new(len)
 }
 
+/// Normalize a recorded path into a platform-neutral relative form: forward slashes only,
+/// with Windows verbatim (`\\?\`), UNC (`\\server\share`) and drive (`C:`) prefixes
+/// dropped along with leading separators and `.` segments.
+pub fn portable_path(path: &str) -> String {
+    let mut s = path.replace('\\', "/");
+    if let Some(rest) = s.strip_prefix("//?/UNC/") {
+        s = rest.splitn(3, '/').nth(2).unwrap_or("").to_string();
+    } else if let Some(rest) = s.strip_prefix("//?/") {
+        s = rest.to_string();
+    } else if let Some(rest) = s.strip_prefix("//") {
+        s = rest.splitn(3, '/').nth(2).unwrap_or("").to_string();
+    }
+    let b = s.as_bytes();
+    if b.len() >= 2 && b[0].is_ascii_alphabetic() && b[1] == b':' {
+        s = s[2..].to_string();
+    }
+    s.split('/')
+        .filter(|c| !c.is_empty() && *c != ".")
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// Thread-safe tally of files skipped because of errors, grouped by `io::ErrorKind`.
+#[derive(Debug, Default)]
+pub struct ErrorTally {
+    counts: Mutex<BTreeMap<String, usize>>,
+}
+
+impl ErrorTally {
+    /// Count one failed file under the kind of the first I/O error in `err`'s chain
+    /// (`other` when it didn't come from I/O).
+    pub fn record(&self, err: &anyhow::Error) {
+        let label = if err.chain().any(|e| e.is::<PathTooLong>()) {
+            "path too long".to_string()
+        } else {
+            err.chain()
+                .find_map(|e| e.downcast_ref::<std::io::Error>())
+                .map(|e| error_kind_label(e.kind()))
+                .unwrap_or_else(|| "other".to_string())
+        };
+        *self.counts.lock().unwrap().entry(label).or_insert(0) += 1;
+    }
+
+    pub fn total(&self) -> usize {
+        self.counts.lock().unwrap().values().sum()
+    }
+
+    /// Counts per kind, most frequent first.
+    pub fn counts(&self) -> Vec<(String, usize)> {
+        let mut counts: Vec<(String, usize)> = self
+            .counts
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(k, v)| (k.clone(), *v))
+            .collect();
+        counts.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        counts
+    }
+
+    /// e.g. "12 files skipped due to errors (9 not found, 3 permission denied)";
+    /// `None` when nothing failed.
+    pub fn summary(&self) -> Option<String> {
+        let total = self.total();
+        if total == 0 {
+            return None;
+        }
+        let groups: Vec<String> = self
+            .counts()
+            .iter()
+            .map(|(kind, n)| format!("{} {}", n, kind))
+            .collect();
+        Some(format!(
+            "{} file{} skipped due to errors ({})",
+            total,
+            if total == 1 { "" } else { "s" },
+            groups.join(", ")
+        ))
+    }
+}
+
+/// `--max-errors`/`--max-error-rate`: how many failed files a run tolerates. Workers
+/// count each failure with [`ErrorBudget::fail`], which reports when the budget is blown
+/// so the scan can stop early.
+#[derive(Debug, Default)]
+pub struct ErrorBudget {
+    max_errors: Option<u64>,
+    /// Percentage of files, 0-100.
+    max_rate: Option<f64>,
+    failed: AtomicU64,
+    /// Files in the listing, once known (0 until then).
+    total: AtomicU64,
+}
+
+impl ErrorBudget {
+    pub fn new(max_errors: Option<u64>, max_rate: Option<f64>) -> Self {
+        Self {
+            max_errors,
+            max_rate,
+            ..Default::default()
+        }
+    }
+
+    /// Record the number of files the run will visit, so the rate limit can trip
+    /// before the scan ends.
+    pub fn set_total(&self, total: u64) {
+        self.total.store(total, Ordering::Relaxed);
+    }
+
+    /// Count one failed file; true when that exceeds a limit. The rate is measured
+    /// against the whole listing, so it only trips mid-run when the listing is known.
+    pub fn fail(&self) -> bool {
+        let failed = self.failed.fetch_add(1, Ordering::Relaxed) + 1;
+        let total = self.total.load(Ordering::Relaxed);
+        self.exceeded(failed, (total > 0).then_some(total))
+            .is_some()
+    }
+
+    /// Why a run of `processed` files broke the budget, if it did.
+    pub fn check(&self, processed: u64) -> Option<String> {
+        let total = self.total.load(Ordering::Relaxed).max(processed);
+        self.exceeded(self.failed.load(Ordering::Relaxed), Some(total))
+    }
+
+    fn exceeded(&self, failed: u64, total: Option<u64>) -> Option<String> {
+        if let Some(max) = self.max_errors.filter(|max| failed > *max) {
+            return Some(format!(
+                "{} files failed, more than --max-errors {}",
+                failed, max
+            ));
+        }
+        let (rate, total) = self.max_rate.zip(total.filter(|t| *t > 0))?;
+        (failed as f64 * 100.0 > rate * total as f64).then(|| {
+            format!(
+                "{} of {} files failed, more than --max-error-rate {}%",
+                failed, total, rate
+            )
+        })
+    }
+}
+
+fn error_kind_label(kind: std::io::ErrorKind) -> String {
+    match kind {
+        std::io::ErrorKind::NotFound => "not found".to_string(),
+        other => other.to_string(),
+    }
+}
+
+/// Windows' classic path length limit (`MAX_PATH`).
+pub const WINDOWS_MAX_PATH: usize = 260;
+
+/// A file that could not be accessed because its path exceeds the platform limit.
+#[derive(Error, Debug)]
+#[error("path too long ({len} characters){hint}")]
+pub struct PathTooLong {
+    pub len: usize,
+    hint: &'static str,
+    #[source]
+    source: std::io::Error,
+}
+
+/// Whether `err` from accessing `path` comes from a path length limit: `ENAMETOOLONG`,
+/// `ERROR_FILENAME_EXCED_RANGE`, or on Windows a "not found" for a non-verbatim path
+/// longer than `MAX_PATH`.
+pub fn is_path_too_long(err: &std::io::Error, path: &Path) -> bool {
+    let len = path.as_os_str().len();
+    match err.kind() {
+        std::io::ErrorKind::InvalidFilename => cfg!(unix) || len >= WINDOWS_MAX_PATH,
+        std::io::ErrorKind::NotFound => {
+            cfg!(windows) && len >= WINDOWS_MAX_PATH && !path.to_string_lossy().starts_with(r"\\?\")
+        }
+        _ => false,
+    }
+}
+
+/// Turn an I/O error from accessing `path` into `PathTooLong` when the length limit is
+/// the cause, so it is reported (and tallied) as such instead of a generic failure.
+pub fn classify_path_error(err: anyhow::Error, path: &Path) -> anyhow::Error {
+    match err.downcast::<std::io::Error>() {
+        Ok(io_err) if is_path_too_long(&io_err, path) => PathTooLong {
+            len: path.as_os_str().len(),
+            hint: if cfg!(windows) {
+                "; --long-paths lifts the 260-character limit"
+            } else {
+                ""
+            },
+            source: io_err,
+        }
+        .into(),
+        Ok(io_err) => io_err.into(),
+        Err(other) => other,
+    }
+}
+
+/// `path` in Windows extended-length form (`\\?\C:\...`, `\\?\UNC\server\share\...`) with
+/// `/` turned into `\`. Relative and already-verbatim paths are returned unchanged.
+pub fn extended_length_path(path: &str) -> String {
+    if path.starts_with(r"\\?\") || path.starts_with("//?/") {
+        return path.to_string();
+    }
+    let p = path.replace('/', "\\");
+    let b = p.as_bytes();
+    if let Some(unc) = p.strip_prefix(r"\\") {
+        format!(r"\\?\UNC\{}", unc)
+    } else if b.len() >= 3 && b[0].is_ascii_alphabetic() && b[1] == b':' && b[2] == b'\\' {
+        format!(r"\\?\{}", p)
+    } else {
+        path.to_string()
+    }
+}
+
+/// With `--long-paths`: the extended-length form of `path` on Windows; unchanged elsewhere.
+pub fn long_path(path: &Path) -> PathBuf {
+    if cfg!(windows) {
+        PathBuf::from(extended_length_path(&path.to_string_lossy()))
+    } else {
+        path.to_path_buf()
+    }
+}
+
+/// `path` with `.` components dropped and each `..` folded into the component before it,
+/// without touching the filesystem.
+pub fn normalize_lexically(path: &Path) -> PathBuf {
+    use std::path::Component;
+    let mut out = PathBuf::new();
+    for comp in path.components() {
+        match comp {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                if !out.pop() {
+                    out.push("..");
+                }
+            }
+            other => out.push(other.as_os_str()),
+        }
+    }
+    out
+}
+
+/// Canonical spelling of a listed input file, so `./a`, `a` and `/abs/root/a` agree: the
+/// parent directory is resolved through the filesystem, the file name is kept as given
+/// (a listed symlink still names itself). Paths whose parent doesn't exist are only
+/// normalized lexically.
+pub fn canonical_input_path(path: &Path) -> PathBuf {
+    let path = normalize_lexically(path);
+    match (path.parent(), path.file_name()) {
+        (Some(parent), Some(name)) => std::fs::canonicalize(if parent.as_os_str().is_empty() {
+            Path::new(".")
+        } else {
+            parent
+        })
+        .map(|p| p.join(name))
+        .unwrap_or(path),
+        _ => path,
+    }
+}
+
+/// Canonicalize `paths` and drop repeats, keeping each path's first position.
+pub fn dedupe_input_paths(paths: Vec<PathBuf>) -> Vec<PathBuf> {
+    let mut seen = std::collections::HashSet::new();
+    paths
+        .into_iter()
+        .map(|p| canonical_input_path(&p))
+        .filter(|p| seen.insert(p.clone()))
+        .collect()
+}
+
+/// Human-readable size with binary units, e.g. `512 B`, `1.5 KiB`, `1.2 GiB`.
+pub fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 7] = ["B", "KiB", "MiB", "GiB", "TiB", "PiB", "EiB"];
+    if bytes < 1024 {
+        return format!("{} B", bytes);
+    }
+    let mut value = bytes as f64;
+    let mut unit = 0;
+    // Step up at 1023.95 so values that would round to "1024.0" print in the next unit
+    while value >= 1023.95 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    format!("{:.1} {}", value, UNITS[unit])
+}
+
 #[derive(Error, Debug)]
 pub enum AppError {
     #[error("IO error: {0}")]
@@ -27,3 +317,127 @@ pub enum AppError {
     #[error("Generic error: {0}")]
     Anyhow(#[from] anyhow::Error),
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn format_bytes_boundaries() {
+        assert_eq!(format_bytes(0), "0 B");
+        assert_eq!(format_bytes(1023), "1023 B");
+        assert_eq!(format_bytes(1024), "1.0 KiB");
+        assert_eq!(format_bytes(1536), "1.5 KiB");
+        assert_eq!(format_bytes(1_048_575), "1.0 MiB");
+        assert_eq!(format_bytes(1_048_576), "1.0 MiB");
+        assert_eq!(format_bytes(1_288_490_189), "1.2 GiB");
+        assert_eq!(format_bytes(1 << 40), "1.0 TiB");
+        assert_eq!(format_bytes(u64::MAX), "16.0 EiB");
+    }
+
+    #[test]
+    fn portable_path_normalizes_windows_forms() {
+        assert_eq!(portable_path(r"sub\dir\file.txt"), "sub/dir/file.txt");
+        assert_eq!(portable_path(r"C:\data\file.txt"), "data/file.txt");
+        assert_eq!(portable_path(r"\\?\C:\data\file.txt"), "data/file.txt");
+        assert_eq!(portable_path(r"\\server\share\data\file.txt"), "data/file.txt");
+        assert_eq!(
+            portable_path(r"\\?\UNC\server\share\data\file.txt"),
+            "data/file.txt"
+        );
+    }
+
+    #[test]
+    fn portable_path_matches_posix_relative_form() {
+        assert_eq!(portable_path("sub/dir/file.txt"), "sub/dir/file.txt");
+        assert_eq!(portable_path("./sub//file.txt"), "sub/file.txt");
+        assert_eq!(
+            portable_path(r"D:\tree\a\b.txt"),
+            portable_path("/tree/a/b.txt")
+        );
+    }
+
+    #[test]
+    fn extended_length_path_prefixes_absolute_windows_paths() {
+        assert_eq!(
+            extended_length_path(r"C:\data\deep\file.txt"),
+            r"\\?\C:\data\deep\file.txt"
+        );
+        assert_eq!(extended_length_path("D:/tree/a.txt"), r"\\?\D:\tree\a.txt");
+        assert_eq!(
+            extended_length_path(r"\\server\share\f.txt"),
+            r"\\?\UNC\server\share\f.txt"
+        );
+        assert_eq!(extended_length_path(r"\\?\C:\already"), r"\\?\C:\already");
+        assert_eq!(extended_length_path(r"rel\file.txt"), r"rel\file.txt");
+        assert_eq!(extended_length_path("/unix/path"), "/unix/path");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn overlong_name_is_classified_as_path_too_long() {
+        let path = std::env::temp_dir().join("x".repeat(300));
+        let err = std::fs::File::open(&path).unwrap_err();
+        assert!(is_path_too_long(&err, &path));
+        let err = classify_path_error(err.into(), &path);
+        assert!(err.is::<PathTooLong>());
+
+        let tally = ErrorTally::default();
+        tally.record(&err);
+        assert_eq!(tally.counts(), vec![("path too long".to_string(), 1)]);
+
+        let missing = std::env::temp_dir().join("hash-folderoo-no-such-file");
+        let err = std::fs::File::open(&missing).unwrap_err();
+        assert!(!is_path_too_long(&err, &missing));
+    }
+
+    #[test]
+    fn error_budget_trips_on_count_and_rate() {
+        let budget = ErrorBudget::new(Some(2), None);
+        assert!(!budget.fail());
+        assert!(!budget.fail());
+        assert!(budget.fail());
+        assert!(budget.check(10).unwrap().contains("--max-errors 2"));
+
+        // Without a listing the rate is only judged once the run's size is known
+        let budget = ErrorBudget::new(None, Some(25.0));
+        assert!(!budget.fail());
+        assert!(budget.check(4).is_none(), "1 of 4 is exactly 25%");
+        assert!(budget.check(3).unwrap().contains("1 of 3 files failed"));
+
+        let budget = ErrorBudget::new(None, Some(25.0));
+        budget.set_total(8);
+        assert!(!budget.fail());
+        assert!(!budget.fail());
+        assert!(budget.fail(), "3 of 8 is over 25%");
+    }
+
+    #[test]
+    fn error_tally_groups_by_kind() {
+        use std::io::{Error, ErrorKind};
+        let tally = ErrorTally::default();
+        assert_eq!(tally.summary(), None);
+
+        for _ in 0..2 {
+            tally.record(&anyhow::Error::new(Error::from(ErrorKind::NotFound)));
+        }
+        tally.record(
+            &anyhow::Error::new(Error::from(ErrorKind::PermissionDenied)).context("open a.txt"),
+        );
+        tally.record(&anyhow::anyhow!("digest mismatch"));
+
+        assert_eq!(tally.total(), 4);
+        assert_eq!(
+            tally.counts(),
+            vec![
+                ("not found".to_string(), 2),
+                ("other".to_string(), 1),
+                ("permission denied".to_string(), 1),
+            ]
+        );
+        assert_eq!(
+            tally.summary().unwrap(),
+            "4 files skipped due to errors (2 not found, 1 other, 1 permission denied)"
+        );
+    }
+}
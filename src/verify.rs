@@ -0,0 +1,435 @@
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::fs::File;
+use std::path::Path;
+
+use anyhow::{Context, Result};
+
+use crate::algorithms::AlgorithmParams;
+use crate::hash::DigestEncoding;
+use crate::io::{self, EntryKind, MapEntry};
+
+/// A structural problem found in a stored hash map.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MapIssue {
+    /// Where the problem is, e.g. `entry 3` (JSON) or `line 4` (CSV).
+    pub location: String,
+    pub message: String,
+}
+
+impl fmt::Display for MapIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.location, self.message)
+    }
+}
+
+/// Result of checking a map file: the number of entries inspected and any problems.
+#[derive(Debug, Clone, Default)]
+pub struct MapCheck {
+    pub entries: usize,
+    pub issues: Vec<MapIssue>,
+}
+
+/// Tracks cross-entry invariants (unique paths, consistent digest length). Rows of a
+/// multi-algorithm CSV map are tracked per algorithm.
+#[derive(Default)]
+struct EntryChecker {
+    encoding: DigestEncoding,
+    /// Algorithm named by the current row's `algorithm` column, if any.
+    algorithm: Option<String>,
+    seen_paths: HashSet<(String, Option<String>)>,
+    hash_len: HashMap<Option<String>, usize>,
+    check: MapCheck,
+}
+
+impl EntryChecker {
+    fn issue(&mut self, location: &str, message: String) {
+        self.check.issues.push(MapIssue {
+            location: location.to_string(),
+            message,
+        });
+    }
+
+    fn check_entry(
+        &mut self,
+        location: &str,
+        path: Option<&str>,
+        hash: Option<&str>,
+        size: Result<(), String>,
+    ) {
+        self.check.entries += 1;
+        match path {
+            Some(p) if !p.is_empty() => {
+                if !self
+                    .seen_paths
+                    .insert((p.to_string(), self.algorithm.clone()))
+                {
+                    self.issue(location, format!("duplicate path {:?}", p));
+                }
+            }
+            _ => self.issue(location, "missing or empty path".to_string()),
+        }
+        match hash {
+            Some(h) if !h.is_empty() => {
+                let valid = match self.encoding {
+                    DigestEncoding::Hex => {
+                        h.len() % 2 == 0 && h.bytes().all(|b| b.is_ascii_hexdigit())
+                    }
+                    other => other.decode(h).is_ok(),
+                };
+                if !valid {
+                    let name = self.encoding.name();
+                    self.issue(location, format!("hash {:?} is not valid {}", h, name));
+                } else {
+                    match self.hash_len.get(&self.algorithm).copied() {
+                        None => {
+                            self.hash_len.insert(self.algorithm.clone(), h.len());
+                        }
+                        Some(expected) if expected != h.len() => self.issue(
+                            location,
+                            format!(
+                                "hash length {} differs from {} used by earlier entries",
+                                h.len(),
+                                expected
+                            ),
+                        ),
+                        Some(_) => {}
+                    }
+                }
+            }
+            _ => self.issue(location, "missing or empty hash".to_string()),
+        }
+        if let Err(msg) = size {
+            self.issue(location, msg);
+        }
+    }
+}
+
+fn check_json(text: &str) -> MapCheck {
+    let mut checker = EntryChecker::default();
+    let v: serde_json::Value = match serde_json::from_str(text) {
+        Ok(v) => v,
+        Err(e) => {
+            checker.issue(
+                &format!("line {}, column {}", e.line(), e.column()),
+                format!("invalid JSON: {}", e),
+            );
+            return checker.check;
+        }
+    };
+    match io::header_digest_encoding(&v) {
+        Ok(encoding) => checker.encoding = encoding,
+        Err(e) => checker.issue("header", e.to_string()),
+    }
+    let entries = match v.get("entries").unwrap_or(&v).as_array() {
+        Some(a) => a,
+        None => {
+            checker.issue("map", "no entries array found".to_string());
+            return checker.check;
+        }
+    };
+    for (idx, e) in entries.iter().enumerate() {
+        let location = format!("entry {}", idx + 1);
+        if !e.is_object() {
+            checker.issue(&location, "entry is not an object".to_string());
+            checker.check.entries += 1;
+            continue;
+        }
+        let size = match e.get("size") {
+            Some(s) if s.is_u64() => Ok(()),
+            Some(s) => Err(format!("size {} is not a non-negative integer", s)),
+            None => Err("missing size".to_string()),
+        };
+        checker.check_entry(
+            &location,
+            e.get("path").and_then(|p| p.as_str()),
+            e.get("hash").and_then(|h| h.as_str()),
+            size,
+        );
+    }
+    checker.check
+}
+
+fn check_csv(path: &Path) -> Result<MapCheck> {
+    let mut checker = EntryChecker::default();
+    let opts = io::CsvOptions::detect_file(path)?;
+    let file = File::open(path).with_context(|| format!("open csv {:?}", path))?;
+    let mut rdr = opts.reader(file);
+    let headers = if !opts.header {
+        csv::StringRecord::from(io::MAP_CSV_COLUMNS.to_vec())
+    } else {
+        match rdr.headers() {
+            Ok(h) => h.clone(),
+            Err(e) => {
+                checker.issue("line 1", format!("invalid header: {}", e));
+                return Ok(checker.check);
+            }
+        }
+    };
+    let col = |name: &str| headers.iter().position(|h| h == name);
+    let (path_col, hash_col, size_col) = (col("path"), col("hash"), col("size"));
+    let algorithm_col = col("algorithm");
+    for (name, idx) in [("path", path_col), ("hash", hash_col), ("size", size_col)] {
+        if idx.is_none() {
+            checker.issue("line 1", format!("missing {} column", name));
+        }
+    }
+    if !checker.check.issues.is_empty() {
+        return Ok(checker.check);
+    }
+
+    for result in rdr.records() {
+        let record = match result {
+            Ok(r) => r,
+            Err(e) => {
+                let line = e.position().map(|p| p.line()).unwrap_or(0);
+                checker.issue(&format!("line {}", line), format!("malformed row: {}", e));
+                checker.check.entries += 1;
+                continue;
+            }
+        };
+        let line = record.position().map(|p| p.line()).unwrap_or(0);
+        let field = |idx: Option<usize>| idx.and_then(|i| record.get(i));
+        checker.algorithm = field(algorithm_col)
+            .filter(|a| !a.is_empty())
+            .map(str::to_string);
+        let size = match field(size_col) {
+            Some(s) if s.parse::<u64>().is_ok() => Ok(()),
+            Some(s) => Err(format!("size {:?} is not a non-negative integer", s)),
+            None => Err("missing size".to_string()),
+        };
+        checker.check_entry(
+            &format!("line {}", line),
+            field(path_col),
+            field(hash_col),
+            size,
+        );
+    }
+    Ok(checker.check)
+}
+
+/// Structurally validate a stored map (JSON or CSV, chosen by extension) without
+/// touching the files it describes.
+pub fn verify_map_file(path: &Path) -> Result<MapCheck> {
+    let is_csv = path
+        .extension()
+        .map(|e| e.eq_ignore_ascii_case("csv"))
+        .unwrap_or(false);
+    if is_csv {
+        check_csv(path)
+    } else {
+        let text = std::fs::read_to_string(path).with_context(|| format!("read map {:?}", path))?;
+        Ok(check_json(&text))
+    }
+}
+
+/// Result of checking the files a map describes against their recorded digests.
+#[derive(Debug, Clone, Default)]
+pub struct ContentCheck {
+    pub ok: usize,
+    pub modified: Vec<String>,
+    pub missing: Vec<String>,
+    /// Files that exist but could not be read, as `path: error`.
+    pub unreadable: Vec<String>,
+    /// How many files were actually rehashed.
+    pub hashed: usize,
+}
+
+impl ContentCheck {
+    pub fn is_clean(&self) -> bool {
+        self.modified.is_empty() && self.missing.is_empty() && self.unreadable.is_empty()
+    }
+}
+
+/// Check each entry's file under `root` against its recorded digest, rehashing it with
+/// `hash`. With `size_first`, a file whose size differs from the map is reported modified
+/// without being read, so only size-matching files are hashed.
+pub fn verify_contents<F>(
+    entries: &[MapEntry],
+    root: &Path,
+    size_first: bool,
+    mut hash: F,
+) -> ContentCheck
+where
+    F: FnMut(&Path, &MapEntry) -> Result<String>,
+{
+    let mut check = ContentCheck::default();
+    for entry in entries {
+        let path = root.join(&entry.path);
+        // symlinks hashed by target path are checked as links, not followed
+        let metadata = if entry.kind == Some(EntryKind::Symlink) {
+            std::fs::symlink_metadata(&path)
+                .ok()
+                .filter(|m| m.is_symlink())
+        } else {
+            std::fs::metadata(&path).ok().filter(|m| m.is_file())
+        };
+        let size = match metadata {
+            Some(m) => m.len(),
+            None => {
+                check.missing.push(entry.path.clone());
+                continue;
+            }
+        };
+        if size_first && size != entry.size {
+            check.modified.push(entry.path.clone());
+            continue;
+        }
+        check.hashed += 1;
+        match hash(&path, entry) {
+            Ok(h) if h.eq_ignore_ascii_case(&entry.hash) => check.ok += 1,
+            Ok(_) => check.modified.push(entry.path.clone()),
+            Err(e) => check.unreadable.push(format!("{}: {}", entry.path, e)),
+        }
+    }
+    check
+}
+
+/// Algorithm name and parameters recorded in a JSON map header (or in the `algorithm`
+/// column of a CSV map), if any.
+pub fn load_map_algorithm(path: &Path) -> Result<Option<(String, AlgorithmParams)>> {
+    if io::format_for_path(path) == Some("csv") {
+        // only the first row is needed; CSV maps name the algorithm on every row
+        let first = io::stream_map_entries(path)
+            .ok()
+            .and_then(|mut entries| entries.next())
+            .and_then(Result::ok);
+        return Ok(first
+            .and_then(|e| e.algorithm)
+            .map(|name| (name, AlgorithmParams::default())));
+    }
+    let text = std::fs::read_to_string(path).with_context(|| format!("read map {:?}", path))?;
+    let v: serde_json::Value = match serde_json::from_str(&text) {
+        Ok(v) => v,
+        Err(_) => return Ok(None),
+    };
+    let name = match v.pointer("/algorithm/name").and_then(|n| n.as_str()) {
+        Some(name) => name,
+        None => return Ok(None),
+    };
+    let param = |key: &str| v.pointer(&format!("/algorithm/params/{}", key));
+    let params = AlgorithmParams {
+        block_size: param("block_size")
+            .and_then(|b| b.as_u64())
+            .map(|b| b as usize),
+        customization: param("customization")
+            .and_then(|c| c.as_str())
+            .map(str::to_string),
+    };
+    Ok(Some((name.to_string(), params)))
+}
+
+/// Digest length in bytes a map was written with: the `xof_length` in a JSON map
+/// header, or the length of the first digest in a CSV map. `None` means the
+/// algorithm's default length.
+pub fn load_map_output_len(path: &Path) -> Result<Option<usize>> {
+    if io::format_for_path(path) == Some("csv") {
+        let first = io::stream_map_entries(path)
+            .ok()
+            .and_then(|mut entries| entries.next())
+            .and_then(Result::ok);
+        return Ok(first.map(|e| e.hash.len() / 2).filter(|len| *len > 0));
+    }
+    let text = std::fs::read_to_string(path).with_context(|| format!("read map {:?}", path))?;
+    let v: serde_json::Value = match serde_json::from_str(&text) {
+        Ok(v) => v,
+        Err(_) => return Ok(None),
+    };
+    Ok(v.pointer("/algorithm/params/xof_length")
+        .and_then(|l| l.as_u64())
+        .map(|l| l as usize))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn json_map_flags_bad_entries() {
+        let text = r#"{"entries":[
+            {"path":"a.txt","hash":"00ff","size":1},
+            {"path":"b.txt","hash":"zz11","size":2},
+            {"path":"a.txt","hash":"abcdef","size":-3},
+            {"path":"","hash":"0011"}
+        ]}"#;
+        let check = check_json(text);
+        assert_eq!(check.entries, 4);
+        let rendered: Vec<String> = check.issues.iter().map(|i| i.to_string()).collect();
+        assert!(rendered.contains(&"entry 2: hash \"zz11\" is not valid hex".to_string()));
+        assert!(rendered
+            .iter()
+            .any(|m| m.starts_with("entry 3: duplicate path")));
+        assert!(rendered
+            .iter()
+            .any(|m| m.starts_with("entry 3: hash length 6")));
+        assert!(rendered.iter().any(|m| m.starts_with("entry 3: size -3")));
+        assert!(rendered.contains(&"entry 4: missing or empty path".to_string()));
+        assert!(rendered.contains(&"entry 4: missing size".to_string()));
+    }
+
+    #[test]
+    fn truncated_json_reports_position() {
+        let check = check_json("{\"entries\": [\n  {\"path\": \"a\"");
+        assert_eq!(check.issues.len(), 1);
+        assert!(check.issues[0].location.starts_with("line 2"));
+    }
+
+    #[test]
+    fn csv_map_reports_line_numbers() {
+        let dir = tempdir().unwrap();
+        let p = dir.path().join("map.csv");
+        std::fs::write(&p, "path,hash,size\na.txt,00ff,1\nb.txt,00gg,x\n").unwrap();
+        let check = verify_map_file(&p).unwrap();
+        assert_eq!(check.entries, 2);
+        assert_eq!(check.issues.len(), 2);
+        assert!(check.issues.iter().all(|i| i.location == "line 3"));
+    }
+
+    #[test]
+    fn resized_files_are_modified_without_hashing() {
+        let dir = tempdir().unwrap();
+        std::fs::write(dir.path().join("same.txt"), b"same").unwrap();
+        std::fs::write(dir.path().join("grown.txt"), b"grown by a lot").unwrap();
+        let entry = |path: &str, size| MapEntry {
+            path: path.to_string(),
+            hash: "ab".to_string(),
+            size,
+            ..Default::default()
+        };
+        let entries = vec![
+            entry("same.txt", 4),
+            entry("grown.txt", 5),
+            entry("gone.txt", 1),
+        ];
+
+        let mut calls = Vec::new();
+        let check = verify_contents(&entries, dir.path(), true, |_, e| {
+            calls.push(e.path.clone());
+            Ok("AB".to_string())
+        });
+        assert_eq!(calls, ["same.txt"]);
+        assert_eq!(check.hashed, 1);
+        assert_eq!(check.ok, 1);
+        assert_eq!(check.modified, ["grown.txt"]);
+        assert_eq!(check.missing, ["gone.txt"]);
+
+        // without the size shortcut every present file is hashed
+        let check = verify_contents(&entries, dir.path(), false, |_, _| Ok("ab".to_string()));
+        assert_eq!(check.hashed, 2);
+        assert_eq!(check.ok, 2);
+    }
+
+    #[test]
+    fn csv_map_algorithm_comes_from_the_first_row() {
+        let dir = tempdir().unwrap();
+        let p = dir.path().join("map.csv");
+        std::fs::write(
+            &p,
+            "path,hash,size,algorithm\na,aa,1,sha256\nb,bb,not-a-size,md5\n",
+        )
+        .unwrap();
+        let (name, _) = load_map_algorithm(&p).unwrap().unwrap();
+        assert_eq!(name, "sha256");
+    }
+}
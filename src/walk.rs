@@ -1,15 +1,18 @@
 use anyhow::{Context, Result};
-use globset::{Glob, GlobSet, GlobSetBuilder};
+use globset::{GlobBuilder, GlobSet, GlobSetBuilder};
 use std::path::{Path, PathBuf};
 use walkdir::WalkDir;
 
-fn build_globset(exclusions: &[String]) -> Result<Option<GlobSet>> {
+fn build_globset(exclusions: &[String], case_insensitive: bool) -> Result<Option<GlobSet>> {
     if exclusions.is_empty() {
         return Ok(None);
     }
     let mut builder = GlobSetBuilder::new();
     for pat in exclusions {
-        let g = Glob::new(pat).with_context(|| format!("invalid glob pattern: {}", pat))?;
+        let g = GlobBuilder::new(pat)
+            .case_insensitive(case_insensitive)
+            .build()
+            .with_context(|| format!("invalid glob pattern: {}", pat))?;
         builder.add(g);
     }
     Ok(Some(builder.build().context("failed to build globset")?))
@@ -19,6 +22,37 @@ pub struct WalkStream {
     root: PathBuf,
     walker: walkdir::IntoIter,
     globset: Option<GlobSet>,
+    /// Exclusion patterns `globset` was built from.
+    exclusions: Vec<String>,
+    /// Device of `root` when the walk must stay on one filesystem.
+    root_dev: Option<u64>,
+    max_depth: Option<usize>,
+    follow_symlinks: bool,
+    /// Root-relative path; files at or before it (in sorted order) are skipped.
+    resume_after: Option<PathBuf>,
+    /// Lowercase extensions (no dot) a file must have to be yielded; empty yields all.
+    extensions: Vec<String>,
+    /// Yield symlinks themselves (when not following them) alongside regular files.
+    symlinks: bool,
+}
+
+fn build_walker(
+    root: &Path,
+    max_depth: Option<usize>,
+    follow_symlinks: bool,
+    sorted: bool,
+) -> walkdir::IntoIter {
+    let mut walk_builder = WalkDir::new(root);
+    if let Some(depth) = max_depth {
+        walk_builder = walk_builder.max_depth(depth);
+    }
+    if follow_symlinks {
+        walk_builder = walk_builder.follow_links(true);
+    }
+    if sorted {
+        walk_builder = walk_builder.sort_by_file_name();
+    }
+    walk_builder.into_iter()
 }
 
 impl WalkStream {
@@ -28,40 +62,192 @@ impl WalkStream {
         max_depth: Option<usize>,
         follow_symlinks: bool,
     ) -> Result<Self> {
-        let globset = build_globset(exclusions)?;
-        let mut walk_builder = WalkDir::new(&root);
-        if let Some(depth) = max_depth {
-            walk_builder = walk_builder.max_depth(depth);
-        }
-        if follow_symlinks {
-            walk_builder = walk_builder.follow_links(true);
-        }
+        let globset = build_globset(exclusions, false)?;
         Ok(Self {
+            walker: build_walker(&root, max_depth, follow_symlinks, false),
             root,
-            walker: walk_builder.into_iter(),
             globset,
+            exclusions: exclusions.to_vec(),
+            root_dev: None,
+            max_depth,
+            follow_symlinks,
+            resume_after: None,
+            extensions: Vec::new(),
+            symlinks: false,
         })
     }
+
+    /// Walk in sorted order (by path component) and skip every file whose root-relative
+    /// path sorts at or before `marker`, so an interrupted run can pick up after the last
+    /// file it finished. Directories lying wholly before the marker are not descended into.
+    pub fn resume_after(mut self, marker: Option<PathBuf>) -> Self {
+        if let Some(marker) = marker {
+            self.walker = build_walker(&self.root, self.max_depth, self.follow_symlinks, true);
+            self.resume_after = Some(marker);
+        }
+        self
+    }
+
+    /// Match the exclusion globs ignoring case, so `*.log` also leaves out `ERROR.LOG`.
+    pub fn exclude_case_insensitive(mut self, enabled: bool) -> Result<Self> {
+        if enabled {
+            self.globset = build_globset(&self.exclusions, true)?;
+        }
+        Ok(self)
+    }
+
+    /// Only yield files whose extension is one of `extensions` (case-insensitive, leading
+    /// dot optional). Checked on the directory entry name alone, before any glob matching.
+    pub fn extensions(mut self, extensions: &[String]) -> Self {
+        self.extensions = extensions
+            .iter()
+            .map(|ext| ext.trim().trim_start_matches('.').to_ascii_lowercase())
+            .filter(|ext| !ext.is_empty())
+            .collect();
+        self
+    }
+
+    /// Also yield symlinks instead of skipping them. Only has an effect when symlinks
+    /// aren't followed, since a followed link reports its target's type.
+    pub fn symlinks(mut self, enabled: bool) -> Self {
+        self.symlinks = enabled;
+        self
+    }
+
+    /// Don't descend into directories on a different device than the root (like
+    /// `find -xdev`). Only supported on Unix; elsewhere this logs a warning and is ignored.
+    pub fn one_file_system(mut self, enabled: bool) -> Self {
+        if !enabled {
+            return self;
+        }
+        if cfg!(unix) {
+            self.root_dev = device_id(&self.root);
+        } else {
+            log::warn!("--one-file-system is only supported on Unix; ignoring");
+        }
+        self
+    }
 }
 
-impl Iterator for WalkStream {
-    type Item = PathBuf;
+impl WalkStream {
+    /// Whether the walk would yield `path`: it lies under the root within `max_depth`, is
+    /// reached without crossing an unfollowed symlink (or another device under
+    /// `one_file_system`), is a regular file (or a symlink when those are yielded) and
+    /// passes the extension, resume and exclusion filters. Holds explicitly listed paths
+    /// to the same rules as a walk.
+    pub fn admits(&self, path: &Path) -> bool {
+        let rel = match path.strip_prefix(&self.root) {
+            Ok(rel) => rel,
+            Err(_) => return false,
+        };
+        if self
+            .max_depth
+            .is_some_and(|max| rel.components().count() > max)
+        {
+            return false;
+        }
+        let mut dir = self.root.clone();
+        for component in rel.parent().into_iter().flat_map(Path::components) {
+            dir.push(component);
+            match std::fs::symlink_metadata(&dir) {
+                Ok(meta) if meta.file_type().is_symlink() && !self.follow_symlinks => return false,
+                Ok(_) => {}
+                Err(_) => return false,
+            }
+            if self.root_dev.is_some() {
+                let dev = std::fs::metadata(&dir)
+                    .ok()
+                    .as_ref()
+                    .and_then(metadata_device);
+                if crosses_device(self.root_dev, dev) {
+                    return false;
+                }
+            }
+        }
+        let hashable = match std::fs::symlink_metadata(path) {
+            Ok(meta) if meta.file_type().is_symlink() => {
+                if self.follow_symlinks {
+                    std::fs::metadata(path).is_ok_and(|m| m.is_file())
+                } else {
+                    self.symlinks
+                }
+            }
+            Ok(meta) => meta.is_file(),
+            Err(_) => false,
+        };
+        if !hashable {
+            return false;
+        }
+        if !self.extensions.is_empty() {
+            let wanted = path
+                .extension()
+                .is_some_and(|ext| self.extensions.iter().any(|x| ext.eq_ignore_ascii_case(x)));
+            if !wanted {
+                return false;
+            }
+        }
+        if self
+            .resume_after
+            .as_ref()
+            .is_some_and(|marker| rel <= marker.as_path())
+        {
+            return false;
+        }
+        !self.globset.as_ref().is_some_and(|gs| gs.is_match(rel))
+    }
 
-    fn next(&mut self) -> Option<Self::Item> {
-        for entry in self.walker.by_ref() {
+    /// Yield each file with its size, read from the directory entry's metadata (so a
+    /// followed symlink reports its target's size). Unreadable metadata counts as 0.
+    pub fn with_sizes(self) -> SizedWalk {
+        SizedWalk(self)
+    }
+
+    fn next_entry(&mut self) -> Option<walkdir::DirEntry> {
+        while let Some(entry) = self.walker.next() {
             match entry {
                 Ok(e) => {
-                    if !e.file_type().is_file() {
+                    if e.file_type().is_dir() {
+                        if let Some(marker) = &self.resume_after {
+                            let rel = e.path().strip_prefix(&self.root).unwrap_or(e.path());
+                            if e.depth() > 0 && rel < marker.as_path() && !marker.starts_with(rel) {
+                                self.walker.skip_current_dir();
+                                continue;
+                            }
+                        }
+                        if e.depth() > 0 && self.root_dev.is_some() {
+                            let dev = e.metadata().ok().as_ref().and_then(metadata_device);
+                            if crosses_device(self.root_dev, dev) {
+                                log::debug!("not crossing into {}", e.path().display());
+                                self.walker.skip_current_dir();
+                            }
+                        }
+                        continue;
+                    }
+                    let hashable =
+                        e.file_type().is_file() || (self.symlinks && e.file_type().is_symlink());
+                    if !hashable {
                         continue;
                     }
-                    let path = e.into_path();
-                    let rel = path.strip_prefix(&self.root).unwrap_or(&path);
+                    if !self.extensions.is_empty() {
+                        let wanted = e.path().extension().is_some_and(|ext| {
+                            self.extensions.iter().any(|x| ext.eq_ignore_ascii_case(x))
+                        });
+                        if !wanted {
+                            continue;
+                        }
+                    }
+                    let rel = e.path().strip_prefix(&self.root).unwrap_or(e.path());
+                    if let Some(marker) = &self.resume_after {
+                        if rel <= marker.as_path() {
+                            continue;
+                        }
+                    }
                     if let Some(gs) = &self.globset {
                         if gs.is_match(rel) {
                             continue;
                         }
                     }
-                    return Some(path);
+                    return Some(e);
                 }
                 Err(_) => continue,
             }
@@ -70,11 +256,33 @@ impl Iterator for WalkStream {
     }
 }
 
+impl Iterator for WalkStream {
+    type Item = PathBuf;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_entry().map(walkdir::DirEntry::into_path)
+    }
+}
+
+/// [`WalkStream`] yielding `(path, size)` pairs; see [`WalkStream::with_sizes`].
+pub struct SizedWalk(WalkStream);
+
+impl Iterator for SizedWalk {
+    type Item = (PathBuf, u64);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let entry = self.0.next_entry()?;
+        let size = entry.metadata().map(|m| m.len()).unwrap_or(0);
+        Some((entry.into_path(), size))
+    }
+}
+
 /// Walk a directory and return a list of file paths, excluding patterns.
 ///
 /// `root` - root directory to walk.
 /// `exclusions` - list of glob patterns (relative to `root`) to exclude, e.g. `["target/**", "**/.git/**"]`.
-/// `max_depth` - optional depth cap.
+/// `max_depth` - optional depth cap counted from `root`: 0 is `root` itself (so a directory
+///   yields nothing), 1 its immediate children, 2 their children, and so on.
 /// `follow_symlinks` - whether to follow symlinked directories.
 pub fn walk_directory<P: AsRef<Path>>(
     root: P,
@@ -96,10 +304,70 @@ pub fn walk_directory_stream<P: AsRef<Path>>(
     WalkStream::new(root_buf, exclusions, max_depth, follow_symlinks)
 }
 
+/// Device id (`st_dev`) of the filesystem holding `path`. Always `None` on non-Unix.
+pub fn device_id(path: &Path) -> Option<u64> {
+    std::fs::metadata(path)
+        .ok()
+        .as_ref()
+        .and_then(metadata_device)
+}
+
+fn metadata_device(meta: &std::fs::Metadata) -> Option<u64> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::MetadataExt;
+        Some(meta.dev())
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = meta;
+        None
+    }
+}
+
+/// `<dev>:<ino>` identifying the data behind a file with more than one hard link, so
+/// links can be coalesced. `None` for singly-linked files and on non-Unix platforms.
+pub fn hardlink_key(meta: &std::fs::Metadata) -> Option<String> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::MetadataExt;
+        (meta.nlink() > 1).then(|| format!("{}:{}", meta.dev(), meta.ino()))
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = meta;
+        None
+    }
+}
+
+/// True when both devices are known and differ, i.e. `dev` lies across a mount point.
+pub fn crosses_device(root_dev: Option<u64>, dev: Option<u64>) -> bool {
+    matches!((root_dev, dev), (Some(root), Some(d)) if root != d)
+}
+
+/// Returns true when `path` is neither a regular file nor a directory, e.g. a block or
+/// character device, FIFO or socket. Such paths are skipped by the walker.
+pub fn is_special_file(path: &Path) -> bool {
+    let meta = match std::fs::metadata(path) {
+        Ok(m) => m,
+        Err(_) => return false,
+    };
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::FileTypeExt;
+        let ft = meta.file_type();
+        ft.is_block_device() || ft.is_char_device() || ft.is_fifo() || ft.is_socket()
+    }
+    #[cfg(not(unix))]
+    {
+        !meta.is_file() && !meta.is_dir()
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use std::fs::{create_dir_all, File};
+    use std::fs::{create_dir_all, write, File};
     use tempfile::tempdir;
 
     #[test]
@@ -131,4 +399,156 @@ mod tests {
         assert_eq!(shallow.len(), 1);
         assert!(shallow.iter().any(|p| p.ends_with("top.txt")));
     }
+
+    #[test]
+    fn sized_walk_reports_each_files_length() {
+        let dir = tempdir().unwrap();
+        let root = dir.path().join("root");
+        create_dir_all(root.join("sub")).unwrap();
+        write(root.join("a.txt"), b"abc").unwrap();
+        write(root.join("sub").join("b.txt"), b"hello").unwrap();
+        File::create(root.join("skip.log")).unwrap();
+
+        let mut sized: Vec<(PathBuf, u64)> =
+            walk_directory_stream(&root, &["*.log".to_string()], None, false)
+                .unwrap()
+                .with_sizes()
+                .collect();
+        sized.sort();
+        assert_eq!(
+            sized,
+            [(root.join("a.txt"), 3), (root.join("sub").join("b.txt"), 5)]
+        );
+    }
+
+    #[test]
+    fn depth_counts_levels_below_the_root() {
+        let dir = tempdir().unwrap();
+        let root = dir.path().join("root");
+        create_dir_all(root.join("a").join("b")).unwrap();
+        File::create(root.join("top.txt")).unwrap();
+        File::create(root.join("a").join("mid.txt")).unwrap();
+        File::create(root.join("a").join("b").join("deep.txt")).unwrap();
+
+        let names = |depth: Option<usize>| -> Vec<String> {
+            let mut names: Vec<String> = walk_directory(&root, &[], depth, false)
+                .unwrap()
+                .iter()
+                .map(|p| p.file_name().unwrap().to_string_lossy().into_owned())
+                .collect();
+            names.sort();
+            names
+        };
+        assert!(names(Some(0)).is_empty(), "depth 0 is the root itself");
+        assert_eq!(names(Some(1)), ["top.txt"]);
+        assert_eq!(names(Some(2)), ["mid.txt", "top.txt"]);
+        assert_eq!(names(Some(3)), ["deep.txt", "mid.txt", "top.txt"]);
+        assert_eq!(names(None), names(Some(3)));
+    }
+
+    #[test]
+    fn case_insensitive_excludes_match_any_case() {
+        let dir = tempdir().unwrap();
+        write(dir.path().join("ERROR.LOG"), b"e").unwrap();
+        write(dir.path().join("keep.txt"), b"k").unwrap();
+        let excludes = vec!["*.log".to_string()];
+
+        let walk = |case_insensitive: bool| -> Vec<String> {
+            let mut names: Vec<String> = walk_directory_stream(dir.path(), &excludes, None, false)
+                .unwrap()
+                .exclude_case_insensitive(case_insensitive)
+                .unwrap()
+                .map(|p| p.file_name().unwrap().to_string_lossy().into_owned())
+                .collect();
+            names.sort();
+            names
+        };
+        assert_eq!(walk(false), ["ERROR.LOG", "keep.txt"]);
+        assert_eq!(walk(true), ["keep.txt"]);
+    }
+
+    #[test]
+    fn regular_files_and_dirs_are_not_special() {
+        let dir = tempdir().unwrap();
+        let file = dir.path().join("a.txt");
+        File::create(&file).unwrap();
+        assert!(!is_special_file(&file));
+        assert!(!is_special_file(dir.path()));
+        assert!(!is_special_file(&dir.path().join("missing")));
+    }
+
+    #[test]
+    fn crosses_device_only_when_both_known_and_different() {
+        assert!(!crosses_device(Some(1), Some(1)));
+        assert!(crosses_device(Some(1), Some(2)));
+        assert!(!crosses_device(None, Some(2)));
+        assert!(!crosses_device(Some(1), None));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn one_file_system_keeps_same_device_files() {
+        let dir = tempdir().unwrap();
+        let root = dir.path().join("root");
+        create_dir_all(root.join("sub")).unwrap();
+        File::create(root.join("a.txt")).unwrap();
+        File::create(root.join("sub").join("b.txt")).unwrap();
+
+        assert_eq!(device_id(&root), device_id(&root.join("sub")));
+        let paths: Vec<PathBuf> = walk_directory_stream(&root, &[], None, false)
+            .unwrap()
+            .one_file_system(true)
+            .collect();
+        assert_eq!(paths.len(), 2);
+    }
+
+    #[test]
+    fn admits_applies_the_walks_filters_to_listed_paths() {
+        let dir = tempdir().unwrap();
+        let root = dir.path();
+        create_dir_all(root.join("a/b")).unwrap();
+        for file in ["top.txt", "skip.log", "a/mid.txt", "a/b/deep.txt"] {
+            File::create(root.join(file)).unwrap();
+        }
+        let walk = walk_directory_stream(root, &["*.log".to_string()], Some(2), false)
+            .unwrap()
+            .extensions(&["txt".to_string(), "log".to_string()]);
+
+        assert!(walk.admits(&root.join("top.txt")));
+        assert!(walk.admits(&root.join("a/mid.txt")));
+        assert!(!walk.admits(&root.join("skip.log")));
+        assert!(!walk.admits(&root.join("a/b/deep.txt")));
+        assert!(!walk.admits(&root.join("a")));
+        assert!(!walk.admits(&root.join("missing.txt")));
+        assert!(!walk.admits(&dir.path().parent().unwrap().join("elsewhere.txt")));
+
+        #[cfg(unix)]
+        {
+            std::os::unix::fs::symlink(root.join("top.txt"), root.join("link.txt")).unwrap();
+            std::os::unix::fs::symlink(root.join("a"), root.join("linked")).unwrap();
+            assert!(!walk.admits(&root.join("link.txt")));
+            assert!(!walk.admits(&root.join("linked/mid.txt")));
+            let walk = walk.symlinks(true);
+            assert!(walk.admits(&root.join("link.txt")));
+        }
+    }
+
+    #[test]
+    fn extensions_filter_is_case_insensitive() {
+        let dir = tempdir().unwrap();
+        let root = dir.path().join("root");
+        create_dir_all(root.join("album")).unwrap();
+        for name in ["a.jpg", "b.PNG", "c.txt", "jpg", "d.jpeg"] {
+            File::create(root.join(name)).unwrap();
+        }
+        File::create(root.join("album").join("e.Jpg")).unwrap();
+
+        let mut names: Vec<String> = walk_directory_stream(&root, &[], None, false)
+            .unwrap()
+            .extensions(&["jpg".to_string(), ".png".to_string()])
+            .map(|p| p.file_name().unwrap().to_string_lossy().into_owned())
+            .collect();
+        names.sort();
+        assert_eq!(names, ["a.jpg", "b.PNG", "e.Jpg"]);
+    }
 }
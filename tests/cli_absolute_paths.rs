@@ -0,0 +1,132 @@
+use assert_cmd::prelude::*;
+use std::fs::{create_dir_all, read_to_string, write};
+use std::path::Path;
+use std::process::Command;
+use tempfile::tempdir;
+
+fn hashmap(cwd: &Path, root: &Path, out: &Path, extra: &[&str]) {
+    Command::new(assert_cmd::cargo::cargo_bin!("hash-folderoo"))
+        .current_dir(cwd)
+        .args([
+            "hashmap",
+            "--path",
+            root.to_str().unwrap(),
+            "--output",
+            out.to_str().unwrap(),
+        ])
+        .args(extra)
+        .assert()
+        .success();
+}
+
+#[test]
+fn absolute_paths_are_recorded_and_compare_with_relative_maps(
+) -> Result<(), Box<dyn std::error::Error>> {
+    let dir = tempdir()?;
+    let root = dir.path().join("root");
+    create_dir_all(root.join("sub"))?;
+    write(root.join("a.txt"), b"alpha")?;
+    write(root.join("sub").join("b.txt"), b"bravo")?;
+    let canonical_root = root.canonicalize()?;
+
+    let abs_map = dir.path().join("abs.json");
+    let rel_map = dir.path().join("rel.json");
+    hashmap(dir.path(), &root, &abs_map, &["--absolute-paths"]);
+    hashmap(dir.path(), &root, &rel_map, &[]);
+
+    let map: serde_json::Value = serde_json::from_str(&read_to_string(&abs_map)?)?;
+    let mut paths: Vec<String> = map["entries"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|e| e["path"].as_str().unwrap().to_string())
+        .collect();
+    paths.sort();
+    assert_eq!(
+        paths,
+        vec![
+            canonical_root.join("a.txt").to_string_lossy().into_owned(),
+            canonical_root
+                .join("sub")
+                .join("b.txt")
+                .to_string_lossy()
+                .into_owned(),
+        ]
+    );
+
+    let out = Command::new(assert_cmd::cargo::cargo_bin!("hash-folderoo"))
+        .current_dir(dir.path())
+        .args([
+            "compare",
+            "--source",
+            abs_map.to_str().unwrap(),
+            "--target",
+            rel_map.to_str().unwrap(),
+        ])
+        .assert()
+        .success()
+        .get_output()
+        .stdout
+        .clone();
+    let report: serde_json::Value = serde_json::from_slice(&out)?;
+    assert_eq!(report["identical"].as_array().unwrap().len(), 2);
+    for section in ["changed", "moved", "missing", "new"] {
+        assert!(
+            report[section].as_array().unwrap().is_empty(),
+            "{}",
+            section
+        );
+    }
+    Ok(())
+}
+
+#[test]
+fn two_absolute_sides_are_each_made_relative_to_their_root(
+) -> Result<(), Box<dyn std::error::Error>> {
+    let dir = tempdir()?;
+    for (root, body) in [("before", &b"one"[..]), ("after", b"two")] {
+        create_dir_all(dir.path().join(root).join("sub"))?;
+        write(dir.path().join(root).join("same.txt"), b"same")?;
+        write(dir.path().join(root).join("sub").join("edit.txt"), body)?;
+    }
+    hashmap(
+        dir.path(),
+        &dir.path().join("before"),
+        &dir.path().join("before.json"),
+        &["--absolute-paths"],
+    );
+    hashmap(
+        dir.path(),
+        &dir.path().join("after"),
+        &dir.path().join("after.json"),
+        &["--absolute-paths"],
+    );
+
+    // two directories, then two absolute maps of different roots
+    for (source, target) in [("before", "after"), ("before.json", "after.json")] {
+        let out = Command::new(assert_cmd::cargo::cargo_bin!("hash-folderoo"))
+            .current_dir(dir.path())
+            .args(["compare", "--source", source, "--target", target])
+            .assert()
+            .success()
+            .get_output()
+            .stdout
+            .clone();
+        let report: serde_json::Value = serde_json::from_slice(&out)?;
+        assert_eq!(report["identical"][0]["path"], "same.txt", "{}", source);
+        assert_eq!(
+            report["changed"][0][0]["path"], "sub/edit.txt",
+            "{}",
+            source
+        );
+        for section in ["moved", "missing", "new"] {
+            assert!(
+                report[section].as_array().unwrap().is_empty(),
+                "{} {}",
+                source,
+                section
+            );
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,36 @@
+use hash_folderoo::memory::{recommend_config, MemoryMode, ASSUME_RAM_ENV};
+use std::process::Command;
+use tempfile::tempdir;
+
+#[test]
+fn assume_ram_env_sizes_the_plan() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = tempdir()?;
+    let assumed: u64 = 1024 * 1024;
+
+    let out = Command::new(assert_cmd::cargo::cargo_bin!("hash-folderoo"))
+        .current_dir(dir.path())
+        .env(ASSUME_RAM_ENV, assumed.to_string())
+        .args([
+            "hashmap",
+            "--path",
+            dir.path().to_str().unwrap(),
+            "--mem-mode",
+            "booster",
+            "--threads",
+            "3",
+            "--explain-plan",
+            "--format",
+            "json",
+        ])
+        .output()?;
+    assert!(out.status.success());
+
+    // Same plan as an explicit budget of the assumed RAM.
+    let expected = recommend_config(MemoryMode::Booster, Some(3), Some(assumed))?;
+    let v: serde_json::Value = serde_json::from_slice(&out.stdout)?;
+    assert_eq!(v["threads"], expected.threads as u64);
+    assert_eq!(v["num_buffers"], expected.num_buffers as u64);
+    assert_eq!(v["total_buffer_bytes"], expected.total_buffer_bytes());
+    assert!(expected.total_buffer_bytes() <= assumed);
+    Ok(())
+}
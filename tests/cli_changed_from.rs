@@ -0,0 +1,240 @@
+use assert_cmd::prelude::*;
+use std::collections::HashMap;
+use std::fs::{create_dir_all, read_to_string, write};
+use std::path::Path;
+use std::process::Command;
+use tempfile::tempdir;
+
+fn hashes_by_path(map: &Path) -> HashMap<String, String> {
+    let v: serde_json::Value = serde_json::from_str(&read_to_string(map).unwrap()).unwrap();
+    v["entries"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|e| {
+            (
+                e["path"].as_str().unwrap().to_string(),
+                e["hash"].as_str().unwrap().to_string(),
+            )
+        })
+        .collect()
+}
+
+#[test]
+fn changed_from_rehashes_only_listed_paths() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = tempdir()?;
+    let root = dir.path().join("root");
+    create_dir_all(root.join("sub"))?;
+    write(root.join("a.txt"), b"alpha")?;
+    write(root.join("b.txt"), b"bravo")?;
+    write(root.join("sub").join("c.txt"), b"charlie")?;
+
+    let baseline = dir.path().join("baseline.json");
+    Command::new(assert_cmd::cargo::cargo_bin!("hash-folderoo"))
+        .args([
+            "hashmap",
+            "--path",
+            root.to_str().unwrap(),
+            "--output",
+            baseline.to_str().unwrap(),
+        ])
+        .assert()
+        .success();
+
+    // both files change on disk, but only a.txt is reported as changed
+    write(root.join("a.txt"), b"alpha v2")?;
+    write(root.join("b.txt"), b"bravo v2")?;
+    let list = dir.path().join("changed.txt");
+    write(&list, "# changed by the build\na.txt\n")?;
+
+    let updated = dir.path().join("updated.json");
+    Command::new(assert_cmd::cargo::cargo_bin!("hash-folderoo"))
+        .args([
+            "hashmap",
+            "--path",
+            root.to_str().unwrap(),
+            "--baseline",
+            baseline.to_str().unwrap(),
+            "--changed-from",
+            list.to_str().unwrap(),
+            "--output",
+            updated.to_str().unwrap(),
+        ])
+        .assert()
+        .success();
+
+    let before = hashes_by_path(&baseline);
+    let after = hashes_by_path(&updated);
+    assert_eq!(after.len(), 3);
+    assert_ne!(after["a.txt"], before["a.txt"]);
+    assert_eq!(after["b.txt"], before["b.txt"]);
+    assert_eq!(after["sub/c.txt"], before["sub/c.txt"]);
+
+    Ok(())
+}
+
+#[test]
+fn changed_from_list_is_deduplicated() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = tempdir()?;
+    let root = dir.path().join("root");
+    create_dir_all(&root)?;
+    write(root.join("x"), b"x")?;
+    write(root.join("y"), b"y")?;
+
+    let baseline = dir.path().join("baseline.json");
+    Command::new(assert_cmd::cargo::cargo_bin!("hash-folderoo"))
+        .args([
+            "hashmap",
+            "--path",
+            root.to_str().unwrap(),
+            "--output",
+            baseline.to_str().unwrap(),
+        ])
+        .assert()
+        .success();
+
+    write(root.join("x"), b"x v2")?;
+    let list = dir.path().join("changed.txt");
+    let abs = root.join("x");
+    write(&list, format!("./x\nx\n{}\n", abs.display()))?;
+
+    let updated = dir.path().join("updated.json");
+    Command::new(assert_cmd::cargo::cargo_bin!("hash-folderoo"))
+        .args([
+            "hashmap",
+            "--path",
+            root.to_str().unwrap(),
+            "--baseline",
+            baseline.to_str().unwrap(),
+            "--changed-from",
+            list.to_str().unwrap(),
+            "--output",
+            updated.to_str().unwrap(),
+        ])
+        .assert()
+        .success();
+
+    let v: serde_json::Value = serde_json::from_str(&read_to_string(&updated)?)?;
+    let paths: Vec<&str> = v["entries"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|e| e["path"].as_str().unwrap())
+        .collect();
+    assert_eq!(paths, ["x", "y"]);
+    Ok(())
+}
+
+#[test]
+fn changed_from_refuses_a_baseline_with_other_settings() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = tempdir()?;
+    let root = dir.path().join("root");
+    create_dir_all(&root)?;
+    write(root.join("a.txt"), b"alpha")?;
+    write(dir.path().join("changed.txt"), "a.txt\n")?;
+    Command::new(assert_cmd::cargo::cargo_bin!("hash-folderoo"))
+        .current_dir(dir.path())
+        .args(["hashmap", "--path", "root", "--output", "baseline.json"])
+        .assert()
+        .success();
+
+    // the merged map would mix 32- and 64-byte digests, or two algorithms
+    for extra in [&["--xof-length", "64"][..], &["--algorithm", "shake256"]] {
+        Command::new(assert_cmd::cargo::cargo_bin!("hash-folderoo"))
+            .current_dir(dir.path())
+            .args([
+                "hashmap",
+                "--path",
+                "root",
+                "--baseline",
+                "baseline.json",
+                "--changed-from",
+                "changed.txt",
+                "--output",
+                "updated.json",
+            ])
+            .args(extra)
+            .assert()
+            .failure()
+            .stderr(predicates::str::contains("baseline"));
+    }
+    assert!(!dir.path().join("updated.json").exists());
+    Ok(())
+}
+
+#[test]
+fn changed_from_applies_the_walks_filters() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = tempdir()?;
+    let root = dir.path().join("root");
+    create_dir_all(root.join("sub").join("deep"))?;
+    write(root.join("a.txt"), b"alpha")?;
+    let filters = ["--exclude", "*.log", "--ext", "txt,log", "--depth", "2"];
+
+    let baseline = dir.path().join("baseline.json");
+    Command::new(assert_cmd::cargo::cargo_bin!("hash-folderoo"))
+        .args(["hashmap", "--path", root.to_str().unwrap()])
+        .args(["--output", baseline.to_str().unwrap()])
+        .args(filters)
+        .assert()
+        .success();
+
+    // none of these would be produced by a full scan with the same filters
+    write(root.join("debug.log"), b"excluded")?;
+    write(root.join("image.png"), b"other extension")?;
+    write(root.join("sub").join("deep").join("c.txt"), b"too deep")?;
+    let list = dir.path().join("changed.txt");
+    write(&list, "debug.log\nimage.png\nsub/deep/c.txt\n")?;
+
+    let updated = dir.path().join("updated.json");
+    Command::new(assert_cmd::cargo::cargo_bin!("hash-folderoo"))
+        .args(["hashmap", "--path", root.to_str().unwrap()])
+        .args(["--baseline", baseline.to_str().unwrap()])
+        .args(["--changed-from", list.to_str().unwrap()])
+        .args(["--output", updated.to_str().unwrap()])
+        .args(filters)
+        .assert()
+        .success();
+
+    let paths: Vec<String> = hashes_by_path(&updated).into_keys().collect();
+    assert_eq!(paths, ["a.txt"]);
+    Ok(())
+}
+
+#[test]
+fn changed_from_rejects_paths_outside_the_root() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = tempdir()?;
+    let root = dir.path().join("root");
+    create_dir_all(&root)?;
+    write(root.join("a.txt"), b"alpha")?;
+    write(dir.path().join("outside.txt"), b"not in the tree")?;
+
+    let baseline = dir.path().join("baseline.json");
+    Command::new(assert_cmd::cargo::cargo_bin!("hash-folderoo"))
+        .args(["hashmap", "--path", root.to_str().unwrap()])
+        .args(["--output", baseline.to_str().unwrap()])
+        .assert()
+        .success();
+
+    for listed in [
+        "../outside.txt".to_string(),
+        dir.path()
+            .join("outside.txt")
+            .to_string_lossy()
+            .into_owned(),
+    ] {
+        let list = dir.path().join("changed.txt");
+        write(&list, format!("{}\n", listed))?;
+        let updated = dir.path().join("updated.json");
+        let out = Command::new(assert_cmd::cargo::cargo_bin!("hash-folderoo"))
+            .args(["hashmap", "--path", root.to_str().unwrap()])
+            .args(["--baseline", baseline.to_str().unwrap()])
+            .args(["--changed-from", list.to_str().unwrap()])
+            .args(["--output", updated.to_str().unwrap()])
+            .assert()
+            .failure();
+        let stderr = String::from_utf8_lossy(&out.get_output().stderr).into_owned();
+        assert!(stderr.contains("is outside"), "{}", stderr);
+        assert!(!updated.exists());
+    }
+    Ok(())
+}
@@ -0,0 +1,176 @@
+use assert_cmd::prelude::*;
+use std::fs::{create_dir_all, read_to_string, write};
+use std::process::Command;
+use tempfile::tempdir;
+
+#[test]
+fn compare_catches_algorithm_mismatch_with_map() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = tempdir()?;
+    let root = dir.path().join("root");
+    create_dir_all(&root)?;
+    write(root.join("a.txt"), b"alpha")?;
+    write(root.join("b.txt"), b"beta")?;
+
+    let map = dir.path().join("map.json");
+    Command::new(assert_cmd::cargo::cargo_bin!("hash-folderoo"))
+        .current_dir(dir.path())
+        .args([
+            "hashmap",
+            "--path",
+            root.to_str().unwrap(),
+            "--output",
+            map.to_str().unwrap(),
+            "--algorithm",
+            "blake3",
+        ])
+        .assert()
+        .success();
+
+    let compare = |extra: &[&str]| {
+        let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("hash-folderoo"));
+        cmd.current_dir(dir.path()).args([
+            "compare",
+            "--source",
+            map.to_str().unwrap(),
+            "--target",
+            root.to_str().unwrap(),
+            "--algorithm",
+            "shake256",
+        ]);
+        cmd.args(extra);
+        cmd
+    };
+
+    let out = compare(&[]).output()?;
+    assert!(!out.status.success());
+    let stderr = String::from_utf8_lossy(&out.stderr);
+    assert!(
+        stderr.contains("blake3") && stderr.contains("shake256"),
+        "{}",
+        stderr
+    );
+
+    let report = dir.path().join("report.json");
+    compare(&[
+        "--algorithm-mismatch",
+        "rehash",
+        "--output",
+        report.to_str().unwrap(),
+    ])
+    .assert()
+    .success();
+    let r: serde_json::Value = serde_json::from_str(&read_to_string(&report)?)?;
+    assert_eq!(r["identical"].as_array().unwrap().len(), 2);
+    assert!(r["changed"].as_array().unwrap().is_empty());
+    Ok(())
+}
+
+#[test]
+fn rehash_uses_the_maps_digest_length_and_params() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = tempdir()?;
+    let root = dir.path().join("root");
+    create_dir_all(&root)?;
+    write(root.join("a.txt"), b"alpha")?;
+    write(root.join("b.txt"), b"beta")?;
+
+    let map = dir.path().join("map.json");
+    Command::new(assert_cmd::cargo::cargo_bin!("hash-folderoo"))
+        .current_dir(dir.path())
+        .args([
+            "hashmap",
+            "--path",
+            root.to_str().unwrap(),
+            "--output",
+            map.to_str().unwrap(),
+            "--algorithm",
+            "k12",
+            "--xof-length",
+            "48",
+            "--customization",
+            "folderoo",
+        ])
+        .assert()
+        .success();
+
+    let report = dir.path().join("report.json");
+    Command::new(assert_cmd::cargo::cargo_bin!("hash-folderoo"))
+        .current_dir(dir.path())
+        .args([
+            "compare",
+            "--source",
+            map.to_str().unwrap(),
+            "--target",
+            root.to_str().unwrap(),
+            "--algorithm",
+            "blake3",
+            "--algorithm-mismatch",
+            "rehash",
+            "--output",
+            report.to_str().unwrap(),
+        ])
+        .assert()
+        .success();
+    let r: serde_json::Value = serde_json::from_str(&read_to_string(&report)?)?;
+    assert_eq!(r["identical"].as_array().unwrap().len(), 2, "{}", r);
+    assert!(r["changed"].as_array().unwrap().is_empty());
+    Ok(())
+}
+
+#[test]
+fn streaming_compare_refuses_maps_with_different_algorithms(
+) -> Result<(), Box<dyn std::error::Error>> {
+    let dir = tempdir()?;
+    let root = dir.path().join("root");
+    create_dir_all(&root)?;
+    write(root.join("a.txt"), b"alpha")?;
+    write(root.join("b.txt"), b"beta")?;
+
+    for (name, alg) in [("blake3.csv", "blake3"), ("xxh3.csv", "xxh3")] {
+        Command::new(assert_cmd::cargo::cargo_bin!("hash-folderoo"))
+            .current_dir(dir.path())
+            .args([
+                "hashmap",
+                "--path",
+                root.to_str().unwrap(),
+                "--output",
+                name,
+            ])
+            .args([
+                "--algorithm",
+                alg,
+                "--csv-include-algorithm",
+                "--sort",
+                "path",
+            ])
+            .assert()
+            .success();
+    }
+
+    let compare = |target: &str, extra: &[&str]| {
+        let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("hash-folderoo"));
+        cmd.current_dir(dir.path()).args([
+            "compare",
+            "--source",
+            "blake3.csv",
+            "--target",
+            target,
+            "--streaming",
+            "--format",
+            "csv",
+        ]);
+        cmd.args(extra);
+        cmd.assert()
+    };
+
+    let refused = compare("xxh3.csv", &[]).failure();
+    let stderr = String::from_utf8_lossy(&refused.get_output().stderr).into_owned();
+    assert!(stderr.contains("--force-compare"), "{}", stderr);
+
+    let forced = compare("xxh3.csv", &["--force-compare"]).success();
+    let stdout = String::from_utf8_lossy(&forced.get_output().stdout).into_owned();
+    assert_eq!(stdout.matches("changed").count(), 2, "{}", stdout);
+
+    // the same algorithm on both sides streams without complaint
+    compare("blake3.csv", &[]).success();
+    Ok(())
+}
@@ -0,0 +1,40 @@
+use std::fs::{create_dir_all, write};
+use std::process::Command;
+use tempfile::tempdir;
+
+#[test]
+fn compare_git_diff_shows_hunks_for_changed_files() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = tempdir()?;
+    create_dir_all(dir.path().join("before"))?;
+    create_dir_all(dir.path().join("after"))?;
+    write(dir.path().join("before/same.txt"), b"unchanged\n")?;
+    write(dir.path().join("after/same.txt"), b"unchanged\n")?;
+    write(dir.path().join("before/notes.txt"), b"one\ntwo\nthree\n")?;
+    write(dir.path().join("after/notes.txt"), b"one\n2\nthree\n")?;
+
+    let output = Command::new(assert_cmd::cargo::cargo_bin!("hash-folderoo"))
+        .current_dir(dir.path())
+        .args([
+            "compare",
+            "--source",
+            "before",
+            "--target",
+            "after",
+            "--git-diff",
+        ])
+        .output()?;
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout)?;
+
+    assert!(
+        stdout.contains("diff --git a/notes.txt b/notes.txt"),
+        "{}",
+        stdout
+    );
+    assert!(stdout.contains("@@ -1,3 +1,3 @@"), "{}", stdout);
+    assert!(stdout.contains("-two\n+2\n"), "{}", stdout);
+    // identical files get no diff, and the diff replaces the report on stdout
+    assert!(!stdout.contains("same.txt"), "{}", stdout);
+    assert!(!stdout.contains("\"identical\""), "{}", stdout);
+    Ok(())
+}
@@ -0,0 +1,81 @@
+use assert_cmd::prelude::*;
+use std::fs::{create_dir_all, read_to_string, write};
+use std::process::Command;
+use tempfile::tempdir;
+
+#[test]
+fn compare_only_missing_drops_other_categories() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = tempdir()?;
+    let src = dir.path().join("src");
+    let dst = dir.path().join("dst");
+    create_dir_all(&src)?;
+    create_dir_all(&dst)?;
+    write(src.join("same.txt"), b"same")?;
+    write(dst.join("same.txt"), b"same")?;
+    write(src.join("edited.txt"), b"before")?;
+    write(dst.join("edited.txt"), b"after")?;
+    write(src.join("gone.txt"), b"only in source")?;
+    write(dst.join("added.txt"), b"only in target")?;
+
+    let map = |root: &std::path::Path, name: &str| -> std::path::PathBuf {
+        let out = dir.path().join(name);
+        Command::new(assert_cmd::cargo::cargo_bin!("hash-folderoo"))
+            .current_dir(dir.path())
+            .args([
+                "hashmap",
+                "--path",
+                root.to_str().unwrap(),
+                "--output",
+                out.to_str().unwrap(),
+            ])
+            .assert()
+            .success();
+        out
+    };
+    let src_map = map(&src, "src.json");
+    let dst_map = map(&dst, "dst.json");
+
+    let out = dir.path().join("report.json");
+    Command::new(assert_cmd::cargo::cargo_bin!("hash-folderoo"))
+        .current_dir(dir.path())
+        .args([
+            "compare",
+            "--source",
+            src_map.to_str().unwrap(),
+            "--target",
+            dst_map.to_str().unwrap(),
+            "--only",
+            "missing",
+            "--output",
+            out.to_str().unwrap(),
+        ])
+        .assert()
+        .success();
+
+    let r: serde_json::Value = serde_json::from_str(&read_to_string(&out)?)?;
+    let missing = r["missing"].as_array().unwrap();
+    assert_eq!(missing.len(), 1);
+    assert_eq!(missing[0]["path"], "gone.txt");
+    for other in ["identical", "changed", "moved", "new"] {
+        assert!(
+            r[other].as_array().unwrap().is_empty(),
+            "{} not empty",
+            other
+        );
+    }
+
+    Command::new(assert_cmd::cargo::cargo_bin!("hash-folderoo"))
+        .current_dir(dir.path())
+        .args([
+            "compare",
+            "--source",
+            src_map.to_str().unwrap(),
+            "--target",
+            dst_map.to_str().unwrap(),
+            "--only",
+            "bogus",
+        ])
+        .assert()
+        .failure();
+    Ok(())
+}
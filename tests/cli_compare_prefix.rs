@@ -0,0 +1,130 @@
+use assert_cmd::prelude::*;
+use std::fs::{create_dir_all, read_to_string, write};
+use std::path::Path;
+use std::process::Command;
+use tempfile::tempdir;
+
+fn compare(dir: &Path, extra: &[&str]) -> serde_json::Value {
+    let out = dir.join("report.json");
+    Command::new(assert_cmd::cargo::cargo_bin!("hash-folderoo"))
+        .current_dir(dir)
+        .args([
+            "compare",
+            "--source",
+            "long.json",
+            "--target",
+            "short.json",
+            "--output",
+            out.to_str().unwrap(),
+        ])
+        .args(extra)
+        .assert()
+        .success();
+    serde_json::from_str(&read_to_string(&out).unwrap()).unwrap()
+}
+
+#[test]
+fn prefix_bytes_lines_up_blake3_maps_of_different_lengths() -> Result<(), Box<dyn std::error::Error>>
+{
+    let dir = tempdir()?;
+    let root = dir.path().join("tree");
+    create_dir_all(&root)?;
+    write(root.join("a.txt"), b"alpha")?;
+    write(root.join("b.txt"), b"beta")?;
+
+    for (name, len) in [("long.json", "64"), ("short.json", "32")] {
+        Command::new(assert_cmd::cargo::cargo_bin!("hash-folderoo"))
+            .current_dir(dir.path())
+            .args([
+                "hashmap",
+                "--path",
+                "tree",
+                "--output",
+                name,
+                "--algorithm",
+                "blake3",
+                "--xof-length",
+                len,
+            ])
+            .assert()
+            .success();
+    }
+
+    // full digests of different lengths never match; the maps' fingerprints differ too
+    let full = compare(dir.path(), &["--force-compare"]);
+    assert_eq!(full["changed"].as_array().unwrap().len(), 2);
+
+    let prefixed = compare(dir.path(), &["--compare-prefix-bytes", "32"]);
+    assert_eq!(prefixed["identical"].as_array().unwrap().len(), 2);
+    assert!(prefixed["changed"].as_array().unwrap().is_empty());
+
+    // a prefix longer than the short map's digests would compare unequal lengths
+    Command::new(assert_cmd::cargo::cargo_bin!("hash-folderoo"))
+        .current_dir(dir.path())
+        .args([
+            "compare",
+            "--source",
+            "long.json",
+            "--target",
+            "short.json",
+            "--compare-prefix-bytes",
+            "48",
+        ])
+        .assert()
+        .failure()
+        .stderr(predicates::str::contains("shorter than the 48-byte prefix"));
+    Ok(())
+}
+
+#[test]
+fn prefix_bytes_still_refuses_maps_with_different_settings(
+) -> Result<(), Box<dyn std::error::Error>> {
+    let dir = tempdir()?;
+    let root = dir.path().join("tree");
+    create_dir_all(&root)?;
+    write(root.join("a.txt"), b"alpha")?;
+
+    for (name, len, custom) in [("long.json", "64", "one"), ("short.json", "32", "two")] {
+        Command::new(assert_cmd::cargo::cargo_bin!("hash-folderoo"))
+            .current_dir(dir.path())
+            .args([
+                "hashmap",
+                "--path",
+                "tree",
+                "--output",
+                name,
+                "--algorithm",
+                "k12",
+                "--customization",
+                custom,
+                "--xof-length",
+                len,
+            ])
+            .assert()
+            .success();
+    }
+
+    let compare = |extra: &[&str]| {
+        let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("hash-folderoo"));
+        cmd.current_dir(dir.path())
+            .args([
+                "compare",
+                "--source",
+                "long.json",
+                "--target",
+                "short.json",
+                "--compare-prefix-bytes",
+                "32",
+                "--output",
+                "report.json",
+            ])
+            .args(extra);
+        cmd
+    };
+    compare(&[])
+        .assert()
+        .failure()
+        .stderr(predicates::str::contains("fingerprint"));
+    compare(&["--force-compare"]).assert().success();
+    Ok(())
+}
@@ -0,0 +1,132 @@
+#![cfg(feature = "url")]
+
+use std::fs::{create_dir_all, read, write};
+use std::process::Command;
+use std::thread;
+
+use tiny_http::{Header, Response, Server};
+
+#[test]
+fn compare_fetches_a_remote_map_by_content_type() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = tempfile::tempdir()?;
+    let root = dir.path().join("root");
+    create_dir_all(&root)?;
+    write(root.join("a.txt"), b"alpha")?;
+    write(root.join("b.txt"), b"bravo")?;
+
+    let map = dir.path().join("map.csv");
+    let status = Command::new(assert_cmd::cargo::cargo_bin!("hash-folderoo"))
+        .current_dir(dir.path())
+        .args(["hashmap", "--path", root.to_str().unwrap(), "--output"])
+        .arg(&map)
+        .output()?
+        .status;
+    assert!(status.success());
+    write(root.join("b.txt"), b"BRAVO")?;
+
+    // No extension on the URL, so the Content-Type has to identify the CSV
+    let server = Server::http("127.0.0.1:0").map_err(|e| e.to_string())?;
+    let addr = server.server_addr().to_ip().unwrap();
+    let body = read(&map)?;
+    let handle = thread::spawn(move || {
+        let request = server.recv().unwrap();
+        let response = Response::from_data(body)
+            .with_header(Header::from_bytes("Content-Type", "text/csv").unwrap());
+        request.respond(response).unwrap();
+    });
+
+    let output = Command::new(assert_cmd::cargo::cargo_bin!("hash-folderoo"))
+        .current_dir(dir.path())
+        .args([
+            "compare",
+            "--source",
+            root.to_str().unwrap(),
+            "--target",
+            &format!("http://{}/maps/latest", addr),
+        ])
+        .output()?;
+    handle.join().unwrap();
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let report: serde_json::Value = serde_json::from_slice(&output.stdout)?;
+    let changed = report["changed"].as_array().unwrap();
+    assert_eq!(changed.len(), 1, "{}", report);
+    assert_eq!(
+        report["identical"].as_array().unwrap().len(),
+        1,
+        "{}",
+        report
+    );
+    assert!(
+        report["missing"].as_array().unwrap().is_empty(),
+        "{}",
+        report
+    );
+    Ok(())
+}
+
+#[test]
+fn compare_uses_the_remote_maps_algorithm() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = tempfile::tempdir()?;
+    let root = dir.path().join("root");
+    create_dir_all(&root)?;
+    write(root.join("a.txt"), b"alpha")?;
+
+    let map = dir.path().join("map.json");
+    let status = Command::new(assert_cmd::cargo::cargo_bin!("hash-folderoo"))
+        .current_dir(dir.path())
+        .args([
+            "hashmap",
+            "--path",
+            root.to_str().unwrap(),
+            "--algorithm",
+            "sha256",
+            "--output",
+        ])
+        .arg(&map)
+        .output()?
+        .status;
+    assert!(status.success());
+
+    let server = Server::http("127.0.0.1:0").map_err(|e| e.to_string())?;
+    let addr = server.server_addr().to_ip().unwrap();
+    let body = read(&map)?;
+    let handle = thread::spawn(move || {
+        let request = server.recv().unwrap();
+        request.respond(Response::from_data(body)).unwrap();
+    });
+
+    // Without --algorithm the directory is hashed with the map header's sha256
+    let output = Command::new(assert_cmd::cargo::cargo_bin!("hash-folderoo"))
+        .current_dir(dir.path())
+        .args([
+            "compare",
+            "--source",
+            root.to_str().unwrap(),
+            "--target",
+            &format!("http://{}/maps/map.json", addr),
+        ])
+        .output()?;
+    handle.join().unwrap();
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let report: serde_json::Value = serde_json::from_slice(&output.stdout)?;
+    assert_eq!(
+        report["identical"].as_array().unwrap().len(),
+        1,
+        "{}",
+        report
+    );
+    assert!(
+        report["changed"].as_array().unwrap().is_empty(),
+        "{}",
+        report
+    );
+    Ok(())
+}
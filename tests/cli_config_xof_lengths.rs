@@ -0,0 +1,61 @@
+use assert_cmd::prelude::*;
+use std::fs::{create_dir_all, write};
+use std::process::Command;
+use tempfile::tempdir;
+
+#[test]
+fn configured_xof_lengths_apply_without_cli_override() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = tempdir()?;
+    let root = dir.path().join("root");
+    create_dir_all(&root)?;
+    write(root.join("a.txt"), b"alpha")?;
+    let config = dir.path().join("config.toml");
+    write(
+        &config,
+        "[algorithm]\nxof_length = 48\n\n[algorithm.xof_lengths]\nblake3 = 20\n",
+    )?;
+
+    let digest_len = |extra: &[&str]| -> Result<usize, Box<dyn std::error::Error>> {
+        let out = Command::new(assert_cmd::cargo::cargo_bin!("hash-folderoo"))
+            .current_dir(dir.path())
+            .arg("--config")
+            .arg(&config)
+            .args([
+                "hashmap",
+                "--path",
+                root.to_str().unwrap(),
+                "--format",
+                "json",
+            ])
+            .args(extra)
+            .output()?;
+        assert!(
+            out.status.success(),
+            "{}",
+            String::from_utf8_lossy(&out.stderr)
+        );
+        let v: serde_json::Value = serde_json::from_slice(&out.stdout)?;
+        Ok(v["entries"][0]["hash"].as_str().unwrap().len() / 2)
+    };
+
+    // Per-algorithm entry, then the global default, then --xof-length over both
+    assert_eq!(digest_len(&[])?, 20);
+    assert_eq!(digest_len(&["--algorithm", "shake256"])?, 48);
+    assert_eq!(digest_len(&["--xof-length", "16"])?, 16);
+
+    // A non-XOF algorithm still needs --force-expand for a configured length
+    Command::new(assert_cmd::cargo::cargo_bin!("hash-folderoo"))
+        .current_dir(dir.path())
+        .arg("--config")
+        .arg(&config)
+        .args([
+            "hashmap",
+            "--path",
+            root.to_str().unwrap(),
+            "--algorithm",
+            "blake2b",
+        ])
+        .assert()
+        .failure();
+    Ok(())
+}
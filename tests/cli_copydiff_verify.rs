@@ -0,0 +1,62 @@
+use assert_cmd::prelude::*;
+use std::fs::{create_dir_all, remove_file, write};
+use std::process::Command;
+use tempfile::tempdir;
+
+#[test]
+fn verify_only_checks_destinations_against_sources() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = tempdir()?;
+    let src = dir.path().join("src");
+    let dst = dir.path().join("dst");
+    create_dir_all(src.join("sub"))?;
+    write(src.join("a.txt"), b"alpha")?;
+    write(src.join("sub").join("b.txt"), b"beta")?;
+    let plan = dir.path().join("plan.json");
+    let ops: Vec<serde_json::Value> = ["a.txt", "sub/b.txt"]
+        .iter()
+        .map(|rel| {
+            serde_json::json!({
+                "src": src.join(rel).to_string_lossy(),
+                "dst": dst.join(rel).to_string_lossy(),
+                "op": "copy",
+            })
+        })
+        .collect();
+    write(
+        &plan,
+        serde_json::to_vec(&serde_json::json!({ "ops": ops }))?,
+    )?;
+
+    let copydiff = |extra: &[&str]| {
+        let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("hash-folderoo"));
+        cmd.current_dir(dir.path())
+            .args(["copydiff", "--plan"])
+            .arg(&plan)
+            .args(extra);
+        cmd
+    };
+
+    copydiff(&["--execute"]).assert().success();
+    let out = copydiff(&["--verify-only"]).output()?;
+    assert!(out.status.success());
+    let stdout = String::from_utf8_lossy(&out.stdout);
+    assert!(
+        stdout.contains("verify: 2 ok, 0 mismatch, 0 missing"),
+        "{}",
+        stdout
+    );
+
+    // A tampered destination fails, as does one that was never written
+    write(dst.join("a.txt"), b"tampered")?;
+    remove_file(dst.join("sub").join("b.txt"))?;
+    let out = copydiff(&["--verify-only"]).output()?;
+    assert!(!out.status.success());
+    let stdout = String::from_utf8_lossy(&out.stdout);
+    assert!(stdout.contains("mismatch"), "{}", stdout);
+    assert!(
+        stdout.contains("verify: 0 ok, 1 mismatch, 1 missing"),
+        "{}",
+        stdout
+    );
+    Ok(())
+}
@@ -0,0 +1,64 @@
+use assert_cmd::prelude::*;
+use filetime::{set_file_mtime, FileTime};
+use std::fs::{create_dir_all, write};
+use std::process::Command;
+use tempfile::tempdir;
+
+#[test]
+fn dedupe_execute_keeps_newest_copy() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = tempdir()?;
+    let root = dir.path().join("root");
+    create_dir_all(root.join("old"))?;
+    create_dir_all(root.join("new"))?;
+    create_dir_all(root.join("mid"))?;
+    for (name, secs) in [
+        ("old/photo.jpg", 1_000_000),
+        ("new/photo.jpg", 3_000_000),
+        ("mid/photo.jpg", 2_000_000),
+    ] {
+        write(root.join(name), b"same picture bytes")?;
+        set_file_mtime(root.join(name), FileTime::from_unix_time(secs, 0))?;
+    }
+    write(root.join("unique.txt"), b"only one")?;
+    let map = dir.path().join("map.json");
+
+    Command::new(assert_cmd::cargo::cargo_bin!("hash-folderoo"))
+        .current_dir(dir.path())
+        .args([
+            "hashmap",
+            "--path",
+            root.to_str().unwrap(),
+            "--output",
+            map.to_str().unwrap(),
+        ])
+        .assert()
+        .success();
+
+    let dedupe = |extra: &[&str]| {
+        Command::new(assert_cmd::cargo::cargo_bin!("hash-folderoo"))
+            .current_dir(dir.path())
+            .args([
+                "report",
+                "--input",
+                map.to_str().unwrap(),
+                "--dedupe-execute",
+                "--keep",
+                "newest",
+            ])
+            .args(extra)
+            .assert()
+            .success()
+    };
+
+    // dry run by default: nothing is touched
+    dedupe(&[]);
+    assert!(root.join("old/photo.jpg").exists());
+    assert!(root.join("mid/photo.jpg").exists());
+
+    dedupe(&["--yes"]);
+    assert!(root.join("new/photo.jpg").exists());
+    assert!(!root.join("old/photo.jpg").exists());
+    assert!(!root.join("mid/photo.jpg").exists());
+    assert!(root.join("unique.txt").exists());
+    Ok(())
+}
@@ -0,0 +1,48 @@
+use assert_cmd::prelude::*;
+use std::fs::{create_dir_all, write};
+use std::process::Command;
+use tempfile::tempdir;
+
+#[test]
+fn depth_counts_levels_below_the_root_and_rejects_zero_for_directories(
+) -> Result<(), Box<dyn std::error::Error>> {
+    let dir = tempdir()?;
+    let root = dir.path().join("root");
+    create_dir_all(root.join("a").join("b"))?;
+    write(root.join("top.txt"), b"1")?;
+    write(root.join("a").join("mid.txt"), b"2")?;
+    write(root.join("a").join("b").join("deep.txt"), b"3")?;
+
+    for (depth, expected) in [("1", vec!["top.txt"]), ("2", vec!["a/mid.txt", "top.txt"])] {
+        let out = Command::new(assert_cmd::cargo::cargo_bin!("hash-folderoo"))
+            .current_dir(dir.path())
+            .args([
+                "hashmap",
+                "--path",
+                root.to_str().unwrap(),
+                "--depth",
+                depth,
+            ])
+            .assert()
+            .success()
+            .get_output()
+            .stdout
+            .clone();
+        let map: serde_json::Value = serde_json::from_slice(&out)?;
+        let paths: Vec<&str> = map["entries"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|e| e["path"].as_str().unwrap())
+            .collect();
+        assert_eq!(paths, expected, "--depth {}", depth);
+    }
+
+    let out = Command::new(assert_cmd::cargo::cargo_bin!("hash-folderoo"))
+        .current_dir(dir.path())
+        .args(["hashmap", "--path", root.to_str().unwrap(), "--depth", "0"])
+        .output()?;
+    assert!(!out.status.success());
+    assert!(String::from_utf8_lossy(&out.stderr).contains("--depth 0"));
+    Ok(())
+}
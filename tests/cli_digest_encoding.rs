@@ -0,0 +1,111 @@
+use assert_cmd::prelude::*;
+use std::fs::{create_dir_all, read_to_string, write};
+use std::path::Path;
+use std::process::Command;
+use tempfile::tempdir;
+
+fn hashmap(root: &Path, out: &Path, encoding: &str) {
+    Command::new(assert_cmd::cargo::cargo_bin!("hash-folderoo"))
+        .args([
+            "hashmap",
+            "--path",
+            root.to_str().unwrap(),
+            "--output",
+            out.to_str().unwrap(),
+            "--xof-length",
+            "32",
+            "--digest-encoding",
+            encoding,
+        ])
+        .assert()
+        .success();
+}
+
+#[test]
+fn encoded_maps_round_trip_through_compare() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = tempdir()?;
+    let root = dir.path().join("root");
+    create_dir_all(root.join("sub"))?;
+    write(root.join("a.txt"), b"alpha")?;
+    write(root.join("sub").join("b.txt"), b"bravo")?;
+    let a_digest = *blake3::hash(b"alpha").as_bytes();
+
+    let hex_map = dir.path().join("hex.json");
+    hashmap(&root, &hex_map, "hex");
+
+    for (encoding, expected) in [
+        ("base64", data_encoding::BASE64URL_NOPAD.encode(&a_digest)),
+        ("base64-std", data_encoding::BASE64.encode(&a_digest)),
+        ("base32", data_encoding::BASE32.encode(&a_digest)),
+    ] {
+        let map = dir.path().join(format!("{}.json", encoding));
+        hashmap(&root, &map, encoding);
+
+        let v: serde_json::Value = serde_json::from_str(&read_to_string(&map)?)?;
+        assert_eq!(v["algorithm"]["params"]["digest_encoding"], encoding);
+        assert_eq!(v["entries"][0]["path"], "a.txt");
+        assert_eq!(v["entries"][0]["hash"], expected.as_str());
+
+        // the encoded map decodes back to the same digests as the hex map
+        let report = dir.path().join(format!("{}-report.json", encoding));
+        Command::new(assert_cmd::cargo::cargo_bin!("hash-folderoo"))
+            .args([
+                "compare",
+                "--source",
+                map.to_str().unwrap(),
+                "--target",
+                hex_map.to_str().unwrap(),
+                "--output",
+                report.to_str().unwrap(),
+            ])
+            .assert()
+            .success();
+        let r: serde_json::Value = serde_json::from_str(&read_to_string(&report)?)?;
+        assert_eq!(r["identical"].as_array().unwrap().len(), 2);
+        assert_eq!(r["changed"].as_array().unwrap().len(), 0);
+
+        Command::new(assert_cmd::cargo::cargo_bin!("hash-folderoo"))
+            .args(["verify-map", "--input", map.to_str().unwrap()])
+            .assert()
+            .success();
+    }
+    Ok(())
+}
+
+#[test]
+fn encoded_multi_algorithm_maps_decode_every_digest() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = tempdir()?;
+    let root = dir.path().join("root");
+    create_dir_all(&root)?;
+    write(root.join("a.txt"), b"alpha")?;
+
+    let mut maps = Vec::new();
+    for encoding in ["hex", "base32"] {
+        let map = dir.path().join(format!("{}.json", encoding));
+        Command::new(assert_cmd::cargo::cargo_bin!("hash-folderoo"))
+            .args(["hashmap", "--path", root.to_str().unwrap()])
+            .args(["--output", map.to_str().unwrap()])
+            .args([
+                "--algorithms",
+                "blake3,shake256",
+                "--digest-encoding",
+                encoding,
+            ])
+            .assert()
+            .success();
+        maps.push(hash_folderoo::io::load_map_from_json(&map)?);
+    }
+    let (hex, base32) = (&maps[0], &maps[1]);
+    let shake256 = &base32[0].hashes.as_ref().unwrap()["shake256"];
+    assert_eq!(shake256, &hex[0].hashes.as_ref().unwrap()["shake256"]);
+    assert!(shake256.bytes().all(|b| b.is_ascii_hexdigit()));
+    assert_eq!(base32[0].hash, hex[0].hash);
+
+    // re-saved as CSV rows, every algorithm's digest stays hex
+    let csv = dir.path().join("base32.csv");
+    hash_folderoo::io::write_csv(&csv, &hash_folderoo::io::algorithm_rows(base32, "blake3"))?;
+    let reloaded = hash_folderoo::io::load_map_from_csv(&csv)?;
+    assert_eq!(reloaded[0].hash, hex[0].hash);
+    assert_eq!(reloaded[0].hashes, hex[0].hashes);
+    Ok(())
+}
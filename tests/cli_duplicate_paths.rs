@@ -0,0 +1,54 @@
+use assert_cmd::prelude::*;
+use predicates::prelude::*;
+use std::fs::write;
+use std::process::Command;
+use tempfile::tempdir;
+
+#[test]
+fn compare_rejects_map_with_repeated_path() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = tempdir()?;
+    let map = dir.path().join("map.json");
+    write(
+        &map,
+        r#"{"entries":[
+            {"path":"a.txt","hash":"00ff","size":1},
+            {"path":"b.txt","hash":"0011","size":1},
+            {"path":"a.txt","hash":"ff00","size":1}
+        ]}"#,
+    )?;
+    let other = dir.path().join("other.json");
+    write(
+        &other,
+        r#"{"entries":[{"path":"a.txt","hash":"00ff","size":1}]}"#,
+    )?;
+
+    Command::new(assert_cmd::cargo::cargo_bin!("hash-folderoo"))
+        .args([
+            "compare",
+            "--source",
+            map.to_str().unwrap(),
+            "--target",
+            other.to_str().unwrap(),
+        ])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("\"a.txt\""))
+        .stderr(predicate::str::contains("--allow-duplicate-paths"));
+
+    Command::new(assert_cmd::cargo::cargo_bin!("hash-folderoo"))
+        .env("RUST_LOG", "warn")
+        .args([
+            "compare",
+            "--source",
+            map.to_str().unwrap(),
+            "--target",
+            other.to_str().unwrap(),
+            "--allow-duplicate-paths",
+        ])
+        .assert()
+        .success()
+        .stderr(predicate::str::contains(
+            "path \"a.txt\" is listed more than once",
+        ));
+    Ok(())
+}
@@ -0,0 +1,54 @@
+use assert_cmd::prelude::*;
+use std::fs::{create_dir_all, read_to_string, write};
+use std::process::Command;
+use tempfile::tempdir;
+
+#[test]
+fn events_file_records_each_file_and_completion_totals() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = tempdir()?;
+    let root = dir.path().join("root");
+    create_dir_all(root.join("sub"))?;
+    write(root.join("a.txt"), b"alpha")?;
+    write(root.join("b.txt"), b"bravo!")?;
+    write(root.join("sub").join("c.txt"), b"charlie")?;
+    let events_path = dir.path().join("events.jsonl");
+
+    Command::new(assert_cmd::cargo::cargo_bin!("hash-folderoo"))
+        .current_dir(dir.path())
+        .args([
+            "hashmap",
+            "--path",
+            root.to_str().unwrap(),
+            "--output",
+            dir.path().join("map.json").to_str().unwrap(),
+            "--events-file",
+            events_path.to_str().unwrap(),
+        ])
+        .assert()
+        .success();
+
+    let events: Vec<serde_json::Value> = read_to_string(&events_path)?
+        .lines()
+        .map(serde_json::from_str)
+        .collect::<Result<_, _>>()?;
+    let kinds: Vec<&str> = events
+        .iter()
+        .map(|e| e["event"].as_str().unwrap())
+        .collect();
+    assert_eq!(kinds.first(), Some(&"scan_started"));
+    assert_eq!(kinds.last(), Some(&"scan_completed"));
+    assert_eq!(kinds.iter().filter(|k| **k == "file_hashed").count(), 3);
+
+    let hashed = events
+        .iter()
+        .find(|e| e["event"] == "file_hashed" && e["path"] == "b.txt")
+        .expect("file_hashed event for b.txt");
+    assert_eq!(hashed["size"], 6);
+    assert!(hashed["duration_ms"].as_f64().is_some());
+
+    let done = events.last().unwrap();
+    assert_eq!(done["files"], 3);
+    assert_eq!(done["bytes"], 18);
+    assert_eq!(done["errors"], 0);
+    Ok(())
+}
@@ -0,0 +1,40 @@
+use hash_folderoo::memory::{recommend_config, MemoryMode};
+use std::process::Command;
+use tempfile::tempdir;
+
+#[test]
+fn explain_plan_matches_recommend_config() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = tempdir()?;
+    let max_ram: u64 = 3 * 1024 * 1024;
+
+    let out = Command::new(assert_cmd::cargo::cargo_bin!("hash-folderoo"))
+        .current_dir(dir.path())
+        .args([
+            "hashmap",
+            "--path",
+            dir.path().to_str().unwrap(),
+            "--mem-mode",
+            "booster",
+            "--threads",
+            "3",
+            "--max-ram",
+            &max_ram.to_string(),
+            "--explain-plan",
+            "--format",
+            "json",
+        ])
+        .output()?;
+    assert!(out.status.success());
+
+    let expected = recommend_config(MemoryMode::Booster, Some(3), Some(max_ram))?;
+    let v: serde_json::Value = serde_json::from_slice(&out.stdout)?;
+    assert_eq!(v["mode"], "booster");
+    assert_eq!(v["threads"], expected.threads as u64);
+    assert_eq!(v["buffer_size"], expected.buffer_size as u64);
+    assert_eq!(v["num_buffers"], expected.num_buffers as u64);
+    assert_eq!(v["prefetch_listing"], expected.prefetch_listing);
+    assert_eq!(v["total_buffer_bytes"], expected.total_buffer_bytes());
+    // nothing was hashed or written
+    assert_eq!(std::fs::read_dir(dir.path())?.count(), 0);
+    Ok(())
+}
@@ -0,0 +1,36 @@
+use assert_cmd::prelude::*;
+use std::fs::{create_dir_all, write};
+use std::process::Command;
+use tempfile::tempdir;
+
+#[test]
+fn fail_on_empty_rejects_a_scan_that_hashes_nothing() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = tempdir()?;
+    let root = dir.path().join("root");
+    create_dir_all(&root)?;
+    write(root.join("a.txt"), b"alpha")?;
+    write(root.join("b.txt"), b"beta")?;
+    let out = dir.path().join("map.json");
+
+    let output = Command::new(assert_cmd::cargo::cargo_bin!("hash-folderoo"))
+        .current_dir(dir.path())
+        .args(["hashmap", "--path", root.to_str().unwrap(), "--output"])
+        .arg(&out)
+        .args(["--exclude", "*", "--fail-on-empty"])
+        .output()?;
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("no files were hashed"), "{}", stderr);
+    assert!(!out.exists());
+
+    // Without the flag the same run writes an empty map
+    Command::new(assert_cmd::cargo::cargo_bin!("hash-folderoo"))
+        .current_dir(dir.path())
+        .args(["hashmap", "--path", root.to_str().unwrap(), "--output"])
+        .arg(&out)
+        .args(["--exclude", "*"])
+        .assert()
+        .success();
+    assert!(out.exists());
+    Ok(())
+}
@@ -0,0 +1,59 @@
+use std::fs::{create_dir_all, read_to_string, write};
+use std::process::Command;
+use tempfile::tempdir;
+
+#[test]
+fn auto_format_follows_output_extension() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = tempdir()?;
+    let root = dir.path().join("root");
+    create_dir_all(&root)?;
+    write(root.join("a.txt"), b"hello")?;
+
+    let map = dir.path().join("x.csv");
+    let status = Command::new(assert_cmd::cargo::cargo_bin!("hash-folderoo"))
+        .current_dir(dir.path())
+        .args([
+            "hashmap",
+            "--path",
+            root.to_str().unwrap(),
+            "--output",
+            map.to_str().unwrap(),
+            "--format",
+            "auto",
+        ])
+        .status()?;
+    assert!(status.success());
+
+    let text = read_to_string(&map)?;
+    let mut lines = text.lines();
+    assert_eq!(lines.next(), Some("path,hash,size,mtime"));
+    assert!(lines.next().unwrap().starts_with("a.txt,"));
+    Ok(())
+}
+
+#[test]
+fn explicit_format_contradicting_extension_warns() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = tempdir()?;
+    let root = dir.path().join("root");
+    create_dir_all(&root)?;
+    write(root.join("a.txt"), b"hello")?;
+
+    let map = dir.path().join("x.csv");
+    let out = Command::new(assert_cmd::cargo::cargo_bin!("hash-folderoo"))
+        .current_dir(dir.path())
+        .args([
+            "hashmap",
+            "--path",
+            root.to_str().unwrap(),
+            "--output",
+            map.to_str().unwrap(),
+            "--format",
+            "json",
+        ])
+        .output()?;
+    assert!(out.status.success());
+    assert!(String::from_utf8_lossy(&out.stderr).contains("contradicts"));
+    let v: serde_json::Value = serde_json::from_str(&read_to_string(&map)?)?;
+    assert_eq!(v["entries"][0]["path"], "a.txt");
+    Ok(())
+}
@@ -0,0 +1,96 @@
+#![cfg(unix)]
+
+use assert_cmd::prelude::*;
+use std::fs::{create_dir_all, hard_link, write};
+use std::process::Command;
+use tempfile::tempdir;
+
+#[test]
+fn coalesce_hardlinks_does_not_inflate_wasted_bytes() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = tempdir()?;
+    let root = dir.path().join("root");
+    create_dir_all(&root)?;
+    write(root.join("a.bin"), b"0123456789")?;
+    hard_link(root.join("a.bin"), root.join("a-link.bin"))?;
+    write(root.join("a-copy.bin"), b"0123456789")?;
+
+    let wasted = |extra: &[&str]| -> Result<u64, Box<dyn std::error::Error>> {
+        let map = dir.path().join("map.json");
+        Command::new(assert_cmd::cargo::cargo_bin!("hash-folderoo"))
+            .current_dir(dir.path())
+            .args([
+                "hashmap",
+                "--path",
+                root.to_str().unwrap(),
+                "--output",
+                map.to_str().unwrap(),
+            ])
+            .args(extra)
+            .assert()
+            .success();
+        let out = Command::new(assert_cmd::cargo::cargo_bin!("hash-folderoo"))
+            .current_dir(dir.path())
+            .args([
+                "report",
+                "--input",
+                map.to_str().unwrap(),
+                "--format",
+                "json",
+            ])
+            .output()?;
+        assert!(out.status.success());
+        let report: serde_json::Value = serde_json::from_slice(&out.stdout)?;
+        Ok(report["stats"]["wasted_bytes"].as_u64().unwrap())
+    };
+
+    // every link counts as a copy without the flag
+    assert_eq!(wasted(&[])?, 20);
+    // with it the two links are one physical file, leaving a single real duplicate
+    assert_eq!(wasted(&["--coalesce-hardlinks"])?, 10);
+    Ok(())
+}
+
+#[test]
+fn coalesce_hardlinks_writes_a_rectangular_csv() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = tempdir()?;
+    let root = dir.path().join("root");
+    create_dir_all(&root)?;
+    write(root.join("a.bin"), b"0123456789")?;
+    hard_link(root.join("a.bin"), root.join("a-link.bin"))?;
+    write(root.join("plain.bin"), b"plain")?;
+    let map = dir.path().join("map.csv");
+
+    Command::new(assert_cmd::cargo::cargo_bin!("hash-folderoo"))
+        .current_dir(dir.path())
+        .args(["hashmap", "--path", root.to_str().unwrap(), "--output"])
+        .arg(&map)
+        .args(["--format", "csv", "--coalesce-hardlinks"])
+        .assert()
+        .success();
+
+    let text = std::fs::read_to_string(&map)?;
+    let widths: Vec<usize> = text.lines().map(|l| l.split(',').count()).collect();
+    assert_eq!(widths.len(), 4, "{}", text);
+    assert!(widths.iter().all(|w| *w == widths[0]), "{}", text);
+    assert!(text.lines().next().unwrap().contains("inode"), "{}", text);
+
+    // the map loads back with the links still recognised
+    let out = Command::new(assert_cmd::cargo::cargo_bin!("hash-folderoo"))
+        .current_dir(dir.path())
+        .args([
+            "report",
+            "--input",
+            map.to_str().unwrap(),
+            "--format",
+            "json",
+        ])
+        .output()?;
+    assert!(
+        out.status.success(),
+        "{}",
+        String::from_utf8_lossy(&out.stderr)
+    );
+    let report: serde_json::Value = serde_json::from_slice(&out.stdout)?;
+    assert_eq!(report["stats"]["wasted_bytes"].as_u64().unwrap(), 0);
+    Ok(())
+}
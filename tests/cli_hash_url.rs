@@ -0,0 +1,44 @@
+#![cfg(feature = "url")]
+
+use std::process::Command;
+use std::thread;
+
+use tiny_http::{Header, Response, Server};
+
+#[test]
+fn hash_url_streams_the_body_and_follows_redirects() -> Result<(), Box<dyn std::error::Error>> {
+    let body: Vec<u8> = (0..100_000u32).map(|i| (i % 251) as u8).collect();
+    let expected = blake3::hash(&body).to_hex().to_string();
+
+    let server = Server::http("127.0.0.1:0").map_err(|e| e.to_string())?;
+    let addr = server.server_addr().to_ip().unwrap();
+    let served = body.clone();
+    let handle = thread::spawn(move || {
+        for _ in 0..2 {
+            let request = server.recv().unwrap();
+            let response = if request.url() == "/old" {
+                Response::from_data(Vec::new())
+                    .with_status_code(302)
+                    .with_header(Header::from_bytes("Location", "/artifact.bin").unwrap())
+            } else {
+                Response::from_data(served.clone()).with_chunked_threshold(usize::MAX)
+            };
+            request.respond(response).unwrap();
+        }
+    });
+
+    let output = Command::new(assert_cmd::cargo::cargo_bin!("hash-folderoo"))
+        .args(["hash", "--url", &format!("http://{}/old", addr)])
+        .output()?;
+    handle.join().unwrap();
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let stdout = String::from_utf8(output.stdout)?;
+    assert_eq!(stdout.split_whitespace().next(), Some(expected.as_str()));
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("Content-Length 100000"), "{}", stderr);
+    Ok(())
+}
@@ -0,0 +1,82 @@
+use assert_cmd::prelude::*;
+use std::fs::{create_dir_all, read_to_string, write};
+use std::process::Command;
+use tempfile::tempdir;
+
+#[test]
+fn hex_case_upper_maps_compare_equal_to_lowercase() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = tempdir()?;
+    let root = dir.path().join("root");
+    create_dir_all(&root)?;
+    write(root.join("a.txt"), b"hello")?;
+    write(root.join("b.txt"), b"world")?;
+
+    let upper = dir.path().join("upper.json");
+    let lower = dir.path().join("lower.json");
+    for (out, case) in [(&upper, "upper"), (&lower, "lower")] {
+        Command::new(assert_cmd::cargo::cargo_bin!("hash-folderoo"))
+            .args([
+                "hashmap",
+                "--path",
+                root.to_str().unwrap(),
+                "--output",
+                out.to_str().unwrap(),
+                "--hex-case",
+                case,
+            ])
+            .assert()
+            .success();
+    }
+
+    let v: serde_json::Value = serde_json::from_str(&read_to_string(&upper)?)?;
+    for e in v["entries"].as_array().unwrap() {
+        let hash = e["hash"].as_str().unwrap();
+        assert!(hash.chars().any(|c| c.is_ascii_alphabetic()));
+        assert!(!hash.chars().any(|c| c.is_ascii_lowercase()), "{}", hash);
+    }
+
+    let report = dir.path().join("report.json");
+    Command::new(assert_cmd::cargo::cargo_bin!("hash-folderoo"))
+        .args([
+            "compare",
+            "--source",
+            upper.to_str().unwrap(),
+            "--target",
+            lower.to_str().unwrap(),
+            "--output",
+            report.to_str().unwrap(),
+        ])
+        .assert()
+        .success();
+
+    let r: serde_json::Value = serde_json::from_str(&read_to_string(&report)?)?;
+    assert_eq!(r["identical"].as_array().unwrap().len(), 2);
+    assert!(r["changed"].as_array().unwrap().is_empty());
+    Ok(())
+}
+
+#[test]
+fn unknown_hex_case_is_rejected() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = tempdir()?;
+    let root = dir.path().join("root");
+    create_dir_all(&root)?;
+    write(root.join("a.txt"), b"hello")?;
+
+    let out = dir.path().join("map.json");
+    let output = Command::new(assert_cmd::cargo::cargo_bin!("hash-folderoo"))
+        .current_dir(dir.path())
+        .args([
+            "hashmap",
+            "--path",
+            root.to_str().unwrap(),
+            "--output",
+            out.to_str().unwrap(),
+            "--hex-case",
+            "uper",
+        ])
+        .output()?;
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("--hex-case"));
+    assert!(!out.exists());
+    Ok(())
+}
@@ -0,0 +1,55 @@
+use std::fs::{create_dir_all, write};
+use std::process::{Command, Stdio};
+use std::thread::sleep;
+use std::time::{Duration, Instant};
+use tempfile::tempdir;
+
+#[test]
+fn interval_rescans_and_logs_churn() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = tempdir()?;
+    let root = dir.path().join("root");
+    create_dir_all(&root)?;
+    write(root.join("a.txt"), b"one")?;
+    write(root.join("b.txt"), b"two")?;
+
+    let map = dir.path().join("map.json");
+    let child = Command::new(assert_cmd::cargo::cargo_bin!("hash-folderoo"))
+        .current_dir(dir.path())
+        .args([
+            "hashmap",
+            "--path",
+            root.to_str().unwrap(),
+            "--output",
+            map.to_str().unwrap(),
+            "--interval",
+            "2",
+            "--max-cycles",
+            "2",
+        ])
+        .stderr(Stdio::piped())
+        .spawn()?;
+
+    // change the tree once the first scan has been written
+    let started = Instant::now();
+    while !map.exists() {
+        assert!(
+            started.elapsed() < Duration::from_secs(10),
+            "first scan never finished"
+        );
+        sleep(Duration::from_millis(20));
+    }
+    write(root.join("a.txt"), b"changed")?;
+    write(root.join("c.txt"), b"three")?;
+
+    let out = child.wait_with_output()?;
+    assert!(out.status.success());
+    let stderr = String::from_utf8_lossy(&out.stderr);
+    assert!(
+        stderr.contains("Rescan 2: 1 added, 0 removed, 1 changed, 0 moved"),
+        "{}",
+        stderr
+    );
+    let v: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(&map)?)?;
+    assert_eq!(v["entries"].as_array().unwrap().len(), 3);
+    Ok(())
+}
@@ -0,0 +1,39 @@
+use std::fs::{create_dir_all, write};
+use std::process::Command;
+use tempfile::tempdir;
+
+#[test]
+fn large_file_threshold_produces_the_same_map() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = tempdir()?;
+    let root = dir.path().join("root");
+    create_dir_all(&root)?;
+    for i in 0..10 {
+        write(root.join(format!("small{}.txt", i)), format!("small {}", i))?;
+    }
+    write(root.join("large.bin"), vec![3u8; 600_000])?;
+
+    let map = |extra: &[&str]| -> Result<String, Box<dyn std::error::Error>> {
+        let out = Command::new(assert_cmd::cargo::cargo_bin!("hash-folderoo"))
+            .current_dir(dir.path())
+            .args([
+                "hashmap",
+                "--path",
+                root.to_str().unwrap(),
+                "--format",
+                "sums",
+            ])
+            .args(extra)
+            .output()?;
+        assert!(
+            out.status.success(),
+            "{}",
+            String::from_utf8_lossy(&out.stderr)
+        );
+        Ok(String::from_utf8(out.stdout)?)
+    };
+
+    let plain = map(&[])?;
+    assert_eq!(plain.lines().count(), 11);
+    assert_eq!(map(&["--large-file-threshold", "100000"])?, plain);
+    Ok(())
+}
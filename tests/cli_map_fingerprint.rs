@@ -0,0 +1,59 @@
+use assert_cmd::prelude::*;
+use std::fs::{create_dir_all, write};
+use std::path::Path;
+use std::process::Command;
+use tempfile::tempdir;
+
+fn hashmap(dir: &Path, root: &Path, customization: &str, out: &str) {
+    Command::new(assert_cmd::cargo::cargo_bin!("hash-folderoo"))
+        .current_dir(dir)
+        .args([
+            "hashmap",
+            "--path",
+            root.to_str().unwrap(),
+            "--algorithm",
+            "k12",
+            "--customization",
+            customization,
+            "--output",
+            out,
+        ])
+        .assert()
+        .success();
+}
+
+#[test]
+fn maps_with_different_settings_refuse_to_compare() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = tempdir()?;
+    let root = dir.path().join("root");
+    create_dir_all(&root)?;
+    write(root.join("a.txt"), "alpha")?;
+
+    hashmap(dir.path(), &root, "seed-one", "one.json");
+    hashmap(dir.path(), &root, "seed-two", "two.json");
+    hashmap(dir.path(), &root, "seed-one", "one-again.json");
+
+    let compare = |target: &str, force: bool| {
+        let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("hash-folderoo"));
+        cmd.current_dir(dir.path())
+            .args(["compare", "--source", "one.json", "--target", target]);
+        if force {
+            cmd.arg("--force-compare");
+        }
+        cmd.output().unwrap()
+    };
+
+    let refused = compare("two.json", false);
+    assert!(!refused.status.success());
+    let stderr = String::from_utf8_lossy(&refused.stderr);
+    assert!(stderr.contains("--force-compare"), "{}", stderr);
+
+    let forced = compare("two.json", true);
+    assert!(forced.status.success());
+    let report: serde_json::Value = serde_json::from_slice(&forced.stdout)?;
+    assert_eq!(report["changed"].as_array().unwrap().len(), 1);
+
+    // same settings compare as usual
+    assert!(compare("one-again.json", false).status.success());
+    Ok(())
+}
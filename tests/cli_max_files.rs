@@ -0,0 +1,57 @@
+use assert_cmd::prelude::*;
+use std::fs::{create_dir_all, write};
+use std::process::Command;
+use tempfile::tempdir;
+
+#[test]
+fn max_files_rejects_larger_trees() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = tempdir()?;
+    let root = dir.path().join("root");
+    create_dir_all(&root)?;
+    for i in 0..5 {
+        write(root.join(format!("f{}.txt", i)), format!("file {}", i))?;
+    }
+
+    for mode in ["balanced", "stream"] {
+        let out = dir.path().join(format!("{}.json", mode));
+        let output = Command::new(assert_cmd::cargo::cargo_bin!("hash-folderoo"))
+            .current_dir(dir.path())
+            .args([
+                "hashmap",
+                "--path",
+                root.to_str().unwrap(),
+                "--output",
+                out.to_str().unwrap(),
+                "--mem-mode",
+                mode,
+                "--max-files",
+                "3",
+            ])
+            .output()?;
+        assert!(!output.status.success(), "{} mode should fail", mode);
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        assert!(
+            stderr.contains("has more than 3 files (--max-files)"),
+            "{}: {}",
+            mode,
+            stderr
+        );
+        assert!(!out.exists(), "{} mode wrote a map", mode);
+    }
+
+    // the limit is inclusive
+    Command::new(assert_cmd::cargo::cargo_bin!("hash-folderoo"))
+        .current_dir(dir.path())
+        .args([
+            "hashmap",
+            "--path",
+            root.to_str().unwrap(),
+            "--output",
+            "ok.json",
+            "--max-files",
+            "5",
+        ])
+        .assert()
+        .success();
+    Ok(())
+}
@@ -0,0 +1,59 @@
+use assert_cmd::prelude::*;
+use std::fs::{create_dir_all, read_to_string, write};
+use std::process::Command;
+use tempfile::tempdir;
+
+#[test]
+fn max_total_bytes_stops_early_and_marks_header() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = tempdir()?;
+    let root = dir.path().join("root");
+    create_dir_all(&root)?;
+    for i in 0..10 {
+        write(root.join(format!("f{:02}.bin", i)), vec![i as u8; 1000])?;
+    }
+
+    let out = dir.path().join("map.json");
+    Command::new(assert_cmd::cargo::cargo_bin!("hash-folderoo"))
+        .args([
+            "hashmap",
+            "--path",
+            root.to_str().unwrap(),
+            "--output",
+            out.to_str().unwrap(),
+            "--threads",
+            "1",
+            "--max-total-bytes",
+            "2500",
+        ])
+        .assert()
+        .success();
+
+    let v: serde_json::Value = serde_json::from_str(&read_to_string(&out)?)?;
+    // one worker: the third file crosses the budget, then dispatch stops
+    assert_eq!(v["entries"].as_array().unwrap().len(), 3);
+    assert_eq!(v["truncated"]["reason"], "max_total_bytes");
+    assert_eq!(v["truncated"]["limit_bytes"], 2500);
+    assert_eq!(v["truncated"]["hashed_bytes"], 3000);
+
+    // a budget the tree fits into, even exactly, leaves the map untouched
+    for budget in ["1000000", "10000"] {
+        Command::new(assert_cmd::cargo::cargo_bin!("hash-folderoo"))
+            .args([
+                "hashmap",
+                "--path",
+                root.to_str().unwrap(),
+                "--output",
+                out.to_str().unwrap(),
+                "--threads",
+                "1",
+                "--max-total-bytes",
+                budget,
+            ])
+            .assert()
+            .success();
+        let v: serde_json::Value = serde_json::from_str(&read_to_string(&out)?)?;
+        assert_eq!(v["entries"].as_array().unwrap().len(), 10);
+        assert!(v.get("truncated").is_none(), "{}", budget);
+    }
+    Ok(())
+}
@@ -0,0 +1,104 @@
+use std::fs::{create_dir_all, read_to_string, write};
+use std::process::Command;
+use tempfile::tempdir;
+
+#[test]
+fn two_algorithm_map_matches_single_runs() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = tempdir()?;
+    let root = dir.path().join("root");
+    create_dir_all(&root)?;
+    write(root.join("a.txt"), b"alpha")?;
+    write(root.join("b.bin"), vec![7u8; 100_000])?;
+
+    let hashmap =
+        |name: &str, algo_args: &[&str]| -> Result<serde_json::Value, Box<dyn std::error::Error>> {
+            let out = dir.path().join(name);
+            let status = Command::new(assert_cmd::cargo::cargo_bin!("hash-folderoo"))
+                .current_dir(dir.path())
+                .args([
+                    "hashmap",
+                    "--path",
+                    root.to_str().unwrap(),
+                    "--output",
+                    out.to_str().unwrap(),
+                ])
+                .args(algo_args)
+                .status()?;
+            assert!(status.success());
+            Ok(serde_json::from_str(&read_to_string(&out)?)?)
+        };
+
+    let multi = hashmap("multi.json", &["--algorithms", "blake3,shake256"])?;
+    let blake3 = hashmap("blake3.json", &["--algorithm", "blake3"])?;
+    let shake = hashmap("shake.json", &["--algorithm", "shake256"])?;
+
+    assert_eq!(
+        multi["algorithm"]["params"]["algorithms"],
+        serde_json::json!(["blake3", "shake256"])
+    );
+    let entries = multi["entries"].as_array().unwrap();
+    assert_eq!(entries.len(), 2);
+    for (i, entry) in entries.iter().enumerate() {
+        assert_eq!(entry["hash"], blake3["entries"][i]["hash"]);
+        assert_eq!(entry["hashes"]["blake3"], blake3["entries"][i]["hash"]);
+        assert_eq!(entry["hashes"]["shake256"], shake["entries"][i]["hash"]);
+    }
+    // single-algorithm maps keep their old shape
+    assert!(blake3["entries"][0].get("hashes").is_none());
+    Ok(())
+}
+
+#[test]
+fn extra_algorithms_take_xof_length_and_params() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = tempdir()?;
+    let root = dir.path().join("root");
+    create_dir_all(&root)?;
+    write(root.join("a.txt"), b"alpha")?;
+
+    let hashmap = |name: &str, args: &[&str]| {
+        let out = dir.path().join(name);
+        let output = Command::new(assert_cmd::cargo::cargo_bin!("hash-folderoo"))
+            .current_dir(dir.path())
+            .args([
+                "hashmap",
+                "--path",
+                root.to_str().unwrap(),
+                "--output",
+                out.to_str().unwrap(),
+            ])
+            .args(args)
+            .output()
+            .unwrap();
+        (output, out)
+    };
+    let json = |path: &std::path::Path| -> serde_json::Value {
+        serde_json::from_str(&read_to_string(path).unwrap()).unwrap()
+    };
+
+    let xof = ["--xof-length", "48", "--customization", "folderoo"];
+    let (out, multi) = hashmap(
+        "multi.json",
+        &[&["--algorithms", "k12,shake256"][..], &xof].concat(),
+    );
+    assert!(out.status.success());
+    let (_, shake) = hashmap(
+        "shake.json",
+        &["--algorithm", "shake256", "--xof-length", "48"],
+    );
+    let (_, k12) = hashmap("k12.json", &[&["--algorithm", "k12"][..], &xof].concat());
+    let (multi, shake, k12) = (json(&multi), json(&shake), json(&k12));
+    let hashes = &multi["entries"][0]["hashes"];
+    assert_eq!(hashes["shake256"], shake["entries"][0]["hash"]);
+    assert_eq!(hashes["k12"], k12["entries"][0]["hash"]);
+    assert_eq!(hashes["k12"].as_str().unwrap().len(), 96);
+
+    let (out, ndjson) = hashmap("multi.ndjson", &["--algorithms", "blake3,shake256"]);
+    assert!(out.status.success());
+    let line: serde_json::Value = serde_json::from_str(read_to_string(&ndjson)?.trim())?;
+    assert!(line["hashes"]["shake256"].is_string(), "{}", line);
+
+    let (out, _) = hashmap("multi.sums", &["--algorithms", "blake3,shake256"]);
+    assert!(!out.status.success());
+    assert!(String::from_utf8_lossy(&out.stderr).contains("--algorithms"));
+    Ok(())
+}
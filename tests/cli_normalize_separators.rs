@@ -0,0 +1,50 @@
+use std::fs::{read_to_string, write};
+use std::process::Command;
+use tempfile::tempdir;
+
+#[test]
+fn backslash_map_matches_forward_slash_map() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = tempdir()?;
+    let windows = dir.path().join("windows.json");
+    let unix = dir.path().join("unix.csv");
+    write(
+        &windows,
+        r#"{"entries": [
+            {"path": "docs\\a.txt", "hash": "aa", "size": 1},
+            {"path": "docs\\sub\\b.txt", "hash": "bb", "size": 2}
+        ]}"#,
+    )?;
+    write(
+        &unix,
+        "path,hash,size,mtime\ndocs/a.txt,aa,1,\ndocs/sub/b.txt,bb,2,\n",
+    )?;
+
+    let compare = |extra: &[&str]| -> Result<serde_json::Value, Box<dyn std::error::Error>> {
+        let out = dir.path().join("report.json");
+        let status = Command::new(assert_cmd::cargo::cargo_bin!("hash-folderoo"))
+            .current_dir(dir.path())
+            .args([
+                "compare",
+                "--source",
+                windows.to_str().unwrap(),
+                "--target",
+                unix.to_str().unwrap(),
+                "--output",
+                out.to_str().unwrap(),
+            ])
+            .args(extra)
+            .status()?;
+        assert!(status.success());
+        Ok(serde_json::from_str(&read_to_string(&out)?)?)
+    };
+
+    let raw = compare(&[])?;
+    assert_eq!(raw["identical"].as_array().unwrap().len(), 0);
+
+    let normalized = compare(&["--normalize-separators"])?;
+    assert_eq!(normalized["identical"].as_array().unwrap().len(), 2);
+    assert!(normalized["moved"].as_array().unwrap().is_empty());
+    assert!(normalized["missing"].as_array().unwrap().is_empty());
+    assert!(normalized["new"].as_array().unwrap().is_empty());
+    Ok(())
+}
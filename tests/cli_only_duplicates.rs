@@ -0,0 +1,49 @@
+use assert_cmd::prelude::*;
+use std::fs::{create_dir_all, write};
+use std::process::Command;
+use tempfile::tempdir;
+
+#[test]
+fn report_only_duplicates_emits_just_the_duplicate_group() -> Result<(), Box<dyn std::error::Error>>
+{
+    let dir = tempdir()?;
+    let root = dir.path().join("root");
+    create_dir_all(root.join("sub"))?;
+    write(root.join("a.txt"), b"same bytes")?;
+    write(root.join("sub").join("b.txt"), b"same bytes")?;
+    // Same size as the pair, so it is hashed, but its content is unique
+    write(root.join("c.txt"), b"diff bytes")?;
+    write(root.join("d.txt"), b"a size of its own")?;
+
+    let out = Command::new(assert_cmd::cargo::cargo_bin!("hash-folderoo"))
+        .current_dir(dir.path())
+        .args([
+            "report",
+            "--input",
+            root.to_str().unwrap(),
+            "--only-duplicates",
+            "--format",
+            "json",
+        ])
+        .assert()
+        .success()
+        .get_output()
+        .stdout
+        .clone();
+
+    let r: serde_json::Value = serde_json::from_slice(&out)?;
+    assert!(r.get("stats").is_none(), "{}", r);
+    assert!(r.get("largest_files").is_none(), "{}", r);
+    let groups = r["duplicates"].as_array().unwrap();
+    assert_eq!(groups.len(), 1, "{}", r);
+    assert_eq!(
+        groups[0]["paths"],
+        serde_json::json!(["a.txt", "sub/b.txt"])
+    );
+    assert_eq!(groups[0]["count"], 2);
+    assert_eq!(
+        groups[0]["hash"],
+        blake3::hash(b"same bytes").to_hex().as_str()
+    );
+    Ok(())
+}
@@ -0,0 +1,47 @@
+#![cfg(unix)]
+
+use assert_cmd::prelude::*;
+use std::fs::{create_dir_all, metadata, write};
+use std::os::unix::fs::PermissionsExt;
+use std::process::Command;
+use tempfile::tempdir;
+
+#[test]
+fn output_mode_sets_map_permissions() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = tempdir()?;
+    let root = dir.path().join("root");
+    create_dir_all(&root)?;
+    write(root.join("a.txt"), b"hello")?;
+    let map = dir.path().join("map.json");
+
+    Command::new(assert_cmd::cargo::cargo_bin!("hash-folderoo"))
+        .current_dir(dir.path())
+        .args([
+            "hashmap",
+            "--path",
+            root.to_str().unwrap(),
+            "--output",
+            map.to_str().unwrap(),
+            "--output-mode",
+            "600",
+        ])
+        .assert()
+        .success();
+
+    assert_eq!(metadata(&map)?.permissions().mode() & 0o7777, 0o600);
+
+    Command::new(assert_cmd::cargo::cargo_bin!("hash-folderoo"))
+        .current_dir(dir.path())
+        .args([
+            "hashmap",
+            "--path",
+            root.to_str().unwrap(),
+            "--output",
+            map.to_str().unwrap(),
+            "--output-mode",
+            "9z",
+        ])
+        .assert()
+        .failure();
+    Ok(())
+}
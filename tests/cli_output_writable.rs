@@ -0,0 +1,39 @@
+use std::fs::{create_dir_all, write};
+use std::process::Command;
+use tempfile::tempdir;
+
+#[test]
+fn unwritable_output_fails_before_hashing() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = tempdir()?;
+    let root = dir.path().join("root");
+    create_dir_all(&root)?;
+    write(root.join("a.txt"), "alpha")?;
+    // a regular file where the output directory should be
+    write(dir.path().join("blocker"), "not a directory")?;
+    let events = dir.path().join("events.jsonl");
+
+    let output = Command::new(assert_cmd::cargo::cargo_bin!("hash-folderoo"))
+        .current_dir(dir.path())
+        .args([
+            "hashmap",
+            "--path",
+            root.to_str().unwrap(),
+            "--output",
+            "blocker/map.json",
+            "--events-file",
+            events.to_str().unwrap(),
+        ])
+        .output()?;
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("cannot create output directory"),
+        "{}",
+        stderr
+    );
+    assert!(
+        !events.exists(),
+        "the scan started before the output was checked"
+    );
+    Ok(())
+}
@@ -0,0 +1,50 @@
+use assert_cmd::prelude::*;
+use std::fs::{create_dir_all, read_to_string, write};
+use std::process::Command;
+use tempfile::tempdir;
+
+fn sums_line(data: &[u8], name: &str) -> String {
+    format!("{}  {}\n", blake3::hash(data).to_hex(), name)
+}
+
+#[test]
+fn per_dir_output_writes_sums_file_into_each_directory() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = tempdir()?;
+    let root = dir.path().join("root");
+    create_dir_all(root.join("sub"))?;
+    write(root.join("a.txt"), b"alpha")?;
+    write(root.join("b.txt"), b"bravo")?;
+    write(root.join("sub").join("c.txt"), b"charlie")?;
+
+    let run = || {
+        Command::new(assert_cmd::cargo::cargo_bin!("hash-folderoo"))
+            // keep the repo's sample config.json (which sets an output) out of the run
+            .current_dir(dir.path())
+            .args([
+                "hashmap",
+                "--path",
+                root.to_str().unwrap(),
+                "--xof-length",
+                "32",
+                "--per-dir-output",
+                "CHECKSUMS.txt",
+            ])
+            .assert()
+            .success()
+            .stdout("");
+    };
+    run();
+
+    let top = read_to_string(root.join("CHECKSUMS.txt"))?;
+    assert_eq!(
+        top,
+        sums_line(b"alpha", "a.txt") + &sums_line(b"bravo", "b.txt")
+    );
+    let sub = read_to_string(root.join("sub").join("CHECKSUMS.txt"))?;
+    assert_eq!(sub, sums_line(b"charlie", "c.txt"));
+
+    // a second run doesn't pick up the sums files it wrote the first time
+    run();
+    assert_eq!(read_to_string(root.join("CHECKSUMS.txt"))?, top);
+    Ok(())
+}
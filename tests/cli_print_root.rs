@@ -0,0 +1,46 @@
+use std::fs::{create_dir_all, write};
+use std::path::Path;
+use std::process::Command;
+use tempfile::tempdir;
+
+fn print_root(dir: &Path, root: &Path) -> String {
+    let output = Command::new(assert_cmd::cargo::cargo_bin!("hash-folderoo"))
+        .current_dir(dir)
+        .args(["hashmap", "--path", root.to_str().unwrap(), "--print-root"])
+        .output()
+        .unwrap();
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    String::from_utf8(output.stdout).unwrap()
+}
+
+#[test]
+fn print_root_is_stable_and_tracks_content() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = tempdir()?;
+    let root = dir.path().join("root");
+    create_dir_all(root.join("sub"))?;
+    write(root.join("a.txt"), "alpha")?;
+    write(root.join("b.txt"), "beta")?;
+    write(root.join("sub").join("c.txt"), "gamma")?;
+
+    let first = print_root(dir.path(), &root);
+    let lines: Vec<&str> = first.lines().collect();
+    assert_eq!(lines.len(), 1, "only the root is printed: {}", first);
+    assert_eq!(lines[0].len(), 64);
+    assert!(lines[0].chars().all(|c| c.is_ascii_hexdigit()));
+    assert_eq!(print_root(dir.path(), &root), first);
+
+    write(root.join("sub").join("c.txt"), "delta")?;
+    let changed = print_root(dir.path(), &root);
+    assert_ne!(changed, first);
+
+    write(root.join("sub").join("c.txt"), "gamma")?;
+    assert_eq!(print_root(dir.path(), &root), first);
+    // paths are part of the root, so a rename changes it too
+    std::fs::rename(root.join("b.txt"), root.join("b2.txt"))?;
+    assert_ne!(print_root(dir.path(), &root), first);
+    Ok(())
+}
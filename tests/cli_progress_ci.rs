@@ -0,0 +1,36 @@
+use std::fs::{create_dir_all, write};
+use std::process::Command;
+use tempfile::tempdir;
+
+#[test]
+fn piped_stderr_gets_plain_progress_unless_forced() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = tempdir()?;
+    let root = dir.path().join("root");
+    create_dir_all(&root)?;
+    for i in 0..3 {
+        write(root.join(format!("f{}.txt", i)), format!("file {}", i))?;
+    }
+
+    let run = |extra: &[&str]| {
+        let output = Command::new(assert_cmd::cargo::cargo_bin!("hash-folderoo"))
+            .current_dir(dir.path())
+            .env_remove("CI")
+            .args(["hashmap", "--path", root.to_str().unwrap(), "--progress"])
+            .args(["--output", "map.json"])
+            .args(extra)
+            .output()
+            .unwrap();
+        assert!(output.status.success());
+        String::from_utf8_lossy(&output.stderr).into_owned()
+    };
+
+    // stderr is a pipe here, so no bar is drawn; plain lines report progress instead
+    let stderr = run(&[]);
+    assert!(stderr.contains("progress: 3/3 files"), "{}", stderr);
+    assert!(!stderr.contains('\r'), "bar redraws in {:?}", stderr);
+
+    let forced = run(&["--force-progress"]);
+    assert!(forced.contains("3/3 done"), "{}", forced);
+    assert!(!forced.contains("progress: "), "{}", forced);
+    Ok(())
+}
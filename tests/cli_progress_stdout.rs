@@ -0,0 +1,31 @@
+use std::fs::{create_dir_all, write};
+use std::process::Command;
+use tempfile::tempdir;
+
+#[test]
+fn piped_stdout_stays_valid_json_with_progress() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = tempdir()?;
+    let root = dir.path().join("root");
+    create_dir_all(root.join("sub"))?;
+    for i in 0..20 {
+        write(root.join(format!("f{}.txt", i)), format!("file {}", i))?;
+    }
+    write(root.join("sub").join("nested.txt"), b"nested")?;
+
+    for extra in [&[][..], &["--progress-to-stderr"][..]] {
+        let out = Command::new(assert_cmd::cargo::cargo_bin!("hash-folderoo"))
+            // run outside the repo so its sample config.json (which sets an output) isn't picked up
+            .current_dir(dir.path())
+            .env("RUST_LOG", "info")
+            .args(["hashmap", "--path", root.to_str().unwrap(), "--progress"])
+            .args(extra)
+            .output()?;
+        assert!(out.status.success());
+
+        // stdout carries only the map; logs and progress go to stderr
+        let v: serde_json::Value = serde_json::from_slice(&out.stdout)?;
+        assert_eq!(v["entries"].as_array().unwrap().len(), 21);
+        assert!(String::from_utf8_lossy(&out.stderr).contains("Processed 21 files"));
+    }
+    Ok(())
+}
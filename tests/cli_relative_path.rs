@@ -0,0 +1,30 @@
+use assert_cmd::prelude::*;
+use std::fs::{create_dir_all, read_to_string, write};
+use std::process::Command;
+use tempfile::tempdir;
+
+#[test]
+fn relative_path_is_not_recorded_in_entry_paths() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = tempdir()?;
+    let root = dir.path().join("root");
+    create_dir_all(root.join("sub"))?;
+    write(root.join("a.txt"), b"alpha")?;
+    write(root.join("sub").join("b.txt"), b"beta")?;
+
+    Command::new(assert_cmd::cargo::cargo_bin!("hash-folderoo"))
+        .current_dir(dir.path())
+        .args(["hashmap", "--path", "root", "--output", "map.json"])
+        .assert()
+        .success();
+
+    let v: serde_json::Value = serde_json::from_str(&read_to_string(dir.path().join("map.json"))?)?;
+    let mut paths: Vec<&str> = v["entries"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|e| e["path"].as_str().unwrap())
+        .collect();
+    paths.sort();
+    assert_eq!(paths, ["a.txt", "sub/b.txt"]);
+    Ok(())
+}
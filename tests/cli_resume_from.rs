@@ -0,0 +1,43 @@
+use std::fs::{create_dir_all, read_to_string, write};
+use std::process::Command;
+use tempfile::tempdir;
+
+#[test]
+fn resume_from_skips_files_up_to_the_marker() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = tempdir()?;
+    let root = dir.path().join("root");
+    create_dir_all(root.join("a"))?;
+    create_dir_all(root.join("b"))?;
+    create_dir_all(root.join("c"))?;
+    write(root.join("a").join("1.txt"), b"a1")?;
+    write(root.join("b").join("1.txt"), b"b1")?;
+    write(root.join("b").join("2.txt"), b"b2")?;
+    write(root.join("b").join("3.txt"), b"b3")?;
+    write(root.join("c").join("1.txt"), b"c1")?;
+    write(root.join("top.txt"), b"top")?;
+
+    let map = dir.path().join("map.json");
+    let status = Command::new(assert_cmd::cargo::cargo_bin!("hash-folderoo"))
+        .current_dir(dir.path())
+        .args([
+            "hashmap",
+            "--path",
+            root.to_str().unwrap(),
+            "--output",
+            map.to_str().unwrap(),
+            "--resume-from",
+            "b/2.txt",
+        ])
+        .status()?;
+    assert!(status.success());
+
+    let v: serde_json::Value = serde_json::from_str(&read_to_string(&map)?)?;
+    let paths: Vec<&str> = v["entries"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|e| e["path"].as_str().unwrap())
+        .collect();
+    assert_eq!(paths, ["b/3.txt", "c/1.txt", "top.txt"]);
+    Ok(())
+}
@@ -45,3 +45,48 @@ fn copydiff_resume_executes_and_persists_plan() -> Result<(), Box<dyn std::error
 
     Ok(())
 }
+
+#[test]
+fn copydiff_resume_skips_ops_already_marked_done() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = tempdir()?;
+    let src = dir.path().join("src");
+    let dst = dir.path().join("dst");
+    create_dir_all(&src)?;
+    create_dir_all(&dst)?;
+
+    write(src.join("a.txt"), b"already copied")?;
+    write(src.join("b.txt"), b"pending")?;
+
+    // the first op is marked done by an earlier, interrupted run and must not be redone
+    let plan = dir.path().join("plan.json");
+    let plan_json = serde_json::json!({
+        "meta": { "version": 1, "generated_at": "now" },
+        "ops": [
+            { "src": src.join("a.txt").to_string_lossy(), "dst": dst.join("a.txt").to_string_lossy(), "op": "copy", "done": true, "status": "done" },
+            { "src": src.join("b.txt").to_string_lossy(), "dst": dst.join("b.txt").to_string_lossy(), "op": "copy", "done": false, "status": "pending" }
+        ]
+    });
+    std::fs::write(&plan, serde_json::to_string_pretty(&plan_json)?)?;
+
+    Command::new(assert_cmd::cargo::cargo_bin!("hash-folderoo"))
+        .args([
+            "copydiff",
+            "--plan",
+            plan.to_str().unwrap(),
+            "--execute",
+            "--resume",
+        ])
+        .assert()
+        .success();
+
+    assert!(!dst.join("a.txt").exists());
+    assert_eq!(std::fs::read(dst.join("b.txt"))?, b"pending");
+
+    let persisted: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(&plan)?)?;
+    let ops = persisted["ops"].as_array().unwrap();
+    assert_eq!(ops[0]["status"], "done");
+    assert_eq!(ops[1]["status"], "done");
+    assert_eq!(ops[1]["done"], true);
+
+    Ok(())
+}
@@ -0,0 +1,57 @@
+use assert_cmd::prelude::*;
+use std::fs::{create_dir_all, read_to_string, write};
+use std::process::Command;
+use tempfile::tempdir;
+
+#[test]
+fn run_summary_records_counts_and_algorithm() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = tempdir()?;
+    let root = dir.path().join("root");
+    create_dir_all(root.join("sub"))?;
+    write(root.join("a.txt"), b"alpha")?;
+    write(root.join("sub").join("b.txt"), b"beta")?;
+    let summary = dir.path().join("summary.json");
+
+    // Map goes to stdout as CSV; the summary is still written to its own file
+    Command::new(assert_cmd::cargo::cargo_bin!("hash-folderoo"))
+        .current_dir(dir.path())
+        .args(["hashmap", "--path", root.to_str().unwrap()])
+        .args(["--algorithm", "xxh3", "--format", "csv", "--threads", "2"])
+        .arg("--run-summary")
+        .arg(&summary)
+        .assert()
+        .success();
+
+    let v: serde_json::Value = serde_json::from_str(&read_to_string(&summary)?)?;
+    assert_eq!(v["files"], 2);
+    assert_eq!(v["bytes"], 9);
+    assert_eq!(v["errors"], 0);
+    assert_eq!(v["algorithm"], "xxh3-1024");
+    assert_eq!(v["mode"], "balanced");
+    assert_eq!(v["threads"], 2);
+    assert!(v["num_buffers"].as_u64().unwrap() >= 1);
+    assert!(v["elapsed_ms"].as_f64().unwrap() >= 0.0);
+    Ok(())
+}
+
+#[test]
+fn run_summary_is_not_written_when_the_map_fails() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = tempdir()?;
+    let root = dir.path().join("root");
+    create_dir_all(&root)?;
+    write(root.join("a.txt"), b"alpha")?;
+    let summary = dir.path().join("summary.json");
+
+    Command::new(assert_cmd::cargo::cargo_bin!("hash-folderoo"))
+        .current_dir(dir.path())
+        .args(["hashmap", "--path", root.to_str().unwrap()])
+        .arg("--output")
+        .arg(dir.path().join("root").join("a.txt").join("map.json"))
+        .arg("--run-summary")
+        .arg(&summary)
+        .assert()
+        .failure();
+
+    assert!(!summary.exists());
+    Ok(())
+}
@@ -0,0 +1,50 @@
+use assert_cmd::prelude::*;
+use std::fs::{create_dir_all, read_to_string, write};
+use std::process::Command;
+use tempfile::tempdir;
+
+#[test]
+fn compare_self_dupes_groups_identical_content() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = tempdir()?;
+    let root = dir.path().join("root");
+    create_dir_all(root.join("copy"))?;
+    write(root.join("photo.jpg"), b"same bytes")?;
+    write(root.join("copy").join("photo-backup.jpg"), b"same bytes")?;
+    write(root.join("other.txt"), b"different")?;
+
+    let map = dir.path().join("map.json");
+    Command::new(assert_cmd::cargo::cargo_bin!("hash-folderoo"))
+        .args([
+            "hashmap",
+            "--path",
+            root.to_str().unwrap(),
+            "--output",
+            map.to_str().unwrap(),
+        ])
+        .assert()
+        .success();
+
+    let out = dir.path().join("report.json");
+    Command::new(assert_cmd::cargo::cargo_bin!("hash-folderoo"))
+        .args([
+            "compare",
+            "--source",
+            map.to_str().unwrap(),
+            "--self-dupes",
+            "--output",
+            out.to_str().unwrap(),
+        ])
+        .assert()
+        .success();
+
+    let r: serde_json::Value = serde_json::from_str(&read_to_string(&out)?)?;
+    let groups = r["self_duplicates"].as_array().unwrap();
+    assert_eq!(groups.len(), 1);
+    assert_eq!(
+        groups[0]["paths"],
+        serde_json::json!(["copy/photo-backup.jpg", "photo.jpg"])
+    );
+    assert_eq!(groups[0]["count"], 2);
+    assert_eq!(r["identical"].as_array().unwrap().len(), 3);
+    Ok(())
+}
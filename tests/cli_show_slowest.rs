@@ -0,0 +1,47 @@
+use std::fs::{create_dir_all, read_to_string, write};
+use std::process::Command;
+use tempfile::tempdir;
+
+#[test]
+fn show_slowest_zero_suppresses_section() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = tempdir()?;
+    let root = dir.path().join("root");
+    create_dir_all(&root)?;
+    for i in 0..3 {
+        write(root.join(format!("f{}.txt", i)), format!("file {}", i))?;
+    }
+    let map = dir.path().join("map.json");
+    let timings = dir.path().join("timings.json");
+
+    let run = |extra: &[&str]| -> Result<String, Box<dyn std::error::Error>> {
+        let out = Command::new(assert_cmd::cargo::cargo_bin!("hash-folderoo"))
+            .current_dir(dir.path())
+            .env("RUST_LOG", "info")
+            .args([
+                "hashmap",
+                "--path",
+                root.to_str().unwrap(),
+                "--output",
+                map.to_str().unwrap(),
+            ])
+            .args(extra)
+            .output()?;
+        assert!(out.status.success());
+        Ok(String::from_utf8(out.stderr)?)
+    };
+
+    assert!(run(&[])?.contains("Top slowest files"));
+    let stderr = run(&[
+        "--show-slowest",
+        "0",
+        "--show-slowest-json",
+        timings.to_str().unwrap(),
+    ])?;
+    assert!(!stderr.contains("Top slowest files"));
+
+    // the JSON dump still lists every file even with the log section disabled
+    let rows: serde_json::Value = serde_json::from_str(&read_to_string(&timings)?)?;
+    assert_eq!(rows.as_array().unwrap().len(), 3);
+    assert!(rows[0]["duration_ms"].is_f64());
+    Ok(())
+}
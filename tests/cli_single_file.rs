@@ -0,0 +1,46 @@
+use assert_cmd::prelude::*;
+use std::fs::{create_dir_all, write};
+use std::process::Command;
+use tempfile::tempdir;
+
+#[test]
+fn hashing_a_single_file_yields_one_entry_keyed_by_basename(
+) -> Result<(), Box<dyn std::error::Error>> {
+    let dir = tempdir()?;
+    let data = dir.path().join("data");
+    create_dir_all(&data)?;
+    write(data.join("somefile.txt"), b"just this one")?;
+    write(data.join("sibling.txt"), b"not hashed")?;
+
+    for extra in [&[][..], &["--depth", "0"][..]] {
+        let out = Command::new(assert_cmd::cargo::cargo_bin!("hash-folderoo"))
+            .current_dir(dir.path())
+            .args([
+                "hashmap",
+                "--path",
+                data.join("somefile.txt").to_str().unwrap(),
+                "--xof-length",
+                "32",
+            ])
+            .args(extra)
+            .assert()
+            .success()
+            .get_output()
+            .stdout
+            .clone();
+        let map: serde_json::Value = serde_json::from_slice(&out)?;
+        let entries = map["entries"].as_array().unwrap();
+        assert_eq!(entries.len(), 1, "{:?}", extra);
+        assert_eq!(entries[0]["path"], "somefile.txt");
+        assert_eq!(entries[0]["size"], 13);
+        assert_eq!(
+            entries[0]["hash"],
+            blake3::hash(b"just this one").to_hex().as_str()
+        );
+        assert_eq!(
+            map["root"].as_str().unwrap(),
+            data.canonicalize()?.to_str().unwrap()
+        );
+    }
+    Ok(())
+}
@@ -0,0 +1,61 @@
+use std::fs::{create_dir_all, write};
+use std::process::Command;
+use tempfile::tempdir;
+
+#[test]
+fn sort_hash_groups_identical_files() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = tempdir()?;
+    let root = dir.path().join("root");
+    create_dir_all(&root)?;
+    // path order interleaves the two contents
+    for (name, content) in [
+        ("a", "one"),
+        ("b", "two"),
+        ("c", "one"),
+        ("d", "two"),
+        ("e", "one"),
+    ] {
+        write(root.join(name), content)?;
+    }
+
+    let output = Command::new(assert_cmd::cargo::cargo_bin!("hash-folderoo"))
+        .current_dir(dir.path())
+        .args([
+            "hashmap",
+            "--path",
+            root.to_str().unwrap(),
+            "--format",
+            "csv",
+            "--sort",
+            "hash",
+        ])
+        .output()?;
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let stdout = String::from_utf8(output.stdout)?;
+    let rows: Vec<(&str, &str)> = stdout
+        .lines()
+        .skip(1)
+        .map(|l| {
+            let mut cols = l.split(',');
+            (cols.next().unwrap(), cols.next().unwrap())
+        })
+        .collect();
+    assert_eq!(rows.len(), 5);
+    let hashes: Vec<&str> = rows.iter().map(|r| r.1).collect();
+    let mut sorted = hashes.clone();
+    sorted.sort();
+    assert_eq!(hashes, sorted, "rows are ordered by hash");
+    // each group is contiguous and ordered by path within
+    let one: Vec<&str> = rows
+        .iter()
+        .filter(|r| r.1 == rows[0].1)
+        .map(|r| r.0)
+        .collect();
+    assert!(one == ["a", "c", "e"] || one == ["b", "d"], "{:?}", rows);
+    assert_eq!(hashes.windows(2).filter(|w| w[0] != w[1]).count(), 1);
+    Ok(())
+}
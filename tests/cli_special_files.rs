@@ -0,0 +1,65 @@
+#![cfg(unix)]
+
+use assert_cmd::prelude::*;
+use std::fs::{read_to_string, OpenOptions};
+use std::io::Write;
+use std::process::Command;
+use std::thread;
+use tempfile::tempdir;
+
+#[test]
+fn hashmap_allow_special_hashes_fifo_stream() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = tempdir()?;
+    let fifo = dir.path().join("stream.fifo");
+    let status = Command::new("mkfifo").arg(&fifo).status()?;
+    assert!(status.success(), "mkfifo failed");
+
+    let payload: Vec<u8> = (0..100_000u32).map(|i| (i % 251) as u8).collect();
+    let expected = blake3::hash(&payload).to_hex().to_string();
+
+    // Opening a FIFO for writing blocks until the reader side opens it.
+    let writer_path = fifo.clone();
+    let writer_payload = payload.clone();
+    let writer = thread::spawn(move || {
+        let mut f = OpenOptions::new().write(true).open(writer_path).unwrap();
+        f.write_all(&writer_payload).unwrap();
+    });
+
+    let out = dir.path().join("map.json");
+    Command::new(assert_cmd::cargo::cargo_bin!("hash-folderoo"))
+        .args([
+            "hashmap",
+            "--path",
+            fifo.to_str().unwrap(),
+            "--output",
+            out.to_str().unwrap(),
+            "--xof-length",
+            "32",
+            "--allow-special",
+        ])
+        .assert()
+        .success();
+    writer.join().unwrap();
+
+    let v: serde_json::Value = serde_json::from_str(&read_to_string(&out)?)?;
+    let entries = v["entries"].as_array().unwrap();
+    assert_eq!(entries.len(), 1);
+    assert_eq!(entries[0]["path"], "stream.fifo");
+    assert_eq!(entries[0]["hash"], expected.as_str());
+    assert_eq!(entries[0]["size"], payload.len() as u64);
+    Ok(())
+}
+
+#[test]
+fn hashmap_rejects_special_path_without_flag() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = tempdir()?;
+    let fifo = dir.path().join("stream.fifo");
+    let status = Command::new("mkfifo").arg(&fifo).status()?;
+    assert!(status.success(), "mkfifo failed");
+
+    Command::new(assert_cmd::cargo::cargo_bin!("hash-folderoo"))
+        .args(["hashmap", "--path", fifo.to_str().unwrap()])
+        .assert()
+        .failure();
+    Ok(())
+}
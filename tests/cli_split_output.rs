@@ -0,0 +1,105 @@
+use assert_cmd::prelude::*;
+use std::fs::{create_dir_all, read_to_string, write};
+use std::process::Command;
+use tempfile::tempdir;
+
+#[test]
+fn split_output_writes_one_csv_per_category() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = tempdir()?;
+    let src = dir.path().join("src");
+    let dst = dir.path().join("dst");
+    create_dir_all(&src)?;
+    create_dir_all(&dst)?;
+    write(src.join("same.txt"), b"same")?;
+    write(dst.join("same.txt"), b"same")?;
+    write(src.join("edited.txt"), b"before")?;
+    write(dst.join("edited.txt"), b"after")?;
+    write(src.join("gone.txt"), b"only in source")?;
+    write(dst.join("added.txt"), b"only in target")?;
+
+    let map = |root: &std::path::Path, name: &str| -> std::path::PathBuf {
+        let out = dir.path().join(name);
+        Command::new(assert_cmd::cargo::cargo_bin!("hash-folderoo"))
+            .current_dir(dir.path())
+            .args([
+                "hashmap",
+                "--path",
+                root.to_str().unwrap(),
+                "--output",
+                out.to_str().unwrap(),
+            ])
+            .assert()
+            .success();
+        out
+    };
+    let src_map = map(&src, "src.json");
+    let dst_map = map(&dst, "dst.json");
+
+    let split = dir.path().join("split");
+    Command::new(assert_cmd::cargo::cargo_bin!("hash-folderoo"))
+        .current_dir(dir.path())
+        .args([
+            "compare",
+            "--source",
+            src_map.to_str().unwrap(),
+            "--target",
+            dst_map.to_str().unwrap(),
+            "--format",
+            "csv",
+            "--split-output",
+            split.to_str().unwrap(),
+        ])
+        .assert()
+        .success();
+
+    let mut files: Vec<String> = std::fs::read_dir(&split)?
+        .map(|e| e.unwrap().file_name().to_string_lossy().into_owned())
+        .collect();
+    files.sort();
+    // nothing moved, so no moved.csv
+    assert_eq!(
+        files,
+        ["changed.csv", "identical.csv", "missing.csv", "new.csv"]
+    );
+
+    let missing = read_to_string(split.join("missing.csv"))?;
+    let lines: Vec<&str> = missing.lines().collect();
+    assert_eq!(lines.len(), 2, "header plus one row: {}", missing);
+    assert!(lines[0].starts_with("status,source_path"));
+    assert!(lines[1].starts_with("missing,gone.txt,"));
+
+    let new = read_to_string(split.join("new.csv"))?;
+    assert_eq!(new.lines().count(), 2);
+    assert!(new.lines().nth(1).unwrap().contains("added.txt"));
+    assert!(read_to_string(split.join("changed.csv"))?.contains("edited.txt"));
+
+    // a rerun into the same directory leaves no stale categories behind, and self
+    // duplicates are named after their report section as in JSON
+    let dupes = dir.path().join("dupes");
+    create_dir_all(&dupes)?;
+    write(dupes.join("a.txt"), b"twice")?;
+    write(dupes.join("b.txt"), b"twice")?;
+    let dupes_map = map(&dupes, "dupes.json");
+    Command::new(assert_cmd::cargo::cargo_bin!("hash-folderoo"))
+        .current_dir(dir.path())
+        .args([
+            "compare",
+            "--source",
+            dupes_map.to_str().unwrap(),
+            "--target",
+            dupes_map.to_str().unwrap(),
+            "--self-dupes",
+            "--format",
+            "csv",
+            "--split-output",
+            split.to_str().unwrap(),
+        ])
+        .assert()
+        .success();
+    let mut files: Vec<String> = std::fs::read_dir(&split)?
+        .map(|e| e.unwrap().file_name().to_string_lossy().into_owned())
+        .collect();
+    files.sort();
+    assert_eq!(files, ["identical.csv", "self_duplicates.csv"]);
+    Ok(())
+}
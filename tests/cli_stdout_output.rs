@@ -0,0 +1,46 @@
+use std::fs::{create_dir_all, write};
+use std::process::Command;
+use tempfile::tempdir;
+
+#[test]
+fn large_stdout_maps_are_complete() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = tempdir()?;
+    let root = dir.path().join("tree");
+    create_dir_all(&root)?;
+    let files = 600;
+    for i in 0..files {
+        write(
+            root.join(format!("file_{:04}.txt", i)),
+            format!("contents {}", i),
+        )?;
+    }
+
+    let run = |format: &str| -> String {
+        let output = Command::new(assert_cmd::cargo::cargo_bin!("hash-folderoo"))
+            .current_dir(dir.path())
+            .args(["hashmap", "--path", "tree", "--format", format])
+            .output()
+            .unwrap();
+        assert!(output.status.success(), "{} failed", format);
+        String::from_utf8(output.stdout).unwrap()
+    };
+
+    let json: serde_json::Value = serde_json::from_str(&run("json"))?;
+    assert_eq!(json["entries"].as_array().unwrap().len(), files);
+
+    let ndjson = run("ndjson");
+    assert_eq!(ndjson.lines().count(), files);
+    for line in ndjson.lines() {
+        let entry: serde_json::Value = serde_json::from_str(line)?;
+        assert!(entry["hash"].is_string());
+    }
+
+    let csv = run("csv");
+    assert_eq!(
+        csv.lines().count(),
+        files + 1,
+        "header plus one row per file"
+    );
+    assert!(csv.ends_with('\n'));
+    Ok(())
+}
@@ -0,0 +1,60 @@
+use std::fs::{create_dir_all, write, File};
+use std::time::Duration;
+use tempfile::tempdir;
+
+#[test]
+fn structure_only_lists_paths_and_sizes_without_reading() -> Result<(), Box<dyn std::error::Error>>
+{
+    let dir = tempdir()?;
+    let root = dir.path().join("root");
+    create_dir_all(root.join("sub"))?;
+    write(root.join("a.txt"), b"alpha")?;
+    write(root.join("sub").join("empty"), b"")?;
+    // A sparse 64 GiB file: reading it would take far longer than the timeout below
+    let huge_size = 64u64 << 30;
+    File::create(root.join("sub").join("huge.img"))?.set_len(huge_size)?;
+
+    let out = assert_cmd::Command::new(assert_cmd::cargo::cargo_bin!("hash-folderoo"))
+        .current_dir(dir.path())
+        .args([
+            "hashmap",
+            "--path",
+            root.to_str().unwrap(),
+            "--structure-only",
+        ])
+        .timeout(Duration::from_secs(20))
+        .assert()
+        .success()
+        .get_output()
+        .stdout
+        .clone();
+
+    let map: serde_json::Value = serde_json::from_slice(&out)?;
+    assert_eq!(map["content_hashed"], false);
+    let mut entries: Vec<(String, u64, String)> = map["entries"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|e| {
+            (
+                e["path"].as_str().unwrap().replace('\\', "/"),
+                e["size"].as_u64().unwrap(),
+                e["hash"].as_str().unwrap().to_string(),
+            )
+        })
+        .collect();
+    entries.sort();
+    assert_eq!(
+        entries,
+        vec![
+            ("a.txt".to_string(), 5, format!("{:016x}", 5)),
+            ("sub/empty".to_string(), 0, format!("{:016x}", 0)),
+            (
+                "sub/huge.img".to_string(),
+                huge_size,
+                format!("{:016x}", huge_size)
+            ),
+        ]
+    );
+    Ok(())
+}
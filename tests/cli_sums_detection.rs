@@ -0,0 +1,56 @@
+use assert_cmd::prelude::*;
+use std::fs::{create_dir_all, read_to_string, write};
+use std::process::Command;
+use tempfile::tempdir;
+
+#[test]
+fn sums_files_without_the_extension_load_by_content() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = tempdir()?;
+    let root = dir.path().join("root");
+    create_dir_all(&root)?;
+    write(root.join("a.txt"), b"alpha")?;
+    // Windows forbids control characters in file names
+    #[cfg(unix)]
+    write(root.join("b\rc.txt"), b"beta")?;
+    let files = if cfg!(unix) { 2 } else { 1 };
+
+    for style in ["gnu", "bsd"] {
+        let sums = dir.path().join(format!("CHECKSUMS-{}", style));
+        Command::new(assert_cmd::cargo::cargo_bin!("hash-folderoo"))
+            .current_dir(dir.path())
+            .args([
+                "hashmap",
+                "--path",
+                root.to_str().unwrap(),
+                "--output",
+                sums.to_str().unwrap(),
+                "--format",
+                "sums",
+                "--checksum-style",
+                style,
+            ])
+            .assert()
+            .success();
+        // the carriage return is escaped as coreutils does, keeping one line per file
+        assert_eq!(read_to_string(&sums)?.lines().count(), files);
+
+        let report = dir.path().join(format!("report-{}.json", style));
+        Command::new(assert_cmd::cargo::cargo_bin!("hash-folderoo"))
+            .current_dir(dir.path())
+            .args([
+                "compare",
+                "--source",
+                sums.to_str().unwrap(),
+                "--target",
+                root.to_str().unwrap(),
+                "--output",
+                report.to_str().unwrap(),
+            ])
+            .assert()
+            .success();
+        let r: serde_json::Value = serde_json::from_str(&read_to_string(&report)?)?;
+        assert_eq!(r["identical"].as_array().unwrap().len(), files, "{}", r);
+        assert!(r["changed"].as_array().unwrap().is_empty());
+    }
+    Ok(())
+}
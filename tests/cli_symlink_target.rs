@@ -0,0 +1,53 @@
+#![cfg(unix)]
+
+use assert_cmd::prelude::*;
+use std::fs::{create_dir_all, read_to_string, remove_file, write};
+use std::os::unix::fs::symlink;
+use std::path::Path;
+use std::process::Command;
+use tempfile::tempdir;
+
+fn link_entry(dir: &Path, root: &Path) -> serde_json::Value {
+    let out = dir.join("map.json");
+    Command::new(assert_cmd::cargo::cargo_bin!("hash-folderoo"))
+        .current_dir(dir)
+        .args([
+            "hashmap",
+            "--path",
+            root.to_str().unwrap(),
+            "--output",
+            out.to_str().unwrap(),
+            "--hash-symlink-target-path",
+        ])
+        .assert()
+        .success();
+    let map: serde_json::Value = serde_json::from_str(&read_to_string(&out).unwrap()).unwrap();
+    map["entries"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|e| e["path"] == "link")
+        .cloned()
+        .expect("symlink recorded")
+}
+
+#[test]
+fn symlink_hash_follows_its_target_path() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = tempdir()?;
+    let root = dir.path().join("tree");
+    create_dir_all(&root)?;
+    write(root.join("a.txt"), b"same")?;
+    write(root.join("b.txt"), b"same")?;
+    symlink("a.txt", root.join("link"))?;
+
+    let first = link_entry(dir.path(), &root);
+    assert_eq!(first["kind"], "symlink");
+    assert_eq!(first["hash"], blake3::hash(b"a.txt").to_hex().as_str());
+
+    // Same contents behind the link, different target path
+    remove_file(root.join("link"))?;
+    symlink("b.txt", root.join("link"))?;
+    let second = link_entry(dir.path(), &root);
+    assert_ne!(first["hash"], second["hash"]);
+    Ok(())
+}
@@ -0,0 +1,56 @@
+use assert_cmd::prelude::*;
+use predicates::prelude::*;
+use std::fs::{create_dir_all, write};
+use std::process::Command;
+use tempfile::tempdir;
+
+#[test]
+fn verify_map_accepts_generated_map() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = tempdir()?;
+    let root = dir.path().join("root");
+    create_dir_all(&root)?;
+    write(root.join("a.txt"), b"hello")?;
+    write(root.join("b.txt"), b"world")?;
+
+    let map = dir.path().join("map.json");
+    Command::new(assert_cmd::cargo::cargo_bin!("hash-folderoo"))
+        .args([
+            "hashmap",
+            "--path",
+            root.to_str().unwrap(),
+            "--output",
+            map.to_str().unwrap(),
+        ])
+        .assert()
+        .success();
+
+    Command::new(assert_cmd::cargo::cargo_bin!("hash-folderoo"))
+        .args(["verify-map", "--input", map.to_str().unwrap()])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("OK (2 entries)"));
+    Ok(())
+}
+
+#[test]
+fn verify_map_flags_non_hex_hash() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = tempdir()?;
+    let map = dir.path().join("map.json");
+    let json = serde_json::json!({
+        "version": 1,
+        "entries": [
+            { "path": "a.txt", "hash": "00aa11bb", "size": 5 },
+            { "path": "b.txt", "hash": "not-a-hash", "size": 5 }
+        ]
+    });
+    write(&map, serde_json::to_string_pretty(&json)?)?;
+
+    Command::new(assert_cmd::cargo::cargo_bin!("hash-folderoo"))
+        .args(["verify-map", "--input", map.to_str().unwrap()])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "entry 2: hash \"not-a-hash\" is not valid hex",
+        ));
+    Ok(())
+}
@@ -0,0 +1,40 @@
+use assert_cmd::prelude::*;
+use std::fs::{create_dir_all, read, read_to_string, write};
+use std::process::Command;
+use tempfile::tempdir;
+
+#[test]
+fn with_checksum_writes_a_matching_sidecar() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = tempdir()?;
+    let root = dir.path().join("root");
+    create_dir_all(&root)?;
+    write(root.join("a.txt"), b"alpha")?;
+    let map = dir.path().join("out").join("map.json");
+    let sidecar = dir.path().join("out").join("map.json.blake3");
+
+    for content in [&b"alpha"[..], b"changed"] {
+        write(root.join("a.txt"), content)?;
+        Command::new(assert_cmd::cargo::cargo_bin!("hash-folderoo"))
+            .current_dir(dir.path())
+            .args(["hashmap", "--path", root.to_str().unwrap(), "--output"])
+            .arg(&map)
+            .arg("--with-checksum")
+            .assert()
+            .success();
+
+        let expected = blake3::hash(&read(&map)?).to_hex().to_string();
+        assert_eq!(
+            read_to_string(&sidecar)?,
+            format!("{}  map.json\n", expected)
+        );
+    }
+
+    Command::new(assert_cmd::cargo::cargo_bin!("hash-folderoo"))
+        .current_dir(dir.path())
+        .args(["hashmap", "--path", root.to_str().unwrap(), "--output"])
+        .arg(&map)
+        .args(["--with-checksum", "nope"])
+        .assert()
+        .failure();
+    Ok(())
+}